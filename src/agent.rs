@@ -2,6 +2,7 @@ use std::{path::Path, sync::Arc};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::{db::SafePawDb, vm::VmApi};
 
@@ -9,7 +10,7 @@ const AGENT_NAMESPACE: &str = "agents";
 const INSTALLATION_NAMESPACE: &str = "agent_installations";
 const PICOCLAW_VERSION: &str = "0.2.1";
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum AgentType {
     Picoclaw,
@@ -23,14 +24,14 @@ impl AgentType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ProviderConfig {
     pub provider: String,
     pub model: Option<String>,
     pub api_key_name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AgentConfig {
     pub agent_type: AgentType,
     pub provider_config: ProviderConfig,
@@ -39,7 +40,7 @@ pub struct AgentConfig {
     pub workspace_path: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum AgentStatus {
     Installing,
@@ -50,7 +51,7 @@ pub enum AgentStatus {
     Failed,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AgentInstance {
     pub id: String,
     pub name: Option<String>,
@@ -58,7 +59,9 @@ pub struct AgentInstance {
     pub config: AgentConfig,
     pub status: AgentStatus,
     pub pid: Option<u32>,
+    #[schema(value_type = String, format = DateTime)]
     pub created_at: chrono::DateTime<chrono::Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
     pub last_activity: Option<chrono::DateTime<chrono::Utc>>,
     pub error_message: Option<String>,
 }
@@ -83,7 +86,7 @@ pub struct AgentErrorDetails {
     pub causes: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct OnboardAgentRequest {
     pub name: Option<String>,
     pub agent_type: AgentType,
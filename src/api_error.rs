@@ -0,0 +1,246 @@
+use axum::{
+    Json,
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{HeaderMap, HeaderValue, Response, StatusCode, header},
+    middleware::Next,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::util::HandlerError;
+use crate::vm::VmError;
+
+/// Header carrying the id of each API request. A client-supplied id is kept
+/// so logs on both sides can be matched up; otherwise one is generated.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request id that is kept as is.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Error bodies larger than this, or of unknown length, are passed through
+/// by `with_request_id` as they are, only gaining the `x-request-id` header.
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// Machine-readable kind of a failed API request. The values are stable, so
+/// clients can match on them instead of on messages or status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// No VM has the requested name.
+    VmNotFound,
+    /// A VM with the requested name already exists.
+    VmAlreadyExists,
+    /// The VM has no agent with the requested id.
+    AgentNotFound,
+    /// No job has the requested id; finished jobs expire after a while.
+    JobNotFound,
+    /// The job already started or finished, so it can no longer be cancelled.
+    JobNotQueued,
+    /// No API route matches the path.
+    RouteNotFound,
+    /// The route exists but not for this method.
+    MethodNotAllowed,
+    /// A malformed body, unknown field, bad query parameter, invalid VM name
+    /// or launch option.
+    ValidationFailed,
+    /// The request body is over the size limit.
+    PayloadTooLarge,
+    /// The client sent too many requests; `details.retry_after` says when to
+    /// try again.
+    RateLimited,
+    /// multipass is missing or its daemon is unreachable.
+    MultipassUnavailable,
+    /// multipass did not answer in time.
+    MultipassTimeout,
+    /// Anything else, including multipass failing in a way SafePaw does not
+    /// recognize.
+    Internal,
+}
+
+impl ErrorCode {
+    /// The code for an error response that was produced without one, e.g.
+    /// axum's own 405, from its status alone.
+    pub fn for_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => Self::RouteNotFound,
+            StatusCode::METHOD_NOT_ALLOWED => Self::MethodNotAllowed,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::SERVICE_UNAVAILABLE => Self::MultipassUnavailable,
+            StatusCode::GATEWAY_TIMEOUT => Self::MultipassTimeout,
+            status if status.is_client_error() => Self::ValidationFailed,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// Body of every failed API response, from `create_api_router` and the
+/// legacy `vm::app` alike.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(as = ApiError)]
+pub struct ApiErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Machine-readable context, e.g. `causes` when verbose errors are on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    /// The request's `x-request-id`, for matching a failure to server logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// A failed API request, rendered as an `ApiErrorBody`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Error for `err`, classified by `classify_vm_error`.
+    pub fn from_vm_error(err: &VmError) -> Self {
+        let (status, code) = classify_vm_error(err);
+        Self::new(status, code, err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response<Body> {
+        let body = ApiErrorBody {
+            code: self.code,
+            message: self.message,
+            details: self.details,
+            request_id: None,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Status and code for the first `VmError` in `err`'s cause chain, looking
+/// through `HandlerError`s to the error they wrap, or `None` when there is
+/// no `VmError` to go by.
+pub fn classify_error(err: &anyhow::Error) -> Option<(StatusCode, ErrorCode)> {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<VmError>() {
+            return Some(classify_vm_error(err));
+        }
+        if let Some(source) = cause
+            .downcast_ref::<HandlerError>()
+            .and_then(HandlerError::source_error)
+        {
+            return classify_error(source);
+        }
+    }
+    None
+}
+
+/// | `VmError`                           | status | code                    |
+/// |-------------------------------------|--------|-------------------------|
+/// | instance does not exist             | 404    | `vm_not_found`          |
+/// | invalid request                     | 422    | `validation_failed`     |
+/// | name already taken                  | 409    | `vm_already_exists`     |
+/// | multipass or its daemon unreachable | 503    | `multipass_unavailable` |
+/// | timed out                           | 504    | `multipass_timeout`     |
+/// | anything else                       | 500    | `internal`              |
+pub fn classify_vm_error(err: &VmError) -> (StatusCode, ErrorCode) {
+    match err {
+        VmError::InvalidRequest { .. } => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::ValidationFailed,
+        ),
+        VmError::TimedOut { .. } => (StatusCode::GATEWAY_TIMEOUT, ErrorCode::MultipassTimeout),
+        _ if err.is_not_found() => (StatusCode::NOT_FOUND, ErrorCode::VmNotFound),
+        _ if err.is_already_exists() => (StatusCode::CONFLICT, ErrorCode::VmAlreadyExists),
+        _ if err.is_unavailable() => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::MultipassUnavailable,
+        ),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::Internal),
+    }
+}
+
+/// Tags every request with an id, echoed in the `x-request-id` response
+/// header, and makes sure every error response is an `ApiErrorBody` carrying
+/// it. Errors produced outside SafePaw's handlers, such as axum's 405 or an
+/// extractor's plain-text rejection, are wrapped with a code from
+/// `ErrorCode::for_status`.
+/// Bodies over `MAX_ERROR_BODY_BYTES` are left alone rather than cut short.
+pub async fn with_request_id(request: Request, next: Next) -> Response<Body> {
+    let request_id = request_id(request.headers());
+    let response = next.run(request).await;
+    let (mut parts, body) = response.into_parts();
+    let header_value = HeaderValue::from_str(&request_id).expect("request ids are valid headers");
+    parts.headers.insert(REQUEST_ID_HEADER, header_value);
+    if !parts.status.is_client_error() && !parts.status.is_server_error() {
+        return Response::from_parts(parts, body);
+    }
+    if body
+        .size_hint()
+        .upper()
+        .is_none_or(|len| len > MAX_ERROR_BODY_BYTES as u64)
+    {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES)
+        .await
+        .unwrap_or_default();
+    let mut error = serde_json::from_slice::<ApiErrorBody>(&bytes).unwrap_or_else(|_| {
+        let text = String::from_utf8_lossy(&bytes).trim().to_owned();
+        ApiErrorBody {
+            code: ErrorCode::for_status(parts.status),
+            message: if text.is_empty() {
+                parts
+                    .status
+                    .canonical_reason()
+                    .unwrap_or("Request failed")
+                    .to_owned()
+            } else {
+                text
+            },
+            details: None,
+            request_id: None,
+        }
+    });
+    error.request_id = Some(request_id);
+    let body = serde_json::to_vec(&error).expect("error bodies always serialize");
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// The client's `x-request-id` when it is short and printable, otherwise a
+/// fresh one.
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map_or_else(|| uuid::Uuid::new_v4().simple().to_string(), str::to_owned)
+}
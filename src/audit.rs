@@ -0,0 +1,130 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// VM operations that leave an audit record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Launch,
+    Start,
+    Stop,
+    Restart,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// One finished VM operation, written as a single JSON line:
+///
+/// ```json
+/// {"timestamp":"2026-01-05T10:00:00Z","action":"launch","vm_name":"agent-1","outcome":"success"}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub vm_name: String,
+    pub outcome: AuditOutcome,
+    /// Why the operation failed; only set for failures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// A record stamped now for `result` of `action` on `vm_name`.
+    pub fn new<T>(action: AuditAction, vm_name: &str, result: &Result<T>) -> Self {
+        let (outcome, error) = match result {
+            Ok(_) => (AuditOutcome::Success, None),
+            Err(err) => (AuditOutcome::Failure, Some(err.to_string())),
+        };
+        Self {
+            timestamp: Utc::now(),
+            action,
+            vm_name: vm_name.to_owned(),
+            outcome,
+            error,
+        }
+    }
+}
+
+/// Where `LocalVmApi` sends a record after every launch, start, stop,
+/// restart and delete. Failing to record never fails the operation itself.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord) -> Result<()>;
+}
+
+/// Drops every record; what `LocalVmApi` uses unless a sink is configured.
+pub struct NoopAuditSink;
+
+impl AuditSink for NoopAuditSink {
+    fn record(&self, _record: &AuditRecord) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Appends records as JSON lines to a file, `~/.safepaw/audit.log` by
+/// default. The file is only ever opened for appending, and writes through
+/// one sink are serialized so lines never interleave.
+pub struct FileAuditSink {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl FileAuditSink {
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(default_audit_path()?))
+    }
+
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record).context("failed to encode audit record")?;
+        line.push('\n');
+
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("failed to write audit log {}", self.path.display()))
+    }
+}
+
+pub fn default_audit_path() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("HOME is not set")?;
+    Ok(PathBuf::from(home).join(".safepaw").join("audit.log"))
+}
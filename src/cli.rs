@@ -1,21 +1,175 @@
-use anyhow::{Context, Result, bail};
-use clap::{Arg, ArgMatches, Command};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::{Value, json};
+use tokio::io::AsyncReadExt;
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
 
 use crate::agent::{
     AgentInstance, AgentManager, AgentType, OnboardAgentRequest, handlers as agent_handlers,
 };
-use crate::vm::{VmApi, VmStatusResponse, VmSummary, handlers};
+use crate::config::Config;
+use crate::manifest::{
+    ApplyOutcome, ApplyResult, Manifest, apply_manifest, default_apply_concurrency,
+};
+use crate::server::CORS_ORIGINS_ENV;
+use crate::util::{
+    HandlerError, HandlerResult, VERBOSE_ERRORS_ENV, env_enables_verbose_errors, format_bytes,
+    format_percent,
+};
+use crate::vm::{
+    CloudConfig, DEFAULT_LAUNCH_CONCURRENCY, DEFAULT_LOG_LINES, DEFAULT_NAME_PREFIX,
+    DEFAULT_PRUNE_CONCURRENCY, DEFAULT_WAIT_TIMEOUT_SECS, LaunchSpec, LineSink, LogSource,
+    MAX_LAUNCH_COUNT, NameError, NamePattern, PruneSelection, RenameOptions, RenameStep,
+    ScriptOptions, SshConfig, StopOptions, VmApi, VmBatchResult, VmError, VmListing, VmProperty,
+    VmState, VmStatusResponse, VmSummary, WaitOptions, WaitTimeout, generate_vm_name, handlers,
+    info_all, launch_vms, numbered_vm_names, prune_vms, run_script, validate_vm_name,
+    wait_for_deletion, wait_for_ready, wait_for_state,
+};
+
+/// How often `--wait` polls the VM state.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ANSI reverse video, marking rows that changed between `--watch` polls.
+pub const WATCH_HIGHLIGHT: &str = "\x1b[7m";
+const WATCH_RESET: &str = "\x1b[0m";
+
+/// ANSI colors for VM states in text output.
+pub const STATE_GREEN: &str = "\x1b[32m";
+pub const STATE_YELLOW: &str = "\x1b[33m";
+pub const STATE_RED: &str = "\x1b[31m";
+
+/// Upper bound on how long shell completion waits for multipass.
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Exit codes scripts can rely on; see `EXIT_CODES_HELP`.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_VM_NOT_FOUND: i32 = 3;
+pub const EXIT_MULTIPASS_UNAVAILABLE: i32 = 4;
+pub const EXIT_TIMEOUT: i32 = 5;
+pub const EXIT_NO_ADDRESS: i32 = 6;
+pub const EXIT_BACKEND: i32 = 7;
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  success
+  1  any other failure
+  2  invalid usage or arguments
+  3  VM not found
+  4  multipass is not installed or its daemon is unavailable
+  5  timed out
+  6  VM has no IPv4 address yet
+  7  multipass ran but failed or returned output SafePaw could not read
+
+`vm exec --script` and `vm shell` exit with the script's or shell's own
+status when it fails.";
+
+/// A command was invoked incorrectly in a way clap cannot check up front.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct UsageError(pub String);
+
+/// A command or script run by `vm exec` exited non-zero; the CLI exits with
+/// the same status.
+#[derive(Debug, thiserror::Error)]
+#[error("command exited with status {0}")]
+pub struct RemoteExit(pub i32);
+
+/// `vm ip` found the VM, but it has no IPv4 address yet.
+#[derive(Debug, thiserror::Error)]
+#[error("VM '{0}' has no IPv4 address yet")]
+pub struct NoAddress(pub String);
+
+/// Maps an error from `run_vm_subcommand` and friends to the documented exit
+/// code by looking for known error types anywhere in its cause chain.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    for cause in err.chain() {
+        if cause.is::<UsageError>() {
+            return EXIT_USAGE;
+        }
+        if cause.is::<WaitTimeout>() {
+            return EXIT_TIMEOUT;
+        }
+        if cause.is::<NoAddress>() {
+            return EXIT_NO_ADDRESS;
+        }
+        if let Some(RemoteExit(status)) = cause.downcast_ref::<RemoteExit>() {
+            // Statuses outside 1..=255 (e.g. -1 for a signal) can't be passed on.
+            return if (1..=255).contains(status) {
+                *status
+            } else {
+                EXIT_FAILURE
+            };
+        }
+        if let Some(err) = cause.downcast_ref::<VmError>() {
+            return vm_error_exit_code(err);
+        }
+        if let Some(source) = cause
+            .downcast_ref::<HandlerError>()
+            .and_then(HandlerError::source_error)
+        {
+            return exit_code(source);
+        }
+    }
+    EXIT_FAILURE
+}
+
+fn vm_error_exit_code(err: &VmError) -> i32 {
+    match err {
+        VmError::TimedOut { .. } => EXIT_TIMEOUT,
+        _ if err.is_not_found() => EXIT_VM_NOT_FOUND,
+        _ if err.is_unavailable() => EXIT_MULTIPASS_UNAVAILABLE,
+        VmError::CommandFailed { .. }
+        | VmError::InvalidOutput { .. }
+        | VmError::PartialFailure { .. } => EXIT_BACKEND,
+        _ => EXIT_FAILURE,
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VmMode {
     Local,
     Network,
+    Remote,
 }
 
 pub fn build_cli() -> Command {
     Command::new("safepaw")
         .about("Agents for the paranoid.")
         .long_about("SafePaw orchestrates isolated agent runtimes backed by Multipass VMs.")
+        .after_long_help(EXIT_CODES_HELP)
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::Count)
+                .global(true)
+                .help("Show debug logs (-vv for trace)"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .conflicts_with("verbose")
+                .help("Only show warnings and errors"),
+        )
+        .arg(
+            Arg::new("no-color")
+                .long("no-color")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Never color output (also set by the NO_COLOR environment variable)"),
+        )
         .subcommand(
             Command::new("start")
                 .about("Start SafePaw server daemon")
@@ -27,6 +181,18 @@ pub fn build_cli() -> Command {
                         .default_value("0.0.0.0")
                         .help("Host address to bind servers (e.g., 0.0.0.0, 127.0.0.1, localhost)"),
                 )
+                .arg(
+                    Arg::new("bind-ui")
+                        .long("bind-ui")
+                        .value_name("ADDR")
+                        .help("IP address for the UI server only; defaults to --host"),
+                )
+                .arg(
+                    Arg::new("bind-api")
+                        .long("bind-api")
+                        .value_name("ADDR")
+                        .help("IP address for the REST API server only; defaults to --host"),
+                )
                 .arg(
                     Arg::new("ui-port")
                         .long("ui-port")
@@ -35,6 +201,49 @@ pub fn build_cli() -> Command {
                         .value_parser(clap::value_parser!(u16))
                         .help("Port for the UI server"),
                 )
+                .arg(
+                    Arg::new("single-port")
+                        .long("single-port")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["api-port", "bind-api"])
+                        .help("Serve the UI and the REST API (under /api) together on --ui-port"),
+                )
+                .arg(
+                    Arg::new("async-launch")
+                        .long("async-launch")
+                        .action(ArgAction::SetTrue)
+                        .help("Launch VMs in the background for POST /vms and return a job to poll"),
+                )
+                .arg(
+                    Arg::new("shutdown-timeout")
+                        .long("shutdown-timeout")
+                        .value_name("SECS")
+                        .default_value("30")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("On shutdown, wait this many seconds for running requests and jobs before cancelling them"),
+                )
+                .arg(
+                    Arg::new("rate-limit")
+                        .long("rate-limit")
+                        .value_name("LIMITS")
+                        .help("Limit API requests per client, e.g. launches=2/min,mutations=30/min,reads=600/min"),
+                )
+                .arg(
+                    Arg::new("job-retention")
+                        .long("job-retention")
+                        .value_name("SECS")
+                        .default_value("3600")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Keep finished background jobs listed under /jobs for this many seconds"),
+                )
+                .arg(
+                    Arg::new("info-cache-ttl")
+                        .long("info-cache-ttl")
+                        .value_name("SECS")
+                        .default_value("0")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Serve GET /vms/{name} from a cache for this many seconds (0 disables it)"),
+                )
                 .arg(
                     Arg::new("api-port")
                         .long("api-port")
@@ -42,6 +251,44 @@ pub fn build_cli() -> Command {
                         .default_value("8889")
                         .value_parser(clap::value_parser!(u16))
                         .help("Port for the REST API server"),
+                )
+                .arg(
+                    Arg::new("banner")
+                        .long("banner")
+                        .value_name("FORMAT")
+                        .value_parser(["text", "json", "none"])
+                        .default_value("text")
+                        .help("Startup banner: text (default), json (one line on stdout) or none"),
+                )
+                .arg(
+                    Arg::new("ui-dir")
+                        .long("ui-dir")
+                        .value_name("PATH")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Serve UI files from this directory instead of the embedded copy"),
+                )
+                .arg(
+                    Arg::new("tls-cert")
+                        .long("tls-cert")
+                        .value_name("PEM")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("tls-key")
+                        .help("Serve the API over HTTPS with this PEM certificate chain"),
+                )
+                .arg(
+                    Arg::new("tls-key")
+                        .long("tls-key")
+                        .value_name("PEM")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("tls-cert")
+                        .help("PEM private key for --tls-cert"),
+                )
+                .arg(
+                    Arg::new("tls-ui")
+                        .long("tls-ui")
+                        .action(ArgAction::SetTrue)
+                        .requires("tls-cert")
+                        .help("Serve the UI over HTTPS too"),
                 ),
         )
         .subcommand(
@@ -51,44 +298,747 @@ pub fn build_cli() -> Command {
                     Arg::new("mode")
                         .long("mode")
                         .value_name("MODE")
-                        .value_parser(["local", "network"])
+                        .value_parser(["local", "network", "remote"])
                         .global(true)
                         .default_value("local")
-                        .help("Execution mode: local (default) or network (planned)"),
+                        .help("Execution mode: local (default), remote (over SSH) or network (planned)"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("FORMAT")
+                        .value_parser(["text", "plain", "json", "jsonl"])
+                        .global(true)
+                        .default_value("text")
+                        .help("Output format: text (default), plain (one ' | ' separated line per VM), json or jsonl (one compact JSON object per line)"),
+                )
+                .arg(
+                    Arg::new("ssh-host")
+                        .long("ssh-host")
+                        .value_name("HOST")
+                        .global(true)
+                        .help("Host running multipass, required with --mode remote"),
+                )
+                .arg(
+                    Arg::new("ssh-user")
+                        .long("ssh-user")
+                        .value_name("USER")
+                        .global(true)
+                        .help("SSH user for --mode remote"),
+                )
+                .arg(
+                    Arg::new("ssh-identity")
+                        .long("ssh-identity")
+                        .value_name("FILE")
+                        .global(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("SSH identity file for --mode remote"),
+                )
+                .arg(
+                    Arg::new("ssh-timeout")
+                        .long("ssh-timeout")
+                        .value_name("SECS")
+                        .global(true)
+                        .default_value("10")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("SSH connection timeout in seconds for --mode remote"),
                 )
                 .subcommand_required(true)
                 .arg_required_else_help(true)
                 .subcommand(
                     Command::new("launch")
                         .about("Launch a new VM")
-                        .arg(Arg::new("name").required(true).help("VM name to create")),
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required_unless_present_any(["auto", "name-prefix", "spec"])
+                                .conflicts_with_all(["auto", "name-prefix"])
+                                .help("VM name to create"),
+                        )
+                        .arg(
+                            Arg::new("auto")
+                                .long("auto")
+                                .action(ArgAction::SetTrue)
+                                .help(format!(
+                                    "Generate an unused name like {DEFAULT_NAME_PREFIX}-k3x9qa"
+                                )),
+                        )
+                        .arg(
+                            Arg::new("name-prefix")
+                                .long("name-prefix")
+                                .value_name("PREFIX")
+                                .help("Prefix for the generated name (implies --auto)"),
+                        )
+                        .arg(
+                            Arg::new("spec")
+                                .long("spec")
+                                .value_name("FILE")
+                                .conflicts_with_all(["name", "auto", "name-prefix", "count", "network"])
+                                .help("Read the whole launch spec as JSON from FILE, or stdin for -"),
+                        )
+                        .arg(
+                            Arg::new("count")
+                                .long("count")
+                                .value_name("N")
+                                .value_parser(
+                                    clap::value_parser!(u32).range(1..=i64::from(MAX_LAUNCH_COUNT)),
+                                )
+                                .help("Launch N identical VMs named NAME-1 through NAME-N (at most 50)"),
+                        )
+                        .arg(
+                            Arg::new("network")
+                                .long("network")
+                                .value_name("NAME")
+                                .action(ArgAction::Append)
+                                .help("Bridge the VM onto a host network (repeatable, see `vm networks`)"),
+                        )
+                        .arg(
+                            Arg::new("timezone")
+                                .long("timezone")
+                                .value_name("TZ")
+                                .conflicts_with("spec")
+                                .help("Set the VM's timezone on first boot, e.g. Europe/Berlin"),
+                        )
+                        .arg(
+                            Arg::new("package")
+                                .long("package")
+                                .value_name("NAME")
+                                .action(ArgAction::Append)
+                                .conflicts_with("spec")
+                                .help("Install a package on first boot (repeatable)"),
+                        )
+                        .arg(
+                            Arg::new("run-cmd")
+                                .long("run-cmd")
+                                .value_name("COMMAND")
+                                .action(ArgAction::Append)
+                                .conflicts_with("spec")
+                                .help("Run a shell command once on first boot (repeatable, in order)"),
+                        )
+                        .arg(
+                            Arg::new("no-preflight")
+                                .long("no-preflight")
+                                .action(ArgAction::SetTrue)
+                                .help("Skip checking host memory and disk before launching"),
+                        )
+                        .args(ready_args()),
                 )
                 .subcommand(
                     Command::new("start")
                         .about("Start a stopped VM")
-                        .arg(Arg::new("name").required(true).help("VM name to start")),
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required_unless_present("name-pattern")
+                                .help("VM name to start"),
+                        )
+                        .arg(
+                            Arg::new("if-needed")
+                                .long("if-needed")
+                                .action(ArgAction::SetTrue)
+                                .help("Skip the start when the VM is already running"),
+                        )
+                        .args(ready_args())
+                        .args(name_pattern_args())
+                        .mut_arg("name-pattern", |arg| arg.conflicts_with("wait")),
                 )
                 .subcommand(
                     Command::new("stop")
                         .about("Stop a running VM")
-                        .arg(Arg::new("name").required(true).help("VM name to stop")),
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required_unless_present("name-pattern")
+                                .help("VM name to stop"),
+                        )
+                        .arg(
+                            Arg::new("if-needed")
+                                .long("if-needed")
+                                .action(ArgAction::SetTrue)
+                                .help("Skip the stop when the VM is already stopped"),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .action(ArgAction::SetTrue)
+                                .help("Power the VM off immediately"),
+                        )
+                        .arg(
+                            Arg::new("force-after")
+                                .long("force-after")
+                                .value_name("SECS")
+                                .value_parser(clap::value_parser!(u64))
+                                .conflicts_with("force")
+                                .help("Force the stop if a graceful stop takes longer than this"),
+                        )
+                        .args(wait_args(VmState::Stopped))
+                        .args(name_pattern_args())
+                        .mut_arg("name-pattern", |arg| arg.conflicts_with("wait")),
                 )
                 .subcommand(
                     Command::new("restart")
                         .about("Restart a VM")
-                        .arg(Arg::new("name").required(true).help("VM name to restart")),
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to restart"),
+                        )
+                        .args(ready_args()),
                 )
                 .subcommand(
                     Command::new("delete")
-                        .about("Delete a VM permanently")
-                        .arg(Arg::new("name").required(true).help("VM name to delete")),
+                        .about("Delete one or more VMs permanently")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required_unless_present("name-pattern")
+                                .num_args(1..)
+                                .help("VM names to delete, removed with a single multipass call"),
+                        )
+                        .args(name_pattern_args())
+                        .arg(yes_arg()),
+                )
+                .subcommand(
+                    Command::new("wait")
+                        .about("Block until a VM reaches a state")
+                        .long_about(
+                            "Polls the VM until it reports the requested state, printing every \
+                             state change it sees. Exits with the timeout exit code if the state \
+                             is not reached in time, and fails at once if the VM does not exist. \
+                             Deleted waits until the VM no longer appears in `vm list`.",
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to wait for"),
+                        )
+                        .arg(
+                            Arg::new("state")
+                                .long("state")
+                                .required(true)
+                                .value_name("STATE")
+                                .value_parser(["Running", "Stopped", "Suspended", "Deleted"])
+                                .ignore_case(true)
+                                .help("State to wait for: Running, Stopped, Suspended or Deleted"),
+                        )
+                        .arg(
+                            Arg::new("timeout")
+                                .long("timeout")
+                                .value_name("SECS")
+                                .default_value("120")
+                                .value_parser(clap::value_parser!(u64))
+                                .help("Maximum number of seconds to wait"),
+                        )
+                        .arg(
+                            Arg::new("interval")
+                                .long("interval")
+                                .value_name("SECS")
+                                .default_value("2")
+                                .value_parser(clap::value_parser!(u64).range(1..))
+                                .help("Seconds between polls"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rename")
+                        .about("Rename a VM")
+                        .long_about(
+                            "Renames a VM by cloning it under the new name and then deleting the \
+                             original. Only stopped VMs can be cloned, so stop the VM first or pass \
+                             --force-stop. If cloning fails, the original VM is kept.",
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to rename"),
+                        )
+                        .arg(
+                            Arg::new("new-name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("New VM name"),
+                        )
+                        .arg(
+                            Arg::new("force-stop")
+                                .long("force-stop")
+                                .action(ArgAction::SetTrue)
+                                .help("Stop the VM first if it is running"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("networks").about("List host networks available to --network"),
+                )
+                .subcommand(
+                    Command::new("images").about("List images and blueprints that can be launched"),
                 )
                 .subcommand(
                     Command::new("info")
                         .about("Get detailed VM information")
-                        .arg(Arg::new("name").required(true).help("VM name to inspect")),
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required_unless_present("all")
+                                .help("VM name to inspect"),
+                        )
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .action(ArgAction::SetTrue)
+                                .conflicts_with_all(["name", "watch"])
+                                .help("Show every VM, separated by blank lines"),
+                        )
+                        .arg(
+                            Arg::new("bytes")
+                                .long("bytes")
+                                .action(ArgAction::SetTrue)
+                                .help("Show memory and disk sizes as raw byte counts"),
+                        )
+                        .args(watch_args()),
+                )
+                .subcommand(
+                    Command::new("top")
+                        .about("Live dashboard of VM resource usage")
+                        .long_about(
+                            "Redraws a table of every VM with its state, CPU count and memory \
+                             and disk usage until Ctrl+C. VMs that aren't running show dashes \
+                             for usage.",
+                        )
+                        .arg(
+                            Arg::new("interval")
+                                .long("interval")
+                                .value_name("SECS")
+                                .default_value("2")
+                                .value_parser(clap::value_parser!(u64).range(1..))
+                                .help("Seconds between redraws"),
+                        )
+                        .arg(
+                            Arg::new("sort")
+                                .long("sort")
+                                .value_name("KEY")
+                                .value_parser(["mem", "disk", "name"])
+                                .default_value("mem")
+                                .help("Sort by memory or disk usage (highest first) or by name"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("ip")
+                        .about("Print the IPv4 address of a VM")
+                        .long_about(
+                            "Prints the first IPv4 address of a VM, or every address with --all. \
+                             Exits with code 6 if the VM exists but has no address yet, so \
+                             scripts can retry, or pass --wait to poll until one appears.",
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to look up"),
+                        )
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .action(ArgAction::SetTrue)
+                                .help("Print every IPv4 address, one per line"),
+                        )
+                        .args(ready_args()),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Change the CPUs, memory or disk of a stopped VM")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to change"),
+                        )
+                        .arg(
+                            Arg::new("key")
+                                .required(true)
+                                .value_parser(VmProperty::NAMES)
+                                .help("Property to change"),
+                        )
+                        .arg(
+                            Arg::new("value")
+                                .required(true)
+                                .help("New value, e.g. 4 for cpus or 8G for memory"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("get")
+                        .about("Print the CPUs, memory or disk setting of a VM")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to look up"),
+                        )
+                        .arg(
+                            Arg::new("key")
+                                .required(true)
+                                .value_parser(VmProperty::NAMES)
+                                .help("Property to print"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("logs")
+                        .about("Show cloud-init, syslog or journal output from a VM")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to read logs from"),
+                        )
+                        .arg(
+                            Arg::new("lines")
+                                .long("lines")
+                                .short('n')
+                                .value_name("N")
+                                .default_value("100")
+                                .value_parser(clap::value_parser!(usize))
+                                .help("Number of lines to show"),
+                        )
+                        .arg(
+                            Arg::new("follow")
+                                .long("follow")
+                                .short('f')
+                                .action(ArgAction::SetTrue)
+                                .help("Keep printing new lines until Ctrl+C or the VM stops"),
+                        )
+                        .arg(
+                            Arg::new("source")
+                                .long("source")
+                                .value_name("SOURCE")
+                                .default_value("cloud-init")
+                                .value_parser(LogSource::NAMES)
+                                .help("Log to read: cloud-init output, syslog or the systemd journal"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("exec")
+                        .about("Run a command or a local script inside a VM")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to run the command in"),
+                        )
+                        .arg(
+                            Arg::new("stdin")
+                                .long("stdin")
+                                .action(ArgAction::SetTrue)
+                                .help("Pipe this process's stdin into the command"),
+                        )
+                        .arg(
+                            Arg::new("script")
+                                .long("script")
+                                .value_name("FILE")
+                                .value_parser(clap::value_parser!(PathBuf))
+                                .conflicts_with_all(["command", "stdin"])
+                                .help("Upload a local script, run it and stream its output"),
+                        )
+                        .arg(
+                            Arg::new("args")
+                                .long("args")
+                                .value_name("ARG")
+                                .num_args(1..)
+                                .allow_hyphen_values(true)
+                                .requires("script")
+                                .conflicts_with_all(["command", "stdin"])
+                                .help("Arguments for --script (takes everything after it)"),
+                        )
+                        .arg(
+                            Arg::new("keep")
+                                .long("keep")
+                                .action(ArgAction::SetTrue)
+                                .requires("script")
+                                // `requires` alone is dropped once a command
+                                // makes --script conflict.
+                                .conflicts_with_all(["command", "stdin"])
+                                .help("Leave the uploaded script in the VM's /tmp"),
+                        )
+                        .arg(
+                            Arg::new("command")
+                                .required_unless_present("script")
+                                .num_args(1..)
+                                .last(true)
+                                .value_name("COMMAND")
+                                .help("Command and arguments, after --"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("shell")
+                        .about("Open an interactive shell in a VM")
+                        .long_about(
+                            "Opens an interactive shell in a VM through `multipass shell`. The \
+                             session is attached to this terminal and nothing is captured, so \
+                             it is for interactive use only and can't be combined with JSON \
+                             output. Ctrl+C goes to the shell; exits with the shell's status.",
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to open a shell in"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List all VMs")
+                        .arg(
+                            Arg::new("tag")
+                                .long("tag")
+                                .value_name("TAG")
+                                .action(ArgAction::Append)
+                                .help("Only list VMs with this tag (repeatable, all must match)"),
+                        )
+                        .arg(
+                            Arg::new("state")
+                                .long("state")
+                                .value_name("STATE")
+                                .action(ArgAction::Append)
+                                .value_parser([
+                                    "running",
+                                    "stopped",
+                                    "suspended",
+                                    "starting",
+                                    "deleted",
+                                ])
+                                .ignore_case(true)
+                                .conflicts_with("watch")
+                                .help("Only list VMs in this state (repeatable, any may match)"),
+                        )
+                        .arg(
+                            Arg::new("sort")
+                                .long("sort")
+                                .value_name("KEY")
+                                .value_parser(["name", "state"])
+                                .conflicts_with("watch")
+                                .help("Sort by name or state (ties broken by name); default keeps multipass order"),
+                        )
+                        .arg(
+                            Arg::new("name-pattern")
+                                .long("name")
+                                .value_name("GLOB")
+                                .conflicts_with("watch")
+                                .help("Only list VMs whose whole name matches this glob (case-sensitive)"),
+                        )
+                        .args(watch_args()),
+                )
+                .subcommand(
+                    Command::new("tag")
+                        .about("Add tags to a VM")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to tag"),
+                        )
+                        .arg(
+                            Arg::new("tags")
+                                .required(true)
+                                .num_args(1..)
+                                .help("Tags to add"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("untag")
+                        .about("Remove tags from a VM")
+                        .arg(
+                            Arg::new("name")
+                                .value_parser(vm_name)
+                                .required(true)
+                                .help("VM name to untag"),
+                        )
+                        .arg(
+                            Arg::new("tags")
+                                .required(true)
+                                .num_args(1..)
+                                .help("Tags to remove"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("prune")
+                        .about("Delete stopped VMs, optionally only those with a name prefix")
+                        .long_about(
+                            "Deletes and purges every stopped VM (and suspended ones with \
+                             --include-suspended), or every VM regardless of state with --all. \
+                             --prefix narrows the selection by name. A failed delete does not \
+                             stop the others; failures are reported at the end and make the \
+                             command exit non-zero.",
+                        )
+                        .arg(
+                            Arg::new("prefix")
+                                .long("prefix")
+                                .value_name("PREFIX")
+                                .help("Only delete VMs whose name starts with PREFIX"),
+                        )
+                        .arg(
+                            Arg::new("include-suspended")
+                                .long("include-suspended")
+                                .action(ArgAction::SetTrue)
+                                .help("Also delete suspended VMs"),
+                        )
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .action(ArgAction::SetTrue)
+                                .conflicts_with("include-suspended")
+                                .help("Delete VMs in any state, including running ones"),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .action(ArgAction::SetTrue)
+                                .help("Only list the VMs that would be deleted"),
+                        )
+                        .arg(yes_arg()),
+                )
+                .subcommand(
+                    Command::new("apply")
+                        .about("Launch every VM in a manifest that does not already exist")
+                        .arg(
+                            Arg::new("manifest")
+                                .required(true)
+                                .value_name("MANIFEST")
+                                .help("YAML manifest listing VMs (name, cpus, memory, disk, image)"),
+                        )
+                        .arg(
+                            Arg::new("concurrency")
+                                .long("concurrency")
+                                .value_name("N")
+                                .value_parser(clap::value_parser!(u32).range(1..))
+                                .help(
+                                    "Launch at most N VMs at once [default: half the host CPUs, \
+                                     at most 8]",
+                                ),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("__complete-vm-names")
+                .hide(true)
+                .about("Print VM names, one per line, for shell completion"),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check that this machine can run SafePaw")
+                .long_about(
+                    "Checks the multipass installation and daemon, that the server ports can \
+                     be bound and that there is enough disk space for VMs. Prints a \
+                     pass/warn/fail line per check and exits non-zero if any check fails.",
+                )
+                .arg(
+                    Arg::new("host")
+                        .long("host")
+                        .value_name("HOST")
+                        .default_value("0.0.0.0")
+                        .help("Host address the servers will bind"),
+                )
+                .arg(
+                    Arg::new("ui-port")
+                        .long("ui-port")
+                        .value_name("PORT")
+                        .default_value("8888")
+                        .value_parser(clap::value_parser!(u16))
+                        .help("UI server port to check"),
                 )
-                .subcommand(Command::new("list").about("List all VMs")),
+                .arg(
+                    Arg::new("api-port")
+                        .long("api-port")
+                        .value_name("PORT")
+                        .default_value("8889")
+                        .value_parser(clap::value_parser!(u16))
+                        .help("REST API server port to check"),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Manage the SafePaw config file")
+                .arg_required_else_help(true)
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Write a commented default config file")
+                        .long_about(
+                            "Writes a config file with every setting at its default and a \
+                             comment explaining it, then prints the path it wrote. The default \
+                             location is ~/.safepaw/config.toml, which is read at startup.",
+                        )
+                        .arg(
+                            Arg::new("path")
+                                .long("path")
+                                .value_name("FILE")
+                                .value_parser(clap::value_parser!(PathBuf))
+                                .help("Write here instead of ~/.safepaw/config.toml"),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .action(ArgAction::SetTrue)
+                                .help("Overwrite an existing file"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the effective configuration as JSON")
+                        .long_about(
+                            "Prints the settings SafePaw runs with as JSON: the config file \
+                             merged over the defaults, plus what the environment decides \
+                             (SAFEPAW_CORS_ORIGINS, SAFEPAW_VERBOSE_ERRORS, RUST_LOG with -q/-v) \
+                             and where the multipass binary resolves on PATH. Keys the file \
+                             doesn't know are left out.",
+                        )
+                        .arg(
+                            Arg::new("path")
+                                .long("path")
+                                .value_name("FILE")
+                                .value_parser(clap::value_parser!(PathBuf))
+                                .help("Read this file instead of ~/.safepaw/config.toml"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Validate the config file")
+                        .long_about(
+                            "Loads the config file and prints one line per problem: unknown \
+                             keys (warnings), and port, size and log level settings that can't \
+                             work (errors). Exits non-zero when any error is found.",
+                        )
+                        .arg(
+                            Arg::new("path")
+                                .long("path")
+                                .value_name("FILE")
+                                .value_parser(clap::value_parser!(PathBuf))
+                                .help("Check this file instead of ~/.safepaw/config.toml"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("man")
+                .hide(true)
+                .about("Print a man page in roff format")
+                .arg(
+                    Arg::new("command")
+                        .num_args(0..)
+                        .value_name("COMMAND")
+                        .help("Subcommand to document, e.g. `vm launch` (default: safepaw)"),
+                ),
+        )
+        .subcommand(
+            Command::new("gen-man")
+                .hide(true)
+                .about("Write man pages for safepaw and its vm subcommands")
+                .arg(
+                    Arg::new("out-dir")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .default_value("man")
+                        .help("Directory to write the pages to, created if missing"),
+                ),
         )
         .subcommand(
             Command::new("agent")
@@ -100,8 +1050,8 @@ pub fn build_cli() -> Command {
                         .about("Install an agent in a VM")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name where agent will be installed")
                                 .long_help("VM name where agent will be installed. Use 'safepaw vm list' to see available VMs."),
@@ -121,8 +1071,8 @@ pub fn build_cli() -> Command {
                         .long_about("Onboard (configure) an agent with LLM provider credentials. The agent must be installed first using 'safepaw agent install'.")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name where agent will be onboarded")
                                 .long_help("VM name where agent will be onboarded. Use 'safepaw vm list' to see available VMs."),
@@ -167,8 +1117,8 @@ pub fn build_cli() -> Command {
                         .about("List agents in a VM")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name to list agents from")
                                 .long_help("VM name to list agents from. Use 'safepaw vm list' to see available VMs."),
@@ -179,8 +1129,8 @@ pub fn build_cli() -> Command {
                         .about("Get agent details")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name")
                                 .long_help("VM name where the agent is running. Use 'safepaw vm list' to see available VMs."),
@@ -199,8 +1149,8 @@ pub fn build_cli() -> Command {
                         .about("Stop an agent")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name")
                                 .long_help("VM name where the agent is running. Use 'safepaw vm list' to see available VMs."),
@@ -219,8 +1169,8 @@ pub fn build_cli() -> Command {
                         .about("Delete an agent")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name")
                                 .long_help("VM name where the agent is running. Use 'safepaw vm list' to see available VMs."),
@@ -239,8 +1189,8 @@ pub fn build_cli() -> Command {
                         .about("Check if an agent type is installed")
                         .arg(
                             Arg::new("vm")
+                                .value_parser(vm_name)
                                 .long("vm")
-                                .short('v')
                                 .required(true)
                                 .help("VM name")
                                 .long_help("VM name to check for agent installation. Use 'safepaw vm list' to see available VMs."),
@@ -257,6 +1207,150 @@ pub fn build_cli() -> Command {
         )
 }
 
+/// `--watch` and `--interval` for commands that can redraw periodically.
+fn watch_args() -> [Arg; 2] {
+    [
+        Arg::new("watch")
+            .long("watch")
+            .action(ArgAction::SetTrue)
+            .help("Redraw every --interval seconds until Ctrl+C"),
+        Arg::new("interval")
+            .long("interval")
+            .value_name("SECS")
+            .default_value("2")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .requires("watch")
+            .help("Seconds between redraws with --watch"),
+    ]
+}
+
+fn yes_arg() -> Arg {
+    Arg::new("yes")
+        .short('y')
+        .long("yes")
+        .action(ArgAction::SetTrue)
+        .help("Skip the confirmation prompt")
+}
+
+/// `--wait` for commands that bring a VM up, which waits for an IPv4 address
+/// rather than just the `Running` state. `--timeout` is kept as an alias.
+fn ready_args() -> [Arg; 2] {
+    [
+        Arg::new("wait")
+            .long("wait")
+            .action(ArgAction::SetTrue)
+            .help("Block until the VM is running and has an IPv4 address"),
+        Arg::new("wait-timeout")
+            .long("wait-timeout")
+            .alias("timeout")
+            .value_name("SECS")
+            .default_value("300")
+            .value_parser(clap::value_parser!(u64))
+            .requires("wait")
+            .help("Maximum number of seconds to wait with --wait"),
+    ]
+}
+
+fn wait_args(target: VmState) -> [Arg; 2] {
+    [
+        Arg::new("wait")
+            .long("wait")
+            .action(ArgAction::SetTrue)
+            .help(format!("Block until the VM reports the {target} state")),
+        Arg::new("timeout")
+            .long("timeout")
+            .value_name("SECS")
+            .default_value("120")
+            .value_parser(clap::value_parser!(u64))
+            .requires("wait")
+            .help("Maximum number of seconds to wait with --wait"),
+    ]
+}
+
+/// `--name GLOB` for commands that can act on every VM whose name matches
+/// instead of a single named VM, plus `--allow-empty` to let a pattern that
+/// matches nothing succeed.
+fn name_pattern_args() -> [Arg; 2] {
+    [
+        Arg::new("name-pattern")
+            .long("name")
+            .value_name("GLOB")
+            .conflicts_with("name")
+            .help("Act on every VM whose whole name matches this glob (case-sensitive)"),
+        Arg::new("allow-empty")
+            .long("allow-empty")
+            .action(ArgAction::SetTrue)
+            .requires("name-pattern")
+            // clap skips `requires` when the required arg conflicts with one
+            // that is present, so rule out a positional name directly.
+            .conflicts_with("name")
+            .help("Succeed without doing anything when --name matches no VMs"),
+    ]
+}
+
+/// Tracing filter to install at startup. An explicitly set `RUST_LOG` wins;
+/// otherwise `-q` keeps warnings only, each `-v` raises the level by one,
+/// and with neither flag `default_level` (from the config file) applies.
+pub fn resolve_log_filter(
+    quiet: bool,
+    verbose: u8,
+    default_level: &str,
+    env: Option<&str>,
+) -> String {
+    if let Some(env) = env.map(str::trim).filter(|env| !env.is_empty()) {
+        return env.to_owned();
+    }
+    let level = match (quiet, verbose) {
+        (true, _) => "warn",
+        (false, 0) => default_level,
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    format!("safepaw={level}")
+}
+
+/// What `config show` prints: `config` as loaded from `path` (`None` when
+/// no file exists and the defaults apply), plus what the environment and
+/// global flags decide on top of it. `env` looks up environment variables.
+/// Keys the file doesn't know are left out, so a stray secret there is
+/// never echoed.
+pub fn effective_config(
+    config: &Config,
+    path: Option<&Path>,
+    log_filter: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<Value> {
+    let mut value = serde_json::to_value(config)?;
+    value["path"] = json!(path);
+    value["server"]["cors_origins"] = json!(env(CORS_ORIGINS_ENV));
+    value["server"]["verbose_errors"] = json!(
+        cfg!(debug_assertions) || env_enables_verbose_errors(env(VERBOSE_ERRORS_ENV).as_deref())
+    );
+    value["multipass"]["path"] = json!(find_executable(
+        &config.multipass.binary,
+        env("PATH").as_deref()
+    ));
+    value["log"]["filter"] = json!(log_filter);
+    Ok(value)
+}
+
+/// Where `binary` runs from: itself when it contains a path separator,
+/// otherwise the first match in the `PATH`-style list `search_path`.
+fn find_executable(binary: &str, search_path: Option<&str>) -> Option<PathBuf> {
+    if Path::new(binary).components().count() > 1 {
+        return Path::new(binary).is_file().then(|| PathBuf::from(binary));
+    }
+    std::env::split_paths(search_path?)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether text output should be colored: not with `--no-color`, not when
+/// `NO_COLOR` is set to a non-empty value, and only on a terminal.
+pub fn use_color(no_color_flag: bool, no_color_env: Option<&OsStr>, is_terminal: bool) -> bool {
+    !no_color_flag && no_color_env.is_none_or(OsStr::is_empty) && is_terminal
+}
+
 pub fn resolve_vm_mode(matches: &ArgMatches) -> Result<VmMode> {
     let mode = matches
         .get_one::<String>("mode")
@@ -266,12 +1360,203 @@ pub fn resolve_vm_mode(matches: &ArgMatches) -> Result<VmMode> {
     match mode {
         "local" => Ok(VmMode::Local),
         "network" => Ok(VmMode::Network),
-        _ => bail!("unsupported vm mode: {mode}"),
+        "remote" => Ok(VmMode::Remote),
+        _ => Err(UsageError(format!("unsupported vm mode: {mode}")).into()),
+    }
+}
+
+pub fn resolve_ssh_config(matches: &ArgMatches) -> Result<SshConfig> {
+    let host = matches
+        .get_one::<String>("ssh-host")
+        .ok_or_else(|| UsageError("--ssh-host is required with --mode remote".to_owned()))?;
+
+    let mut config = SshConfig::new(host);
+    config.user = matches.get_one::<String>("ssh-user").cloned();
+    config.identity_file = matches.get_one::<PathBuf>("ssh-identity").cloned();
+    if let Some(secs) = matches.get_one::<u64>("ssh-timeout") {
+        config.connect_timeout = Duration::from_secs(*secs);
+    }
+    Ok(config)
+}
+
+/// IPv4 addresses shown per VM in the `vm list` table before collapsing the
+/// rest into a `+N more` suffix.
+const TABLE_MAX_IPS: usize = 1;
+
+/// Renders VMs as a column-aligned table with a header row.
+pub fn render_table(vms: Vec<VmSummary>) -> Vec<String> {
+    render_state_table(vms, false)
+}
+
+/// `render_table`, with the STATE column colored when `color` is set.
+fn render_state_table(vms: Vec<VmSummary>, color: bool) -> Vec<String> {
+    const HEADERS: [&str; 4] = ["NAME", "STATE", "IPV4", "RELEASE"];
+
+    let state_colors: Vec<&str> = vms.iter().map(|vm| state_color(&vm.state)).collect();
+    let rows: Vec<[String; 4]> = vms
+        .into_iter()
+        .map(|vm| {
+            let ipv4 = match vm.ipv4.as_deref() {
+                None | Some([]) => "-".to_string(),
+                Some(addrs) if addrs.len() > TABLE_MAX_IPS => format!(
+                    "{} +{} more",
+                    addrs[..TABLE_MAX_IPS].join(","),
+                    addrs.len() - TABLE_MAX_IPS
+                ),
+                Some(addrs) => addrs.join(","),
+            };
+            [
+                vm.name,
+                vm.state.to_string(),
+                ipv4,
+                vm.release.unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    let widths = column_widths(HEADERS, &rows);
+
+    // Padding is computed on the plain cell so escape codes don't skew
+    // the column widths.
+    let format_row = |cells: [&str; 4], state_color: Option<&str>| {
+        cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| match state_color {
+                Some(code) if column == 1 => format!(
+                    "{code}{cell}{WATCH_RESET}{}",
+                    " ".repeat(width - cell.chars().count())
+                ),
+                _ => format!("{:<width$}", cell, width = width),
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    std::iter::once(format_row(HEADERS, None))
+        .chain(rows.iter().zip(state_colors).map(|(row, code)| {
+            format_row([&row[0], &row[1], &row[2], &row[3]], color.then_some(code))
+        }))
+        .collect()
+}
+
+/// Width of each column: its widest cell, header included.
+fn column_widths<const N: usize>(headers: [&str; N], rows: &[[String; N]]) -> [usize; N] {
+    let mut widths = headers.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    widths
+}
+
+/// Row order of the `vm top` table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopSort {
+    /// Highest memory usage first.
+    #[default]
+    Memory,
+    /// Highest disk usage first.
+    Disk,
+    Name,
+}
+
+/// `(used, total)` for a running VM that reports both, `None` otherwise:
+/// the figures of a stopped VM are stale or missing, not zero.
+fn running_usage(
+    info: &VmStatusResponse,
+    used: Option<u64>,
+    total: Option<u64>,
+) -> Option<(u64, u64)> {
+    match (used, total) {
+        (Some(used), Some(total)) if info.state == VmState::Running && total > 0 => {
+            Some((used, total))
+        }
+        _ => None,
+    }
+}
+
+/// The `vm top` table: state, CPU count and memory/disk usage of each VM,
+/// with dashes for VMs that aren't running. Usage sorts put VMs without
+/// usage last; ties are broken by name.
+pub fn render_top_table(infos: &[VmStatusResponse], sort: TopSort) -> Vec<String> {
+    const HEADERS: [&str; 5] = ["NAME", "STATE", "CPUS", "MEM", "DISK"];
+
+    if infos.is_empty() {
+        return vec!["No VMs found".to_string()];
+    }
+
+    let memory = |info: &VmStatusResponse| running_usage(info, info.memory_used, info.memory_total);
+    let disk = |info: &VmStatusResponse| running_usage(info, info.disk_used, info.disk_total);
+    let ratio = |usage: Option<(u64, u64)>| usage.map(|(used, total)| used as f64 / total as f64);
+
+    let mut infos: Vec<&VmStatusResponse> = infos.iter().collect();
+    infos.sort_by(|a, b| {
+        let by_usage = match sort {
+            TopSort::Memory => ratio(memory(b)).partial_cmp(&ratio(memory(a))),
+            TopSort::Disk => ratio(disk(b)).partial_cmp(&ratio(disk(a))),
+            TopSort::Name => None,
+        };
+        by_usage
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let percent = |usage: Option<(u64, u64)>| {
+        usage
+            .and_then(|(used, total)| format_percent(used, total))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let rows: Vec<[String; 5]> = infos
+        .into_iter()
+        .map(|info| {
+            [
+                info.name.clone(),
+                info.state.to_string(),
+                info.cpu_count.clone().unwrap_or_else(|| "-".to_string()),
+                percent(memory(info)),
+                percent(disk(info)),
+            ]
+        })
+        .collect();
+
+    let widths = column_widths(HEADERS, &rows);
+    let format_row = |cells: [&str; 5]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    std::iter::once(format_row(HEADERS))
+        .chain(
+            rows.iter()
+                .map(|row| format_row([&row[0], &row[1], &row[2], &row[3], &row[4]])),
+        )
+        .collect()
+}
+
+/// Color for a VM state: green when up, yellow while changing or paused,
+/// red when down or unknown.
+fn state_color(state: &VmState) -> &'static str {
+    match state {
+        VmState::Running => STATE_GREEN,
+        VmState::Starting | VmState::Suspended => STATE_YELLOW,
+        VmState::Unknown(state) if state.eq_ignore_ascii_case("restarting") => STATE_YELLOW,
+        VmState::Stopped | VmState::Deleted | VmState::Unknown(_) => STATE_RED,
     }
 }
 
 fn format_vm_summary(vm: &VmSummary) -> String {
-    let mut parts = vec![vm.name.clone(), vm.state.clone()];
+    let mut parts = vec![vm.name.clone(), vm.state.to_string()];
 
     if let Some(ref ipv4_addrs) = vm.ipv4
         && !ipv4_addrs.is_empty()
@@ -286,11 +1571,35 @@ fn format_vm_summary(vm: &VmSummary) -> String {
     parts.join(" | ")
 }
 
-fn format_vm_info(info: &VmStatusResponse) -> Vec<String> {
-    let mut lines = vec![
-        format!("Name:  {}", info.name),
-        format!("State: {}", info.state),
-    ];
+/// How `vm info` shows memory and disk sizes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeFormat {
+    /// `1.4 GiB`, with the unit picked per value.
+    #[default]
+    Human,
+    /// Raw byte counts, for scripts.
+    Bytes,
+}
+
+/// `used / total (pct)`, leaving out the percentage when `total` is 0.
+fn format_usage(used: u64, total: u64, sizes: SizeFormat) -> String {
+    let usage = match sizes {
+        SizeFormat::Human => format!("{} / {}", format_bytes(used), format_bytes(total)),
+        SizeFormat::Bytes => format!("{used} / {total}"),
+    };
+    match format_percent(used, total) {
+        Some(percent) => format!("{usage} ({percent})"),
+        None => usage,
+    }
+}
+
+fn format_vm_info(info: &VmStatusResponse, sizes: SizeFormat, color: bool) -> Vec<String> {
+    let state = if color {
+        format!("{}{}{WATCH_RESET}", state_color(&info.state), info.state)
+    } else {
+        info.state.to_string()
+    };
+    let mut lines = vec![format!("Name:  {}", info.name), format!("State: {}", state)];
 
     if let Some(ref ipv4_addrs) = info.ipv4
         && !ipv4_addrs.is_empty()
@@ -298,6 +1607,12 @@ fn format_vm_info(info: &VmStatusResponse) -> Vec<String> {
         lines.push(format!("IPv4:  {}", ipv4_addrs.join(", ")));
     }
 
+    if let Some(ref ipv6_addrs) = info.ipv6
+        && !ipv6_addrs.is_empty()
+    {
+        lines.push(format!("IPv6:  {}", ipv6_addrs.join(", ")));
+    }
+
     if let Some(ref release) = info.release {
         lines.push(format!("Release: {}", release));
     }
@@ -311,105 +1626,1196 @@ fn format_vm_info(info: &VmStatusResponse) -> Vec<String> {
     }
 
     if let (Some(total), Some(used)) = (info.memory_total, info.memory_used) {
-        let total_mb = total / 1024 / 1024;
-        let used_mb = used / 1024 / 1024;
-        let percent = (used as f64 / total as f64 * 100.0) as u64;
-        lines.push(format!(
-            "Memory: {} MiB / {} MiB ({}%)",
-            used_mb, total_mb, percent
-        ));
+        lines.push(format!("Memory: {}", format_usage(used, total, sizes)));
     }
 
     if let (Some(total), Some(used)) = (info.disk_total, info.disk_used) {
-        let total_gb = total / 1024 / 1024 / 1024;
-        let used_gb = used / 1024 / 1024 / 1024;
-        let percent = (used as f64 / total as f64 * 100.0) as u64;
-        lines.push(format!(
-            "Disk:   {} GiB / {} GiB ({}%)",
-            used_gb, total_gb, percent
-        ));
+        lines.push(format!("Disk:   {}", format_usage(used, total, sizes)));
+    }
+
+    for warning in &info.warnings {
+        lines.push(format!("Warning: {}", warning));
     }
 
     lines
 }
 
-pub async fn run_vm_subcommand(matches: &ArgMatches, api: &dyn VmApi) -> Result<Vec<String>> {
+/// Result of a CLI subcommand. Commands return data; `main.rs` turns it into
+/// text with [`CommandResult::render`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandResult {
+    /// Human-readable lines.
+    Lines(Vec<String>),
+    /// VMs from `vm list`, shown as a table, plain lines or JSON.
+    Summaries(Vec<VmSummary>),
+    /// A single VM from `vm info`.
+    Info(Box<VmStatusResponse>),
+    /// Every VM from `vm info --all`.
+    Infos(Vec<VmStatusResponse>),
+    /// Machine-readable output for `--output json`.
+    Json(Value),
+    /// Nothing to print, e.g. after `--watch` already streamed its output.
+    Empty,
+}
+
+impl CommandResult {
+    /// Renders the output as the lines printed to stdout.
+    pub fn render(self, options: &RenderOptions) -> Vec<String> {
+        // Colors are for people reading text output, never for scripts.
+        let color = options.color && options.format == OutputFormat::Text;
+        match self {
+            Self::Lines(lines) => lines,
+            Self::Summaries(vms) => match options.format {
+                OutputFormat::Json => render_json(&vms),
+                OutputFormat::JsonLines => render_json_lines(&vms),
+                OutputFormat::Plain if !options.state_filter.is_empty() => {
+                    let header = format!("# state: {}", options.state_filter.join(", "));
+                    let rest = Self::Summaries(vms).render(&RenderOptions {
+                        state_filter: Vec::new(),
+                        ..options.clone()
+                    });
+                    std::iter::once(header).chain(rest).collect()
+                }
+                _ if vms.is_empty() => vec!["No VMs found".to_string()],
+                OutputFormat::Plain => vms.iter().map(format_vm_summary).collect(),
+                OutputFormat::Text => render_state_table(vms, color),
+            },
+            Self::Info(info) => match options.format {
+                OutputFormat::Json => render_json(&info),
+                OutputFormat::JsonLines => render_json_lines([&info]),
+                OutputFormat::Text | OutputFormat::Plain => {
+                    format_vm_info(&info, options.sizes, color)
+                }
+            },
+            Self::Infos(infos) => match options.format {
+                OutputFormat::Json => render_json(&infos),
+                OutputFormat::JsonLines => render_json_lines(&infos),
+                _ if infos.is_empty() => vec!["No VMs found".to_string()],
+                OutputFormat::Text | OutputFormat::Plain => {
+                    let blocks: Vec<Vec<String>> = infos
+                        .iter()
+                        .map(|info| format_vm_info(info, options.sizes, color))
+                        .collect();
+                    blocks.join(&String::new())
+                }
+            },
+            Self::Json(value) => match (options.format, value) {
+                (OutputFormat::JsonLines, Value::Array(items)) => render_json_lines(&items),
+                (OutputFormat::JsonLines, value) => render_json_lines([&value]),
+                (_, value) => render_json(&value),
+            },
+            Self::Empty => Vec::new(),
+        }
+    }
+
+    /// Renders with the default text options.
+    pub fn into_lines(self) -> Vec<String> {
+        self.render(&RenderOptions::default())
+    }
+}
+
+fn render_json(value: &impl serde::Serialize) -> Vec<String> {
+    vec![serde_json::to_string_pretty(value).expect("CLI output always serializes")]
+}
+
+/// One compact JSON document per item, for `--output jsonl`.
+fn render_json_lines<T: serde::Serialize>(items: impl IntoIterator<Item = T>) -> Vec<String> {
+    items
+        .into_iter()
+        .map(|item| serde_json::to_string(&item).expect("CLI output always serializes"))
+        .collect()
+}
+
+/// How `main.rs` prints a [`CommandResult`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub format: OutputFormat,
+    pub sizes: SizeFormat,
+    /// `vm list --state` values, echoed as a header comment in plain output.
+    pub state_filter: Vec<String>,
+    /// Color VM states; only honored for text output.
+    pub color: bool,
+}
+
+impl RenderOptions {
+    /// Options for a `vm` command: `--output` and `--no-color`, plus
+    /// `--bytes` on `vm info` and `--state` on `vm list`.
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let sizes = match matches.subcommand_matches("info") {
+            Some(info) if info.get_flag("bytes") => SizeFormat::Bytes,
+            _ => SizeFormat::Human,
+        };
+        Self {
+            format: OutputFormat::from_matches(matches),
+            sizes,
+            state_filter: matches
+                .subcommand_matches("list")
+                .and_then(|list| list.get_many::<String>("state"))
+                .map(|states| states.map(|state| state.to_lowercase()).collect())
+                .unwrap_or_default(),
+            color: use_color(
+                matches.get_flag("no-color"),
+                std::env::var_os("NO_COLOR").as_deref(),
+                std::io::stdout().is_terminal(),
+            ),
+        }
+    }
+}
+
+/// `--output` format of `vm` commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// Like `Text`, but `vm list` keeps the legacy ` | ` separated lines.
+    Plain,
+    Json,
+    /// JSON Lines: the same documents as `Json`, compact, with lists split
+    /// into one document per line.
+    JsonLines,
+}
+
+impl OutputFormat {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        match matches.get_one::<String>("output").map(String::as_str) {
+            Some("json") => Self::Json,
+            Some("jsonl") => Self::JsonLines,
+            Some("plain") => Self::Plain,
+            _ => Self::Text,
+        }
+    }
+
+    /// Whether commands should build JSON documents rather than lines.
+    pub fn is_json(self) -> bool {
+        matches!(self, Self::Json | Self::JsonLines)
+    }
+
+    /// Output for commands that change a VM: the human lines, or
+    /// `{"ok":true,"action":...,"name":...}`.
+    fn mutation(self, action: &str, name: &str, lines: Vec<String>) -> CommandResult {
+        match self {
+            Self::Text | Self::Plain => CommandResult::Lines(lines),
+            Self::Json | Self::JsonLines => CommandResult::Json(json!({
+                "ok": true,
+                "action": action,
+                "name": name,
+            })),
+        }
+    }
+}
+
+/// Asks the user to confirm a destructive operation.
+pub trait Confirm: Send + Sync {
+    /// Whether the user can be asked at all, i.e. stdin is a terminal.
+    fn is_interactive(&self) -> bool;
+
+    /// Shows `prompt` and returns whether the user agreed.
+    fn confirm(&self, prompt: &str) -> Result<bool>;
+}
+
+/// Prompts on stderr and reads the answer from stdin. Anything other than
+/// `y`/`yes` declines.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalConfirm;
+
+impl Confirm for TerminalConfirm {
+    fn is_interactive(&self) -> bool {
+        std::io::stdin().is_terminal()
+    }
+
+    fn confirm(&self, prompt: &str) -> Result<bool> {
+        eprint!("{prompt} [y/N] ");
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .context("failed to read confirmation")?;
+        Ok(matches!(
+            answer.trim().to_ascii_lowercase().as_str(),
+            "y" | "yes"
+        ))
+    }
+}
+
+/// Whether a destructive command may proceed: `--yes` skips the prompt, and
+/// without a terminal the command fails rather than guessing.
+fn confirm_destructive(matches: &ArgMatches, confirm: &dyn Confirm, prompt: &str) -> Result<bool> {
+    if matches.get_flag("yes") {
+        return Ok(true);
+    }
+    if !confirm.is_interactive() {
+        return Err(UsageError(
+            "refusing to continue without confirmation: stdin is not a terminal, pass --yes"
+                .to_owned(),
+        )
+        .into());
+    }
+    confirm.confirm(prompt)
+}
+
+pub async fn run_vm_subcommand(matches: &ArgMatches, api: &dyn VmApi) -> Result<CommandResult> {
+    run_vm_subcommand_with(matches, api, &TerminalConfirm).await
+}
+
+/// `run_vm_subcommand` with an injectable confirmation prompt.
+pub async fn run_vm_subcommand_with(
+    matches: &ArgMatches,
+    api: &dyn VmApi,
+    confirm: &dyn Confirm,
+) -> Result<CommandResult> {
+    let format = OutputFormat::from_matches(matches);
     match matches.subcommand() {
         Some(("launch", launch_matches)) => {
-            let name = required_arg(launch_matches, "name")?;
-            let result = handlers::launch_vm(api, name).await;
+            let spec = match launch_matches.get_one::<String>("spec") {
+                Some(source) => read_launch_spec(source).await?,
+                None => LaunchSpec {
+                    networks: launch_matches
+                        .get_many::<String>("network")
+                        .map(|networks| networks.cloned().collect())
+                        .unwrap_or_default(),
+                    cloud_init: launch_cloud_init(launch_matches)?,
+                    ..LaunchSpec::new(launch_name(api, launch_matches).await?)
+                },
+            };
+            let name = spec.name.clone();
+            let name = name.as_str();
+            let cancel = cancel_on_ctrl_c();
+            let _stop_listening = cancel.clone().drop_guard();
+            if let Some(&count) = launch_matches.get_one::<u32>("count").filter(|&&n| n > 1) {
+                // `agent-1 --count 3` would make agent-1-1..agent-1-3, and the
+                // plain agent-1 the user likely meant is never created.
+                if let Some((base, suffix)) = name.rsplit_once('-')
+                    && !suffix.is_empty()
+                    && suffix.bytes().all(|b| b.is_ascii_digit())
+                {
+                    return Err(UsageError(format!(
+                        "'{name}' already ends in a number; pass the base name with --count, \
+                         e.g. 'vm launch {base} --count {count}'"
+                    ))
+                    .into());
+                }
+                let specs: Vec<LaunchSpec> = numbered_vm_names(name, count)
+                    .into_iter()
+                    .map(|name| LaunchSpec {
+                        name,
+                        ..spec.clone()
+                    })
+                    .collect();
+                let wait = launch_matches
+                    .get_flag("wait")
+                    .then(|| ready_wait_options(launch_matches));
+                // Progress goes to stderr so `-o json` output stays parseable.
+                let progress = |result: &VmBatchResult| match &result.error {
+                    None => eprintln!("VM '{}' launched", result.name),
+                    Some(error) => eprintln!("VM '{}' failed to launch: {}", result.name, error),
+                };
+                let results = launch_vms(
+                    api,
+                    &specs,
+                    DEFAULT_LAUNCH_CONCURRENCY,
+                    wait.as_ref(),
+                    &cancel,
+                    &progress,
+                )
+                .await;
+                return batch_output(format, "launch", "launch", "launched", results);
+            }
+            // The progress line would garble piped or JSON output.
+            let result = if std::io::stdout().is_terminal() && !format.is_json() {
+                let message = LaunchMessage::default();
+                let on_progress = |line: &str| message.record(line);
+                let launch = handlers::launch_vm_with_progress(api, &spec, &on_progress, &cancel);
+                with_launch_messages(api, name, &TerminalProgress, &message, launch).await
+            } else {
+                handlers::launch_vm(api, &spec, &cancel).await
+            };
             if result.success {
-                Ok(vec![result.message])
+                let lines = finish_with_ready(launch_matches, api, name, result.message).await?;
+                Ok(format.mutation("launch", name, lines))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("start", start_matches)) => {
+            if let Some(pattern) = name_pattern(start_matches)? {
+                let names = matching_vm_names(api, &pattern).await?;
+                if names.is_empty() {
+                    return no_vms_matched(start_matches, &pattern);
+                }
+                let if_needed = start_matches.get_flag("if-needed");
+                let results = run_bulk(&names, "started", |name| async move {
+                    if if_needed {
+                        handlers::start_vm_if_needed(api, &name).await
+                    } else {
+                        handlers::start_vm(api, &name).await
+                    }
+                })
+                .await;
+                return batch_output(format, "start", "start", "started", results);
+            }
             let name = required_arg(start_matches, "name")?;
-            let result = handlers::start_vm(api, name).await;
+            let result = if start_matches.get_flag("if-needed") {
+                handlers::start_vm_if_needed(api, name).await
+            } else {
+                handlers::start_vm(api, name).await
+            };
             if result.success {
-                Ok(vec![result.message])
+                let lines = finish_with_ready(start_matches, api, name, result.message).await?;
+                Ok(format.mutation("start", name, lines))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("stop", stop_matches)) => {
+            let mut opts = StopOptions {
+                force: stop_matches.get_flag("force"),
+                ..StopOptions::default()
+            };
+            if let Some(secs) = stop_matches.get_one::<u64>("force-after") {
+                opts = opts.with_timeout(Duration::from_secs(*secs));
+            }
+            if let Some(pattern) = name_pattern(stop_matches)? {
+                let names = matching_vm_names(api, &pattern).await?;
+                if names.is_empty() {
+                    return no_vms_matched(stop_matches, &pattern);
+                }
+                let if_needed = stop_matches.get_flag("if-needed");
+                let opts = &opts;
+                let results = run_bulk(&names, "stopped", |name| async move {
+                    if if_needed {
+                        handlers::stop_vm_if_needed(api, &name, opts).await
+                    } else {
+                        handlers::stop_vm(api, &name, opts).await
+                    }
+                })
+                .await;
+                return batch_output(format, "stop", "stop", "stopped", results);
+            }
             let name = required_arg(stop_matches, "name")?;
-            let result = handlers::stop_vm(api, name).await;
+            let result = if stop_matches.get_flag("if-needed") {
+                handlers::stop_vm_if_needed(api, name, &opts).await
+            } else {
+                handlers::stop_vm(api, name, &opts).await
+            };
             if result.success {
-                Ok(vec![result.message])
+                let lines =
+                    finish_with_wait(stop_matches, api, name, VmState::Stopped, result.message)
+                        .await?;
+                Ok(format.mutation("stop", name, lines))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("restart", restart_matches)) => {
             let name = required_arg(restart_matches, "name")?;
             let result = handlers::restart_vm(api, name).await;
             if result.success {
-                Ok(vec![result.message])
+                let lines = finish_with_ready(restart_matches, api, name, result.message).await?;
+                Ok(format.mutation("restart", name, lines))
+            } else {
+                Err(result.into_error())
+            }
+        }
+        Some(("rename", rename_matches)) => {
+            let name = required_arg(rename_matches, "name")?;
+            let new_name = required_arg(rename_matches, "new-name")?;
+            let opts = RenameOptions {
+                force_stop: rename_matches.get_flag("force-stop"),
+            };
+            // Progress goes to stderr so `-o json` output stays parseable.
+            let progress = |step| eprintln!("{}", rename_progress_line(step, name, new_name));
+            let result = handlers::rename_vm(api, name, new_name, &opts, &progress).await;
+            if result.success {
+                Ok(format.mutation("rename", new_name, vec![result.message]))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("delete", delete_matches)) => {
-            let name = required_arg(delete_matches, "name")?;
+            let names: Vec<String> = match name_pattern(delete_matches)? {
+                Some(pattern) => {
+                    let names = matching_vm_names(api, &pattern).await?;
+                    if names.is_empty() {
+                        return no_vms_matched(delete_matches, &pattern);
+                    }
+                    names
+                }
+                None => delete_matches
+                    .get_many::<String>("name")
+                    .context("missing required argument: name")?
+                    .cloned()
+                    .collect(),
+            };
+            if let [_, _, ..] = names.as_slice() {
+                let prompt = format!(
+                    "Delete {} VMs ({})? This cannot be undone.",
+                    names.len(),
+                    names.join(", ")
+                );
+                if !confirm_destructive(delete_matches, confirm, &prompt)? {
+                    return Ok(CommandResult::Lines(vec![
+                        "Aborted; no VMs were deleted".to_owned(),
+                    ]));
+                }
+                let result = handlers::delete_vms(api, &names).await;
+                return if result.success {
+                    Ok(match format {
+                        OutputFormat::Text | OutputFormat::Plain => {
+                            CommandResult::Lines(vec![result.message])
+                        }
+                        OutputFormat::Json | OutputFormat::JsonLines => {
+                            CommandResult::Json(json!({
+                                "ok": true,
+                                "action": "delete",
+                                "names": names,
+                            }))
+                        }
+                    })
+                } else {
+                    Err(result.into_error())
+                };
+            }
+            let name = names[0].as_str();
+            let prompt = format!("Delete VM '{}'? This cannot be undone.", name);
+            if !confirm_destructive(delete_matches, confirm, &prompt)? {
+                return Ok(CommandResult::Lines(vec![format!(
+                    "Aborted; VM '{}' was not deleted",
+                    name
+                )]));
+            }
             let result = handlers::delete_vm(api, name).await;
             if result.success {
-                Ok(vec![result.message])
+                Ok(format.mutation("delete", name, vec![result.message]))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
+        Some(("wait", wait_matches)) => {
+            let name = required_arg(wait_matches, "name")?;
+            let target = VmState::from(required_arg(wait_matches, "state")?);
+            let opts = WaitOptions::default()
+                .with_timeout(Duration::from_secs(
+                    *wait_matches.get_one::<u64>("timeout").unwrap_or(&120),
+                ))
+                .with_interval(Duration::from_secs(
+                    *wait_matches.get_one::<u64>("interval").unwrap_or(&2),
+                ));
+            // State changes go to stderr so `-o json` output stays parseable.
+            let on_change = |state: &VmState| eprintln!("VM '{}' is {}", name, state);
+            if target == VmState::Deleted {
+                wait_for_deletion(api, name, &opts, &on_change).await?;
+            } else {
+                wait_for_state(api, name, &target, &opts, &on_change).await?;
+            }
+            Ok(match format {
+                OutputFormat::Text | OutputFormat::Plain => {
+                    CommandResult::Lines(vec![format!("VM '{}' reached {}", name, target)])
+                }
+                OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                    "ok": true,
+                    "action": "wait",
+                    "name": name,
+                    "state": target,
+                })),
+            })
+        }
         Some(("info", info_matches)) => {
+            if info_matches.get_flag("all") {
+                return Ok(CommandResult::Infos(info_all(api).await?));
+            }
             let name = required_arg(info_matches, "name")?;
+            if info_matches.get_flag("watch") {
+                if format.is_json() {
+                    return Err(UsageError(
+                        "--watch cannot be combined with --output json".to_owned(),
+                    )
+                    .into());
+                }
+                let sizes = RenderOptions::from_matches(matches).sizes;
+                let interval = watch_interval(info_matches);
+                let cancel = cancel_on_ctrl_c();
+                let _stop_listening = cancel.clone().drop_guard();
+                watch_vm_info(api, name, sizes, interval, &mut TerminalSink, &cancel).await?;
+                return Ok(CommandResult::Empty);
+            }
             let result = handlers::get_vm_info(api, name).await;
             if result.success {
-                if let Some(info) = result.data {
-                    Ok(format_vm_info(&info))
-                } else {
-                    Ok(vec![result.message])
+                Ok(match result.data {
+                    Some(info) => CommandResult::Info(Box::new(info)),
+                    None => CommandResult::Lines(vec![result.message]),
+                })
+            } else {
+                Err(result.into_error())
+            }
+        }
+        Some(("top", top_matches)) => {
+            if format.is_json() {
+                return Err(
+                    UsageError("vm top cannot be combined with --output json".to_owned()).into(),
+                );
+            }
+            let sort = match top_matches.get_one::<String>("sort").map(String::as_str) {
+                Some("disk") => TopSort::Disk,
+                Some("name") => TopSort::Name,
+                _ => TopSort::Memory,
+            };
+            let cancel = cancel_on_ctrl_c();
+            let _stop_listening = cancel.clone().drop_guard();
+            watch_vm_top(
+                api,
+                sort,
+                watch_interval(top_matches),
+                &mut TerminalSink,
+                &cancel,
+            )
+            .await?;
+            Ok(CommandResult::Empty)
+        }
+        Some(("ip", ip_matches)) => {
+            let name = required_arg(ip_matches, "name")?;
+            let info = if ip_matches.get_flag("wait") {
+                wait_for_ready(api, name, &ready_wait_options(ip_matches)).await?
+            } else {
+                let result = handlers::get_vm_info(api, name).await;
+                match result.data {
+                    Some(info) if result.success => info,
+                    _ => return Err(result.into_error()),
                 }
+            };
+            let mut addresses = info.ipv4.unwrap_or_default();
+            if addresses.is_empty() {
+                return Err(NoAddress(name.to_owned()).into());
+            }
+            if !ip_matches.get_flag("all") {
+                addresses.truncate(1);
+            }
+            Ok(match format {
+                OutputFormat::Text | OutputFormat::Plain => CommandResult::Lines(addresses),
+                OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                    "name": name,
+                    "ipv4": addresses,
+                })),
+            })
+        }
+        Some(("set", set_matches)) => {
+            let name = required_arg(set_matches, "name")?;
+            let key: VmProperty = required_arg(set_matches, "key")?.parse()?;
+            let value = required_arg(set_matches, "value")?;
+            let result = handlers::set_vm_property(api, name, key, value).await;
+            if result.success {
+                Ok(format.mutation("set", name, vec![result.message]))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
-        Some(("list", _)) => {
-            let result = handlers::list_vms(api).await;
+        Some(("get", get_matches)) => {
+            let name = required_arg(get_matches, "name")?;
+            let key: VmProperty = required_arg(get_matches, "key")?.parse()?;
+            let result = handlers::get_vm_property(api, name, key).await;
+            let value = match result.data {
+                Some(value) if result.success => value,
+                _ => return Err(result.into_error()),
+            };
+            Ok(match format {
+                OutputFormat::Text | OutputFormat::Plain => CommandResult::Lines(vec![value]),
+                OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                    "name": name,
+                    "key": key.as_str(),
+                    "value": value,
+                })),
+            })
+        }
+        Some(("logs", logs_matches)) => {
+            let name = required_arg(logs_matches, "name")?;
+            let lines = logs_matches
+                .get_one::<usize>("lines")
+                .copied()
+                .unwrap_or(DEFAULT_LOG_LINES);
+            let source: LogSource = logs_matches
+                .get_one::<String>("source")
+                .map_or(Ok(LogSource::default()), |source| source.parse())?;
+            ensure_running(api, name).await?;
+            if logs_matches.get_flag("follow") {
+                if format.is_json() {
+                    return Err(UsageError(
+                        "--follow prints raw lines and can't be combined with JSON output"
+                            .to_owned(),
+                    )
+                    .into());
+                }
+                let cancel = cancel_on_ctrl_c();
+                let _stop_listening = cancel.clone().drop_guard();
+                let print_line = |line: &str| println!("{line}");
+                let command = source.command(lines, true);
+                let end = follow_vm_logs(api, name, &command, &print_line, &cancel).await?;
+                if let LogFollowEnd::VmStopped(state) = end {
+                    eprintln!("VM '{}' is no longer running ({})", name, state);
+                }
+                return Ok(CommandResult::Lines(Vec::new()));
+            }
+            let result = handlers::vm_logs(api, name, source, lines).await;
             if result.success {
-                if let Some(vms) = result.data {
-                    if vms.is_empty() {
-                        Ok(vec!["No VMs found".to_string()])
-                    } else {
-                        Ok(vms.into_iter().map(|vm| format_vm_summary(&vm)).collect())
+                let lines: Vec<String> = result
+                    .data
+                    .unwrap_or_default()
+                    .lines()
+                    .map(String::from)
+                    .collect();
+                Ok(match format {
+                    OutputFormat::Text | OutputFormat::Plain => CommandResult::Lines(lines),
+                    OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                        "name": name,
+                        "lines": lines,
+                    })),
+                })
+            } else {
+                Err(result.into_error())
+            }
+        }
+        Some(("exec", exec_matches)) => {
+            let name = required_arg(exec_matches, "name")?;
+            if let Some(script) = exec_matches.get_one::<PathBuf>("script") {
+                if format.is_json() {
+                    return Err(UsageError(
+                        "--script streams raw output and can't be combined with JSON output"
+                            .to_owned(),
+                    )
+                    .into());
+                }
+                let opts = ScriptOptions {
+                    args: exec_matches
+                        .get_many::<String>("args")
+                        .map(|args| args.cloned().collect())
+                        .unwrap_or_default(),
+                    keep: exec_matches.get_flag("keep"),
+                };
+                let cancel = cancel_on_ctrl_c();
+                let _stop_listening = cancel.clone().drop_guard();
+                let print_line = |line: &str| println!("{line}");
+                let status = run_script(api, name, script, &opts, &print_line, &cancel).await?;
+                if status != 0 {
+                    return Err(RemoteExit(status).into());
+                }
+                return Ok(CommandResult::Lines(Vec::new()));
+            }
+            let command: Vec<String> = exec_matches
+                .get_many::<String>("command")
+                .context("missing required argument: command")?
+                .cloned()
+                .collect();
+            let stdin = if exec_matches.get_flag("stdin") {
+                let mut buffer = Vec::new();
+                tokio::io::stdin()
+                    .read_to_end(&mut buffer)
+                    .await
+                    .context("failed to read stdin")?;
+                Some(buffer)
+            } else {
+                None
+            };
+
+            let result = handlers::exec_in_vm(api, name, &command, stdin.as_deref()).await;
+            match result.data {
+                Some(output) if result.success => Ok(match format {
+                    OutputFormat::Text | OutputFormat::Plain if output.status_code != 0 => {
+                        // The status is the error, so print the output here.
+                        print!("{}", output.stdout);
+                        eprint!("{}", output.stderr);
+                        return Err(RemoteExit(output.status_code).into());
                     }
-                } else {
-                    Ok(vec![result.message])
+                    OutputFormat::Text | OutputFormat::Plain => {
+                        CommandResult::Lines(output.stdout.lines().map(String::from).collect())
+                    }
+                    OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                        "name": name,
+                        "status_code": output.status_code,
+                        "stdout": output.stdout,
+                        "stderr": output.stderr,
+                    })),
+                }),
+                _ => Err(result.into_error()),
+            }
+        }
+        Some(("shell", shell_matches)) => {
+            let name = required_arg(shell_matches, "name")?;
+            if format.is_json() {
+                return Err(UsageError(
+                    "shell is interactive and can't be combined with JSON output".to_owned(),
+                )
+                .into());
+            }
+            let status = api.shell(name).await?;
+            if status != 0 {
+                return Err(RemoteExit(status).into());
+            }
+            Ok(CommandResult::Lines(Vec::new()))
+        }
+        Some(("list", list_matches)) => {
+            let tags: Vec<String> = list_matches
+                .get_many::<String>("tag")
+                .map(|tags| tags.cloned().collect())
+                .unwrap_or_default();
+            let pattern = name_pattern(list_matches)?;
+            if list_matches.get_flag("watch") {
+                if format.is_json() {
+                    return Err(UsageError(
+                        "--watch cannot be combined with --output json".to_owned(),
+                    )
+                    .into());
+                }
+                let interval = watch_interval(list_matches);
+                let cancel = cancel_on_ctrl_c();
+                let _stop_listening = cancel.clone().drop_guard();
+                watch_vm_list(api, &tags, interval, &mut TerminalSink, &cancel).await?;
+                return Ok(CommandResult::Empty);
+            }
+            let result = if tags.is_empty() {
+                handlers::list_vms(api).await
+            } else {
+                handlers::list_vms_tagged(api, &tags).await
+            };
+            if result.success {
+                let VmListing { mut vms, warnings } = result.data.unwrap_or_default();
+                // On stderr, so `--output json` stays a plain array.
+                for warning in &warnings {
+                    eprintln!("warning: {}", warning);
+                }
+                let states: Vec<VmState> = list_matches
+                    .get_many::<String>("state")
+                    .map(|states| states.map(|state| VmState::from(state.as_str())).collect())
+                    .unwrap_or_default();
+                if !states.is_empty() {
+                    vms.retain(|vm| states.contains(&vm.state));
+                }
+                if let Some(pattern) = &pattern {
+                    vms = pattern.filter(vms);
+                    // JSON and plain output stay an empty list for scripts.
+                    if vms.is_empty() && format == OutputFormat::Text {
+                        return Ok(CommandResult::Lines(vec![format!(
+                            "No VMs matched '{}'",
+                            pattern.as_str()
+                        )]));
+                    }
+                }
+                match list_matches.get_one::<String>("sort").map(String::as_str) {
+                    Some("name") => vms.sort_by(|a, b| a.name.cmp(&b.name)),
+                    Some("state") => vms.sort_by(|a, b| {
+                        (a.state.to_string(), &a.name).cmp(&(b.state.to_string(), &b.name))
+                    }),
+                    _ => {}
                 }
+                Ok(CommandResult::Summaries(vms))
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
-        _ => Ok(Vec::new()),
+        Some((action @ ("tag" | "untag"), tag_matches)) => {
+            let name = required_arg(tag_matches, "name")?;
+            let tags: Vec<String> = tag_matches
+                .get_many::<String>("tags")
+                .context("missing required argument: tags")?
+                .cloned()
+                .collect();
+            let result = if action == "tag" {
+                handlers::tag_vm(api, name, &tags).await
+            } else {
+                handlers::untag_vm(api, name, &tags).await
+            };
+            match result.data {
+                Some(tags) if result.success => Ok(match format {
+                    OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                        "ok": true,
+                        "action": action,
+                        "name": name,
+                        "tags": tags,
+                    })),
+                    OutputFormat::Text | OutputFormat::Plain if tags.is_empty() => {
+                        CommandResult::Lines(vec![format!("VM '{}' has no tags", name)])
+                    }
+                    OutputFormat::Text | OutputFormat::Plain => {
+                        CommandResult::Lines(vec![format!(
+                            "VM '{}' tags: {}",
+                            name,
+                            tags.join(", ")
+                        )])
+                    }
+                }),
+                _ => Err(result.into_error()),
+            }
+        }
+        Some(("networks", _)) => {
+            let result = handlers::list_networks(api).await;
+            if result.success {
+                let networks = result.data.unwrap_or_default();
+                match format {
+                    OutputFormat::Json | OutputFormat::JsonLines => {
+                        Ok(CommandResult::Json(serde_json::to_value(networks)?))
+                    }
+                    _ if networks.is_empty() => {
+                        Ok(CommandResult::Lines(vec!["No networks found".to_string()]))
+                    }
+                    OutputFormat::Text | OutputFormat::Plain => Ok(CommandResult::Lines(
+                        networks
+                            .iter()
+                            .map(|network| {
+                                format!(
+                                    "{} | {} | {}",
+                                    network.name, network.kind, network.description
+                                )
+                            })
+                            .collect(),
+                    )),
+                }
+            } else {
+                Err(result.into_error())
+            }
+        }
+        Some(("images", _)) => {
+            let result = handlers::list_images(api).await;
+            if result.success {
+                let images = result.data.unwrap_or_default();
+                match format {
+                    OutputFormat::Json | OutputFormat::JsonLines => {
+                        Ok(CommandResult::Json(serde_json::to_value(images)?))
+                    }
+                    _ if images.is_empty() => {
+                        Ok(CommandResult::Lines(vec!["No images found".to_string()]))
+                    }
+                    OutputFormat::Text | OutputFormat::Plain => Ok(CommandResult::Lines(
+                        images
+                            .iter()
+                            .map(|image| {
+                                format!(
+                                    "{} | {} | {} | {}",
+                                    image.alias, image.kind, image.version, image.description
+                                )
+                            })
+                            .collect(),
+                    )),
+                }
+            } else {
+                Err(result.into_error())
+            }
+        }
+        Some(("prune", prune_matches)) => {
+            let selection = PruneSelection {
+                prefix: prune_matches
+                    .get_one::<String>("prefix")
+                    .cloned()
+                    .unwrap_or_default(),
+                include_suspended: prune_matches.get_flag("include-suspended"),
+                any_state: prune_matches.get_flag("all"),
+            };
+            let candidates: Vec<VmSummary> = api
+                .list()
+                .await
+                .context("failed to list VMs")?
+                .into_iter()
+                .filter(|vm| selection.matches(vm))
+                .collect();
+            if candidates.is_empty() {
+                return Ok(CommandResult::Lines(vec!["No VMs to prune".to_owned()]));
+            }
+            let names: Vec<String> = candidates.iter().map(|vm| vm.name.clone()).collect();
+
+            if prune_matches.get_flag("dry-run") {
+                return Ok(match format {
+                    OutputFormat::Text | OutputFormat::Plain => CommandResult::Lines(
+                        std::iter::once(format!("Would delete {} VM(s):", names.len()))
+                            .chain(
+                                candidates
+                                    .iter()
+                                    .map(|vm| format!("  {} ({})", vm.name, vm.state)),
+                            )
+                            .collect(),
+                    ),
+                    OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+                        "ok": true,
+                        "action": "prune",
+                        "dry_run": true,
+                        "candidates": names,
+                    })),
+                });
+            }
+
+            let prompt = format!(
+                "Delete {} VM(s) ({})? This cannot be undone.",
+                names.len(),
+                names.join(", ")
+            );
+            if !confirm_destructive(prune_matches, confirm, &prompt)? {
+                return Ok(CommandResult::Lines(vec![
+                    "Aborted; no VMs were deleted".to_owned(),
+                ]));
+            }
+
+            // Progress goes to stderr so `-o json` output stays parseable.
+            let progress = |result: &VmBatchResult| match &result.error {
+                None => eprintln!("VM '{}' deleted", result.name),
+                Some(error) => eprintln!("VM '{}' failed to delete: {}", result.name, error),
+            };
+            let results = prune_vms(api, &names, DEFAULT_PRUNE_CONCURRENCY, &progress).await;
+            batch_output(format, "prune", "delete", "removed", results)
+        }
+        Some(("apply", apply_matches)) => {
+            let manifest = Manifest::load(required_arg(apply_matches, "manifest")?)?;
+            let cancel = cancel_on_ctrl_c();
+            let _stop_listening = cancel.clone().drop_guard();
+            let concurrency = apply_matches
+                .get_one::<u32>("concurrency")
+                .map_or_else(default_apply_concurrency, |n| *n as usize);
+            // Progress goes to stderr so `-o json` output stays parseable.
+            let progress = |result: &ApplyResult| eprintln!("{} | {}", result.name, result.outcome);
+            let results = apply_manifest(api, &manifest, concurrency, &cancel, &progress).await?;
+            let failures: Vec<String> = results
+                .iter()
+                .filter_map(|result| match &result.outcome {
+                    ApplyOutcome::Failed(reason) => Some(format!("{}: {}", result.name, reason)),
+                    _ => None,
+                })
+                .collect();
+            if !failures.is_empty() {
+                anyhow::bail!(
+                    "applied {} of {} VMs; failed to launch {}",
+                    results.len() - failures.len(),
+                    results.len(),
+                    failures.join("; ")
+                );
+            }
+            Ok(match format {
+                OutputFormat::Text | OutputFormat::Plain => CommandResult::Lines(
+                    results
+                        .into_iter()
+                        .map(|result| format!("{} | {}", result.name, result.outcome))
+                        .collect(),
+                ),
+                OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(Value::Array(
+                    results
+                        .into_iter()
+                        .map(|result| {
+                            json!({
+                                "name": result.name,
+                                "outcome": result.outcome.to_string(),
+                            })
+                        })
+                        .collect(),
+                )),
+            })
+        }
+        _ => Ok(CommandResult::Empty),
+    }
+}
+
+/// The `--name` glob given to a command, if any. A malformed glob is a
+/// usage error.
+fn name_pattern(matches: &ArgMatches) -> Result<Option<NamePattern>> {
+    matches
+        .get_one::<String>("name-pattern")
+        .map(|pattern| NamePattern::new(pattern).map_err(|e| UsageError(e.to_string()).into()))
+        .transpose()
+}
+
+/// Names of the VMs matching `pattern`, in multipass list order.
+async fn matching_vm_names(api: &dyn VmApi, pattern: &NamePattern) -> Result<Vec<String>> {
+    let vms = api.list().await.context("failed to list VMs")?;
+    Ok(pattern.filter(vms).into_iter().map(|vm| vm.name).collect())
+}
+
+/// Outcome of a bulk `--name` command whose glob matched nothing: an error,
+/// so scripts notice a mistyped pattern, unless `--allow-empty` was given.
+fn no_vms_matched(matches: &ArgMatches, pattern: &NamePattern) -> Result<CommandResult> {
+    if matches.get_flag("allow-empty") {
+        Ok(CommandResult::Lines(vec![format!(
+            "No VMs matched '{}'",
+            pattern.as_str()
+        )]))
+    } else {
+        anyhow::bail!("no VMs matched '{}'", pattern.as_str())
+    }
+}
+
+/// Runs `op` for each VM in turn, reporting every outcome on stderr so
+/// `-o json` output stays parseable. A failure does not stop the rest.
+async fn run_bulk<F, Fut>(names: &[String], done: &str, op: F) -> Vec<VmBatchResult>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = HandlerResult<()>>,
+{
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        let result = op(name.clone()).await;
+        let error = (!result.success).then_some(result.message);
+        match &error {
+            None => eprintln!("VM '{}' {}", name, done),
+            Some(error) => eprintln!("{}", error),
+        }
+        results.push(VmBatchResult {
+            name: name.clone(),
+            error,
+        });
+    }
+    results
+}
+
+/// Summarizes a batch command: the names handled, or an error listing every
+/// VM that failed. `verb` names the per-VM operation (`delete`) and `done`
+/// its past tense (`removed`), which is also the JSON key for the names.
+fn batch_output(
+    format: OutputFormat,
+    action: &str,
+    verb: &str,
+    done: &str,
+    results: Vec<VmBatchResult>,
+) -> Result<CommandResult> {
+    let total = results.len();
+    let (succeeded, failed): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .partition(|result| result.error.is_none());
+    let names: Vec<String> = succeeded.into_iter().map(|result| result.name).collect();
+    if !failed.is_empty() {
+        let failures: Vec<String> = failed
+            .iter()
+            .map(|result| {
+                format!(
+                    "{}: {}",
+                    result.name,
+                    result.error.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+        anyhow::bail!(
+            "{} {} of {} VMs; failed to {} {}",
+            done,
+            names.len(),
+            total,
+            verb,
+            failures.join("; ")
+        );
+    }
+    Ok(match format {
+        OutputFormat::Text | OutputFormat::Plain => CommandResult::Lines(vec![format!(
+            "{}{} {} VM(s): {}",
+            done[..1].to_uppercase(),
+            &done[1..],
+            names.len(),
+            names.join(", ")
+        )]),
+        OutputFormat::Json | OutputFormat::JsonLines => CommandResult::Json(json!({
+            "ok": true,
+            "action": action,
+            done: names,
+        })),
+    })
+}
+
+/// Completes a lifecycle command, polling until `target` is reached when
+/// `--wait` was passed.
+async fn finish_with_wait(
+    matches: &ArgMatches,
+    api: &dyn VmApi,
+    name: &str,
+    target: VmState,
+    message: String,
+) -> Result<Vec<String>> {
+    if !matches.get_flag("wait") {
+        return Ok(vec![message]);
+    }
+
+    let timeout = Duration::from_secs(*matches.get_one::<u64>("timeout").unwrap_or(&120));
+    let opts = WaitOptions::default()
+        .with_timeout(timeout)
+        .with_interval(WAIT_POLL_INTERVAL);
+    wait_for_state(api, name, &target, &opts, &|_| {}).await?;
+    Ok(vec![message, format!("VM '{}' is {}", name, target)])
+}
+
+/// Wait settings from `--wait-timeout`.
+fn ready_wait_options(matches: &ArgMatches) -> WaitOptions {
+    let timeout = *matches
+        .get_one::<u64>("wait-timeout")
+        .unwrap_or(&DEFAULT_WAIT_TIMEOUT_SECS);
+    WaitOptions::default().with_timeout(Duration::from_secs(timeout))
+}
+
+/// Completes `launch`, `start` or `restart`, waiting until the VM is ready
+/// when `--wait` was passed.
+async fn finish_with_ready(
+    matches: &ArgMatches,
+    api: &dyn VmApi,
+    name: &str,
+    message: String,
+) -> Result<Vec<String>> {
+    if !matches.get_flag("wait") {
+        return Ok(vec![message]);
+    }
+
+    let info = wait_for_ready(api, name, &ready_wait_options(matches)).await?;
+    let ipv4 = info.ipv4.unwrap_or_default().join(", ");
+    Ok(vec![message, format!("VM '{}' is ready at {}", name, ipv4)])
+}
+
+/// Renders the man page for `safepaw` or one of its subcommands (e.g.
+/// `["vm", "launch"]`, titled `safepaw-vm-launch`) as roff.
+pub fn render_man_page(path: &[&str]) -> Result<Vec<u8>> {
+    let mut cmd = build_cli();
+    cmd.build();
+
+    let mut name = cmd.get_name().to_owned();
+    for part in path {
+        cmd = cmd
+            .find_subcommand(part)
+            .cloned()
+            .ok_or_else(|| UsageError(format!("no such command: {name} {part}")))?;
+        name = format!("{name}-{part}");
+    }
+
+    let mut page = Vec::new();
+    let title = name.to_uppercase();
+    clap_mangen::Man::new(cmd.name(name))
+        .title(title)
+        .render(&mut page)
+        .context("failed to render man page")?;
+    Ok(page)
+}
+
+/// Writes `safepaw.1`, `safepaw-vm.1` and one page per `vm` subcommand
+/// (`safepaw-vm-launch.1`, ...) into `out_dir`, creating it if needed.
+/// Returns the paths written, in that order.
+pub fn write_man_pages(out_dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("failed to create {}", out_dir.display()))?;
+
+    let cli = build_cli();
+    let vm = cli
+        .find_subcommand("vm")
+        .expect("vm subcommand is always defined");
+    let mut paths: Vec<Vec<&str>> = vec![vec![], vec!["vm"]];
+    paths.extend(
+        vm.get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(|sub| vec!["vm", sub.get_name()]),
+    );
+
+    let mut written = Vec::with_capacity(paths.len());
+    for path in paths {
+        let file_name = std::iter::once(cli.get_name())
+            .chain(path.iter().copied())
+            .collect::<Vec<_>>()
+            .join("-");
+        let file = out_dir.join(format!("{file_name}.1"));
+        std::fs::write(&file, render_man_page(&path)?)
+            .with_context(|| format!("failed to write {}", file.display()))?;
+        written.push(file);
+    }
+    Ok(written)
+}
+
+/// VM names offered when completing a VM argument. Completion must never
+/// break the shell, so failures and timeouts simply yield no names.
+pub async fn complete_vm_names(api: &dyn VmApi) -> Vec<String> {
+    match tokio::time::timeout(COMPLETION_TIMEOUT, api.list()).await {
+        Ok(Ok(vms)) => vms.into_iter().map(|vm| vm.name).collect(),
+        _ => Vec::new(),
     }
 }
 
@@ -427,7 +2833,7 @@ pub async fn run_agent_subcommand(
             if result.success {
                 Ok(vec![result.message])
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("onboard", onboard_matches)) => {
@@ -465,7 +2871,7 @@ pub async fn run_agent_subcommand(
                     Ok(vec![result.message])
                 }
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("list", list_matches)) => {
@@ -485,7 +2891,7 @@ pub async fn run_agent_subcommand(
                     Ok(vec![result.message])
                 }
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("get", get_matches)) => {
@@ -499,7 +2905,7 @@ pub async fn run_agent_subcommand(
                     Ok(vec![result.message])
                 }
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("stop", stop_matches)) => {
@@ -509,7 +2915,7 @@ pub async fn run_agent_subcommand(
             if result.success {
                 Ok(vec![result.message])
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("delete", delete_matches)) => {
@@ -519,7 +2925,7 @@ pub async fn run_agent_subcommand(
             if result.success {
                 Ok(vec![result.message])
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         Some(("check", check_matches)) => {
@@ -531,7 +2937,7 @@ pub async fn run_agent_subcommand(
             if result.success {
                 Ok(vec![result.message])
             } else {
-                Err(anyhow::anyhow!(result.message))
+                Err(result.into_error())
             }
         }
         _ => Ok(Vec::new()),
@@ -541,7 +2947,7 @@ pub async fn run_agent_subcommand(
 fn parse_agent_type(s: &str) -> Result<AgentType> {
     match s {
         "picoclaw" => Ok(AgentType::Picoclaw),
-        _ => bail!("unsupported agent type: {}", s),
+        _ => Err(UsageError(format!("unsupported agent type: {}", s)).into()),
     }
 }
 
@@ -584,6 +2990,463 @@ fn format_agent_instance(agent: &AgentInstance) -> Vec<String> {
     lines
 }
 
+/// Where watch mode draws its frames.
+pub trait RenderSink: Send {
+    fn render(&mut self, frame: &[String]) -> Result<()>;
+}
+
+/// Clears the terminal and draws each frame from the top-left corner.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalSink;
+
+impl RenderSink for TerminalSink {
+    fn render(&mut self, frame: &[String]) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        write!(stdout, "\x1b[2J\x1b[H")?;
+        for line in frame {
+            writeln!(stdout, "{line}")?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Where `vm launch` reports progress while multipass works.
+pub trait ProgressSink: Send + Sync {
+    /// Replaces the current progress line.
+    fn update(&self, line: &str);
+    /// Clears the progress line once the operation is over.
+    fn finish(&self);
+}
+
+/// Redraws the progress line in place on stderr.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalProgress;
+
+impl ProgressSink for TerminalProgress {
+    fn update(&self, line: &str) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{line}");
+        let _ = stderr.flush();
+    }
+
+    fn finish(&self) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+    }
+}
+
+/// How often the launch progress line is redrawn and the VM polled.
+pub const LAUNCH_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Awaits `launch`, pushing a heartbeat line to `sink` every
+/// `LAUNCH_PROGRESS_INTERVAL` with the elapsed time and the last state
+/// multipass reported for `name`. Until the instance exists (e.g. while
+/// the image downloads) only the elapsed time is shown.
+pub async fn with_launch_progress<F: Future>(
+    api: &dyn VmApi,
+    name: &str,
+    sink: &dyn ProgressSink,
+    launch: F,
+) -> F::Output {
+    with_launch_messages(api, name, sink, &LaunchMessage::default(), launch).await
+}
+
+/// The latest progress line a streaming launch reported.
+#[derive(Debug, Default)]
+pub struct LaunchMessage(std::sync::Mutex<Option<String>>);
+
+impl LaunchMessage {
+    pub fn record(&self, line: &str) {
+        *self.lock() = Some(line.trim().to_owned());
+    }
+
+    pub fn latest(&self) -> Option<String> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<String>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Like `with_launch_progress`, but once `message` has recorded a line
+/// from multipass, the heartbeat shows that instead of polling `info`.
+pub async fn with_launch_messages<F: Future>(
+    api: &dyn VmApi,
+    name: &str,
+    sink: &dyn ProgressSink,
+    message: &LaunchMessage,
+    launch: F,
+) -> F::Output {
+    let started = tokio::time::Instant::now();
+    let mut ticker = tokio::time::interval(LAUNCH_PROGRESS_INTERVAL);
+    let mut frames = SPINNER_FRAMES.iter().cycle();
+    let mut last_state = None;
+    tokio::pin!(launch);
+    let output = loop {
+        tokio::select! {
+            biased;
+            output = &mut launch => break output,
+            _ = ticker.tick() => {
+                let latest = message.latest();
+                if latest.is_none()
+                    && let Ok(info) = api.info(name).await
+                {
+                    last_state = Some(info.state.to_string());
+                }
+                let frame = frames.next().copied().unwrap_or('|');
+                let mut line = format!(
+                    "{} Launching '{}' ({}s)",
+                    frame,
+                    name,
+                    started.elapsed().as_secs()
+                );
+                if let Some(status) = latest.as_ref().or(last_state.as_ref()) {
+                    line.push_str(&format!(" - {}", status));
+                }
+                sink.update(&line);
+            }
+        }
+    };
+    sink.finish();
+    output
+}
+
+/// How often `vm logs --follow` checks that the VM is still running.
+pub const LOG_FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Why `follow_vm_logs` stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogFollowEnd {
+    /// `cancel` fired, e.g. on Ctrl+C.
+    Interrupted,
+    /// The VM left the Running state.
+    VmStopped(VmState),
+    /// The log command exited on its own.
+    Finished,
+}
+
+/// Fails unless `name` is Running, since there is nothing to exec into.
+async fn ensure_running(api: &dyn VmApi, name: &str) -> Result<()> {
+    let info = api.info(name).await?;
+    if info.state != VmState::Running {
+        anyhow::bail!(
+            "VM '{}' is not running (state: {}); start it with `safepaw vm start {}`",
+            name,
+            info.state,
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Streams `command`'s output from `name` to `on_line` until `cancel`
+/// fires, the VM stops (checked every `LOG_FOLLOW_POLL_INTERVAL`) or the
+/// command exits.
+pub async fn follow_vm_logs(
+    api: &dyn VmApi,
+    name: &str,
+    command: &[String],
+    on_line: &LineSink<'_>,
+    cancel: &CancellationToken,
+) -> Result<LogFollowEnd> {
+    let stream_cancel = cancel.child_token();
+    let stream = api.exec_streaming(name, command, on_line, &stream_cancel);
+    tokio::pin!(stream);
+    let watch_state = async {
+        let mut ticker = tokio::time::interval(LOG_FOLLOW_POLL_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Ok(info) = api.info(name).await
+                && info.state != VmState::Running
+            {
+                return info.state;
+            }
+        }
+    };
+
+    let (result, stopped) = tokio::select! {
+        result = &mut stream => (result, None),
+        state = watch_state => {
+            // Let the stream kill the exec before returning.
+            stream_cancel.cancel();
+            (stream.await, Some(state))
+        }
+    };
+    if cancel.is_cancelled() {
+        return Ok(LogFollowEnd::Interrupted);
+    }
+    if let Some(state) = stopped {
+        return Ok(LogFollowEnd::VmStopped(state));
+    }
+    let result = result.and_then(|output| {
+        if output.status_code != 0 {
+            anyhow::bail!(
+                "'{}' exited with status {}: {}",
+                command.join(" "),
+                output.status_code,
+                output.stderr.trim()
+            );
+        }
+        Ok(output)
+    });
+    match result {
+        Ok(_) => Ok(LogFollowEnd::Finished),
+        // Shutting down the VM also ends the exec session with an error.
+        Err(err) => match api.info(name).await {
+            Ok(info) if info.state != VmState::Running => Ok(LogFollowEnd::VmStopped(info.state)),
+            _ => Err(err),
+        },
+    }
+}
+
+fn watch_interval(matches: &ArgMatches) -> Duration {
+    Duration::from_secs(*matches.get_one::<u64>("interval").unwrap_or(&2))
+}
+
+/// Redraws `vm info` every `interval` until `cancel` fires.
+pub async fn watch_vm_info(
+    api: &dyn VmApi,
+    name: &str,
+    sizes: SizeFormat,
+    interval: Duration,
+    sink: &mut dyn RenderSink,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    while !cancel.is_cancelled() {
+        let info = api.info(name).await?;
+        sink.render(&format_vm_info(&info, sizes, false))?;
+        tokio::select! {
+            _ = cancel.cancelled() => {}
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Redraws the `vm top` table every `interval` until `cancel` fires. When
+/// multipass fails, the last good table stays up with a stale marker.
+pub async fn watch_vm_top(
+    api: &dyn VmApi,
+    sort: TopSort,
+    interval: Duration,
+    sink: &mut dyn RenderSink,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let mut last_good: Option<Vec<VmStatusResponse>> = None;
+
+    while !cancel.is_cancelled() {
+        let frame = match info_all(api).await {
+            Ok(infos) => {
+                let frame = render_top_table(&infos, sort);
+                last_good = Some(infos);
+                frame
+            }
+            Err(err) => {
+                let mut frame = match &last_good {
+                    Some(infos) => render_top_table(infos, sort),
+                    None => Vec::new(),
+                };
+                frame.push(format!("[stale] {:#}", err));
+                frame
+            }
+        };
+        sink.render(&frame)?;
+
+        tokio::select! {
+            _ = cancel.cancelled() => {}
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Redraws the `vm list` table every `interval` until `cancel` fires.
+///
+/// Rows whose state changed since the previous poll are highlighted. When
+/// multipass fails, the last good table stays up with a stale marker.
+pub async fn watch_vm_list(
+    api: &dyn VmApi,
+    tags: &[String],
+    interval: Duration,
+    sink: &mut dyn RenderSink,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let mut last_good: Option<Vec<VmSummary>> = None;
+    let mut previous_states: Option<HashMap<String, VmState>> = None;
+
+    while !cancel.is_cancelled() {
+        let result = if tags.is_empty() {
+            handlers::list_vms(api).await
+        } else {
+            handlers::list_vms_tagged(api, tags).await
+        };
+
+        let frame = match result.data {
+            Some(VmListing { vms, .. }) if result.success => {
+                let frame = render_watch_table(&vms, previous_states.as_ref());
+                previous_states = Some(
+                    vms.iter()
+                        .map(|vm| (vm.name.clone(), vm.state.clone()))
+                        .collect(),
+                );
+                last_good = Some(vms);
+                frame
+            }
+            _ => {
+                let mut frame = match &last_good {
+                    Some(vms) => render_watch_table(vms, None),
+                    None => Vec::new(),
+                };
+                frame.push(format!("[stale] {}", result.message));
+                frame
+            }
+        };
+        sink.render(&frame)?;
+
+        tokio::select! {
+            _ = cancel.cancelled() => {}
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+    Ok(())
+}
+
+/// `render_table` with rows whose state differs from `previous` shown in
+/// reverse video.
+fn render_watch_table(
+    vms: &[VmSummary],
+    previous: Option<&HashMap<String, VmState>>,
+) -> Vec<String> {
+    if vms.is_empty() {
+        return vec!["No VMs found".to_string()];
+    }
+
+    let mut lines = render_table(vms.to_vec());
+    if let Some(previous) = previous {
+        // Row `i + 1` of the table is `vms[i]`, after the header.
+        for (vm, line) in vms.iter().zip(lines.iter_mut().skip(1)) {
+            if previous.get(&vm.name) != Some(&vm.state) {
+                *line = format!("{WATCH_HIGHLIGHT}{line}{WATCH_RESET}");
+            }
+        }
+    }
+    lines
+}
+
+/// Returns a token that is cancelled when the user presses Ctrl+C. Cancelling
+/// the token yourself (or dropping a guard for it) stops the listener.
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let listener = cancel.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            result = signal::ctrl_c() => {
+                if result.is_ok() {
+                    listener.cancel();
+                }
+            }
+            _ = listener.cancelled() => {}
+        }
+    });
+    cancel
+}
+
+/// Human description of a `vm rename` step, printed as it starts.
+pub fn rename_progress_line(step: RenameStep, name: &str, new_name: &str) -> String {
+    match step {
+        RenameStep::Stopping => format!("Stopping VM '{}'...", name),
+        RenameStep::Cloning => format!("Cloning VM '{}' to '{}'...", name, new_name),
+        RenameStep::Verifying => format!("Verifying clone '{}'...", new_name),
+        RenameStep::DeletingOriginal => format!("Deleting original VM '{}'...", name),
+    }
+}
+
+/// Parses and validates a `LaunchSpec` JSON object, as given to
+/// `vm launch --spec`. Anything wrong with it is a usage error.
+pub fn parse_launch_spec(json: &str) -> Result<LaunchSpec> {
+    let spec: LaunchSpec = serde_json::from_str(json)
+        .map_err(|err| UsageError(format!("invalid launch spec: {}", err)))?;
+    spec.validate()
+        .map_err(|err| UsageError(format!("invalid launch spec: {}", err)))?;
+    Ok(spec)
+}
+
+/// Reads `--spec`'s JSON from stdin for `-`, otherwise from the named file.
+async fn read_launch_spec(source: &str) -> Result<LaunchSpec> {
+    let json = if source == "-" {
+        let mut json = String::new();
+        tokio::io::stdin()
+            .read_to_string(&mut json)
+            .await
+            .context("failed to read the launch spec from stdin")?;
+        json
+    } else {
+        tokio::fs::read_to_string(source)
+            .await
+            .with_context(|| format!("failed to read launch spec {}", source))?
+    };
+    parse_launch_spec(&json)
+}
+
+/// The cloud-config for `--timezone`, `--package` and `--run-cmd`, or
+/// `None` when none of them was given.
+fn launch_cloud_init(matches: &ArgMatches) -> Result<Option<String>> {
+    let many = |id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+    let config = CloudConfig {
+        timezone: matches.get_one::<String>("timezone").cloned(),
+        packages: many("package"),
+        run_cmds: many("run-cmd"),
+    };
+    if config.is_empty() {
+        return Ok(None);
+    }
+    config
+        .to_yaml()
+        .map(Some)
+        .map_err(|err| UsageError(format!("invalid cloud-init option: {err}")).into())
+}
+
+/// The positional name, or with `--auto`/`--name-prefix` a generated name
+/// no existing VM uses, announced on stderr.
+async fn launch_name(api: &dyn VmApi, matches: &ArgMatches) -> Result<String> {
+    let prefix = match matches.get_one::<String>("name-prefix") {
+        Some(prefix) => prefix.as_str(),
+        None if matches.get_flag("auto") => DEFAULT_NAME_PREFIX,
+        None => return required_arg(matches, "name").map(str::to_owned),
+    };
+    // Any valid suffix works here; this only checks the prefix.
+    if validate_vm_name(&format!("{prefix}-a")).is_err() {
+        return Err(UsageError(format!(
+            "invalid --name-prefix '{}': use lowercase letters, digits and hyphens, starting with a letter",
+            prefix
+        ))
+        .into());
+    }
+    let taken: Vec<String> = api.list().await?.into_iter().map(|vm| vm.name).collect();
+    let name = generate_vm_name(prefix, &taken, &mut rand::rng());
+    eprintln!("Generated VM name '{}'", name);
+    Ok(name)
+}
+
+/// Value parser for VM name arguments, so a name multipass would refuse
+/// is a usage error before anything runs, just as the API answers 422.
+fn vm_name(value: &str) -> Result<String, NameError> {
+    validate_vm_name(value).map(|()| value.to_owned())
+}
+
 fn required_arg<'a>(matches: &'a ArgMatches, name: &str) -> Result<&'a str> {
     matches
         .get_one::<String>(name)
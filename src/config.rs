@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::rate_limit::RateLimits;
+use crate::util::parse_size;
+use crate::vm::{LaunchSpec, validate_vm_name};
+
+/// Log levels accepted by `[log] level`.
+const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+/// What `safepaw config init` writes: every setting at its built-in default,
+/// with comments explaining each one.
+pub const DEFAULT_CONFIG_TOML: &str = r#"# SafePaw configuration.
+#
+# Every setting is optional; the values below are the built-in defaults.
+# Command-line flags and RUST_LOG take precedence over this file.
+
+[server]
+# Address the UI and API servers bind to (`safepaw start --host`).
+host = "0.0.0.0"
+# Port for the web UI (`--ui-port`).
+ui_port = 8888
+# Port for the REST API (`--api-port`).
+api_port = 8889
+# Serve the UI and the API (under /api) together on ui_port (`--single-port`).
+single_port = false
+# Run every launch through the API in the background and answer with a job
+# to poll at /jobs/<id>, as if it asked for `?async=true` (`--async-launch`).
+async_launch = false
+# Per-client limits on API requests, as `CLASS=N/UNIT` pairs for launches
+# (POST /vms and DELETE /vms/<name>), other mutations and reads. Clients
+# over a limit get a 429 (`--rate-limit`). Off unless set.
+# rate_limit = "launches=2/min,mutations=30/min"
+
+[vm]
+# Sizing for `safepaw vm launch` and VMs launched through the API. Leave a
+# setting commented out to use the multipass default.
+# cpus = 2
+# memory = "4G"
+# disk = "20G"
+# image = "24.04"
+
+[multipass]
+# multipass executable, looked up on PATH unless it is an absolute path.
+binary = "multipass"
+# Only list VMs whose names start with this, and refuse to launch others.
+# Keeps SafePaw out of VMs other tools created on a shared host.
+# managed_prefix = "sp-"
+
+[log]
+# Log level when neither RUST_LOG nor -q/-v is given: error, warn, info,
+# debug or trace.
+level = "info"
+
+[audit]
+# Append a JSON line for every VM launch, start, stop, restart and delete,
+# from both the CLI and the API server.
+enabled = false
+# Where the audit log goes; defaults to ~/.safepaw/audit.log.
+# path = "/var/log/safepaw/audit.log"
+"#;
+
+/// Keys a section doesn't know, kept so `Config::validate` can warn about
+/// them instead of failing the whole load.
+type UnknownKeys = BTreeMap<String, toml::Value>;
+
+/// Settings read from `~/.safepaw/config.toml` at startup.
+///
+/// A missing file, section or key falls back to the built-in default, so an
+/// empty file is a valid config. Problems that parse fine, such as unknown
+/// keys or port 0, are reported by `validate`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub vm: VmDefaults,
+    pub multipass: MultipassConfig,
+    pub log: LogConfig,
+    pub audit: AuditConfig,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub ui_port: u16,
+    pub api_port: u16,
+    pub single_port: bool,
+    pub async_launch: bool,
+    pub rate_limit: Option<String>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_owned(),
+            ui_port: 8888,
+            api_port: 8889,
+            single_port: false,
+            async_launch: false,
+            rate_limit: None,
+            unknown: UnknownKeys::new(),
+        }
+    }
+}
+
+/// Sizing applied to launches that don't set their own.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VmDefaults {
+    pub cpus: Option<u32>,
+    pub memory: Option<String>,
+    pub disk: Option<String>,
+    pub image: Option<String>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+impl VmDefaults {
+    /// `spec` with every unset sizing field taken from these defaults.
+    pub fn apply(&self, spec: &LaunchSpec) -> LaunchSpec {
+        LaunchSpec {
+            cpus: spec.cpus.or(self.cpus),
+            memory: spec.memory.clone().or_else(|| self.memory.clone()),
+            disk: spec.disk.clone().or_else(|| self.disk.clone()),
+            image: spec.image.clone().or_else(|| self.image.clone()),
+            ..spec.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultipassConfig {
+    pub binary: String,
+    pub managed_prefix: Option<String>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+impl Default for MultipassConfig {
+    fn default() -> Self {
+        Self {
+            binary: "multipass".to_owned(),
+            managed_prefix: None,
+            unknown: UnknownKeys::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub level: String,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_owned(),
+            unknown: UnknownKeys::new(),
+        }
+    }
+}
+
+/// Whether and where VM operations are recorded; see `crate::audit`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    pub path: Option<PathBuf>,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: UnknownKeys,
+}
+
+/// How serious a `ConfigIssue` is. Errors stop the server from starting;
+/// warnings are only reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueLevel {
+    Warning,
+    Error,
+}
+
+/// A problem `Config::validate` found, tied to the dotted key it concerns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub level: IssueLevel,
+    /// E.g. `server.ui_port`.
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            level: IssueLevel::Warning,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            level: IssueLevel::Error,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == IssueLevel::Error
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            IssueLevel::Warning => "warning",
+            IssueLevel::Error => "error",
+        };
+        write!(f, "{}: {}: {}", level, self.key, self.message)
+    }
+}
+
+impl Config {
+    /// Loads `~/.safepaw/config.toml`, or the defaults when it doesn't exist.
+    pub fn load_default() -> Result<Self> {
+        let path = default_config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(&path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// Problems with settings that parsed but can't work, plus a warning for
+    /// every key nothing reads. Unknown keys are listed first.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let unknown = [
+            ("", &self.unknown),
+            ("server.", &self.server.unknown),
+            ("vm.", &self.vm.unknown),
+            ("multipass.", &self.multipass.unknown),
+            ("log.", &self.log.unknown),
+            ("audit.", &self.audit.unknown),
+        ];
+        for (section, keys) in unknown {
+            for key in keys.keys() {
+                issues.push(ConfigIssue::warning(
+                    format!("{section}{key}"),
+                    "unknown key, ignored",
+                ));
+            }
+        }
+
+        if self.server.host.parse::<std::net::IpAddr>().is_err() {
+            issues.push(ConfigIssue::error(
+                "server.host",
+                format!("'{}' is not an IP address", self.server.host),
+            ));
+        }
+        for (key, port) in [
+            ("server.ui_port", self.server.ui_port),
+            ("server.api_port", self.server.api_port),
+        ] {
+            if port == 0 {
+                issues.push(ConfigIssue::error(key, "port must be between 1 and 65535"));
+            }
+        }
+        if !self.server.single_port
+            && self.server.ui_port != 0
+            && self.server.ui_port == self.server.api_port
+        {
+            issues.push(ConfigIssue::error(
+                "server.api_port",
+                format!("same port as server.ui_port ({})", self.server.ui_port),
+            ));
+        }
+
+        if let Some(rate_limit) = &self.server.rate_limit
+            && let Err(err) = RateLimits::parse(rate_limit)
+        {
+            issues.push(ConfigIssue::error("server.rate_limit", err.to_string()));
+        }
+
+        if self.vm.cpus == Some(0) {
+            issues.push(ConfigIssue::error("vm.cpus", "must be at least 1"));
+        }
+        for (key, size) in [("vm.memory", &self.vm.memory), ("vm.disk", &self.vm.disk)] {
+            if let Some(size) = size
+                && parse_size(size).is_none_or(|bytes| bytes == 0)
+            {
+                issues.push(ConfigIssue::error(
+                    key,
+                    format!("'{}' is not a size like 512M, 4G or 20GiB", size),
+                ));
+            }
+        }
+
+        if self.multipass.binary.trim().is_empty() {
+            issues.push(ConfigIssue::error("multipass.binary", "must not be empty"));
+        }
+        // Any valid suffix works here; this only checks the prefix.
+        if let Some(prefix) = &self.multipass.managed_prefix
+            && validate_vm_name(&format!("{prefix}a")).is_err()
+        {
+            issues.push(ConfigIssue::error(
+                "multipass.managed_prefix",
+                format!(
+                    "'{}' cannot start a VM name; use lowercase letters, digits and hyphens, \
+                     starting with a letter",
+                    prefix
+                ),
+            ));
+        }
+
+        if !LOG_LEVELS.contains(&self.log.level.as_str()) {
+            issues.push(ConfigIssue::error(
+                "log.level",
+                format!(
+                    "'{}' is not one of {}",
+                    self.log.level,
+                    LOG_LEVELS.join(", ")
+                ),
+            ));
+        }
+
+        if self
+            .audit
+            .path
+            .as_ref()
+            .is_some_and(|path| path.as_os_str().is_empty())
+        {
+            issues.push(ConfigIssue::error("audit.path", "must not be empty"));
+        }
+        issues
+    }
+}
+
+/// Writes the commented default config to `path`, creating its directory.
+/// An existing file is only replaced when `force` is set.
+pub fn init_config(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "config file already exists: {} (pass --force to overwrite it)",
+            path.display()
+        );
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, DEFAULT_CONFIG_TOML)
+        .with_context(|| format!("failed to write config file {}", path.display()))
+}
+
+pub fn default_config_path() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("HOME is not set")?;
+    Ok(PathBuf::from(home).join(".safepaw").join("config.toml"))
+}
@@ -0,0 +1,434 @@
+use std::fmt;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+use crate::util::format_bytes;
+use crate::vm::{CommandExecutor, Multipass};
+
+const INSTALL_HINT: &str = "Install multipass from https://multipass.run/install";
+
+/// Free space below this in the multipass data dir is reported as a warning.
+pub const DISK_WARN_BELOW_BYTES: u64 = 20 * 1024 * 1024 * 1024;
+/// Free space below this is not enough to launch a default VM.
+pub const DISK_FAIL_BELOW_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+        })
+    }
+}
+
+/// Result of one diagnostic check, with a remediation hint when it did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckOutcome {
+    pub status: CheckStatus,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl CheckOutcome {
+    pub fn pass(detail: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warn(detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn fail(detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// One environment check run by `safepaw doctor`.
+#[async_trait]
+pub trait DiagnosticCheck: Send + Sync {
+    fn name(&self) -> String;
+    async fn run(&self) -> CheckOutcome;
+}
+
+/// Outcomes of every check, in the order they ran.
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub results: Vec<(String, CheckOutcome)>,
+}
+
+impl DoctorReport {
+    pub fn failures(&self) -> usize {
+        self.count(CheckStatus::Fail)
+    }
+
+    pub fn into_lines(self) -> Vec<String> {
+        let summary = format!(
+            "{} passed, {} warnings, {} failed",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail)
+        );
+        let mut lines = Vec::new();
+        for (name, outcome) in self.results {
+            lines.push(format!("[{}] {name}: {}", outcome.status, outcome.detail));
+            if let Some(hint) = outcome.hint {
+                lines.push(format!("       hint: {hint}"));
+            }
+        }
+        lines.push(summary);
+        lines
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, outcome)| outcome.status == status)
+            .count()
+    }
+}
+
+pub async fn run_checks(checks: &[Box<dyn DiagnosticCheck>]) -> DoctorReport {
+    let mut results = Vec::with_capacity(checks.len());
+    for check in checks {
+        results.push((check.name(), check.run().await));
+    }
+    DoctorReport { results }
+}
+
+/// The checks `safepaw doctor` runs, in order.
+pub fn default_checks<E>(
+    executor: E,
+    binary: &str,
+    multipass: Arc<dyn Multipass>,
+    host: &str,
+    ports: &[(&str, u16)],
+) -> Vec<Box<dyn DiagnosticCheck>>
+where
+    E: CommandExecutor + Clone + 'static,
+{
+    let mut checks: Vec<Box<dyn DiagnosticCheck>> = vec![
+        Box::new(MultipassBinaryCheck::new(executor.clone()).with_binary(binary)),
+        Box::new(MultipassDaemonCheck::new(executor.clone()).with_binary(binary)),
+        Box::new(MultipassListCheck::new(multipass)),
+    ];
+    for (label, port) in ports {
+        checks.push(Box::new(PortCheck::new(*label, host, *port)));
+    }
+    checks.push(Box::new(DiskSpaceCheck::new(
+        executor,
+        default_multipass_data_dir(),
+    )));
+    checks
+}
+
+/// Where multipassd keeps VM images on this platform.
+pub fn default_multipass_data_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/var/root/Library/Application Support/multipassd")
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\Multipass\data")
+    } else {
+        PathBuf::from("/var/snap/multipass/common/data/multipassd")
+    }
+}
+
+/// Client and daemon versions from `multipass version`. The daemon line is
+/// missing when multipassd cannot be reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultipassVersions {
+    pub client: Option<String>,
+    pub daemon: Option<String>,
+}
+
+pub fn parse_version_output(stdout: &str) -> MultipassVersions {
+    let mut versions = MultipassVersions::default();
+    for line in stdout.lines() {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some("multipass"), Some(version)) => versions.client = Some(version.to_owned()),
+            (Some("multipassd"), Some(version)) => versions.daemon = Some(version.to_owned()),
+            _ => {}
+        }
+    }
+    versions
+}
+
+async fn multipass_version<E: CommandExecutor>(
+    executor: &E,
+    binary: &str,
+) -> anyhow::Result<(MultipassVersions, String)> {
+    let output = executor
+        .run(binary, &["version".to_owned()], &CancellationToken::new())
+        .await?;
+    Ok((parse_version_output(&output.stdout), output.stderr))
+}
+
+/// The `multipass` binary, from PATH unless another is configured, runs and
+/// reports its version.
+pub struct MultipassBinaryCheck<E> {
+    executor: E,
+    binary: String,
+}
+
+impl<E> MultipassBinaryCheck<E> {
+    pub fn new(executor: E) -> Self {
+        Self {
+            executor,
+            binary: "multipass".to_owned(),
+        }
+    }
+
+    /// Runs this multipass executable instead of the one on `PATH`.
+    pub fn with_binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+}
+
+#[async_trait]
+impl<E: CommandExecutor> DiagnosticCheck for MultipassBinaryCheck<E> {
+    fn name(&self) -> String {
+        "multipass binary".to_owned()
+    }
+
+    async fn run(&self) -> CheckOutcome {
+        match multipass_version(&self.executor, &self.binary).await {
+            Ok((
+                MultipassVersions {
+                    client: Some(version),
+                    ..
+                },
+                _,
+            )) => CheckOutcome::pass(format!("multipass {version}")),
+            Ok(_) => CheckOutcome::warn(
+                "found, but `multipass version` printed no client version",
+                "Reinstall multipass or check that `multipass` on PATH is the real binary",
+            ),
+            Err(err) => CheckOutcome::fail(format!("not found: {err}"), INSTALL_HINT),
+        }
+    }
+}
+
+/// multipassd answers `multipass version`.
+pub struct MultipassDaemonCheck<E> {
+    executor: E,
+    binary: String,
+}
+
+impl<E> MultipassDaemonCheck<E> {
+    pub fn new(executor: E) -> Self {
+        Self {
+            executor,
+            binary: "multipass".to_owned(),
+        }
+    }
+
+    /// Runs this multipass executable instead of the one on `PATH`.
+    pub fn with_binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+}
+
+#[async_trait]
+impl<E: CommandExecutor> DiagnosticCheck for MultipassDaemonCheck<E> {
+    fn name(&self) -> String {
+        "multipass daemon".to_owned()
+    }
+
+    async fn run(&self) -> CheckOutcome {
+        let hint = "Start the daemon, e.g. `sudo snap restart multipass` on Linux or \
+                    `sudo launchctl kickstart -k system/com.canonical.multipassd` on macOS";
+        match multipass_version(&self.executor, &self.binary).await {
+            Ok((
+                MultipassVersions {
+                    daemon: Some(version),
+                    ..
+                },
+                _,
+            )) => CheckOutcome::pass(format!("multipassd {version}")),
+            Ok((_, stderr)) if !stderr.trim().is_empty() => {
+                CheckOutcome::fail(format!("unreachable: {}", stderr.trim()), hint)
+            }
+            Ok(_) => CheckOutcome::fail("unreachable", hint),
+            Err(err) => CheckOutcome::fail(format!("unreachable: {err}"), INSTALL_HINT),
+        }
+    }
+}
+
+/// `multipass list` succeeds and its JSON output parses.
+pub struct MultipassListCheck {
+    multipass: Arc<dyn Multipass>,
+}
+
+impl MultipassListCheck {
+    pub fn new(multipass: Arc<dyn Multipass>) -> Self {
+        Self { multipass }
+    }
+}
+
+#[async_trait]
+impl DiagnosticCheck for MultipassListCheck {
+    fn name(&self) -> String {
+        "multipass list".to_owned()
+    }
+
+    async fn run(&self) -> CheckOutcome {
+        match self.multipass.list().await {
+            Ok(vms) => CheckOutcome::pass(format!("{} instance(s)", vms.len())),
+            Err(err) => CheckOutcome::fail(
+                err.to_string(),
+                "Run `multipass list` directly to see the full error",
+            ),
+        }
+    }
+}
+
+/// A server port can be bound on the configured host.
+pub struct PortCheck {
+    label: String,
+    host: String,
+    port: u16,
+}
+
+impl PortCheck {
+    pub fn new(label: impl Into<String>, host: impl Into<String>, port: u16) -> Self {
+        Self {
+            label: label.into(),
+            host: host.into(),
+            port,
+        }
+    }
+}
+
+#[async_trait]
+impl DiagnosticCheck for PortCheck {
+    fn name(&self) -> String {
+        format!("{} port {}", self.label, self.port)
+    }
+
+    async fn run(&self) -> CheckOutcome {
+        match TcpListener::bind((self.host.as_str(), self.port)) {
+            Ok(_) => CheckOutcome::pass(format!("{}:{} is available", self.host, self.port)),
+            Err(err) if err.kind() == std::io::ErrorKind::AddrInUse => CheckOutcome::fail(
+                format!("{}:{} is already in use", self.host, self.port),
+                format!(
+                    "Stop the process listening on port {} or pick another with --{}-port",
+                    self.port, self.label
+                ),
+            ),
+            Err(err) => CheckOutcome::fail(
+                format!("cannot bind {}:{}: {err}", self.host, self.port),
+                "Check the --host value and that you may bind this port",
+            ),
+        }
+    }
+}
+
+/// Enough free disk space where multipass stores VM images.
+pub struct DiskSpaceCheck<E> {
+    executor: E,
+    path: PathBuf,
+    warn_below: u64,
+    fail_below: u64,
+}
+
+impl<E> DiskSpaceCheck<E> {
+    pub fn new(executor: E, path: impl Into<PathBuf>) -> Self {
+        Self {
+            executor,
+            path: path.into(),
+            warn_below: DISK_WARN_BELOW_BYTES,
+            fail_below: DISK_FAIL_BELOW_BYTES,
+        }
+    }
+
+    pub fn with_thresholds(mut self, warn_below: u64, fail_below: u64) -> Self {
+        self.warn_below = warn_below;
+        self.fail_below = fail_below;
+        self
+    }
+}
+
+/// Available bytes from `df -Pk` output (POSIX format, 1K blocks).
+pub fn parse_df_available(stdout: &str) -> Option<u64> {
+    let row = stdout.lines().nth(1)?;
+    let kilobytes: u64 = row.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// The data dir may not exist yet (or be unreadable without root), so
+/// measure the closest ancestor that does; it lives on the same filesystem.
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|candidate| candidate.exists())
+        .unwrap_or(Path::new("/"))
+}
+
+#[async_trait]
+impl<E: CommandExecutor> DiagnosticCheck for DiskSpaceCheck<E> {
+    fn name(&self) -> String {
+        "disk space".to_owned()
+    }
+
+    async fn run(&self) -> CheckOutcome {
+        let target = existing_ancestor(&self.path);
+        let args = vec!["-Pk".to_owned(), target.display().to_string()];
+        let available = match self
+            .executor
+            .run("df", &args, &CancellationToken::new())
+            .await
+        {
+            Ok(output) if output.status_code == 0 => parse_df_available(&output.stdout),
+            _ => None,
+        };
+
+        let hint = format!(
+            "Free up space on the filesystem holding {}",
+            self.path.display()
+        );
+        match available {
+            None => CheckOutcome::warn(
+                format!("could not determine free space for {}", target.display()),
+                format!("Run `df -h {}` to check manually", target.display()),
+            ),
+            Some(bytes) if bytes < self.fail_below => CheckOutcome::fail(
+                format!("only {} free for {}", format_bytes(bytes), target.display()),
+                hint,
+            ),
+            Some(bytes) if bytes < self.warn_below => CheckOutcome::warn(
+                format!("{} free for {}", format_bytes(bytes), target.display()),
+                hint,
+            ),
+            Some(bytes) => CheckOutcome::pass(format!(
+                "{} free for {}",
+                format_bytes(bytes),
+                target.display()
+            )),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::vm::{VmApi, VmState};
+
+/// Events a subscriber can miss before it starts skipping ahead.
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// How often `watch_vm_states` compares `VmApi::list` snapshots.
+pub const EVENT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VmEventKind {
+    #[serde(rename = "vm.launched")]
+    Launched,
+    #[serde(rename = "vm.started")]
+    Started,
+    #[serde(rename = "vm.stopped")]
+    Stopped,
+    #[serde(rename = "vm.deleted")]
+    Deleted,
+    /// The VM's state in `multipass list` differs from the previous poll.
+    #[serde(rename = "vm.state_changed")]
+    StateChanged,
+}
+
+impl VmEventKind {
+    /// The SSE `event:` name, e.g. `vm.launched`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Launched => "vm.launched",
+            Self::Started => "vm.started",
+            Self::Stopped => "vm.stopped",
+            Self::Deleted => "vm.deleted",
+            Self::StateChanged => "vm.state_changed",
+        }
+    }
+}
+
+/// A VM lifecycle change, as sent to `GET /events` subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VmEvent {
+    /// Increases by one per event from the same `EventBus`.
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: VmEventKind,
+    pub timestamp: DateTime<Utc>,
+    pub vm_name: String,
+    /// The state after the change; only set for `vm.state_changed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// The state before the change, when the VM was known before.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_state: Option<String>,
+}
+
+/// Fans VM events out to every current subscriber. Nothing is kept for
+/// subscribers that join later.
+pub struct EventBus {
+    sender: broadcast::Sender<VmEvent>,
+    next_id: AtomicU64,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_CHANNEL_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<VmEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn publish(&self, kind: VmEventKind, vm_name: &str) -> VmEvent {
+        self.send(kind, vm_name, None, None)
+    }
+
+    pub fn publish_state_change(
+        &self,
+        vm_name: &str,
+        previous: Option<&VmState>,
+        state: Option<&VmState>,
+    ) -> VmEvent {
+        self.send(
+            VmEventKind::StateChanged,
+            vm_name,
+            state.map(ToString::to_string),
+            previous.map(ToString::to_string),
+        )
+    }
+
+    fn send(
+        &self,
+        kind: VmEventKind,
+        vm_name: &str,
+        state: Option<String>,
+        previous_state: Option<String>,
+    ) -> VmEvent {
+        let event = VmEvent {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            kind,
+            timestamp: Utc::now(),
+            vm_name: vm_name.to_owned(),
+            state,
+            previous_state,
+        };
+        // No subscribers is fine: the event simply has no audience.
+        let _ = self.sender.send(event.clone());
+        event
+    }
+}
+
+/// Publishes a `vm.state_changed` event for every VM whose state in
+/// `VmApi::list` differs from the previous poll, including VMs that
+/// appeared or disappeared, until `cancel` fires. The first poll only
+/// records a baseline, and polls are skipped while nobody is subscribed.
+pub async fn watch_vm_states(
+    api: Arc<dyn VmApi>,
+    bus: Arc<EventBus>,
+    interval: Duration,
+    cancel: CancellationToken,
+) {
+    let mut previous: Option<HashMap<String, VmState>> = None;
+    loop {
+        if bus.has_subscribers() {
+            match api.list().await {
+                Ok(vms) => {
+                    let current: HashMap<String, VmState> =
+                        vms.into_iter().map(|vm| (vm.name, vm.state)).collect();
+                    if let Some(previous) = &previous {
+                        publish_changes(&bus, previous, &current);
+                    }
+                    previous = Some(current);
+                }
+                Err(err) => debug!("failed to poll VM states for events: {:#}", err),
+            }
+        } else {
+            // Changes while nobody listened would be reported as stale.
+            previous = None;
+        }
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+fn publish_changes(
+    bus: &EventBus,
+    previous: &HashMap<String, VmState>,
+    current: &HashMap<String, VmState>,
+) {
+    let mut names: Vec<&String> = previous.keys().chain(current.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let (before, after) = (previous.get(name), current.get(name));
+        if before != after {
+            bus.publish_state_change(name, before, after);
+        }
+    }
+}
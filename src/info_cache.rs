@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::time::Instant;
+
+use crate::vm::{VmApi, VmStatusResponse};
+
+/// Remembers `VmApi::info` results per VM name for `ttl`, so clients polling
+/// `GET /vms/{name}` don't run `multipass info` on every request. Failed
+/// lookups are never cached.
+pub struct InfoCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, VmStatusResponse)>>,
+}
+
+impl InfoCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The cached info for `name` if it is younger than the TTL, otherwise
+    /// a fresh `api.info` that replaces the cached entry.
+    pub async fn info(&self, api: &dyn VmApi, name: &str) -> Result<VmStatusResponse> {
+        if let Some((fetched_at, info)) = self.lock().get(name)
+            && fetched_at.elapsed() < self.ttl
+        {
+            return Ok(info.clone());
+        }
+
+        let info = api.info(name).await?;
+        self.lock()
+            .insert(name.to_owned(), (Instant::now(), info.clone()));
+        Ok(info)
+    }
+
+    /// Drops the entry for `name`, e.g. after an operation changed its state.
+    pub fn invalidate(&self, name: &str) {
+        self.lock().remove(name);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Instant, VmStatusResponse)>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
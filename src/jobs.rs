@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::vm::DEFAULT_LAUNCH_CONCURRENCY;
+
+/// How long finished jobs stay visible unless configured otherwise.
+pub const DEFAULT_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Longest pause between two `collect_garbage` sweeps.
+pub const JOB_GC_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Launch,
+    Delete,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Launch => "launch",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for a free slot; can still be cancelled.
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    /// Cancelled while queued, so it never ran.
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+/// A background VM operation, as returned by `GET /jobs/{id}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub vm_name: String,
+    pub state: JobState,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The latest progress line the backend reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<String>,
+    /// The outcome once the job has succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Why the job failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Why `JobStore::cancel` refused.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CancelError {
+    #[error("job '{0}' does not exist")]
+    NotFound(String),
+    /// Only queued jobs can be cancelled.
+    #[error("job '{id}' is {} and can no longer be cancelled", .state.as_str())]
+    NotQueued { id: String, state: JobState },
+}
+
+struct Entry {
+    job: Job,
+    /// Orders `list`, since creation timestamps can tie.
+    seq: u64,
+    /// When the job finished, on the clock retention is measured with.
+    finished: Option<Instant>,
+    cancel: CancellationToken,
+}
+
+#[derive(Default)]
+struct Jobs {
+    entries: HashMap<String, Entry>,
+    next_seq: u64,
+}
+
+/// Jobs the API server runs in the background, kept in memory only.
+///
+/// At most `max_running` jobs run at once; the rest wait as `Queued`.
+/// Finished jobs are dropped by `collect_garbage` once they are older
+/// than the retention period.
+pub struct JobStore {
+    retention: Duration,
+    slots: Arc<Semaphore>,
+    jobs: Mutex<Jobs>,
+    /// Parent of every job's token; see `cancel_all`.
+    shutdown: CancellationToken,
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_JOB_RETENTION, DEFAULT_LAUNCH_CONCURRENCY)
+    }
+}
+
+impl JobStore {
+    pub fn new(retention: Duration, max_running: usize) -> Self {
+        Self {
+            retention,
+            slots: Arc::new(Semaphore::new(max_running.max(1))),
+            jobs: Mutex::new(Jobs::default()),
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Queues `work` as a `kind` job on `vm_name` and returns its id. `work`
+    /// gets a sink for progress lines, which also carries the job's
+    /// cancellation token, and returns the success message or the error
+    /// text.
+    pub fn spawn<F, Fut>(self: &Arc<Self>, kind: JobKind, vm_name: &str, work: F) -> String
+    where
+        F: FnOnce(JobProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        let cancel = self.shutdown.child_token();
+        let id = self.insert(kind, vm_name, cancel.clone());
+        let store = self.clone();
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            let _permit = tokio::select! {
+                permit = store.slots.clone().acquire_owned() => match permit {
+                    Ok(permit) => permit,
+                    Err(_) => return,
+                },
+                _ = cancel.cancelled() => {
+                    store.mark_cancelled(&job_id);
+                    return;
+                }
+            };
+            if !store.start(&job_id) {
+                return;
+            }
+            let outcome = work(JobProgress {
+                store: store.clone(),
+                id: job_id.clone(),
+                cancel,
+            })
+            .await;
+            store.finish(&job_id, outcome);
+        });
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.lock().entries.get(id).map(|entry| entry.job.clone())
+    }
+
+    /// Every job still kept, oldest first.
+    pub fn list(&self) -> Vec<Job> {
+        let jobs = self.lock();
+        let mut entries: Vec<&Entry> = jobs.entries.values().collect();
+        entries.sort_by_key(|entry| entry.seq);
+        entries.into_iter().map(|entry| entry.job.clone()).collect()
+    }
+
+    /// Jobs that are still queued or running, oldest first.
+    pub fn unfinished(&self) -> Vec<Job> {
+        self.list()
+            .into_iter()
+            .filter(|job| !job.state.is_finished())
+            .collect()
+    }
+
+    /// Cancels a queued job so it never runs, and returns it.
+    pub fn cancel(&self, id: &str) -> Result<Job, CancelError> {
+        let mut jobs = self.lock();
+        let entry = jobs
+            .entries
+            .get_mut(id)
+            .ok_or_else(|| CancelError::NotFound(id.to_owned()))?;
+        if entry.job.state != JobState::Queued {
+            return Err(CancelError::NotQueued {
+                id: id.to_owned(),
+                state: entry.job.state,
+            });
+        }
+        entry.job.state = JobState::Cancelled;
+        entry.job.finished_at = Some(Utc::now());
+        entry.finished = Some(Instant::now());
+        entry.cancel.cancel();
+        Ok(entry.job.clone())
+    }
+
+    /// Cancels every job, e.g. when the server shuts down: queued jobs never
+    /// run and running ones see their `JobProgress::cancel_token` fire.
+    pub fn cancel_all(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Drops finished jobs older than the retention period.
+    pub fn prune_expired(&self) {
+        let retention = self.retention;
+        self.lock().entries.retain(|_, entry| {
+            entry
+                .finished
+                .is_none_or(|finished| finished.elapsed() < retention)
+        });
+    }
+
+    /// Calls `prune_expired` periodically, forever; spawn it next to the
+    /// server.
+    pub async fn collect_garbage(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(
+            self.retention
+                .clamp(Duration::from_secs(1), JOB_GC_INTERVAL),
+        );
+        loop {
+            ticker.tick().await;
+            self.prune_expired();
+        }
+    }
+
+    fn insert(&self, kind: JobKind, vm_name: &str, cancel: CancellationToken) -> String {
+        let job = Job {
+            id: Uuid::new_v4().to_string(),
+            kind,
+            vm_name: vm_name.to_owned(),
+            state: JobState::Queued,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            progress: None,
+            message: None,
+            error: None,
+        };
+        let id = job.id.clone();
+        let mut jobs = self.lock();
+        let seq = jobs.next_seq;
+        jobs.next_seq += 1;
+        jobs.entries.insert(
+            id.clone(),
+            Entry {
+                job,
+                seq,
+                finished: None,
+                cancel,
+            },
+        );
+        id
+    }
+
+    /// Marks a job cancelled before it got a slot, unless `cancel` already did.
+    fn mark_cancelled(&self, id: &str) {
+        if let Some(entry) = self.lock().entries.get_mut(id)
+            && entry.job.state == JobState::Queued
+        {
+            entry.job.state = JobState::Cancelled;
+            entry.job.finished_at = Some(Utc::now());
+            entry.finished = Some(Instant::now());
+        }
+    }
+
+    /// Moves a queued job to running; false if it was cancelled meanwhile.
+    fn start(&self, id: &str) -> bool {
+        let mut jobs = self.lock();
+        match jobs.entries.get_mut(id) {
+            Some(entry) if entry.job.state == JobState::Queued => {
+                entry.job.state = JobState::Running;
+                entry.job.started_at = Some(Utc::now());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn finish(&self, id: &str, outcome: Result<String, String>) {
+        if let Some(entry) = self.lock().entries.get_mut(id) {
+            entry.job.finished_at = Some(Utc::now());
+            entry.finished = Some(Instant::now());
+            match outcome {
+                Ok(message) => {
+                    entry.job.state = JobState::Succeeded;
+                    entry.job.message = Some(message);
+                }
+                Err(error) => {
+                    entry.job.state = JobState::Failed;
+                    entry.job.error = Some(error);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Jobs> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Records progress lines for one running job.
+pub struct JobProgress {
+    store: Arc<JobStore>,
+    id: String,
+    cancel: CancellationToken,
+}
+
+impl JobProgress {
+    /// Fires when the job should give up, e.g. on server shutdown.
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    pub fn report(&self, line: &str) {
+        if let Some(entry) = self.store.lock().entries.get_mut(&self.id) {
+            entry.job.progress = Some(line.to_owned());
+        }
+    }
+}
@@ -1,6 +1,17 @@
 pub mod agent;
+pub mod api_error;
+pub mod audit;
 pub mod cli;
+pub mod config;
 pub mod db;
+pub mod doctor;
+pub mod events;
+pub mod info_cache;
+pub mod jobs;
+pub mod manifest;
+pub mod rate_limit;
+pub mod redact;
 pub mod server;
+pub mod tags;
 pub mod util;
 pub mod vm;
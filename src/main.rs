@@ -1,63 +1,243 @@
 use std::env;
+use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::bail;
+use anyhow::{anyhow, bail};
+use clap::ArgMatches;
+use clap::parser::ValueSource;
 use safepaw::agent::LocalAgentManager;
-use safepaw::cli::{VmMode, build_cli, resolve_vm_mode, run_agent_subcommand, run_vm_subcommand};
-use safepaw::vm::{LocalVmApi, MultipassCli, TokioCommandExecutor};
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use safepaw::audit::{AuditSink, FileAuditSink, NoopAuditSink};
+use safepaw::cli::{
+    RenderOptions, VmMode, build_cli, complete_vm_names, effective_config, exit_code,
+    render_man_page, resolve_log_filter, resolve_ssh_config, resolve_vm_mode, run_agent_subcommand,
+    run_vm_subcommand, write_man_pages,
+};
+use safepaw::config::{Config, default_config_path, init_config};
+use safepaw::doctor::{default_checks, run_checks};
+use safepaw::rate_limit::RateLimits;
+use safepaw::server::{BannerFormat, ServerOptions, TlsConfig};
+use safepaw::tags::TagRegistry;
+use safepaw::vm::{
+    CommandExecutor, LocalVmApi, MultipassCli, RetryConfig, SshCommandExecutor, SysinfoProbe,
+    TokioCommandExecutor,
+};
+use tracing_subscriber::{EnvFilter, fmt, fmt::format::FmtSpan, prelude::*};
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing subscriber with environment filter
-    // Can be controlled via RUST_LOG env var (e.g., RUST_LOG=debug)
+    if env::args_os().nth(1).is_none() {
+        let mut cli = build_cli();
+        cli.print_help().expect("failed to print help");
+        println!();
+        return;
+    }
+
+    let matches = build_cli().get_matches();
+
+    // `config init --force` has to work even when the current file is broken.
+    let config = if matches.subcommand_name() == Some("config") {
+        Config::default()
+    } else {
+        load_startup_config().unwrap_or_else(|err| exit_with(err))
+    };
+
+    // Initialize tracing subscriber before running any command.
+    // RUST_LOG (e.g. RUST_LOG=debug) wins over the -v/-q flags, which win
+    // over the level in the config file.
+    // Logs go to stderr so command output on stdout stays machine-readable.
+    // Closing a multipass command's span logs its action, VM, duration and
+    // exit status.
+    let filter = EnvFilter::new(resolve_log_filter(
+        matches.get_flag("quiet"),
+        matches.get_count("verbose"),
+        &config.log.level,
+        env::var(EnvFilter::DEFAULT_ENV).ok().as_deref(),
+    ));
     tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("safepaw=info")))
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_span_events(FmtSpan::CLOSE),
+        )
+        .with(filter)
         .init();
 
-    if let Err(err) = run().await {
-        eprintln!("error: {err}");
-        for cause in err.chain().skip(1) {
-            eprintln!("caused by: {cause}");
-        }
-        std::process::exit(1);
+    if let Err(err) = run(&matches, &config).await {
+        exit_with(err);
     }
 }
 
-async fn run() -> anyhow::Result<()> {
-    if env::args_os().nth(1).is_none() {
-        let mut cli = build_cli();
-        cli.print_help().expect("failed to print help");
-        println!();
-        return Ok(());
+/// Loads `~/.safepaw/config.toml` and validates it, printing warnings and
+/// refusing to start when any setting is an error.
+fn load_startup_config() -> anyhow::Result<Config> {
+    let config = Config::load_default()?;
+    let issues = config.validate();
+    if issues.is_empty() {
+        return Ok(config);
     }
+    let path = default_config_path()?;
+    for issue in &issues {
+        eprintln!("{}: {}", path.display(), issue);
+    }
+    if issues.iter().any(|issue| issue.is_error()) {
+        bail!(
+            "invalid config file {}; run `safepaw config check` for details",
+            path.display()
+        );
+    }
+    Ok(config)
+}
 
-    let matches = build_cli().get_matches();
+fn exit_with(err: anyhow::Error) -> ! {
+    eprintln!("error: {err}");
+    for cause in err.chain().skip(1) {
+        eprintln!("caused by: {cause}");
+    }
+    std::process::exit(exit_code(&err));
+}
+
+/// The value of flag `id`, or `configured` when the flag was left at its
+/// built-in default.
+fn flag_or_config<T>(matches: &ArgMatches, id: &str, configured: T) -> T
+where
+    T: Clone + Send + Sync + 'static,
+{
+    match matches.value_source(id) {
+        Some(ValueSource::DefaultValue) | None => configured,
+        Some(_) => matches.get_one::<T>(id).cloned().unwrap_or(configured),
+    }
+}
+
+/// The file sink from `[audit]` when it is enabled, otherwise a no-op.
+fn audit_sink(config: &Config) -> anyhow::Result<Arc<dyn AuditSink>> {
+    if !config.audit.enabled {
+        return Ok(Arc::new(NoopAuditSink));
+    }
+    Ok(match &config.audit.path {
+        Some(path) => Arc::new(FileAuditSink::new(path)),
+        None => Arc::new(FileAuditSink::open_default()?),
+    })
+}
+
+fn local_multipass(config: &Config) -> Arc<MultipassCli<TokioCommandExecutor>> {
+    let multipass =
+        MultipassCli::new(TokioCommandExecutor::new()).with_binary(&config.multipass.binary);
+    Arc::new(scoped_to_managed_prefix(multipass, config))
+}
 
+/// `multipass` limited to `[multipass] managed_prefix` VMs, when one is set.
+fn scoped_to_managed_prefix<E: CommandExecutor>(
+    multipass: MultipassCli<E>,
+    config: &Config,
+) -> MultipassCli<E> {
+    match &config.multipass.managed_prefix {
+        Some(prefix) => multipass.with_managed_prefix(prefix),
+        None => multipass,
+    }
+}
+
+async fn run(matches: &ArgMatches, config: &Config) -> anyhow::Result<()> {
     match matches.subcommand() {
         Some(("start", start_matches)) => {
-            let host = start_matches
-                .get_one::<String>("host")
-                .map(String::as_str)
-                .unwrap_or("0.0.0.0");
-            let ui_port = *start_matches.get_one::<u16>("ui-port").unwrap_or(&8888);
-            let api_port = *start_matches.get_one::<u16>("api-port").unwrap_or(&8889);
-
-            let multipass = Arc::new(MultipassCli::new(TokioCommandExecutor));
-            let vm_api =
-                Arc::new(LocalVmApi::new(multipass.clone())) as Arc<dyn safepaw::vm::VmApi>;
+            let host = flag_or_config(start_matches, "host", config.server.host.clone());
+            let host = host.as_str();
+            let ui_port = flag_or_config(start_matches, "ui-port", config.server.ui_port);
+            let api_port = flag_or_config(start_matches, "api-port", config.server.api_port);
+            let single_port =
+                flag_or_config(start_matches, "single-port", config.server.single_port);
+            let async_launch =
+                flag_or_config(start_matches, "async-launch", config.server.async_launch);
+
+            let rate_limits = match start_matches
+                .get_one::<String>("rate-limit")
+                .or(config.server.rate_limit.as_ref())
+            {
+                Some(limits) => RateLimits::parse(limits)?,
+                None => RateLimits::default(),
+            };
+
+            let multipass = local_multipass(config);
+            let vm_api = Arc::new(
+                LocalVmApi::new(multipass.clone())
+                    .with_launch_defaults(config.vm.clone())
+                    .with_preflight(Arc::new(SysinfoProbe::default()))
+                    .with_audit_sink(audit_sink(config)?),
+            ) as Arc<dyn safepaw::vm::VmApi>;
             let agent_manager = Arc::new(LocalAgentManager::new(vm_api.clone())?)
                 as Arc<dyn safepaw::agent::AgentManager>;
 
-            safepaw::server::run_server(vm_api, agent_manager, host, ui_port, api_port).await?;
+            let banner = start_matches
+                .get_one::<String>("banner")
+                .map(|banner| banner.parse::<BannerFormat>())
+                .transpose()?
+                .unwrap_or_default();
+            let tls = match (
+                start_matches.get_one::<PathBuf>("tls-cert"),
+                start_matches.get_one::<PathBuf>("tls-key"),
+            ) {
+                (Some(cert), Some(key)) => {
+                    Some(TlsConfig::new(cert, key).with_ui(start_matches.get_flag("tls-ui")))
+                }
+                _ => None,
+            };
+            let options = ServerOptions {
+                ui_dir: start_matches.get_one::<PathBuf>("ui-dir").cloned(),
+                banner,
+                tls,
+                bind_ui: start_matches.get_one::<String>("bind-ui").cloned(),
+                bind_api: start_matches.get_one::<String>("bind-api").cloned(),
+                single_port,
+                info_cache_ttl: Duration::from_secs(
+                    start_matches
+                        .get_one::<u64>("info-cache-ttl")
+                        .copied()
+                        .unwrap_or_default(),
+                ),
+                async_launch,
+                job_retention: start_matches
+                    .get_one::<u64>("job-retention")
+                    .map(|secs| Duration::from_secs(*secs)),
+                shutdown_timeout: start_matches
+                    .get_one::<u64>("shutdown-timeout")
+                    .map(|secs| Duration::from_secs(*secs)),
+                rate_limits,
+            };
+
+            safepaw::server::run_server(vm_api, agent_manager, host, ui_port, api_port, options)
+                .await?;
         }
         Some(("vm", vm_matches)) => match resolve_vm_mode(vm_matches)? {
             VmMode::Local => {
-                let multipass = Arc::new(MultipassCli::new(TokioCommandExecutor));
-                let api = LocalVmApi::new(multipass);
-                let lines = run_vm_subcommand(vm_matches, &api).await?;
-                for line in lines {
+                let mut api = LocalVmApi::new(local_multipass(config))
+                    .with_launch_defaults(config.vm.clone())
+                    .with_tag_registry(Arc::new(TagRegistry::open_default()?))
+                    .with_audit_sink(audit_sink(config)?);
+                // Only local mode preflights: the probe sees this machine, not a remote host.
+                let skip_preflight = vm_matches
+                    .subcommand_matches("launch")
+                    .is_some_and(|launch| launch.get_flag("no-preflight"));
+                if !skip_preflight {
+                    api = api.with_preflight(Arc::new(SysinfoProbe::default()));
+                }
+                let output = run_vm_subcommand(vm_matches, &api).await?;
+                for line in output.render(&RenderOptions::from_matches(vm_matches)) {
+                    println!("{line}");
+                }
+            }
+            VmMode::Remote => {
+                let executor = SshCommandExecutor::new(resolve_ssh_config(vm_matches)?);
+                let multipass = Arc::new(scoped_to_managed_prefix(
+                    MultipassCli::new(executor),
+                    config,
+                ));
+                let api = LocalVmApi::new(multipass)
+                    .with_launch_defaults(config.vm.clone())
+                    .with_tag_registry(Arc::new(TagRegistry::open_default()?))
+                    .with_audit_sink(audit_sink(config)?);
+                let output = run_vm_subcommand(vm_matches, &api).await?;
+                for line in output.render(&RenderOptions::from_matches(vm_matches)) {
                     println!("{line}");
                 }
             }
@@ -65,9 +245,121 @@ async fn run() -> anyhow::Result<()> {
                 bail!("network mode is planned but not implemented yet");
             }
         },
+        Some(("doctor", doctor_matches)) => {
+            let host = flag_or_config(doctor_matches, "host", config.server.host.clone());
+            let ui_port = flag_or_config(doctor_matches, "ui-port", config.server.ui_port);
+            let api_port = flag_or_config(doctor_matches, "api-port", config.server.api_port);
+
+            // Report the environment as it is rather than retrying past problems.
+            let multipass = Arc::new(
+                MultipassCli::new_with_retry(TokioCommandExecutor::new(), RetryConfig::disabled())
+                    .with_binary(&config.multipass.binary),
+            );
+            let ports: &[(&str, u16)] = if config.server.single_port {
+                &[("ui", ui_port)]
+            } else {
+                &[("ui", ui_port), ("api", api_port)]
+            };
+            let checks = default_checks(
+                TokioCommandExecutor::new(),
+                &config.multipass.binary,
+                multipass,
+                &host,
+                ports,
+            );
+            let report = run_checks(&checks).await;
+            let failures = report.failures();
+            for line in report.into_lines() {
+                println!("{line}");
+            }
+            if failures > 0 {
+                return Err(anyhow!("{failures} doctor check(s) failed"));
+            }
+        }
+        Some(("__complete-vm-names", _)) => {
+            let api = LocalVmApi::new(local_multipass(config));
+            for name in complete_vm_names(&api).await {
+                println!("{name}");
+            }
+        }
+        Some(("man", man_matches)) => {
+            let path: Vec<&str> = man_matches
+                .get_many::<String>("command")
+                .map(|parts| parts.map(String::as_str).collect())
+                .unwrap_or_default();
+            std::io::stdout().write_all(&render_man_page(&path)?)?;
+        }
+        Some(("gen-man", gen_matches)) => {
+            let out_dir = gen_matches
+                .get_one::<PathBuf>("out-dir")
+                .expect("out-dir has a default");
+            for path in write_man_pages(out_dir)? {
+                println!("{}", path.display());
+            }
+        }
+        Some(("config", config_matches)) => match config_matches.subcommand() {
+            Some(("init", init_matches)) => {
+                let path = match init_matches.get_one::<PathBuf>("path") {
+                    Some(path) => path.clone(),
+                    None => default_config_path()?,
+                };
+                init_config(&path, init_matches.get_flag("force"))?;
+                println!("Wrote {}", path.display());
+            }
+            Some(("show", show_matches)) => {
+                let path = match show_matches.get_one::<PathBuf>("path") {
+                    Some(path) => path.clone(),
+                    None => default_config_path()?,
+                };
+                let (config, path) = if path.exists() {
+                    (Config::load(&path)?, Some(path))
+                } else {
+                    (Config::default(), None)
+                };
+                let log_filter = resolve_log_filter(
+                    matches.get_flag("quiet"),
+                    matches.get_count("verbose"),
+                    &config.log.level,
+                    env::var(EnvFilter::DEFAULT_ENV).ok().as_deref(),
+                );
+                let effective = effective_config(&config, path.as_deref(), &log_filter, &|name| {
+                    env::var(name).ok()
+                })?;
+                println!("{}", serde_json::to_string_pretty(&effective)?);
+            }
+            Some(("check", check_matches)) => {
+                let path = match check_matches.get_one::<PathBuf>("path") {
+                    Some(path) => path.clone(),
+                    None => {
+                        let path = default_config_path()?;
+                        if !path.exists() {
+                            println!(
+                                "No config file at {}; the built-in defaults apply",
+                                path.display()
+                            );
+                            return Ok(());
+                        }
+                        path
+                    }
+                };
+                let issues = Config::load(&path)?.validate();
+                for issue in &issues {
+                    println!("{}: {}", path.display(), issue);
+                }
+                let errors = issues.iter().filter(|issue| issue.is_error()).count();
+                if errors > 0 {
+                    bail!("{} has {} error(s)", path.display(), errors);
+                }
+                if issues.is_empty() {
+                    println!("{}: OK", path.display());
+                }
+            }
+            _ => {}
+        },
         Some(("agent", agent_matches)) => {
-            let multipass = Arc::new(MultipassCli::new(TokioCommandExecutor));
-            let vm_api = Arc::new(LocalVmApi::new(multipass.clone()));
+            let vm_api = Arc::new(
+                LocalVmApi::new(local_multipass(config)).with_audit_sink(audit_sink(config)?),
+            );
             let agent_manager = LocalAgentManager::new(vm_api)?;
             let lines = run_agent_subcommand(agent_matches, &agent_manager).await?;
             for line in lines {
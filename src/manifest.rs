@@ -0,0 +1,133 @@
+use std::{collections::HashSet, fmt, path::Path};
+
+use anyhow::{Context, Result, bail};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::vm::{LaunchSpec, VmApi};
+
+/// Upper bound for `default_apply_concurrency`, however many cores there are.
+pub const MAX_DEFAULT_APPLY_CONCURRENCY: usize = 8;
+
+/// Launches `vm apply` runs at once unless told otherwise: one per two host
+/// CPUs, since each launch boots a VM, capped at
+/// `MAX_DEFAULT_APPLY_CONCURRENCY`.
+pub fn default_apply_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|cpus| cpus.get() / 2)
+        .unwrap_or(1)
+        .clamp(1, MAX_DEFAULT_APPLY_CONCURRENCY)
+}
+
+/// A fleet of VMs described in YAML:
+///
+/// ```yaml
+/// vms:
+///   - name: agent-1
+///     cpus: 2
+///     memory: 4G
+///     disk: 20G
+///     image: "24.04"
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Manifest {
+    pub vms: Vec<LaunchSpec>,
+}
+
+impl Manifest {
+    /// Parses a manifest, rejecting one that lists a VM name twice: both
+    /// entries would be launched at once and one would fail as a duplicate.
+    pub fn from_yaml(contents: &str) -> Result<Self> {
+        let manifest: Self =
+            serde_yaml::from_str(contents).context("failed to parse VM manifest")?;
+        manifest.check_unique_names()?;
+        Ok(manifest)
+    }
+
+    fn check_unique_names(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for spec in &self.vms {
+            if !seen.insert(spec.name.as_str()) {
+                bail!("VM manifest lists '{}' more than once", spec.name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read manifest {}", path.display()))?;
+        Self::from_yaml(&contents)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyOutcome {
+    Created,
+    Exists,
+    Failed(String),
+}
+
+impl fmt::Display for ApplyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::Exists => write!(f, "exists"),
+            Self::Failed(reason) => write!(f, "failed: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyResult {
+    pub name: String,
+    pub outcome: ApplyOutcome,
+}
+
+/// Launches every VM in the manifest that does not already exist, leaving
+/// existing VMs untouched. At most `concurrency` launches run at once, and a
+/// failed launch doesn't stop the others. `progress` sees each result as it
+/// finishes; the returned results are in manifest order.
+pub async fn apply_manifest(
+    api: &dyn VmApi,
+    manifest: &Manifest,
+    concurrency: usize,
+    cancel: &CancellationToken,
+    progress: &(dyn Fn(&ApplyResult) + Send + Sync),
+) -> Result<Vec<ApplyResult>> {
+    manifest.check_unique_names()?;
+    let existing: HashSet<String> = api
+        .list()
+        .await
+        .context("failed to list existing VMs")?
+        .into_iter()
+        .map(|vm| vm.name)
+        .collect();
+    let existing = &existing;
+
+    let mut results: Vec<(usize, ApplyResult)> = stream::iter(manifest.vms.iter().enumerate())
+        .map(|(index, spec)| async move {
+            let outcome = if existing.contains(&spec.name) {
+                ApplyOutcome::Exists
+            } else {
+                match api.launch(spec, cancel).await {
+                    Ok(()) => ApplyOutcome::Created,
+                    Err(e) => ApplyOutcome::Failed(e.to_string()),
+                }
+            };
+            let result = ApplyResult {
+                name: spec.name.clone(),
+                outcome,
+            };
+            progress(&result);
+            (index, result)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by_key(|(index, _)| *index);
+
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
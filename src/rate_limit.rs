@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use axum::http::Method;
+use tokio::time::Instant;
+
+/// Buckets kept before full ones are dropped, so a stream of one-off
+/// clients can't grow the limiter without bound.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// Which limit in `RateLimits` a request counts against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// `POST /vms` and `DELETE /vms/{name}`, the requests that cost
+    /// multipassd the most.
+    Launch,
+    /// Every other request that changes something.
+    Mutation,
+    /// `GET`, `HEAD` and `OPTIONS`.
+    Read,
+}
+
+impl RouteClass {
+    /// The class of a request to `path`, relative to the API root. `None`
+    /// for the health probes, which orchestrators must always be able to
+    /// reach.
+    pub fn of(method: &Method, path: &str) -> Option<Self> {
+        let path = path.trim_end_matches('/');
+        if matches!(path, "/health" | "/livez" | "/readyz") {
+            return None;
+        }
+        let is_vm = path
+            .strip_prefix("/vms/")
+            .is_some_and(|name| !name.is_empty() && !name.contains('/'));
+        Some(match *method {
+            Method::GET | Method::HEAD | Method::OPTIONS => Self::Read,
+            Method::POST if path == "/vms" => Self::Launch,
+            Method::DELETE if is_vm => Self::Launch,
+            _ => Self::Mutation,
+        })
+    }
+}
+
+/// At most `requests` requests every `per`, e.g. `2/min`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl FromStr for RateLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (requests, unit) = value
+            .split_once('/')
+            .with_context(|| format!("invalid rate '{value}': expected N/UNIT, e.g. 30/min"))?;
+        let requests: u32 = requests
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&requests| requests > 0)
+            .with_context(|| format!("invalid rate '{value}': N must be a positive number"))?;
+        let per = match unit.trim() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            _ => bail!("invalid rate '{value}': UNIT must be sec, min or hour"),
+        };
+        Ok(Self { requests, per })
+    }
+}
+
+/// Per-client limits for each `RouteClass`; an unset limit leaves that
+/// class unlimited. The default limits nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub launches: Option<RateLimit>,
+    pub mutations: Option<RateLimit>,
+    pub reads: Option<RateLimit>,
+}
+
+impl RateLimits {
+    /// Parses `launches=2/min,mutations=30/min`, the syntax of
+    /// `safepaw start --rate-limit` and `server.rate_limit`. Any of
+    /// `launches`, `mutations` and `reads` may be left out.
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut limits = Self::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (class, rate) = entry
+                .split_once('=')
+                .with_context(|| format!("invalid rate limit '{entry}': expected CLASS=N/UNIT"))?;
+            let slot = match class.trim() {
+                "launches" => &mut limits.launches,
+                "mutations" => &mut limits.mutations,
+                "reads" => &mut limits.reads,
+                other => {
+                    bail!("unknown rate limit class '{other}': use launches, mutations or reads")
+                }
+            };
+            *slot = Some(rate.parse()?);
+        }
+        Ok(limits)
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.launches.is_none() && self.mutations.is_none() && self.reads.is_none()
+    }
+
+    fn get(&self, class: RouteClass) -> Option<RateLimit> {
+        match class {
+            RouteClass::Launch => self.launches,
+            RouteClass::Mutation => self.mutations,
+            RouteClass::Read => self.reads,
+        }
+    }
+}
+
+/// Tokens left for one client and class, refilled continuously so a limit
+/// of `30/min` allows a burst of 30 and then one request every 2 seconds.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets keyed by client IP and `RouteClass`.
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<(IpAddr, RouteClass), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for `client` from `class`'s bucket, or returns how long
+    /// until the next one is available.
+    pub fn check(&self, client: IpAddr, class: RouteClass) -> Result<(), Duration> {
+        let Some(limit) = self.limits.get(class) else {
+            return Ok(());
+        };
+        let capacity = f64::from(limit.requests);
+        let per_token = limit.per.as_secs_f64() / capacity;
+        let now = Instant::now();
+
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            let limits = &self.limits;
+            buckets.retain(|(_, class), bucket| {
+                limits
+                    .get(*class)
+                    .is_some_and(|limit| now.duration_since(bucket.updated) < limit.per)
+            });
+        }
+        let bucket = buckets.entry((client, class)).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let refilled = now.duration_since(bucket.updated).as_secs_f64() / per_token;
+        bucket.tokens = (bucket.tokens + refilled).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) * per_token))
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Redaction of secrets from multipass command lines before they are logged or
+// echoed back in error messages. The executor always receives the real values.
+
+/// Replacement text for redacted values.
+pub const REDACTED: &str = "***";
+
+/// Decides which parts of a command line are secret.
+///
+/// - `sensitive_flags`: the argument after the flag (or the `--flag=value`
+///   suffix) is hidden, e.g. inline `--cloud-init` data.
+/// - `sensitive_patterns`: case-insensitive `key=` markers whose value is
+///   hidden wherever they appear, e.g. `token=` inside a URL.
+/// - Environment assignments (`NAME=value`) in the command run by `exec`
+///   always have their value hidden.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    pub sensitive_flags: Vec<String>,
+    pub sensitive_patterns: Vec<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self {
+            sensitive_flags: vec!["--cloud-init".to_owned()],
+            sensitive_patterns: vec![
+                "token=".to_owned(),
+                "password=".to_owned(),
+                "passwd=".to_owned(),
+                "secret=".to_owned(),
+                "api_key=".to_owned(),
+                "apikey=".to_owned(),
+            ],
+        }
+    }
+}
+
+impl Redactor {
+    pub fn with_flag(mut self, flag: impl Into<String>) -> Self {
+        self.sensitive_flags.push(flag.into());
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.sensitive_patterns.push(pattern.into());
+        self
+    }
+
+    /// Redacts a full multipass argv (e.g. `["exec", "vm", "--", ...]`).
+    /// Everything after `--` is treated as the command run inside the VM.
+    pub fn redact_args(&self, args: &[String]) -> Vec<String> {
+        self.scan(args, false).0
+    }
+
+    /// Redacts a command that runs inside the VM.
+    pub fn redact_command(&self, command: &[String]) -> Vec<String> {
+        self.scan(command, true).0
+    }
+
+    /// Redacts text produced by running `args`, such as stderr that echoes
+    /// the offending argument back.
+    pub fn redact_output(&self, text: &str, args: &[String]) -> String {
+        let mut redacted = text.to_owned();
+        for secret in self.scan(args, false).1 {
+            redacted = redacted.replace(&secret, REDACTED);
+        }
+        self.redact_text(&redacted)
+    }
+
+    /// Hides the value following every sensitive pattern in `text`.
+    pub fn redact_text(&self, text: &str) -> String {
+        // ASCII lowercasing keeps byte offsets identical to `text`.
+        let lower = text.to_ascii_lowercase();
+        let patterns: Vec<String> = self
+            .sensitive_patterns
+            .iter()
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| pattern.to_ascii_lowercase())
+            .collect();
+
+        let mut redacted = String::with_capacity(text.len());
+        let mut pos = 0;
+        while let Some((start, len)) = patterns
+            .iter()
+            .filter_map(|pattern| lower[pos..].find(pattern).map(|i| (pos + i, pattern.len())))
+            .min_by_key(|(start, _)| *start)
+        {
+            let value_start = start + len;
+            let value_end = text[value_start..]
+                .find(|c: char| c.is_whitespace() || matches!(c, '&' | ';' | ',' | '"' | '\''))
+                .map_or(text.len(), |i| value_start + i);
+
+            redacted.push_str(&text[pos..value_start]);
+            if value_end > value_start {
+                redacted.push_str(REDACTED);
+            }
+            pos = value_end;
+        }
+        redacted.push_str(&text[pos..]);
+        redacted
+    }
+
+    /// Returns the redacted arguments and the secret values that were hidden.
+    fn scan(&self, args: &[String], mut in_command: bool) -> (Vec<String>, Vec<String>) {
+        let mut redacted = Vec::with_capacity(args.len());
+        let mut secrets = Vec::new();
+        let mut hide_next = false;
+
+        for arg in args {
+            if hide_next {
+                hide_next = false;
+                secrets.push(arg.clone());
+                redacted.push(REDACTED.to_owned());
+                continue;
+            }
+
+            if !in_command && arg == "--" {
+                in_command = true;
+                redacted.push(arg.clone());
+                continue;
+            }
+
+            if !in_command && self.sensitive_flags.iter().any(|flag| flag == arg) {
+                hide_next = true;
+                redacted.push(arg.clone());
+                continue;
+            }
+
+            if let Some((key, value)) = arg.split_once('=') {
+                let hide = if in_command {
+                    is_env_name(key)
+                } else {
+                    self.sensitive_flags.iter().any(|flag| flag == key)
+                };
+                if hide {
+                    if !value.is_empty() {
+                        secrets.push(value.to_owned());
+                    }
+                    redacted.push(format!("{key}={REDACTED}"));
+                    continue;
+                }
+            }
+
+            redacted.push(self.redact_text(arg));
+        }
+
+        (redacted, secrets)
+    }
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_uppercase() || c == '_')
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
@@ -1,34 +1,154 @@
-use std::net::SocketAddr;
-use std::sync::Arc;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use axum::{
     Json, Router,
-    body::Body,
-    extract::{State, rejection::JsonRejection},
-    http::{HeaderValue, Method, Response, StatusCode, Uri, header},
-    response::IntoResponse,
+    body::{Body, Bytes},
+    extract::{
+        ConnectInfo, DefaultBodyLimit, OriginalUri, Query, RawPathParams, Request, State,
+        rejection::JsonRejection,
+    },
+    http::{HeaderMap, HeaderValue, Method, Response, StatusCode, Uri, header},
+    middleware::{self, Next},
+    response::{
+        Html, IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{get, post},
 };
+use axum_server::tls_rustls::RustlsConfig;
+use futures::Stream;
 use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
 use tokio::signal;
-use tower_http::cors::CorsLayer;
-use tracing::{info, warn};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+use tracing::{debug, info, warn};
+use utoipa::{OpenApi, ToSchema};
 
-use crate::agent::{AgentManager, AgentType, OnboardAgentRequest};
-use crate::util::HandlerResult;
-use crate::vm::{VmApi, handlers};
+use crate::agent::{
+    AgentConfig, AgentInstance, AgentManager, AgentStatus, AgentType, OnboardAgentRequest,
+    ProviderConfig,
+};
+use crate::api_error::{ApiError, ApiErrorBody, ErrorCode, classify_error, with_request_id};
+use crate::doctor::MultipassVersions;
+use crate::events::{EVENT_POLL_INTERVAL, EventBus, VmEvent, VmEventKind, watch_vm_states};
+use crate::info_cache::InfoCache;
+use crate::jobs::{
+    CancelError, DEFAULT_JOB_RETENTION, Job, JobKind, JobProgress, JobState, JobStore,
+};
+use crate::rate_limit::{RateLimiter, RateLimits, RouteClass};
+use crate::util::{HandlerResult, verbose_error_details};
+use crate::vm::{
+    DEFAULT_LAUNCH_CONCURRENCY, ImageInfo, LaunchSpec, NameError, StopOptions, VmApi, VmListing,
+    VmState, VmStatusResponse, VmSummary, handlers, run_until_disconnect, validate_vm_name,
+};
 
 // Embed the UI assets directly into the binary
 #[derive(RustEmbed)]
 #[folder = "ui/"]
 struct UiAssets;
 
+/// Environment variable holding the allowed CORS origins: `*` or a
+/// comma-separated list such as `http://localhost:8888,https://paw.example`.
+pub const CORS_ORIGINS_ENV: &str = "SAFEPAW_CORS_ORIGINS";
+
+/// Which browser origins may call the REST API.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CorsConfig {
+    /// No origins configured: allow everything (the historical behavior).
+    #[default]
+    Permissive,
+    /// `*`: any origin, but without credentials as the CORS spec requires.
+    AnyOrigin,
+    /// Only these origins; credentials are allowed.
+    Origins(Vec<String>),
+}
+
+impl CorsConfig {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(Self::Permissive);
+        }
+        if value == "*" {
+            return Ok(Self::AnyOrigin);
+        }
+
+        let origins = value
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| {
+                if origin == "*" {
+                    anyhow::bail!("'*' cannot be combined with specific CORS origins");
+                }
+                HeaderValue::from_str(origin)
+                    .with_context(|| format!("invalid CORS origin: {}", origin))?;
+                Ok(origin.to_owned())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::Origins(origins))
+    }
+
+    /// Reads [`CORS_ORIGINS_ENV`], falling back to permissive when unset.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(CORS_ORIGINS_ENV) {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Ok(Self::Permissive),
+        }
+    }
+
+    fn layer(&self) -> CorsLayer {
+        match self {
+            Self::Permissive => CorsLayer::permissive(),
+            Self::AnyOrigin => CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any),
+            Self::Origins(origins) => CorsLayer::new()
+                .allow_origin(AllowOrigin::list(
+                    origins
+                        .iter()
+                        .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+                ))
+                .allow_methods(AllowMethods::mirror_request())
+                .allow_headers(AllowHeaders::mirror_request())
+                .allow_credentials(true),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub(crate) vm_api: Arc<dyn VmApi>,
     pub(crate) agent_manager: Arc<dyn AgentManager>,
+    pub(crate) cors: CorsConfig,
+    pub(crate) events: Arc<EventBus>,
+    /// Serves `GET /vms/{name}` when set; see `with_info_cache`.
+    pub(crate) info_cache: Option<Arc<InfoCache>>,
+    /// Background VM operations, polled through `GET /jobs/{id}`.
+    pub(crate) jobs: Arc<JobStore>,
+    /// Run every `POST /vms` as a job, as if it asked for `?async=true`.
+    pub(crate) async_launch: bool,
+    /// Answers clients over their limit with a 429; see `with_rate_limits`.
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    /// Requests whose handler hasn't returned yet; see `drain`.
+    pub(crate) in_flight: Arc<AtomicUsize>,
+    /// Cancels the multipass commands of in-flight requests once `drain`
+    /// gives up on them.
+    pub(crate) shutdown: CancellationToken,
+    /// The last deep `/health` probe and when it ran.
+    pub(crate) health_probe: Arc<Mutex<Option<(Instant, HealthChecks)>>>,
 }
 
 impl AppState {
@@ -36,250 +156,1265 @@ impl AppState {
         Self {
             vm_api,
             agent_manager,
+            cors: CorsConfig::default(),
+            events: Arc::new(EventBus::default()),
+            info_cache: None,
+            jobs: Arc::new(JobStore::default()),
+            async_launch: false,
+            rate_limiter: None,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            shutdown: CancellationToken::new(),
+            health_probe: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The bus `GET /events` streams from, e.g. to publish from elsewhere.
+    pub fn events(&self) -> Arc<EventBus> {
+        self.events.clone()
+    }
+
+    /// The background jobs behind `/jobs`, e.g. to run its garbage collector.
+    pub fn jobs(&self) -> Arc<JobStore> {
+        self.jobs.clone()
+    }
+
+    pub fn with_cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Caches VM info for `ttl`; VM operations through the API drop the
+    /// entry of the VM they touched. A zero `ttl` leaves caching off.
+    pub fn with_info_cache(mut self, ttl: Duration) -> Self {
+        self.info_cache = (!ttl.is_zero()).then(|| Arc::new(InfoCache::new(ttl)));
+        self
+    }
+
+    /// Runs background jobs through `jobs`, e.g. to change how long
+    /// finished ones are kept or how many run at once.
+    pub fn with_jobs(mut self, jobs: JobStore) -> Self {
+        self.jobs = Arc::new(jobs);
+        self
+    }
+
+    /// Makes `POST /vms` launch in the background unless the request says
+    /// `?async=false`.
+    pub fn with_async_launch(mut self, async_launch: bool) -> Self {
+        self.async_launch = async_launch;
+        self
+    }
+
+    /// The deep `/health` checks, probed at most once per `HEALTH_PROBE_TTL`.
+    async fn health_checks(&self) -> HealthChecks {
+        if let Some((probed_at, checks)) = self.lock_health_probe().as_ref()
+            && probed_at.elapsed() < HEALTH_PROBE_TTL
+        {
+            return checks.clone();
+        }
+        let checks = HealthChecks::probe(self.vm_api.as_ref()).await;
+        *self.lock_health_probe() = Some((Instant::now(), checks.clone()));
+        checks
+    }
+
+    fn lock_health_probe(&self) -> std::sync::MutexGuard<'_, Option<(Instant, HealthChecks)>> {
+        self.health_probe
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// What shutdown still waits for: in-flight requests and unfinished jobs.
+    pub fn pending(&self) -> PendingWork {
+        PendingWork {
+            requests: self.in_flight.load(Ordering::SeqCst),
+            jobs: self.jobs.unfinished(),
+        }
+    }
+
+    /// Waits up to `timeout` for in-flight requests and background jobs to
+    /// finish, logging what is left every `SHUTDOWN_PROGRESS_INTERVAL`. Then
+    /// cancels whatever is still running and gives it
+    /// `SHUTDOWN_CANCEL_GRACE` to stop. Returns whether everything finished
+    /// within `timeout`.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut next_report = Instant::now() + SHUTDOWN_PROGRESS_INTERVAL;
+        loop {
+            let pending = self.pending();
+            if pending.is_empty() {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                warn!("shutdown timed out; cancelling {}", pending);
+                break;
+            }
+            if now >= next_report {
+                info!("shutting down; waiting for {}", pending);
+                next_report += SHUTDOWN_PROGRESS_INTERVAL;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL.min(deadline - now)).await;
+        }
+
+        self.shutdown.cancel();
+        self.jobs.cancel_all();
+        let deadline = Instant::now() + SHUTDOWN_CANCEL_GRACE;
+        while !self.pending().is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+        false
+    }
+
+    /// Limits how often each client IP may call the API. Limits that are
+    /// all unset leave rate limiting off.
+    pub fn with_rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limiter = (!limits.is_disabled()).then(|| Arc::new(RateLimiter::new(limits)));
+        self
+    }
+}
+
+/// How long shutdown waits for requests and jobs unless configured otherwise.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a draining server logs what it is still waiting for.
+pub const SHUTDOWN_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long cancelled operations get to stop once the shutdown timeout hit.
+pub const SHUTDOWN_CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Work that keeps a shutting-down server from exiting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingWork {
+    pub requests: usize,
+    pub jobs: Vec<Job>,
+}
+
+impl PendingWork {
+    pub fn is_empty(&self) -> bool {
+        self.requests == 0 && self.jobs.is_empty()
+    }
+}
+
+impl std::fmt::Display for PendingWork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} request(s)", self.requests)?;
+        if !self.jobs.is_empty() {
+            let jobs: Vec<String> = self
+                .jobs
+                .iter()
+                .map(|job| format!("{} {}", job.kind.as_str(), job.vm_name))
+                .collect();
+            write!(f, " and {} job(s): {}", jobs.len(), jobs.join(", "))?;
         }
+        Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct VmStatusDto {
     pub name: String,
     pub state: String,
     pub ipv4: Option<Vec<String>>,
+    pub ipv6: Option<Vec<String>>,
     pub release: Option<String>,
+    pub image_release: Option<String>,
+    /// As multipass reports it, e.g. `"2"`.
+    pub cpu_count: Option<String>,
     pub memory_total: Option<u64>,
     pub memory_used: Option<u64>,
     pub disk_total: Option<u64>,
     pub disk_used: Option<u64>,
+    /// Errors multipass reported next to the VM's details.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
-// REST API handlers
-async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
-}
-
-async fn list_vms(State(state): State<AppState>) -> impl IntoResponse {
-    match state.vm_api.list().await {
-        Ok(vms) => {
-            let dtos: Vec<VmStatusDto> = vms
-                .into_iter()
-                .map(|vm| VmStatusDto {
-                    name: vm.name,
-                    state: vm.state,
-                    ipv4: vm.ipv4,
-                    release: vm.release,
-                    memory_total: None,
-                    memory_used: None,
-                    disk_total: None,
-                    disk_used: None,
-                })
-                .collect();
-            (StatusCode::OK, Json(dtos)).into_response()
+/// Body of `GET /vms` when `limit` or `offset` is given: one name-sorted
+/// page of VMs.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VmPage {
+    pub items: Vec<VmStatusDto>,
+    /// VMs matching the filters across all pages.
+    pub total: usize,
+    /// `offset` of the next page, or `null` on the last one.
+    pub next_offset: Option<usize>,
+    /// Errors multipass reported for VMs it could not list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Body of `GET /vms`: the bare array unless pagination was asked for.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum VmListResponse {
+    All(Vec<VmStatusDto>),
+    Page(VmPage),
+}
+
+/// Body of a VM operation that was queued as a background job.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct JobAccepted {
+    pub job_id: String,
+    /// Where to poll the job, relative to the server root.
+    pub status_url: String,
+}
+
+/// Body of `GET /health`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthStatus {
+    /// `ok`; a failed deep check is a 503 error instead.
+    pub status: String,
+    /// What `?deep=true` probed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checks: Option<HealthChecks>,
+}
+
+impl HealthStatus {
+    fn ok() -> Self {
+        Self {
+            status: "ok".to_owned(),
+            checks: None,
         }
-        Err(e) => {
-            warn!("failed to list VMs: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": format!("{}", e)})),
-            )
-                .into_response()
+    }
+}
+
+/// How long a deep `/health` probe is reused, so frequent monitoring
+/// doesn't run multipass on every request.
+pub const HEALTH_PROBE_TTL: Duration = Duration::from_secs(5);
+
+/// Results of the multipass probes behind `GET /health?deep=true`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthChecks {
+    /// `multipass version` ran and printed the client version.
+    pub multipass_binary: HealthCheck,
+    /// multipassd answered `multipass version` with its version.
+    pub daemon: HealthCheck,
+    /// `multipass list` succeeded.
+    pub list: HealthCheck,
+    /// How long `multipass list` took; null when it failed.
+    pub list_latency_ms: Option<u64>,
+}
+
+impl HealthChecks {
+    async fn probe(api: &dyn VmApi) -> Self {
+        let (multipass_binary, daemon) = match api.version().await {
+            Ok(versions) => (
+                HealthCheck::found(versions.client, "multipass printed no client version"),
+                HealthCheck::found(versions.daemon, "multipassd did not report a version"),
+            ),
+            Err(err) => (
+                HealthCheck::failed(err.to_string()),
+                HealthCheck::failed("not checked: multipass is unavailable"),
+            ),
+        };
+        let started = Instant::now();
+        let (list, list_latency_ms) = match api.list().await {
+            Ok(_) => (
+                HealthCheck {
+                    ok: true,
+                    detail: None,
+                },
+                Some(started.elapsed().as_millis() as u64),
+            ),
+            Err(err) => (HealthCheck::failed(err.to_string()), None),
+        };
+        Self {
+            multipass_binary,
+            daemon,
+            list,
+            list_latency_ms,
         }
     }
+
+    pub fn healthy(&self) -> bool {
+        self.multipass_binary.ok && self.daemon.ok && self.list.ok
+    }
 }
 
-async fn get_vm_info(
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthCheck {
+    pub ok: bool,
+    /// The version found, or why the check failed.
+    pub detail: Option<String>,
+}
+
+impl HealthCheck {
+    fn found(version: Option<String>, missing: &str) -> Self {
+        match version {
+            Some(version) => Self {
+                ok: true,
+                detail: Some(version),
+            },
+            None => Self::failed(missing),
+        }
+    }
+
+    fn failed(reason: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            detail: Some(reason.into()),
+        }
+    }
+}
+
+/// Body of a successful `GET /readyz`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessStatus {
+    pub status: String,
+    pub multipass: Option<String>,
+    pub multipassd: String,
+}
+
+/// Body of a successful mutation: what was done to which VM or agent.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MutationResponse {
+    /// The VM, or for agent stop/delete the agent, acted on.
+    pub name: String,
+    /// `launch`, `start`, `stop`, `restart`, `delete`, `install_agent`,
+    /// `stop_agent` or `delete_agent`.
+    pub action: String,
+    pub message: String,
+}
+
+impl MutationResponse {
+    fn new(name: impl Into<String>, action: &str, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            action: action.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl ApiError {
+    /// Error for a failure returned straight from `VmApi`, with the cause
+    /// chain under `details` when verbose errors are enabled.
+    pub fn from_vm_api(err: &anyhow::Error) -> Self {
+        let (status, code) =
+            classify_error(err).unwrap_or((StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::Internal));
+        Self {
+            details: verbose_error_details(err),
+            ..Self::new(status, code, err.to_string())
+        }
+    }
+
+    /// 422 for a name `validate_vm_name` rejected, naming the broken rule
+    /// in `details.violation`.
+    fn invalid_name(err: &NameError) -> Self {
+        Self::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::ValidationFailed,
+            err.to_string(),
+        )
+        .with_details(serde_json::json!({
+            "field": "name",
+            "violation": err.violation.code(),
+        }))
+    }
+
+    fn from_cancel(err: CancelError) -> Self {
+        let (status, code) = match err {
+            CancelError::NotFound(_) => (StatusCode::NOT_FOUND, ErrorCode::JobNotFound),
+            CancelError::NotQueued { .. } => (StatusCode::CONFLICT, ErrorCode::JobNotQueued),
+        };
+        Self::new(status, code, err.to_string())
+    }
+
+    /// Error for a failed `HandlerResult`, classified by the error it wraps;
+    /// `fallback` when it wraps no `VmError`.
+    fn from_handler_or<T>(result: HandlerResult<T>, fallback: (StatusCode, ErrorCode)) -> Self {
+        let (status, code) = result
+            .error
+            .as_ref()
+            .and_then(|err| classify_error(err))
+            .unwrap_or(fallback);
+        Self {
+            details: result.error_details,
+            ..Self::new(status, code, result.message)
+        }
+    }
+
+    /// Error for a failed `HandlerResult`, classified by the error it wraps.
+    pub fn from_handler<T>(result: HandlerResult<T>) -> Self {
+        Self::from_handler_or(
+            result,
+            (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::Internal),
+        )
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    /// A request body that could not be read as the expected JSON. Oversized
+    /// bodies keep their 413 and fields the endpoint doesn't take are a 422;
+    /// everything else is a 400.
+    fn from(rejection: JsonRejection) -> Self {
+        let (status, code) = match &rejection {
+            _ if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                (StatusCode::PAYLOAD_TOO_LARGE, ErrorCode::PayloadTooLarge)
+            }
+            JsonRejection::JsonDataError(err) if err.body_text().contains("unknown field") => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::ValidationFailed,
+            ),
+            _ => (StatusCode::BAD_REQUEST, ErrorCode::ValidationFailed),
+        };
+        Self::new(status, code, rejection.body_text())
+    }
+}
+
+/// OpenAPI description of the VM API, served at `GET /openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "SafePaw API", description = "Manage SafePaw VMs"),
+    paths(
+        health_check,
+        livez,
+        readyz,
+        vm_events,
+        list_vms,
+        launch_vm,
+        get_vm_info,
+        delete_vm,
+        start_vm,
+        stop_vm,
+        restart_vm,
+        list_images,
+        list_jobs,
+        get_job,
+        cancel_job,
+        install_agent,
+        check_agent_installed,
+        onboard_agent,
+        list_agents,
+        get_agent,
+        stop_agent,
+        delete_agent
+    ),
+    components(schemas(
+        VmStatusDto,
+        VmPage,
+        VmListResponse,
+        LaunchVmRequest,
+        ImageInfo,
+        JobAccepted,
+        Job,
+        JobKind,
+        JobState,
+        HealthStatus,
+        HealthChecks,
+        HealthCheck,
+        ReadinessStatus,
+        MutationResponse,
+        ApiErrorBody,
+        ErrorCode,
+        InstallAgentRequest,
+        CheckAgentRequest,
+        OnboardAgentRequest,
+        AgentInstalledResponse,
+        AgentResponse,
+        AgentListResponse,
+        AgentInstance,
+        AgentConfig,
+        AgentType,
+        AgentStatus,
+        ProviderConfig
+    ))
+)]
+pub struct ApiDoc;
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI for `openapi.json`. The page is tiny and pulls the viewer
+/// itself from a CDN, so it needs network access in the browser; the spec
+/// URL is relative so it works under `/api` as well.
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>SafePaw API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+// REST API handlers
+#[utoipa::path(
+    get,
+    path = "/health",
+    params(
+        ("deep" = Option<bool>, Query, description = "Also probe multipass version, the daemon and `multipass list`; results are cached for a few seconds")
+    ),
+    responses(
+        (status = 200, description = "Server is up, and multipass too when deep", body = HealthStatus),
+        (status = 400, description = "Malformed deep flag", body = ApiErrorBody),
+        (status = 503, description = "A deep check failed; `details` holds `status: degraded` and the checks", body = ApiErrorBody)
+    )
+)]
+async fn health_check(
     State(state): State<AppState>,
-    axum::extract::Path(name): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    match state.vm_api.info(&name).await {
-        Ok(info) => {
-            let dto = VmStatusDto {
-                name: info.name,
-                state: info.state,
-                ipv4: info.ipv4,
-                release: info.release,
-                memory_total: info.memory_total,
-                memory_used: info.memory_used,
-                disk_total: info.disk_total,
-                disk_used: info.disk_used,
-            };
-            (StatusCode::OK, Json(dto)).into_response()
-        }
-        Err(e) => {
-            warn!("failed to get VM info for {}: {}", name, e);
-            (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": format!("{}", e)})),
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response<Body>, ApiError> {
+    if !bool_param(&params, "deep", false)? {
+        return Ok(Json(HealthStatus::ok()).into_response());
+    }
+    let checks = state.health_checks().await;
+    if !checks.healthy() {
+        let details = serde_json::json!({ "status": "degraded", "checks": checks });
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::MultipassUnavailable,
+            "a deep health check failed",
+        )
+        .with_details(details));
+    }
+    let body = HealthStatus {
+        status: "ok".to_owned(),
+        checks: Some(checks),
+    };
+    Ok(Json(body).into_response())
+}
+
+/// Liveness probe: 200 whenever the process can answer, like `/health`.
+#[utoipa::path(
+    get,
+    path = "/livez",
+    responses((status = 200, description = "Server is up", body = HealthStatus))
+)]
+async fn livez() -> Json<HealthStatus> {
+    Json(HealthStatus::ok())
+}
+
+/// Readiness probe: 200 once multipassd answers `multipass version`, 503
+/// while multipass is missing or its daemon is unreachable.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "multipassd is reachable", body = ReadinessStatus),
+        (status = 503, description = "multipass is missing or multipassd is unreachable", body = ApiErrorBody)
+    )
+)]
+async fn readyz(State(state): State<AppState>) -> Response<Body> {
+    match state.vm_api.version().await {
+        Ok(MultipassVersions {
+            client,
+            daemon: Some(daemon),
+        }) => Json(ReadinessStatus {
+            status: "ready".to_owned(),
+            multipass: client,
+            multipassd: daemon,
+        })
+        .into_response(),
+        Ok(_) => ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::MultipassUnavailable,
+            "multipassd did not report a version; is the daemon running?",
+        )
+        .into_response(),
+        Err(err) => ApiError {
+            details: verbose_error_details(&err),
+            ..ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorCode::MultipassUnavailable,
+                err.to_string(),
             )
-                .into_response()
+        }
+        .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/vms",
+    params(
+        ("state" = Option<Vec<String>>, Query, description = "Only VMs in this state, case-insensitive; repeat to match any of several"),
+        ("limit" = Option<usize>, Query, description = "Page size; returns a VmPage sorted by name"),
+        ("offset" = Option<usize>, Query, description = "VMs to skip; returns a VmPage sorted by name")
+    ),
+    responses(
+        (status = 200, description = "All VMs as an array, or a VmPage when limit or offset is given", body = VmListResponse,
+            headers(("warning" = String, description = "One per VM multipass failed to list, as `199 safepaw \"<error>\"`"))),
+        (status = 400, description = "Unknown state or bad limit/offset", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
+async fn list_vms(
+    State(state): State<AppState>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<(HeaderMap, Json<VmListResponse>), ApiError> {
+    let query = ListVmsQuery::parse(&params).map_err(|err| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
+            err.to_string(),
+        )
+    })?;
+    let VmListing { mut vms, warnings } = state.vm_api.list_with_warnings().await.map_err(|e| {
+        warn!("failed to list VMs: {}", e);
+        ApiError::from_vm_api(&e)
+    })?;
+    let headers = warning_headers(&warnings);
+    vms.retain(|vm| query.states.is_empty() || query.states.contains(&vm.state));
+    if !query.paginated() {
+        return Ok((
+            headers,
+            Json(VmListResponse::All(
+                vms.into_iter().map(VmStatusDto::from).collect(),
+            )),
+        ));
+    }
+
+    vms.sort_by(|a, b| a.name.cmp(&b.name));
+    let total = vms.len();
+    let offset = query.offset.unwrap_or(0).min(total);
+    let end = query
+        .limit
+        .map_or(total, |limit| offset.saturating_add(limit).min(total));
+    let items: Vec<VmStatusDto> = vms.drain(offset..end).map(VmStatusDto::from).collect();
+    Ok((
+        headers,
+        Json(VmListResponse::Page(VmPage {
+            items,
+            total,
+            next_offset: (end < total).then_some(end),
+            warnings,
+        })),
+    ))
+}
+
+/// One `Warning: 199` header per partial failure multipass reported, since
+/// the bare-array form of `GET /vms` has no room for them in the body.
+fn warning_headers(warnings: &[String]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for warning in warnings {
+        let text: String = warning
+            .chars()
+            .map(|c| match c {
+                '"' | '\\' => '\'',
+                c if c == ' ' || c.is_ascii_graphic() => c,
+                _ => '?',
+            })
+            .collect();
+        let value = HeaderValue::from_str(&format!("199 safepaw \"{text}\""))
+            .expect("sanitized warnings are valid headers");
+        headers.append(header::WARNING, value);
+    }
+    headers
+}
+
+/// Query parameters of `GET /vms`.
+#[derive(Debug, Default)]
+struct ListVmsQuery {
+    states: Vec<VmState>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl ListVmsQuery {
+    /// Reads `state` (repeatable), `limit` and `offset`, ignoring anything
+    /// else.
+    fn parse(params: &[(String, String)]) -> Result<Self> {
+        let mut query = Self::default();
+        for (key, value) in params {
+            match key.as_str() {
+                "state" => query.states.push(VmState::parse_known(value)?),
+                "limit" => {
+                    let limit = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .with_context(|| {
+                            format!("limit must be a whole number of at least 1, got '{value}'")
+                        })?;
+                    query.limit = Some(limit);
+                }
+                "offset" => {
+                    let offset = value
+                        .parse::<usize>()
+                        .with_context(|| format!("offset must be a whole number, got '{value}'"))?;
+                    query.offset = Some(offset);
+                }
+                _ => {}
+            }
+        }
+        Ok(query)
+    }
+
+    fn paginated(&self) -> bool {
+        self.limit.is_some() || self.offset.is_some()
+    }
+}
+
+impl From<VmSummary> for VmStatusDto {
+    fn from(vm: VmSummary) -> Self {
+        Self {
+            name: vm.name,
+            state: vm.state.to_string(),
+            ipv4: vm.ipv4,
+            ipv6: vm.ipv6,
+            release: vm.release,
+            image_release: None,
+            cpu_count: None,
+            memory_total: None,
+            memory_used: None,
+            disk_total: None,
+            disk_used: None,
+            warnings: vec![],
+        }
+    }
+}
+
+impl From<VmStatusResponse> for VmStatusDto {
+    fn from(info: VmStatusResponse) -> Self {
+        Self {
+            name: info.name,
+            state: info.state.to_string(),
+            ipv4: info.ipv4,
+            ipv6: info.ipv6,
+            release: info.release,
+            image_release: info.image_release,
+            cpu_count: info.cpu_count,
+            memory_total: info.memory_total,
+            memory_used: info.memory_used,
+            disk_total: info.disk_total,
+            disk_used: info.disk_used,
+            warnings: info.warnings,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[utoipa::path(
+    get,
+    path = "/vms/{name}",
+    params(("name" = String, Path, description = "VM name")),
+    responses(
+        (status = 200, description = "VM details", body = VmStatusDto),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 422, description = "Invalid VM name", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
+async fn get_vm_info(
+    State(state): State<AppState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<Json<VmStatusDto>, ApiError> {
+    let info = match &state.info_cache {
+        Some(cache) => cache.info(state.vm_api.as_ref(), &name).await,
+        None => state.vm_api.info(&name).await,
+    };
+    let info = info.map_err(|e| {
+        warn!("failed to get VM info for {}: {}", name, e);
+        ApiError::from_vm_api(&e)
+    })?;
+    Ok(Json(VmStatusDto::from(info)))
+}
+
+/// Largest request body the API accepts. Requests are small JSON objects, so
+/// anything bigger is a client bug or abuse.
+pub const MAX_REQUEST_BODY_BYTES: usize = 64 * 1024;
+
+/// Body of `POST /vms`. Unset sizing falls back to the server's `[vm]`
+/// defaults, then to multipass's own.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 struct LaunchVmRequest {
     name: String,
+    #[serde(default)]
+    cpus: Option<u32>,
+    /// E.g. `4G`.
+    #[serde(default)]
+    memory: Option<String>,
+    /// E.g. `20G`.
+    #[serde(default)]
+    disk: Option<String>,
+    /// Release or alias such as `24.04`, or an image URL.
+    #[serde(default)]
+    image: Option<String>,
+    /// cloud-config document applied on first boot.
+    #[serde(default)]
+    cloud_init: Option<String>,
+}
+
+impl From<LaunchVmRequest> for LaunchSpec {
+    fn from(request: LaunchVmRequest) -> Self {
+        Self {
+            cpus: request.cpus,
+            memory: request.memory,
+            disk: request.disk,
+            image: request.image,
+            cloud_init: request.cloud_init,
+            ..Self::new(request.name)
+        }
+    }
 }
 
+#[utoipa::path(
+    post,
+    path = "/vms",
+    request_body = LaunchVmRequest,
+    params(
+        ("async" = Option<bool>, Query, description = "Launch in the background and return a job to poll; defaults to the server's async_launch setting")
+    ),
+    responses(
+        (status = 201, description = "VM launched", body = MutationResponse),
+        (status = 202, description = "Launch queued as a job", body = JobAccepted),
+        (status = 400, description = "Malformed request body or async flag", body = ApiErrorBody),
+        (status = 413, description = "Request body too large", body = ApiErrorBody),
+        (status = 409, description = "A VM with that name exists", body = ApiErrorBody),
+        (status = 422, description = "Invalid launch options or unknown field", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Launch failed", body = ApiErrorBody)
+    )
+)]
 async fn launch_vm(
     State(state): State<AppState>,
-    Json(payload): Json<LaunchVmRequest>,
-) -> impl IntoResponse {
-    let result = handlers::launch_vm(state.vm_api.as_ref(), &payload.name).await;
-    if result.success {
-        (
-            StatusCode::CREATED,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": result.message})),
-        )
-            .into_response()
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<Vec<(String, String)>>,
+    payload: Result<Json<LaunchVmRequest>, JsonRejection>,
+) -> Result<Response<Body>, ApiError> {
+    let run_async = bool_param(&params, "async", state.async_launch)?;
+    let Json(payload) = payload?;
+    let spec = LaunchSpec::from(payload);
+    spec.validate()
+        .map_err(|err| match err.downcast_ref::<NameError>() {
+            Some(err) => ApiError::invalid_name(err),
+            None => ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::ValidationFailed,
+                err.to_string(),
+            ),
+        })?;
+    let name = spec.name.clone();
+
+    if run_async {
+        let vm_api = state.vm_api.clone();
+        let job_id = spawn_vm_job(
+            &state,
+            JobKind::Launch,
+            VmEventKind::Launched,
+            &name,
+            |progress| async move {
+                let on_progress = |line: &str| progress.report(line);
+                handlers::launch_vm_with_progress(
+                    vm_api.as_ref(),
+                    &spec,
+                    &on_progress,
+                    progress.cancel_token(),
+                )
+                .await
+            },
+        );
+        return Ok(job_accepted(uri.path().strip_suffix("/vms"), job_id));
+    }
+
+    let vm_api = state.vm_api.clone();
+    let result = run_until_disconnect(&state.shutdown, |cancel| async move {
+        handlers::launch_vm(vm_api.as_ref(), &spec, &cancel).await
+    })
+    .await
+    .unwrap_or_else(|e| HandlerResult::err(e.to_string()));
+    vm_operation_response(&state, VmEventKind::Launched, &name, "launch", result)
+        .map(|message| (StatusCode::CREATED, message).into_response())
+}
+
+/// The last `name` query parameter as a bool, or `default` when absent.
+fn bool_param(params: &[(String, String)], name: &str, default: bool) -> Result<bool, ApiError> {
+    match params.iter().rev().find(|(key, _)| key == name) {
+        Some((_, value)) => value.parse::<bool>().map_err(|_| {
+            ApiError::new(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::ValidationFailed,
+                format!("{name} must be true or false, got '{value}'"),
+            )
+        }),
+        None => Ok(default),
     }
 }
 
+/// Queues a VM operation as a job and returns its id. The operation is not
+/// tied to the request, so it keeps going after the client disconnects;
+/// once it is done, `event` is published as for a blocking request.
+fn spawn_vm_job<F, Fut>(
+    state: &AppState,
+    kind: JobKind,
+    event: VmEventKind,
+    name: &str,
+    operation: F,
+) -> String
+where
+    F: FnOnce(JobProgress) -> Fut + Send + 'static,
+    Fut: Future<Output = HandlerResult<()>> + Send + 'static,
+{
+    let job_state = state.clone();
+    let vm_name = name.to_owned();
+    state.jobs.spawn(kind, name, move |progress| async move {
+        let result = operation(progress).await;
+        match vm_operation_response(&job_state, event, &vm_name, kind.as_str(), result) {
+            Ok(Json(message)) => Ok(message.message),
+            Err(err) => {
+                warn!(vm_name = %vm_name, "{} job failed: {}", kind.as_str(), err.message);
+                Err(err.message)
+            }
+        }
+    })
+}
+
+/// 202 pointing at job `job_id`. `api_base` is the path the API is nested
+/// under (e.g. `/api` in single-port mode), so `status_url` works as is.
+fn job_accepted(api_base: Option<&str>, job_id: String) -> Response<Body> {
+    let status_url = format!("{}/jobs/{}", api_base.unwrap_or_default(), job_id);
+    let location = HeaderValue::from_str(&status_url).ok();
+    let mut response = (
+        StatusCode::ACCEPTED,
+        Json(JobAccepted { job_id, status_url }),
+    )
+        .into_response();
+    if let Some(location) = location {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    response
+}
+
+#[utoipa::path(
+    get,
+    path = "/images",
+    responses(
+        (status = 200, description = "Images and blueprints that can be launched", body = Vec<ImageInfo>),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
+async fn list_images(State(state): State<AppState>) -> Result<Json<Vec<ImageInfo>>, ApiError> {
+    state.vm_api.find().await.map(Json).map_err(|e| {
+        warn!("failed to list images: {}", e);
+        ApiError::from_vm_api(&e)
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    responses((status = 200, description = "Every job still kept, oldest first", body = Vec<Job>))
+)]
+async fn list_jobs(State(state): State<AppState>) -> Json<Vec<Job>> {
+    Json(state.jobs.list())
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    params(("id" = String, Path, description = "Job id from the 202 response")),
+    responses(
+        (status = 200, description = "Job status", body = Job),
+        (status = 404, description = "No such job", body = ApiErrorBody)
+    )
+)]
+async fn get_job(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .jobs
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::from_cancel(CancelError::NotFound(id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/jobs/{id}",
+    params(("id" = String, Path, description = "Job id from the 202 response")),
+    responses(
+        (status = 200, description = "Job cancelled before it started", body = Job),
+        (status = 404, description = "No such job", body = ApiErrorBody),
+        (status = 409, description = "Job already running or finished", body = ApiErrorBody)
+    )
+)]
+async fn cancel_job(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .jobs
+        .cancel(&id)
+        .map(Json)
+        .map_err(ApiError::from_cancel)
+}
+
+#[utoipa::path(
+    post,
+    path = "/vms/{name}/start",
+    params(("name" = String, Path, description = "VM name")),
+    responses(
+        (status = 200, description = "VM started", body = MutationResponse),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 422, description = "Invalid VM name", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
 async fn start_vm(
     State(state): State<AppState>,
     axum::extract::Path(name): axum::extract::Path<String>,
-) -> impl IntoResponse {
+) -> Result<Json<MutationResponse>, ApiError> {
     let result = handlers::start_vm(state.vm_api.as_ref(), &name).await;
-    if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": result.message})),
-        )
-            .into_response()
-    }
+    vm_operation_response(&state, VmEventKind::Started, &name, "start", result)
 }
 
+#[utoipa::path(
+    post,
+    path = "/vms/{name}/stop",
+    params(("name" = String, Path, description = "VM name")),
+    responses(
+        (status = 200, description = "VM stopped", body = MutationResponse),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 422, description = "Invalid VM name", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
 async fn stop_vm(
     State(state): State<AppState>,
     axum::extract::Path(name): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    let result = handlers::stop_vm(state.vm_api.as_ref(), &name).await;
-    if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": result.message})),
-        )
-            .into_response()
-    }
+) -> Result<Json<MutationResponse>, ApiError> {
+    let result = handlers::stop_vm(state.vm_api.as_ref(), &name, &StopOptions::default()).await;
+    vm_operation_response(&state, VmEventKind::Stopped, &name, "stop", result)
 }
 
+#[utoipa::path(
+    post,
+    path = "/vms/{name}/restart",
+    params(("name" = String, Path, description = "VM name")),
+    responses(
+        (status = 200, description = "VM restarted", body = MutationResponse),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 422, description = "Invalid VM name", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
 async fn restart_vm(
     State(state): State<AppState>,
     axum::extract::Path(name): axum::extract::Path<String>,
-) -> impl IntoResponse {
+) -> Result<Json<MutationResponse>, ApiError> {
     let result = handlers::restart_vm(state.vm_api.as_ref(), &name).await;
-    if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": result.message})),
-        )
-            .into_response()
-    }
+    vm_operation_response(&state, VmEventKind::Started, &name, "restart", result)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/vms/{name}",
+    params(
+        ("name" = String, Path, description = "VM name"),
+        ("async" = Option<bool>, Query, description = "Delete in the background and return a job to poll")
+    ),
+    responses(
+        (status = 200, description = "VM deleted", body = MutationResponse),
+        (status = 202, description = "Delete queued as a job", body = JobAccepted),
+        (status = 400, description = "Bad async flag", body = ApiErrorBody),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 422, description = "Invalid VM name", body = ApiErrorBody),
+        (status = 503, description = "Multipass unavailable", body = ApiErrorBody),
+        (status = 504, description = "Multipass timed out", body = ApiErrorBody),
+        (status = 500, description = "Multipass failed", body = ApiErrorBody)
+    )
+)]
 async fn delete_vm(
     State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
     axum::extract::Path(name): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    let result = handlers::delete_vm(state.vm_api.as_ref(), &name).await;
-    if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"success": false, "error": result.message})),
-        )
-            .into_response()
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response<Body>, ApiError> {
+    if bool_param(&params, "async", false)? {
+        let vm_api = state.vm_api.clone();
+        let vm_name = name.clone();
+        let job_id = spawn_vm_job(
+            &state,
+            JobKind::Delete,
+            VmEventKind::Deleted,
+            &name,
+            |_| async move { handlers::delete_vm(vm_api.as_ref(), &vm_name).await },
+        );
+        let path = uri.path();
+        return Ok(job_accepted(
+            path.rfind("/vms/").map(|at| &path[..at]),
+            job_id,
+        ));
     }
+    let result = handlers::delete_vm(state.vm_api.as_ref(), &name).await;
+    vm_operation_response(&state, VmEventKind::Deleted, &name, "delete", result)
+        .map(IntoResponse::into_response)
 }
 
-fn error_response(
-    status: StatusCode,
-    error: impl Into<String>,
-    details: Option<serde_json::Value>,
-) -> Response<Body> {
-    let mut payload = serde_json::json!({
-        "success": false,
-        "error": error.into(),
+/// Streams VM lifecycle events as server-sent events: the `event:` field is
+/// the event type and `data:` the `VmEvent` JSON. A reconnecting client's
+/// `Last-Event-ID` is accepted, but missed events are not replayed; the
+/// stream just continues live.
+#[utoipa::path(
+    get,
+    path = "/events",
+    responses((status = 200, description = "Stream of VM events", content_type = "text/event-stream"))
+)]
+async fn vm_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    if let Some(last_id) = headers.get("last-event-id") {
+        debug!(last_event_id = ?last_id, "event client reconnected; resuming live");
+    }
+    let receiver = state.events.subscribe();
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((Ok(sse_event(&event)), receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("event client fell behind; skipped {} event(s)", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
     });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
 
-    if let Some(details) = details {
-        payload
-            .as_object_mut()
-            .expect("error payload should be a JSON object")
-            .insert("details".to_owned(), details);
-    }
+fn sse_event(event: &VmEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(event.kind.as_str())
+        .json_data(event)
+        .expect("VM events always serialize")
+}
 
-    (status, Json(payload)).into_response()
+/// The handler's message for a successful VM `action`, after publishing
+/// `event` for it; otherwise the matching `ApiError`.
+fn vm_operation_response(
+    state: &AppState,
+    event: VmEventKind,
+    name: &str,
+    action: &str,
+    result: HandlerResult<()>,
+) -> Result<Json<MutationResponse>, ApiError> {
+    // Even a failed operation may have changed the VM's state.
+    if let Some(cache) = &state.info_cache {
+        cache.invalidate(name);
+    }
+    if result.success {
+        state.events.publish(event, name);
+        Ok(Json(MutationResponse::new(name, action, result.message)))
+    } else {
+        Err(ApiError::from_handler(result))
+    }
 }
 
-fn handler_error_response<T>(status: StatusCode, result: HandlerResult<T>) -> Response<Body> {
-    error_response(status, result.message, result.error_details)
+/// Error for a failed agent `HandlerResult`: classified by the `VmError` it
+/// wraps when there is one, otherwise `fallback`.
+fn handler_error_response<T>(
+    result: HandlerResult<T>,
+    fallback: (StatusCode, ErrorCode),
+) -> Response<Body> {
+    ApiError::from_handler_or(result, fallback).into_response()
 }
 
+const AGENT_FAILED: (StatusCode, ErrorCode) =
+    (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::Internal);
+
 fn agent_request_rejection_response(
     operation: &str,
     vm_name: &str,
     rejection: JsonRejection,
 ) -> Response<Body> {
     let reason = rejection.body_text();
-    error_response(
+    ApiError::new(
         StatusCode::BAD_REQUEST,
+        ErrorCode::ValidationFailed,
         format!(
             "Invalid agent request for operation '{}' in VM '{}': {}",
             operation, vm_name, reason
         ),
-        Some(serde_json::json!({
-            "code": "agent_request_invalid",
-            "operation": operation,
-            "vm_name": vm_name,
-            "causes": [reason],
-        })),
     )
+    .with_details(serde_json::json!({
+        "operation": operation,
+        "vm_name": vm_name,
+        "causes": [reason],
+    }))
+    .into_response()
 }
 
 // ============================================================================
 // Agent REST API DTOs and Handlers
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct InstallAgentRequest {
     agent_type: AgentType,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct CheckAgentRequest {
     agent_type: AgentType,
 }
 
+/// Body of `POST /agents/{vm_name}/check`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentInstalledResponse {
+    pub success: bool,
+    pub installed: bool,
+    pub message: String,
+}
+
+/// Body of a successful single-agent response.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentResponse {
+    pub success: bool,
+    pub agent: Option<AgentInstance>,
+    pub message: String,
+}
+
+/// Body of `GET /agents/{vm_name}`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentListResponse {
+    pub success: bool,
+    pub agents: Option<Vec<AgentInstance>>,
+    pub message: String,
+}
+
 /// POST /agents/{vm_name}/install
+#[utoipa::path(
+    post,
+    path = "/agents/{vm_name}/install",
+    request_body = InstallAgentRequest,
+    params(("vm_name" = String, Path, description = "VM the agent runs in")),
+    responses(
+        (status = 200, description = "Agent installed", body = MutationResponse),
+        (status = 400, description = "Malformed request body", body = ApiErrorBody),
+        (status = 500, description = "Installation failed", body = ApiErrorBody)
+    )
+)]
 async fn install_agent(
     State(state): State<AppState>,
     axum::extract::Path(vm_name): axum::extract::Path<String>,
@@ -300,17 +1435,25 @@ async fn install_agent(
     .await;
 
     if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
+        let body = MutationResponse::new(vm_name, "install_agent", result.message);
+        (StatusCode::OK, Json(body)).into_response()
     } else {
-        handler_error_response(StatusCode::INTERNAL_SERVER_ERROR, result)
+        handler_error_response(result, AGENT_FAILED)
     }
 }
 
 /// POST /agents/{vm_name}/check
+#[utoipa::path(
+    post,
+    path = "/agents/{vm_name}/check",
+    request_body = CheckAgentRequest,
+    params(("vm_name" = String, Path, description = "VM the agent runs in")),
+    responses(
+        (status = 200, description = "Whether the agent is installed", body = AgentInstalledResponse),
+        (status = 400, description = "Malformed request body", body = ApiErrorBody),
+        (status = 500, description = "Check failed", body = ApiErrorBody)
+    )
+)]
 async fn check_agent_installed(
     State(state): State<AppState>,
     axum::extract::Path(vm_name): axum::extract::Path<String>,
@@ -333,19 +1476,30 @@ async fn check_agent_installed(
     if result.success {
         (
             StatusCode::OK,
-            Json(serde_json::json!({
-                "success": true,
-                "installed": result.data.unwrap_or(false),
-                "message": result.message
-            })),
+            Json(AgentInstalledResponse {
+                success: true,
+                installed: result.data.unwrap_or(false),
+                message: result.message,
+            }),
         )
             .into_response()
     } else {
-        handler_error_response(StatusCode::INTERNAL_SERVER_ERROR, result)
+        handler_error_response(result, AGENT_FAILED)
     }
 }
 
 /// POST /agents/{vm_name}/onboard
+#[utoipa::path(
+    post,
+    path = "/agents/{vm_name}/onboard",
+    request_body = OnboardAgentRequest,
+    params(("vm_name" = String, Path, description = "VM the agent runs in")),
+    responses(
+        (status = 201, description = "Agent onboarded", body = AgentResponse),
+        (status = 400, description = "Malformed request body", body = ApiErrorBody),
+        (status = 500, description = "Onboarding failed", body = ApiErrorBody)
+    )
+)]
 async fn onboard_agent(
     State(state): State<AppState>,
     axum::extract::Path(vm_name): axum::extract::Path<String>,
@@ -365,19 +1519,28 @@ async fn onboard_agent(
     if result.success {
         (
             StatusCode::CREATED,
-            Json(serde_json::json!({
-                "success": true,
-                "agent": result.data,
-                "message": result.message
-            })),
+            Json(AgentResponse {
+                success: true,
+                agent: result.data,
+                message: result.message,
+            }),
         )
             .into_response()
     } else {
-        handler_error_response(StatusCode::INTERNAL_SERVER_ERROR, result)
+        handler_error_response(result, AGENT_FAILED)
     }
 }
 
 /// GET /agents/{vm_name}
+#[utoipa::path(
+    get,
+    path = "/agents/{vm_name}",
+    params(("vm_name" = String, Path, description = "VM the agent runs in")),
+    responses(
+        (status = 200, description = "Agents in the VM", body = AgentListResponse),
+        (status = 500, description = "Listing failed", body = ApiErrorBody)
+    )
+)]
 async fn list_agents(
     State(state): State<AppState>,
     axum::extract::Path(vm_name): axum::extract::Path<String>,
@@ -387,19 +1550,28 @@ async fn list_agents(
     if result.success {
         (
             StatusCode::OK,
-            Json(serde_json::json!({
-                "success": true,
-                "agents": result.data,
-                "message": result.message
-            })),
+            Json(AgentListResponse {
+                success: true,
+                agents: result.data,
+                message: result.message,
+            }),
         )
             .into_response()
     } else {
-        handler_error_response(StatusCode::INTERNAL_SERVER_ERROR, result)
+        handler_error_response(result, AGENT_FAILED)
     }
 }
 
 /// GET /agents/{vm_name}/{agent_id}
+#[utoipa::path(
+    get,
+    path = "/agents/{vm_name}/{agent_id}",
+    params(("vm_name" = String, Path, description = "VM the agent runs in"), ("agent_id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, description = "Agent details", body = AgentResponse),
+        (status = 404, description = "No such agent", body = ApiErrorBody)
+    )
+)]
 async fn get_agent(
     State(state): State<AppState>,
     axum::extract::Path((vm_name, agent_id)): axum::extract::Path<(String, String)>,
@@ -410,19 +1582,28 @@ async fn get_agent(
     if result.success {
         (
             StatusCode::OK,
-            Json(serde_json::json!({
-                "success": true,
-                "agent": result.data,
-                "message": result.message
-            })),
+            Json(AgentResponse {
+                success: true,
+                agent: result.data,
+                message: result.message,
+            }),
         )
             .into_response()
     } else {
-        handler_error_response(StatusCode::NOT_FOUND, result)
+        handler_error_response(result, (StatusCode::NOT_FOUND, ErrorCode::AgentNotFound))
     }
 }
 
 /// POST /agents/{vm_name}/{agent_id}/stop
+#[utoipa::path(
+    post,
+    path = "/agents/{vm_name}/{agent_id}/stop",
+    params(("vm_name" = String, Path, description = "VM the agent runs in"), ("agent_id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, description = "Agent stopped", body = MutationResponse),
+        (status = 500, description = "Stopping failed", body = ApiErrorBody)
+    )
+)]
 async fn stop_agent(
     State(state): State<AppState>,
     axum::extract::Path((vm_name, agent_id)): axum::extract::Path<(String, String)>,
@@ -431,17 +1612,23 @@ async fn stop_agent(
         crate::agent::handlers::stop_agent(state.agent_manager.as_ref(), &vm_name, &agent_id).await;
 
     if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
+        let body = MutationResponse::new(agent_id, "stop_agent", result.message);
+        (StatusCode::OK, Json(body)).into_response()
     } else {
-        handler_error_response(StatusCode::INTERNAL_SERVER_ERROR, result)
+        handler_error_response(result, AGENT_FAILED)
     }
 }
 
 /// DELETE /agents/{vm_name}/{agent_id}
+#[utoipa::path(
+    delete,
+    path = "/agents/{vm_name}/{agent_id}",
+    params(("vm_name" = String, Path, description = "VM the agent runs in"), ("agent_id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, description = "Agent deleted", body = MutationResponse),
+        (status = 500, description = "Deletion failed", body = ApiErrorBody)
+    )
+)]
 async fn delete_agent(
     State(state): State<AppState>,
     axum::extract::Path((vm_name, agent_id)): axum::extract::Path<(String, String)>,
@@ -451,36 +1638,116 @@ async fn delete_agent(
             .await;
 
     if result.success {
-        (
-            StatusCode::OK,
-            Json(serde_json::json!({"success": true, "message": result.message})),
-        )
-            .into_response()
+        let body = MutationResponse::new(agent_id, "delete_agent", result.message);
+        (StatusCode::OK, Json(body)).into_response()
     } else {
-        handler_error_response(StatusCode::INTERNAL_SERVER_ERROR, result)
+        handler_error_response(result, AGENT_FAILED)
+    }
+}
+
+/// Counts the request in `AppState::in_flight` until its handler returns or
+/// is dropped.
+async fn track_in_flight(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    struct Done(Arc<AtomicUsize>);
+    impl Drop for Done {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    state.in_flight.fetch_add(1, Ordering::SeqCst);
+    let _done = Done(state.in_flight.clone());
+    next.run(request).await
+}
+
+/// Rejects the request with a 429 and `Retry-After` when its client has
+/// used up the limit for the route's class. Requests without a known peer
+/// address, e.g. from tests, share one bucket.
+async fn enforce_rate_limit(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let Some(limiter) = &state.rate_limiter else {
+        return next.run(request).await;
+    };
+    let Some(class) = RouteClass::of(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or(IpAddr::from([0, 0, 0, 0]), |ConnectInfo(addr)| addr.ip());
+    match limiter.check(client, class) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            let mut response = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::RateLimited,
+                format!("Too many requests; retry in {} second(s)", retry_after),
+            )
+            .with_details(serde_json::json!({ "retry_after": retry_after }))
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+/// Rejects a request whose `{name}` or `{vm_name}` path parameter
+/// `validate_vm_name` refuses, before its handler can pass it to multipass.
+async fn reject_invalid_vm_names(
+    params: RawPathParams,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    for (key, value) in &params {
+        if matches!(key, "name" | "vm_name")
+            && let Err(err) = validate_vm_name(value)
+        {
+            return ApiError::invalid_name(&err).into_response();
+        }
     }
+    next.run(request).await
 }
 
-async fn api_not_found(method: Method, uri: Uri) -> impl IntoResponse {
-    error_response(
+/// Fallback for unknown API paths.
+async fn api_not_found(method: Method, uri: Uri) -> ApiError {
+    ApiError::new(
         StatusCode::NOT_FOUND,
+        ErrorCode::RouteNotFound,
         format!("API route not found: {} {}", method, uri.path()),
-        Some(serde_json::json!({
-            "code": "route_not_found",
-            "method": method.as_str(),
-            "path": uri.path(),
-        })),
     )
+    .with_details(serde_json::json!({
+        "method": method.as_str(),
+        "path": uri.path(),
+    }))
 }
 
 pub fn create_api_router(state: AppState) -> Router {
+    let cors = state.cors.layer();
     Router::new()
         .route("/health", get(health_check))
+        .route("/livez", get(livez))
+        .route("/readyz", get(readyz))
+        .route("/openapi.json", get(openapi_json))
+        .route("/docs", get(swagger_ui))
         .route("/vms", get(list_vms).post(launch_vm))
         .route("/vms/{name}", get(get_vm_info).delete(delete_vm))
         .route("/vms/{name}/start", post(start_vm))
         .route("/vms/{name}/stop", post(stop_vm))
         .route("/vms/{name}/restart", post(restart_vm))
+        .route("/images", get(list_images))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{id}", get(get_job).delete(cancel_job))
+        .route("/events", get(vm_events))
         // Agent routes
         .route("/agents/{vm_name}/install", post(install_agent))
         .route("/agents/{vm_name}/check", post(check_agent_installed))
@@ -491,107 +1758,644 @@ pub fn create_api_router(state: AppState) -> Router {
             get(get_agent).delete(delete_agent),
         )
         .route("/agents/{vm_name}/{agent_id}/stop", post(stop_agent))
+        .route_layer(middleware::from_fn(reject_invalid_vm_names))
         .fallback(api_not_found)
-        .layer(CorsLayer::permissive())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            enforce_rate_limit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            track_in_flight,
+        ))
+        .layer(DefaultBodyLimit::max(MAX_REQUEST_BODY_BYTES))
+        .layer(middleware::from_fn(with_request_id))
+        .layer(cors)
+        .layer(CompressionLayer::new())
         .with_state(state)
 }
 
+/// Where `create_single_port_router` nests the API.
+pub const SINGLE_PORT_API_BASE: &str = "/api";
+
+/// What `/config.json` tells the UI about where the API lives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Path prefix of every API route on the UI's own origin.
+    pub api_base: String,
+}
+
+async fn ui_config() -> Json<UiConfig> {
+    Json(UiConfig {
+        api_base: SINGLE_PORT_API_BASE.to_owned(),
+    })
+}
+
+/// Serves the API under `/api`, `/config.json` for the UI, and `ui_router`
+/// for every other path, so one listener can do the job of both servers.
+pub fn create_single_port_router(state: AppState, ui_router: Router) -> Router {
+    Router::new()
+        .route("/config.json", get(ui_config))
+        .nest(SINGLE_PORT_API_BASE, create_api_router(state))
+        .fallback_service(ui_router)
+}
+
 pub fn create_ui_router() -> Router {
-    Router::new().fallback(serve_embedded_file)
+    Router::new()
+        .fallback(serve_embedded_file)
+        .layer(CompressionLayer::new())
 }
 
-async fn serve_embedded_file(uri: Uri) -> impl IntoResponse {
-    let mut path = uri.path().trim_start_matches('/').to_string();
+/// UI router that serves files from `ui_dir` on disk, falling back to the
+/// embedded copy for anything missing there. Useful while iterating on the UI.
+pub fn create_ui_router_with_dir(ui_dir: impl AsRef<Path>) -> Result<Router> {
+    let ui_dir = ui_dir.as_ref();
+    let root = ui_dir
+        .canonicalize()
+        .with_context(|| format!("invalid UI directory: {}", ui_dir.display()))?;
+    Ok(Router::new()
+        .fallback(serve_ui_file)
+        .layer(CompressionLayer::new())
+        .with_state(Arc::new(root)))
+}
+
+/// `Cache-Control` max age for embedded static assets. `index.html` is always
+/// revalidated so new releases are picked up immediately.
+const UI_ASSET_MAX_AGE_SECS: u64 = 3600;
+
+/// Normalizes the request path into an asset path, or `None` if it tries to
+/// climb out of the asset root with `..`.
+fn ui_asset_path(uri: &Uri) -> Option<String> {
+    let mut segments = Vec::new();
+    for segment in uri.path().split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            segment => segments.push(segment),
+        }
+    }
 
     // Default to index.html if path is empty or ends with /
-    if path.is_empty() || path.ends_with('/') {
-        path = "index.html".to_string();
+    if segments.is_empty() || uri.path().ends_with('/') {
+        Some("index.html".to_string())
+    } else {
+        Some(segments.join("/"))
     }
+}
 
-    match UiAssets::get(&path) {
-        Some(content) => {
-            let mime = mime_guess::from_path(&path).first_or_octet_stream();
-            let body = Body::from(content.data.into_owned());
+fn ui_cache_control(path: &str) -> String {
+    if path.ends_with(".html") {
+        "no-cache".to_string()
+    } else {
+        format!("public, max-age={}", UI_ASSET_MAX_AGE_SECS)
+    }
+}
 
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(
-                    header::CONTENT_TYPE,
-                    HeaderValue::from_str(mime.as_ref()).unwrap(),
-                )
-                .body(body)
-                .unwrap()
+/// What a `Range` header asks of a file, as far as `ui_file_response`
+/// supports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RangeRequest {
+    /// No usable range: send the whole file.
+    Full,
+    Partial(std::ops::Range<usize>),
+    /// A range that starts past the end of the file.
+    Unsatisfiable,
+}
+
+/// Reads a single `bytes=` range for a `len`-byte file. Malformed and
+/// multi-range headers fall back to the whole file, as do ranges whose
+/// `If-Range` doesn't strongly match `etag`; a weak `etag` never does.
+fn requested_range(headers: &HeaderMap, len: usize, etag: Option<&str>) -> RangeRequest {
+    let Some(range) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return RangeRequest::Full;
+    };
+    if let Some(if_range) = headers.get(header::IF_RANGE)
+        && etag.is_none_or(|etag| etag.starts_with("W/") || if_range.as_bytes() != etag.as_bytes())
+    {
+        return RangeRequest::Full;
+    }
+    let Some((first, last)) = range
+        .strip_prefix("bytes=")
+        .filter(|spec| !spec.contains(','))
+        .and_then(|spec| spec.trim().split_once('-'))
+    else {
+        return RangeRequest::Full;
+    };
+
+    let (start, end) = match (first.parse::<usize>(), last.parse::<usize>()) {
+        // `bytes=-N`: the last N bytes.
+        (Err(_), Ok(suffix)) if first.is_empty() => {
+            if suffix == 0 || len == 0 {
+                return RangeRequest::Unsatisfiable;
+            }
+            (len.saturating_sub(suffix), len - 1)
         }
-        None => Response::builder()
+        (Ok(start), Err(_)) if last.is_empty() => (start, len.saturating_sub(1)),
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+        _ => return RangeRequest::Full,
+    };
+    if start >= len {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Partial(start..end + 1)
+}
+
+/// The file, or the part of it `headers` asks for with `Range`.
+fn ui_file_response(
+    path: &str,
+    data: Bytes,
+    cache_control: &str,
+    headers: &HeaderMap,
+    etag: Option<&str>,
+) -> Response<Body> {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let len = data.len();
+
+    let mut response = Response::builder()
+        .header(
+            header::CONTENT_TYPE,
+            HeaderValue::from_str(mime.as_ref()).unwrap(),
+        )
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::ACCEPT_RANGES, "bytes");
+    if let Some(etag) = etag {
+        response = response.header(header::ETAG, etag);
+    }
+
+    match requested_range(headers, len, etag) {
+        RangeRequest::Full => response
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::from(data)),
+        RangeRequest::Partial(range) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+            )
+            .header(header::CONTENT_LENGTH, range.len())
+            .body(Body::from(data.slice(range))),
+        RangeRequest::Unsatisfiable => response
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty()),
+    }
+    .unwrap()
+}
+
+fn ui_bad_path_response() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .body(Body::from("400 Bad Request"))
+        .unwrap()
+}
+
+/// Whether an `If-None-Match` list names `etag`, using the weak comparison
+/// RFC 9110 prescribes for it.
+fn etag_list_matches(list: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    let etag = opaque(etag);
+    list.split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+fn embedded_ui_response(path: &str, headers: &HeaderMap) -> Response<Body> {
+    let Some(content) = UiAssets::get(path) else {
+        return Response::builder()
             .status(StatusCode::NOT_FOUND)
             .body(Body::from("404 Not Found"))
-            .unwrap(),
+            .unwrap();
+    };
+
+    // Weak, because `CompressionLayer` may gzip the body: the tag names the
+    // asset, not one particular byte stream.
+    let etag = format!("W/\"{}\"", hex::encode(content.metadata.sha256_hash()));
+    let cache_control = ui_cache_control(path);
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_list_matches(value, &etag))
+    {
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, &etag)
+            .header(header::CACHE_CONTROL, &cache_control)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    // Assets compiled into the binary are served without copying them.
+    let data = match content.data {
+        Cow::Borrowed(data) => Bytes::from_static(data),
+        Cow::Owned(data) => Bytes::from(data),
+    };
+    ui_file_response(path, data, &cache_control, headers, Some(&etag))
+}
+
+async fn serve_embedded_file(uri: Uri, headers: HeaderMap) -> impl IntoResponse {
+    match ui_asset_path(&uri) {
+        Some(path) => embedded_ui_response(&path, &headers),
+        None => ui_bad_path_response(),
+    }
+}
+
+async fn serve_ui_file(
+    State(root): State<Arc<PathBuf>>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Some(path) = ui_asset_path(&uri) else {
+        return ui_bad_path_response();
+    };
+
+    // Canonicalizing resolves symlinks, so anything that ends up outside the
+    // UI directory is ignored rather than served.
+    let on_disk = match root.join(&path).canonicalize() {
+        Ok(resolved) if resolved.starts_with(root.as_ref()) && resolved.is_file() => {
+            tokio::fs::read(&resolved).await.ok()
+        }
+        Ok(resolved) => {
+            if !resolved.starts_with(root.as_ref()) {
+                warn!("rejected UI path outside of UI directory: {}", path);
+            }
+            None
+        }
+        Err(_) => None,
+    };
+
+    match on_disk {
+        // Files on disk change while iterating, so never let them be cached.
+        Some(data) => ui_file_response(&path, Bytes::from(data), "no-cache", &headers, None),
+        None => embedded_ui_response(&path, &headers),
+    }
+}
+
+/// How `run_server` announces where it is listening.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BannerFormat {
+    /// Friendly log lines (with emoji).
+    #[default]
+    Text,
+    /// One `StartupBanner` JSON line on stdout.
+    Json,
+    None,
+}
+
+impl std::str::FromStr for BannerFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "none" => Ok(Self::None),
+            other => anyhow::bail!("unsupported banner format: {other}"),
+        }
+    }
+}
+
+/// Where the servers started by `run_server` can be reached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartupBanner {
+    pub ui_url: String,
+    pub api_url: String,
+    pub health_url: String,
+}
+
+impl StartupBanner {
+    pub fn new(host: &str, ui_port: u16, api_port: u16) -> Self {
+        Self::for_hosts(host, ui_port, host, api_port)
+    }
+
+    /// Banner for servers bound to different hosts.
+    pub fn for_hosts(ui_host: &str, ui_port: u16, api_host: &str, api_port: u16) -> Self {
+        Self {
+            ui_url: format!("http://{ui_host}:{ui_port}"),
+            api_url: format!("http://{api_host}:{api_port}"),
+            health_url: format!("http://{api_host}:{api_port}/health"),
+        }
+    }
+
+    /// Banner for `--single-port`, with the API under `/api` on the UI's port.
+    pub fn single_port(host: &str, port: u16) -> Self {
+        let ui_url = format!("http://{host}:{port}");
+        Self {
+            api_url: format!("{ui_url}{SINGLE_PORT_API_BASE}"),
+            health_url: format!("{ui_url}{SINGLE_PORT_API_BASE}/health"),
+            ui_url,
+        }
+    }
+
+    /// Switches the API URLs, and the UI URL when `ui` is set, to `https`.
+    pub fn with_tls(mut self, ui: bool) -> Self {
+        let https = |url: String| url.replacen("http://", "https://", 1);
+        self.api_url = https(self.api_url);
+        self.health_url = https(self.health_url);
+        if ui {
+            self.ui_url = https(self.ui_url);
+        }
+        self
+    }
+
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("startup banner should serialize")
+    }
+
+    fn log(&self) {
+        info!("🏡 Starting SafePaw village UI on {}", self.ui_url);
+        info!("📡 Starting REST API server on {}", self.api_url);
+        info!("🌐 Visit the UI to access the SafePaw village");
+        info!("🔌 API health check: {}", self.health_url);
     }
 }
 
+/// HTTPS settings for `run_server`. Without them both servers speak plain
+/// HTTP; with them the API is served over TLS, and the UI too when `ui` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// PEM certificate chain.
+    pub cert_path: PathBuf,
+    /// PEM private key for the certificate.
+    pub key_path: PathBuf,
+    /// Also serve the UI over HTTPS.
+    pub ui: bool,
+}
+
+impl TlsConfig {
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            ui: false,
+        }
+    }
+
+    pub fn with_ui(mut self, ui: bool) -> Self {
+        self.ui = ui;
+        self
+    }
+
+    /// Reads and parses the certificate and key, so a bad path or PEM file
+    /// fails at startup rather than on the first connection.
+    pub async fn load(&self) -> Result<RustlsConfig> {
+        for (what, path) in [("certificate", &self.cert_path), ("key", &self.key_path)] {
+            if !path.is_file() {
+                anyhow::bail!("TLS {} not found: {}", what, path.display());
+            }
+        }
+        RustlsConfig::from_pem_file(&self.cert_path, &self.key_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "invalid TLS certificate {} or key {}",
+                    self.cert_path.display(),
+                    self.key_path.display()
+                )
+            })
+    }
+}
+
+/// Addresses the UI and API servers listen on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindAddrs {
+    pub ui: IpAddr,
+    pub api: IpAddr,
+}
+
+/// Resolves the UI and API bind addresses from `--bind-ui` and `--bind-api`,
+/// each falling back to the shared `host` when unset.
+pub fn resolve_bind_addrs(
+    host: &str,
+    bind_ui: Option<&str>,
+    bind_api: Option<&str>,
+) -> Result<BindAddrs> {
+    let parse = |addr: Option<&str>, flag: &str| -> Result<IpAddr> {
+        match addr {
+            Some(addr) => addr
+                .parse()
+                .with_context(|| format!("invalid {} address: {}", flag, addr)),
+            None => host
+                .parse()
+                .with_context(|| format!("invalid host address: {}", host)),
+        }
+    };
+    Ok(BindAddrs {
+        ui: parse(bind_ui, "--bind-ui")?,
+        api: parse(bind_api, "--bind-api")?,
+    })
+}
+
+/// Optional settings for `run_server`; the defaults serve the embedded UI
+/// over plain HTTP with the text banner, both servers on the shared host.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// Serve UI files from this directory, falling back to the embedded copy.
+    pub ui_dir: Option<PathBuf>,
+    pub banner: BannerFormat,
+    pub tls: Option<TlsConfig>,
+    /// Bind the UI server here instead of the shared host.
+    pub bind_ui: Option<String>,
+    /// Bind the API server here instead of the shared host.
+    pub bind_api: Option<String>,
+    /// Serve the UI and the API (under `/api`) from one listener on the UI
+    /// address and port. With `tls`, everything is served over HTTPS.
+    pub single_port: bool,
+    /// How long `GET /vms/{name}` may serve cached VM info; zero disables
+    /// the cache.
+    pub info_cache_ttl: Duration,
+    /// Launch in the background for every `POST /vms` that doesn't pass
+    /// `?async=false`.
+    pub async_launch: bool,
+    /// How long finished jobs stay listed; `DEFAULT_JOB_RETENTION` if unset.
+    pub job_retention: Option<Duration>,
+    /// How long shutdown waits for in-flight work before cancelling it;
+    /// `DEFAULT_SHUTDOWN_TIMEOUT` if unset.
+    pub shutdown_timeout: Option<Duration>,
+    /// Per-client API limits; the default limits nothing.
+    pub rate_limits: RateLimits,
+}
+
 pub async fn run_server(
     vm_api: Arc<dyn VmApi>,
     agent_manager: Arc<dyn AgentManager>,
     host: &str,
     ui_port: u16,
     api_port: u16,
+    options: ServerOptions,
 ) -> Result<()> {
-    let state = AppState::new(vm_api, agent_manager);
+    let ServerOptions {
+        ui_dir,
+        banner,
+        tls,
+        bind_ui,
+        bind_api,
+        single_port,
+        info_cache_ttl,
+        async_launch,
+        job_retention,
+        shutdown_timeout,
+        rate_limits,
+    } = options;
+    let addrs = resolve_bind_addrs(host, bind_ui.as_deref(), bind_api.as_deref())?;
+    let tls = tls.as_ref();
+    let state = AppState::new(vm_api.clone(), agent_manager)
+        .with_cors(CorsConfig::from_env()?)
+        .with_info_cache(info_cache_ttl)
+        .with_async_launch(async_launch)
+        .with_rate_limits(rate_limits)
+        .with_jobs(JobStore::new(
+            job_retention.unwrap_or(DEFAULT_JOB_RETENTION),
+            DEFAULT_LAUNCH_CONCURRENCY,
+        ));
+    tokio::spawn(state.jobs().collect_garbage());
+    let rustls = match tls {
+        Some(tls) => Some(tls.load().await?),
+        None => None,
+    };
 
-    // Parse host address
-    let host_addr: std::net::IpAddr = host
-        .parse()
-        .context(format!("invalid host address: {}", host))?;
+    // UI server (using embedded assets)
+    let ui_router = match ui_dir {
+        Some(ui_dir) => {
+            if banner == BannerFormat::Text {
+                info!(
+                    "🎨 Serving UI from {} (embedded fallback)",
+                    ui_dir.display()
+                );
+            } else {
+                info!("serving UI from {} (embedded fallback)", ui_dir.display());
+            }
+            create_ui_router_with_dir(ui_dir)?
+        }
+        None => create_ui_router(),
+    };
+    let ui_addr = SocketAddr::from((addrs.ui, ui_port));
 
-    // API server
-    let api_router = create_api_router(state.clone());
-    let api_addr = SocketAddr::from((host_addr, api_port));
+    let mut startup = if single_port {
+        StartupBanner::single_port(&addrs.ui.to_string(), ui_port)
+    } else {
+        StartupBanner::for_hosts(
+            &addrs.ui.to_string(),
+            ui_port,
+            &addrs.api.to_string(),
+            api_port,
+        )
+    };
+    if let Some(tls) = tls {
+        startup = startup.with_tls(single_port || tls.ui);
+    }
+    match banner {
+        BannerFormat::Text => startup.log(),
+        BannerFormat::Json => println!("{}", startup.to_json_line()),
+        BannerFormat::None => {}
+    }
 
-    // UI server (using embedded assets)
-    let ui_router = create_ui_router();
-    let ui_addr = SocketAddr::from((host_addr, ui_port));
+    let stop_watching = CancellationToken::new();
+    tokio::spawn(watch_vm_states(
+        vm_api,
+        state.events(),
+        EVENT_POLL_INTERVAL,
+        stop_watching.clone(),
+    ));
+    let _stop_watching = stop_watching.drop_guard();
 
-    info!(
-        "🏡 Starting SafePaw village UI on http://{}:{}",
-        host, ui_port
-    );
-    info!(
-        "📡 Starting REST API server on http://{}:{}",
-        host, api_port
-    );
-    info!("🌐 Visit the UI to access the SafePaw village");
-    info!("🔌 API health check: http://{}:{}/health", host, api_port);
+    let draining = CancellationToken::new();
+    tokio::spawn(watch_shutdown_signals(draining.clone()));
+    let drain_state = state.clone();
+    let servers = async {
+        if single_port {
+            let router = create_single_port_router(state, ui_router);
+            return serve(router, ui_addr, rustls, "UI and API", &draining).await;
+        }
 
-    // Spawn both servers concurrently
-    let api_server = async {
-        let listener = tokio::net::TcpListener::bind(api_addr)
-            .await
-            .context(format!(
-                "failed to bind API server to {}:{}",
-                host, api_port
-            ))?;
-        axum::serve(listener, api_router)
-            .with_graceful_shutdown(shutdown_signal())
-            .await
-            .context("API server failed")
+        // Spawn both servers concurrently
+        let api_router = create_api_router(state);
+        let api_addr = SocketAddr::from((addrs.api, api_port));
+        let ui_tls = rustls.clone().filter(|_| tls.is_some_and(|tls| tls.ui));
+        tokio::try_join!(
+            serve(api_router, api_addr, rustls, "API", &draining),
+            serve(ui_router, ui_addr, ui_tls, "UI", &draining),
+        )?;
+        Ok(())
     };
+    tokio::pin!(servers);
+    tokio::select! {
+        result = &mut servers => return result,
+        _ = draining.cancelled() => {}
+    }
 
-    let ui_server = async {
-        let listener = tokio::net::TcpListener::bind(ui_addr)
-            .await
-            .context(format!("failed to bind UI server to {}:{}", host, ui_port))?;
-        axum::serve(listener, ui_router)
-            .with_graceful_shutdown(shutdown_signal())
-            .await
-            .context("UI server failed")
+    // The listeners are closing; open streams such as `/events` may keep
+    // the servers alive, so only in-flight requests and jobs are waited for.
+    let drain = drain_state.drain(shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT));
+    tokio::pin!(drain);
+    let drained = tokio::select! {
+        result = &mut servers => {
+            result?;
+            (&mut drain).await
+        }
+        drained = &mut drain => drained,
     };
+    if drained {
+        info!("Shutdown complete");
+    }
+    Ok(())
+}
 
-    tokio::try_join!(api_server, ui_server)?;
+/// Serves `router` on `addr` until `draining` is cancelled, over TLS when
+/// `tls` is given.
+async fn serve(
+    router: Router,
+    addr: SocketAddr,
+    tls: Option<RustlsConfig>,
+    name: &str,
+    draining: &CancellationToken,
+) -> Result<()> {
+    match tls {
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .context(format!("failed to bind {} server to {}", name, addr))?;
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(draining.clone().cancelled_owned())
+            .await
+            .context(format!("{} server failed", name))
+        }
+        Some(config) => {
+            let handle = axum_server::Handle::new();
+            let shutdown = handle.clone();
+            let draining = draining.clone();
+            tokio::spawn(async move {
+                draining.cancelled().await;
+                shutdown.graceful_shutdown(None);
+            });
+            axum_server::bind_rustls(addr, config)
+                .handle(handle)
+                .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .context(format!("{} server failed on {}", name, addr))
+        }
+    }
+}
 
-    Ok(())
+/// Cancels `draining` on the first Ctrl+C or terminate signal, and exits
+/// the process right away on the second.
+async fn watch_shutdown_signals(draining: CancellationToken) {
+    let signal = shutdown_signal().await;
+    info!(
+        "Received {}, shutting down gracefully (repeat to exit immediately)",
+        signal
+    );
+    draining.cancel();
+    let signal = shutdown_signal().await;
+    warn!("Received {} again, exiting immediately", signal);
+    std::process::exit(130);
 }
 
-async fn shutdown_signal() {
+/// Waits for Ctrl+C or, on unix, SIGTERM and names the one that arrived.
+async fn shutdown_signal() -> &'static str {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -610,11 +2414,7 @@ async fn shutdown_signal() {
     let terminate = std::future::pending::<()>();
 
     tokio::select! {
-        _ = ctrl_c => {
-            info!("Received Ctrl+C, shutting down gracefully");
-        }
-        _ = terminate => {
-            info!("Received terminate signal, shutting down gracefully");
-        }
+        _ = ctrl_c => "Ctrl+C",
+        _ = terminate => "terminate signal",
     }
 }
@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result, bail};
+
+/// VM name -> tags, as stored on disk.
+type TagMap = BTreeMap<String, BTreeSet<String>>;
+
+/// Sidecar registry of per-VM tags, since multipass has no notion of them.
+///
+/// Stored as a JSON object at `~/.safepaw/tags.json`:
+///
+/// ```json
+/// { "agent-1": ["project-a", "gpu"] }
+/// ```
+///
+/// A missing file is an empty registry. Updates through one registry are
+/// serialized by a lock, and each write goes to a temporary file that is
+/// renamed into place so readers never see a half-written file.
+pub struct TagRegistry {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl TagRegistry {
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(default_tags_path()?))
+    }
+
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds `tags` to the VM and returns its full tag set.
+    pub fn tag(&self, name: &str, tags: &[String]) -> Result<Vec<String>> {
+        let tags = validate_tags(tags)?;
+        self.update(|map| {
+            let entry = map.entry(name.to_owned()).or_default();
+            entry.extend(tags);
+            entry.iter().cloned().collect()
+        })
+    }
+
+    /// Removes `tags` from the VM and returns the tags it still has.
+    pub fn untag(&self, name: &str, tags: &[String]) -> Result<Vec<String>> {
+        self.update(|map| {
+            let Some(entry) = map.get_mut(name) else {
+                return Vec::new();
+            };
+            for tag in tags {
+                entry.remove(tag.trim());
+            }
+            let remaining = entry.iter().cloned().collect();
+            if entry.is_empty() {
+                map.remove(name);
+            }
+            remaining
+        })
+    }
+
+    /// Drops every tag of the VM, e.g. after it is deleted.
+    pub fn remove(&self, name: &str) -> Result<()> {
+        self.update(|map| {
+            map.remove(name);
+        })
+    }
+
+    pub fn tags(&self, name: &str) -> Result<Vec<String>> {
+        Ok(self
+            .load()?
+            .remove(name)
+            .map(|tags| tags.into_iter().collect())
+            .unwrap_or_default())
+    }
+
+    fn load(&self) -> Result<TagMap> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(TagMap::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read tags {}", self.path.display()));
+            }
+        };
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse tags {}", self.path.display()))
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut TagMap) -> T) -> Result<T> {
+        let _guard = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut map = self.load()?;
+        let result = change(&mut map);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create tags directory {}", parent.display()))?;
+        }
+        let contents = serde_json::to_vec_pretty(&map).context("failed to serialize tags")?;
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)
+            .with_context(|| format!("failed to write tags {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &self.path)
+            .with_context(|| format!("failed to replace tags {}", self.path.display()))?;
+
+        Ok(result)
+    }
+}
+
+pub fn default_tags_path() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("HOME is not set")?;
+    Ok(PathBuf::from(home).join(".safepaw").join("tags.json"))
+}
+
+fn validate_tags(tags: &[String]) -> Result<Vec<String>> {
+    tags.iter()
+        .map(|tag| {
+            let tag = tag.trim();
+            if tag.is_empty() || tag.contains(char::is_whitespace) {
+                bail!(
+                    "invalid tag '{}': tags must be non-empty and contain no spaces",
+                    tag
+                );
+            }
+            Ok(tag.to_owned())
+        })
+        .collect()
+}
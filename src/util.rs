@@ -1,5 +1,7 @@
 // Shared utilities for SafePaw
 
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -15,6 +17,10 @@ pub struct HandlerResult<T> {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_details: Option<Value>,
+    /// The error behind a failed result, kept so callers can still inspect
+    /// its cause chain (e.g. to pick a CLI exit code).
+    #[serde(skip)]
+    pub error: Option<Arc<anyhow::Error>>,
 }
 
 impl<T> HandlerResult<T> {
@@ -24,6 +30,7 @@ impl<T> HandlerResult<T> {
             data: Some(data),
             message: message.into(),
             error_details: None,
+            error: None,
         }
     }
 
@@ -33,6 +40,7 @@ impl<T> HandlerResult<T> {
             data: None,
             message: message.into(),
             error_details: None,
+            error: None,
         }
     }
 
@@ -42,6 +50,7 @@ impl<T> HandlerResult<T> {
             data: None,
             message: message.into(),
             error_details: None,
+            error: None,
         }
     }
 
@@ -51,6 +60,153 @@ impl<T> HandlerResult<T> {
             data: None,
             message: message.into(),
             error_details: Some(error_details),
+            error: None,
+        }
+    }
+
+    /// Builds an error result for `err`, attaching its cause chain as
+    /// `{"causes": [..]}` details when verbose errors are enabled.
+    pub fn from_error(message: impl Into<String>, err: anyhow::Error) -> Self {
+        let mut result = match verbose_error_details(&err) {
+            Some(details) => Self::err_with_details(message, details),
+            None => Self::err(message),
+        };
+        result.error = Some(Arc::new(err));
+        result
+    }
+
+    /// Turns a failed result into an error displaying its message.
+    pub fn into_error(self) -> anyhow::Error {
+        HandlerError {
+            message: self.message,
+            source: self.error,
         }
+        .into()
+    }
+}
+
+/// Error for a failed `HandlerResult`. Displays only the handler message,
+/// which already summarizes the cause; the original error stays reachable
+/// through `source_error` without being repeated in the printed chain.
+#[derive(Debug)]
+pub struct HandlerError {
+    message: String,
+    source: Option<Arc<anyhow::Error>>,
+}
+
+impl HandlerError {
+    pub fn source_error(&self) -> Option<&anyhow::Error> {
+        self.source.as_deref()
+    }
+}
+
+impl std::fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for HandlerError {}
+
+// ============================================================================
+// Verbose Errors - Cause chains in API error bodies
+// ============================================================================
+
+/// Env var that opts release builds into reporting error cause chains.
+pub const VERBOSE_ERRORS_ENV: &str = "SAFEPAW_VERBOSE_ERRORS";
+
+/// Whether a `SAFEPAW_VERBOSE_ERRORS` value turns verbose errors on.
+/// Unset, empty, `0` and `false` leave them off.
+pub fn env_enables_verbose_errors(value: Option<&str>) -> bool {
+    match value.map(str::trim) {
+        None | Some("") | Some("0") => false,
+        Some(value) => !value.eq_ignore_ascii_case("false"),
+    }
+}
+
+/// Debug builds always report cause chains; release builds only when
+/// `SAFEPAW_VERBOSE_ERRORS` is set.
+pub fn verbose_errors_enabled() -> bool {
+    cfg!(debug_assertions)
+        || env_enables_verbose_errors(std::env::var(VERBOSE_ERRORS_ENV).ok().as_deref())
+}
+
+/// The causes below the top-level error, outermost first.
+pub fn error_causes(err: &anyhow::Error) -> Vec<String> {
+    err.chain().skip(1).map(ToString::to_string).collect()
+}
+
+/// `{"causes": [..]}` for `err` when verbose errors are enabled and the error
+/// has any causes.
+pub fn verbose_error_details(err: &anyhow::Error) -> Option<Value> {
+    if !verbose_errors_enabled() {
+        return None;
+    }
+    let causes = error_causes(err);
+    if causes.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({ "causes": causes }))
+}
+
+// ============================================================================
+// Sizes - Human-readable byte counts
+// ============================================================================
+
+const BYTE_UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Formats a byte count with the largest binary unit that keeps the value at
+/// or above 1, e.g. `512 B`, `512.0 MiB`, `1.4 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = BYTE_UNITS[0];
+    for next in &BYTE_UNITS[1..] {
+        // Compare the rounded value so 1023.96 KiB shows as 1.0 MiB, not 1024.0 KiB.
+        if (value * 10.0).round() / 10.0 < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
+}
+
+/// `used` as a percentage of `total` with one decimal, or `None` when
+/// `total` is 0.
+pub fn format_percent(used: u64, total: u64) -> Option<String> {
+    if total == 0 {
+        return None;
+    }
+    Some(format!("{:.1}%", used as f64 / total as f64 * 100.0))
+}
+
+/// Parses a size the way multipass reads `--memory`/`--disk`: a number with
+/// an optional K, M, G or T suffix (binary multiples), optionally followed
+/// by `B` or `iB`, e.g. `512M`, `1.5G`, `20GiB`. A bare number is bytes.
+pub fn parse_size(size: &str) -> Option<u64> {
+    let digits_end = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(digits_end);
+    let number = number.parse::<f64>().ok()?;
+    let unit = unit.to_ascii_uppercase();
+    let (prefix, rest) = match unit.as_bytes() {
+        [] => (None, &[][..]),
+        [prefix, rest @ ..] => (Some(*prefix), rest),
+    };
+    if !matches!(rest, [] | b"B" | b"IB") {
+        return None;
     }
+    let multiplier: u64 = match prefix {
+        None => 1,
+        Some(b'K') => 1 << 10,
+        Some(b'M') => 1 << 20,
+        Some(b'G') => 1 << 30,
+        Some(b'T') => 1 << 40,
+        Some(_) => return None,
+    };
+    Some((number * multiplier as f64) as u64)
 }
@@ -1,4 +1,7 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -6,26 +9,582 @@ use axum::{
     Json, Router,
     extract::{Path, State},
     http::StatusCode,
+    middleware,
     routing::{get, post},
 };
+use futures::stream::{self, StreamExt};
+use globset::{Glob, GlobMatcher};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
 use tokio::process::Command;
-use tracing::{debug, info, warn};
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, debug, field, info, info_span, warn};
+
+use crate::api_error::{ApiError, ErrorCode, with_request_id};
+use crate::audit::{AuditAction, AuditRecord, AuditSink, NoopAuditSink};
+use crate::config::VmDefaults;
+use crate::doctor::{MultipassVersions, parse_version_output};
+use crate::redact::Redactor;
+use crate::tags::TagRegistry;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SpawnVmRequest {
     pub name: String,
 }
 
+/// Most CPUs `LaunchSpec::validate` accepts; anything above is a typo.
+pub const MAX_LAUNCH_CPUS: u32 = 256;
+
+/// Most VMs one `vm launch --count` creates; more is almost certainly a typo.
+pub const MAX_LAUNCH_COUNT: u32 = 50;
+
+/// Whether `image` looks like something `multipass launch` takes: an alias
+/// or release (`noble`, `24.04`, `daily:24.04`) or an image URL.
+fn is_valid_image(image: &str) -> bool {
+    image.len() <= 255
+        && image.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && image
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._:/+-".contains(c))
+}
+
+/// Everything needed to launch a VM. Only `name` is required; unset resources
+/// fall back to Multipass defaults.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct LaunchSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Host networks to bridge the VM onto, as listed by `multipass networks`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<String>,
+    /// cloud-config document for first boot, fed to `multipass launch
+    /// --cloud-init -` on stdin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud_init: Option<String>,
+}
+
+impl LaunchSpec {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Checks what multipass would otherwise reject later: a valid name,
+    /// at least one and at most `MAX_LAUNCH_CPUS` CPUs, positive memory and
+    /// disk sizes it can parse, a plausible image and non-blank cloud-init.
+    pub fn validate(&self) -> Result<()> {
+        validate_vm_name(&self.name)?;
+        if let Some(cpus) = self.cpus
+            && !(1..=MAX_LAUNCH_CPUS).contains(&cpus)
+        {
+            anyhow::bail!(
+                "cpus must be between 1 and {}, got {}",
+                MAX_LAUNCH_CPUS,
+                cpus
+            );
+        }
+        for (field, size) in [("memory", &self.memory), ("disk", &self.disk)] {
+            if let Some(size) = size
+                && crate::util::parse_size(size).is_none_or(|bytes| bytes == 0)
+            {
+                anyhow::bail!("{} '{}' is not a size like 512M, 4G or 20GiB", field, size);
+            }
+        }
+        if let Some(image) = &self.image
+            && !is_valid_image(image)
+        {
+            anyhow::bail!(
+                "image '{}' is not a multipass image alias, release or URL",
+                image
+            );
+        }
+        if self
+            .cloud_init
+            .as_ref()
+            .is_some_and(|cloud_init| cloud_init.trim().is_empty())
+        {
+            anyhow::bail!("cloud_init must not be empty");
+        }
+        Ok(())
+    }
+
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["launch".to_owned(), "--name".to_owned(), self.name.clone()];
+        if let Some(cpus) = self.cpus {
+            args.extend(["--cpus".to_owned(), cpus.to_string()]);
+        }
+        if let Some(ref memory) = self.memory {
+            args.extend(["--memory".to_owned(), memory.clone()]);
+        }
+        if let Some(ref disk) = self.disk {
+            args.extend(["--disk".to_owned(), disk.clone()]);
+        }
+        for network in &self.networks {
+            args.extend(["--network".to_owned(), network.clone()]);
+        }
+        if self.cloud_init.is_some() {
+            args.extend(["--cloud-init".to_owned(), "-".to_owned()]);
+        }
+        if let Some(ref image) = self.image {
+            args.push(image.clone());
+        }
+        args
+    }
+}
+
+/// A minimal cloud-config built from `vm launch --timezone`, `--package`
+/// and `--run-cmd`, for when writing a whole cloud-init file is overkill.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CloudConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Installed with the distribution's package manager on first boot.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+    /// Shell commands run once, in order, after the packages are installed.
+    #[serde(rename = "runcmd", skip_serializing_if = "Vec::is_empty")]
+    pub run_cmds: Vec<String>,
+}
+
+impl CloudConfig {
+    pub fn is_empty(&self) -> bool {
+        self.timezone.is_none() && self.packages.is_empty() && self.run_cmds.is_empty()
+    }
+
+    /// Renders the document, starting with the `#cloud-config` line
+    /// cloud-init needs to recognise it. Blank values are rejected.
+    pub fn to_yaml(&self) -> Result<String> {
+        if self
+            .timezone
+            .as_ref()
+            .is_some_and(|tz| tz.trim().is_empty())
+        {
+            anyhow::bail!("timezone must not be empty");
+        }
+        if self
+            .packages
+            .iter()
+            .any(|package| package.trim().is_empty())
+        {
+            anyhow::bail!("package names must not be empty");
+        }
+        if self
+            .run_cmds
+            .iter()
+            .any(|command| command.trim().is_empty())
+        {
+            anyhow::bail!("run commands must not be empty");
+        }
+        let body = serde_yaml::to_string(self)?;
+        Ok(format!("#cloud-config\n{body}"))
+    }
+}
+
+/// A host network that VMs can be bridged onto, from `multipass networks`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NetworkInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub description: String,
+}
+
+/// An image or blueprint that `multipass launch` accepts, from
+/// `multipass find`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct ImageInfo {
+    /// The name to use as `LaunchSpec::image`, e.g. `24.04` or `docker`.
+    pub alias: String,
+    /// `image` or `blueprint`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// How a VM should be stopped.
+///
+/// - `force`: `multipass stop --force`, which powers the VM off immediately.
+/// - `timeout`: give a graceful stop this long before giving up; `LocalVmApi`
+///   then retries with `force`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StopOptions {
+    pub force: bool,
+    pub timeout: Option<Duration>,
+}
+
+impl StopOptions {
+    pub fn forced() -> Self {
+        Self {
+            force: true,
+            timeout: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Options for `VmApi::rename`.
+///
+/// - `force_stop`: stop a running VM first instead of refusing to rename it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenameOptions {
+    pub force_stop: bool,
+}
+
+/// Progress of a rename, which multipass can only do as clone-then-delete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameStep {
+    Stopping,
+    Cloning,
+    Verifying,
+    DeletingOriginal,
+}
+
+/// Longest VM name accepted, leaving room under the 63-character hostname
+/// limit for the suffixes multipass and cloud-init may add.
+pub const MAX_VM_NAME_LEN: usize = 61;
+
+/// The first rule a VM name breaks, see `validate_vm_name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameViolation {
+    Empty,
+    TooLong { length: usize },
+    StartsWithDigit,
+    LeadingHyphen,
+    TrailingHyphen,
+    Uppercase(char),
+    InvalidCharacter(char),
+}
+
+impl NameViolation {
+    /// Machine-readable name of the rule, e.g. `too_long`, for API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong { .. } => "too_long",
+            Self::StartsWithDigit => "starts_with_digit",
+            Self::LeadingHyphen => "leading_hyphen",
+            Self::TrailingHyphen => "trailing_hyphen",
+            Self::Uppercase(_) => "uppercase",
+            Self::InvalidCharacter(_) => "invalid_character",
+        }
+    }
+}
+
+impl std::fmt::Display for NameViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "a VM name is required"),
+            Self::TooLong { length } => write!(
+                f,
+                "it is {length} characters long, the limit is {MAX_VM_NAME_LEN}"
+            ),
+            Self::StartsWithDigit => write!(f, "it must start with a letter, not a digit"),
+            Self::LeadingHyphen => write!(f, "it must not start with a hyphen"),
+            Self::TrailingHyphen => write!(f, "it must not end with a hyphen"),
+            Self::Uppercase(c) => write!(f, "'{c}' is uppercase; use lowercase letters"),
+            Self::InvalidCharacter(c) => write!(
+                f,
+                "{c:?} is not allowed; use lowercase letters, digits and hyphens"
+            ),
+        }
+    }
+}
+
+/// A VM name rejected by `validate_vm_name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameError {
+    pub name: String,
+    pub violation: NameViolation,
+}
+
+impl std::fmt::Display for NameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.violation {
+            NameViolation::Empty => write!(f, "{}", self.violation),
+            _ => write!(f, "invalid VM name '{}': {}", self.name, self.violation),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// Checks a VM name is a hostname multipass will accept: 1 to
+/// `MAX_VM_NAME_LEN` lowercase letters, digits and hyphens, starting with a
+/// letter and not ending in a hyphen. The API and the CLI both call this
+/// before handing a name to multipass.
+pub fn validate_vm_name(name: &str) -> Result<(), NameError> {
+    let violation = if name.is_empty() {
+        Some(NameViolation::Empty)
+    } else if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-')
+    {
+        Some(NameViolation::InvalidCharacter(c))
+    } else if let Some(c) = name.chars().find(char::is_ascii_uppercase) {
+        Some(NameViolation::Uppercase(c))
+    } else if name.len() > MAX_VM_NAME_LEN {
+        Some(NameViolation::TooLong { length: name.len() })
+    } else if name.starts_with('-') {
+        Some(NameViolation::LeadingHyphen)
+    } else if name.ends_with('-') {
+        Some(NameViolation::TrailingHyphen)
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(NameViolation::StartsWithDigit)
+    } else {
+        None
+    };
+    match violation {
+        Some(violation) => Err(NameError {
+            name: name.to_owned(),
+            violation,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Number of journal lines `vm logs` shows when `--lines` is not given.
+pub const DEFAULT_LOG_LINES: usize = 100;
+
+/// Command run inside the VM to read the last `lines` entries of the
+/// systemd journal (which includes cloud-init output).
+pub fn journal_command(lines: usize) -> Vec<String> {
+    vec![
+        "journalctl".to_owned(),
+        "--no-pager".to_owned(),
+        "-n".to_owned(),
+        lines.to_string(),
+    ]
+}
+
+/// Where `vm logs` reads from inside the VM.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogSource {
+    /// `/var/log/cloud-init-output.log`: what provisioning printed.
+    #[default]
+    CloudInit,
+    /// `/var/log/syslog`.
+    Syslog,
+    /// The systemd journal.
+    Journal,
+}
+
+impl LogSource {
+    /// Names accepted by `vm logs --source`.
+    pub const NAMES: [&'static str; 3] = ["cloud-init", "syslog", "journal"];
+
+    /// Command run inside the VM to print the last `lines` lines, and keep
+    /// printing new ones when `follow` is set.
+    pub fn command(self, lines: usize, follow: bool) -> Vec<String> {
+        let mut command = match self {
+            Self::Journal => journal_command(lines),
+            Self::CloudInit | Self::Syslog => {
+                vec!["tail".to_owned(), "-n".to_owned(), lines.to_string()]
+            }
+        };
+        if follow {
+            command.push("-f".to_owned());
+        }
+        match self {
+            Self::CloudInit => command.push("/var/log/cloud-init-output.log".to_owned()),
+            Self::Syslog => command.push("/var/log/syslog".to_owned()),
+            Self::Journal => {}
+        }
+        command
+    }
+}
+
+impl std::str::FromStr for LogSource {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        match source {
+            "cloud-init" => Ok(Self::CloudInit),
+            "syslog" => Ok(Self::Syslog),
+            "journal" => Ok(Self::Journal),
+            other => anyhow::bail!(
+                "unknown log source '{}' (expected one of: {})",
+                other,
+                Self::NAMES.join(", ")
+            ),
+        }
+    }
+}
+
+/// Instance settings `vm set`/`vm get` may touch. Multipass has many more
+/// under `local.<name>.*`; only these are passed through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmProperty {
+    Cpus,
+    Memory,
+    Disk,
+}
+
+impl VmProperty {
+    /// Keys accepted by `vm set`/`vm get`.
+    pub const NAMES: [&'static str; 3] = ["cpus", "memory", "disk"];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cpus => "cpus",
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+        }
+    }
+
+    /// The multipass settings key, e.g. `local.agent-1.cpus`.
+    pub fn settings_key(self, vm_name: &str) -> String {
+        format!("local.{}.{}", vm_name, self.as_str())
+    }
+
+    /// Rejects values multipass would: cpus must be a positive whole number
+    /// and memory and disk a size like `4G`.
+    pub fn validate_value(self, value: &str) -> Result<()> {
+        let valid = match self {
+            Self::Cpus => value.parse::<u32>().is_ok_and(|cpus| cpus > 0),
+            Self::Memory | Self::Disk => {
+                crate::util::parse_size(value).is_some_and(|bytes| bytes > 0)
+            }
+        };
+        if !valid {
+            let expected = match self {
+                Self::Cpus => "a positive whole number",
+                Self::Memory | Self::Disk => "a size like 512M, 4G or 20GiB",
+            };
+            anyhow::bail!("{} '{}' is not {}", self.as_str(), value, expected);
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for VmProperty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for VmProperty {
+    type Err = anyhow::Error;
+
+    fn from_str(key: &str) -> Result<Self> {
+        match key {
+            "cpus" => Ok(Self::Cpus),
+            "memory" => Ok(Self::Memory),
+            "disk" => Ok(Self::Disk),
+            other => anyhow::bail!(
+                "unknown VM property '{}' (expected one of: {})",
+                other,
+                Self::NAMES.join(", ")
+            ),
+        }
+    }
+}
+
+/// Lifecycle state of a VM as reported by multipass.
+///
+/// Serializes as multipass's own string (`"Running"`, `"Stopped"`, ...), so
+/// JSON output is unchanged. States SafePaw has no use for keep their raw
+/// text in `Unknown`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum VmState {
+    Running,
+    Stopped,
+    Suspended,
+    Starting,
+    Deleted,
+    Unknown(String),
+}
+
+impl VmState {
+    /// Every state multipass reports that has its own variant.
+    pub const NAMES: [&'static str; 5] = ["Running", "Stopped", "Suspended", "Starting", "Deleted"];
+
+    /// Parses a state name case-insensitively, rejecting anything that
+    /// would only be `Unknown`.
+    pub fn parse_known(value: &str) -> Result<Self> {
+        match Self::from(value) {
+            Self::Unknown(other) => anyhow::bail!(
+                "unknown VM state '{}' (expected one of: {})",
+                other,
+                Self::NAMES.join(", ")
+            ),
+            state => Ok(state),
+        }
+    }
+}
+
+impl From<&str> for VmState {
+    fn from(value: &str) -> Self {
+        match value.trim() {
+            s if s.eq_ignore_ascii_case("running") => Self::Running,
+            s if s.eq_ignore_ascii_case("stopped") => Self::Stopped,
+            s if s.eq_ignore_ascii_case("suspended") => Self::Suspended,
+            s if s.eq_ignore_ascii_case("starting") => Self::Starting,
+            s if s.eq_ignore_ascii_case("deleted") => Self::Deleted,
+            other => Self::Unknown(other.to_owned()),
+        }
+    }
+}
+
+impl std::str::FromStr for VmState {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(value))
+    }
+}
+
+impl std::fmt::Display for VmState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Running => "Running",
+            Self::Stopped => "Stopped",
+            Self::Suspended => "Suspended",
+            Self::Starting => "Starting",
+            Self::Deleted => "Deleted",
+            Self::Unknown(state) => state,
+        })
+    }
+}
+
+impl From<String> for VmState {
+    fn from(value: String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl From<VmState> for String {
+    fn from(state: VmState) -> Self {
+        state.to_string()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VmStatusResponse {
     pub name: String,
-    pub state: String,
+    pub state: VmState,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ipv4: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub release: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_release: Option<String>,
@@ -39,14 +598,19 @@ pub struct VmStatusResponse {
     pub disk_total: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disk_used: Option<u64>,
+    /// Errors multipass reported alongside the VM's details, e.g. a mount it
+    /// could not inspect.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl VmStatusResponse {
-    pub fn minimal(name: impl Into<String>, state: impl Into<String>) -> Self {
+    pub fn minimal(name: impl Into<String>, state: impl Into<VmState>) -> Self {
         Self {
             name: name.into(),
             state: state.into(),
             ipv4: None,
+            ipv6: None,
             release: None,
             image_release: None,
             cpu_count: None,
@@ -54,6 +618,7 @@ impl VmStatusResponse {
             memory_used: None,
             disk_total: None,
             disk_used: None,
+            warnings: vec![],
         }
     }
 }
@@ -61,28 +626,43 @@ impl VmStatusResponse {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VmSummary {
     pub name: String,
-    pub state: String,
+    pub state: VmState,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ipv4: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub release: Option<String>,
 }
 
 impl VmSummary {
-    pub fn minimal(name: impl Into<String>, state: impl Into<String>) -> Self {
+    pub fn minimal(name: impl Into<String>, state: impl Into<VmState>) -> Self {
         Self {
             name: name.into(),
             state: state.into(),
             ipv4: None,
+            ipv6: None,
             release: None,
         }
     }
 }
 
+/// The VMs a list call returned, with any errors multipass reported for the
+/// ones it could not list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VmListing {
+    pub vms: Vec<VmSummary>,
+    pub warnings: Vec<String>,
+}
+
 #[derive(Debug, Error)]
 pub enum VmError {
     #[error("VM operation not implemented")]
     NotImplemented,
+    /// multipass could not be started, so nothing reached its daemon.
+    #[error("{0}")]
+    SpawnFailed(String),
+    /// Running multipass failed after it started, e.g. reading its output.
     #[error("failed to execute command: {0}")]
     CommandIo(String),
     #[error("multipass {action} failed with status {status_code}: {stderr}")]
@@ -96,34 +676,293 @@ pub enum VmError {
         action: &'static str,
         reason: String,
     },
+    #[error("multipass {action} was cancelled")]
+    Cancelled { action: &'static str },
+    #[error("multipass {action} timed out after {}s", timeout.as_secs())]
+    TimedOut {
+        action: &'static str,
+        timeout: Duration,
+    },
+    #[error("remote transport failed: {0}")]
+    Transport(String),
+    #[error("invalid multipass {action} request: {reason}")]
+    InvalidRequest {
+        action: &'static str,
+        reason: String,
+    },
+    /// Multipass has no instance with this name. Only `exec` reports this
+    /// separately; other commands return `CommandFailed`.
+    #[error("instance \"{name}\" does not exist")]
+    NotFound { name: String },
+    /// Multipass exited successfully but listed `errors` in its JSON output
+    /// in place of the data that was asked for.
+    #[error("multipass {action} reported errors: {}", messages.join("; "))]
+    PartialFailure {
+        action: &'static str,
+        messages: Vec<String>,
+    },
+}
+
+/// What multipass prints when its daemon's socket cannot be reached.
+const MULTIPASS_SOCKET_ERROR: &str = "cannot connect to the multipass socket";
+
+/// Stderr fragments multipass prints when its daemon cannot be reached.
+const MULTIPASS_UNAVAILABLE_PATTERNS: &[&str] = &[MULTIPASS_SOCKET_ERROR, "failed to connect"];
+
+impl VmError {
+    /// Whether multipass reported that the instance does not exist.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::NotFound { .. } => true,
+            Self::CommandFailed { stderr, .. } => stderr.to_lowercase().contains("does not exist"),
+            Self::PartialFailure { messages, .. } => messages
+                .iter()
+                .any(|message| message.to_lowercase().contains("does not exist")),
+            _ => false,
+        }
+    }
+
+    /// Whether multipass refused to create an instance whose name is taken.
+    pub fn is_already_exists(&self) -> bool {
+        matches!(self, Self::CommandFailed { stderr, .. }
+            if stderr.to_lowercase().contains("already exists"))
+    }
+
+    /// Whether multipass could not be run at all: the binary is missing, the
+    /// remote transport failed, or its daemon cannot be reached.
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            Self::SpawnFailed(_) | Self::CommandIo(_) | Self::Transport(_) => true,
+            Self::CommandFailed { stderr, .. } => {
+                let stderr = stderr.to_lowercase();
+                MULTIPASS_UNAVAILABLE_PATTERNS
+                    .iter()
+                    .any(|pattern| stderr.contains(pattern))
+            }
+            _ => false,
+        }
+    }
 }
 
 // High-level VM API trait (used by CLI and server)
 #[async_trait]
 pub trait VmApi: Send + Sync {
-    async fn launch(&self, name: &str) -> Result<()>;
+    async fn launch(&self, spec: &LaunchSpec, cancel: &CancellationToken) -> Result<()>;
+    /// Like `launch`, but passes each progress line from the backend to
+    /// `on_progress` as it arrives. Backends without progress reporting
+    /// just launch.
+    async fn launch_with_progress(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        let _ = on_progress;
+        self.launch(spec, cancel).await
+    }
     async fn start(&self, name: &str) -> Result<()>;
-    async fn stop(&self, name: &str) -> Result<()>;
+    async fn stop(&self, name: &str, opts: &StopOptions) -> Result<()>;
     async fn restart(&self, name: &str) -> Result<()>;
     async fn delete(&self, name: &str) -> Result<()>;
+    /// Deletes several VMs with a single backend call. Fails without touching
+    /// anything when `names` is empty.
+    async fn delete_many(&self, names: &[String]) -> Result<()> {
+        let _ = names;
+        anyhow::bail!("deleting several VMs at once is not supported by this VM backend")
+    }
     async fn info(&self, name: &str) -> Result<VmStatusResponse>;
     async fn list(&self) -> Result<Vec<VmSummary>>;
+    /// Like `list`, but also returns the errors the backend reported for VMs
+    /// it could not list. Backends without partial failures have none.
+    async fn list_with_warnings(&self) -> Result<VmListing> {
+        Ok(VmListing {
+            vms: self.list().await?,
+            warnings: vec![],
+        })
+    }
     async fn exec(&self, name: &str, command: &[String]) -> Result<CommandOutput>;
-    async fn transfer(&self, name: &str, source: &str, destination: &str) -> Result<()>;
+    /// Like `exec`, but pipes `stdin` into the command.
+    async fn exec_with_stdin(
+        &self,
+        name: &str,
+        command: &[String],
+        stdin: &[u8],
+    ) -> Result<CommandOutput> {
+        let _ = (name, command, stdin);
+        anyhow::bail!("exec with stdin is not supported by this VM backend")
+    }
+    /// Like `exec`, but passes each stdout line to `on_line` as it arrives,
+    /// until the command exits or `cancel` fires.
+    async fn exec_streaming(
+        &self,
+        name: &str,
+        command: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput> {
+        let _ = (name, command, on_line, cancel);
+        anyhow::bail!("streaming exec is not supported by this VM backend")
+    }
+    /// Host networks available for `LaunchSpec::networks`.
+    async fn networks(&self) -> Result<Vec<NetworkInfo>> {
+        anyhow::bail!("listing networks is not supported by this VM backend")
+    }
+    /// Images and blueprints that can be launched.
+    async fn find(&self) -> Result<Vec<ImageInfo>> {
+        anyhow::bail!("listing images is not supported by this VM backend")
+    }
+    /// Versions of the multipass client and daemon behind this backend.
+    async fn version(&self) -> Result<MultipassVersions> {
+        anyhow::bail!("reporting versions is not supported by this VM backend")
+    }
+    /// Gives a VM a new name, reporting each step to `progress` as it starts.
+    async fn rename(
+        &self,
+        name: &str,
+        new_name: &str,
+        opts: &RenameOptions,
+        progress: &(dyn Fn(RenameStep) + Send + Sync),
+    ) -> Result<()> {
+        let _ = (name, new_name, opts, progress);
+        anyhow::bail!("renaming VMs is not supported by this VM backend")
+    }
+    /// Changes the CPUs, memory or disk of a stopped VM.
+    async fn set_property(&self, name: &str, key: VmProperty, value: &str) -> Result<()> {
+        let _ = (name, key, value);
+        anyhow::bail!("VM properties are not supported by this VM backend")
+    }
+    /// Reads the CPUs, memory or disk setting of a VM.
+    async fn get_property(&self, name: &str, key: VmProperty) -> Result<String> {
+        let _ = (name, key);
+        anyhow::bail!("VM properties are not supported by this VM backend")
+    }
+    /// Opens an interactive shell in a VM on this process's terminal and
+    /// returns the shell's exit status. Nothing is captured.
+    async fn shell(&self, name: &str) -> Result<i32> {
+        let _ = name;
+        anyhow::bail!("interactive shells are not supported by this VM backend")
+    }
+    /// Adds tags to a VM and returns all of its tags.
+    async fn tag(&self, name: &str, tags: &[String]) -> Result<Vec<String>> {
+        let _ = (name, tags);
+        anyhow::bail!("tags are not supported by this VM backend")
+    }
+    /// Removes tags from a VM and returns the tags it still has.
+    async fn untag(&self, name: &str, tags: &[String]) -> Result<Vec<String>> {
+        let _ = (name, tags);
+        anyhow::bail!("tags are not supported by this VM backend")
+    }
+    async fn tags(&self, name: &str) -> Result<Vec<String>> {
+        let _ = name;
+        anyhow::bail!("tags are not supported by this VM backend")
+    }
+    async fn transfer(
+        &self,
+        name: &str,
+        source: &str,
+        destination: &str,
+        cancel: &CancellationToken,
+    ) -> Result<()>;
 }
 
 // Low-level Multipass CLI trait
 #[async_trait]
 pub trait Multipass: Send + Sync {
-    async fn launch(&self, name: &str) -> Result<(), VmError>;
+    async fn launch(&self, spec: &LaunchSpec, cancel: &CancellationToken) -> Result<(), VmError>;
+    /// Like `launch`, but passes each progress line multipass prints (image
+    /// download, boot waits) to `on_progress` as it arrives.
+    async fn launch_with_progress(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<(), VmError> {
+        let _ = on_progress;
+        self.launch(spec, cancel).await
+    }
     async fn start(&self, name: &str) -> Result<(), VmError>;
-    async fn stop(&self, name: &str) -> Result<(), VmError>;
+    async fn stop(&self, name: &str, opts: &StopOptions) -> Result<(), VmError>;
     async fn restart(&self, name: &str) -> Result<(), VmError>;
     async fn delete(&self, name: &str) -> Result<(), VmError>;
+    /// Deletes every named instance in one `multipass delete` call.
+    async fn delete_many(&self, names: &[String], purge: bool) -> Result<(), VmError> {
+        let _ = (names, purge);
+        Err(VmError::NotImplemented)
+    }
     async fn info(&self, name: &str) -> Result<VmStatusResponse, VmError>;
     async fn list(&self) -> Result<Vec<VmSummary>, VmError>;
+    /// Like `list`, but keeps the `errors` multipass reported next to the
+    /// VMs it did list.
+    async fn list_with_warnings(&self) -> Result<VmListing, VmError> {
+        Ok(VmListing {
+            vms: self.list().await?,
+            warnings: vec![],
+        })
+    }
     async fn exec(&self, name: &str, command: &[String]) -> Result<CommandOutput, VmError>;
-    async fn transfer(&self, name: &str, source: &str, destination: &str) -> Result<(), VmError>;
+    /// Like `exec`, but pipes `stdin` into the command.
+    async fn exec_with_stdin(
+        &self,
+        name: &str,
+        command: &[String],
+        stdin: &[u8],
+    ) -> Result<CommandOutput, VmError> {
+        let _ = (name, command, stdin);
+        Err(VmError::NotImplemented)
+    }
+    /// Like `exec`, but passes each stdout line to `on_line` as it arrives.
+    /// The returned output's `stdout` is empty.
+    async fn exec_streaming(
+        &self,
+        name: &str,
+        command: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        let _ = (name, command, on_line, cancel);
+        Err(VmError::NotImplemented)
+    }
+    /// Host networks available for `LaunchSpec::networks`.
+    async fn networks(&self) -> Result<Vec<NetworkInfo>, VmError> {
+        Err(VmError::NotImplemented)
+    }
+    /// Images and blueprints available to launch (`multipass find`).
+    async fn find(&self) -> Result<Vec<ImageInfo>, VmError> {
+        Err(VmError::NotImplemented)
+    }
+    /// Client and daemon versions (`multipass version`).
+    async fn version(&self) -> Result<MultipassVersions, VmError> {
+        Err(VmError::NotImplemented)
+    }
+    /// Copies a stopped instance under a new name (`multipass clone`).
+    async fn clone_vm(&self, source: &str, destination: &str) -> Result<(), VmError> {
+        let _ = (source, destination);
+        Err(VmError::NotImplemented)
+    }
+    /// Changes a setting of a stopped instance
+    /// (`multipass set local.<name>.<key>=<value>`).
+    async fn set_property(&self, name: &str, key: VmProperty, value: &str) -> Result<(), VmError> {
+        let _ = (name, key, value);
+        Err(VmError::NotImplemented)
+    }
+    /// Reads a setting of an instance (`multipass get local.<name>.<key>`).
+    async fn get_property(&self, name: &str, key: VmProperty) -> Result<String, VmError> {
+        let _ = (name, key);
+        Err(VmError::NotImplemented)
+    }
+    /// Runs `multipass shell <name>` on this process's terminal and returns
+    /// its exit status.
+    async fn shell(&self, name: &str) -> Result<i32, VmError> {
+        let _ = name;
+        Err(VmError::NotImplemented)
+    }
+    async fn transfer(
+        &self,
+        name: &str,
+        source: &str,
+        destination: &str,
+        cancel: &CancellationToken,
+    ) -> Result<(), VmError>;
 }
 
 #[derive(Debug, Clone)]
@@ -131,6 +970,9 @@ pub struct CommandOutput {
     pub status_code: i32,
     pub stdout: String,
     pub stderr: String,
+    /// Whether stdout or stderr went over the executor's capture limit and
+    /// was cut short.
+    pub truncated: bool,
 }
 
 impl CommandOutput {
@@ -139,77 +981,982 @@ impl CommandOutput {
             status_code: 0,
             stdout: stdout.into(),
             stderr: String::new(),
+            truncated: false,
         }
     }
 }
 
 #[async_trait]
 pub trait CommandExecutor: Send + Sync {
-    async fn run(&self, program: &str, args: &[String]) -> anyhow::Result<CommandOutput>;
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct TokioCommandExecutor;
+    /// Runs `program` to completion, feeding it `stdin` (if any) and killing
+    /// it early if `cancel` fires.
+    async fn run_with_stdin(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput>;
+
+    /// Runs `program` with no stdin.
+    async fn run(
+        &self,
+        program: &str,
+        args: &[String],
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        self.run_with_stdin(program, args, None, cancel).await
+    }
 
-#[async_trait]
-impl CommandExecutor for TokioCommandExecutor {
-    async fn run(&self, program: &str, args: &[String]) -> anyhow::Result<CommandOutput> {
-        let output = Command::new(program).args(args).output().await?;
+    /// Runs `program` with no stdin, passing each stdout line to `on_line`
+    /// as it arrives. The returned output's `stdout` is left empty.
+    ///
+    /// The default waits for the command to finish and then replays its
+    /// output, which is only right for commands that end on their own.
+    async fn run_streaming(
+        &self,
+        program: &str,
+        args: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let output = self.run(program, args, cancel).await?;
+        output.stdout.lines().for_each(on_line);
         Ok(CommandOutput {
-            status_code: output.status.code().unwrap_or(-1),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            stdout: String::new(),
+            ..output
         })
     }
+
+    /// Like `run_with_stdin`, but also passes each stderr line to
+    /// `on_progress` as it arrives, for commands such as `multipass launch`
+    /// that report progress there. The returned output still carries the
+    /// full stderr.
+    ///
+    /// The default waits for the command to finish and then replays its
+    /// stderr.
+    async fn run_with_progress(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let output = self.run_with_stdin(program, args, stdin, cancel).await?;
+        progress_lines(&output.stderr).for_each(on_progress);
+        Ok(output)
+    }
+
+    /// Runs `program` attached to this process's terminal (stdin, stdout
+    /// and stderr are inherited, nothing is captured) and returns its exit
+    /// status, or -1 if it was killed by a signal.
+    async fn run_interactive(&self, program: &str, args: &[String]) -> anyhow::Result<i32> {
+        let _ = args;
+        anyhow::bail!("{program} can't be run interactively by this executor")
+    }
+}
+
+/// Splits progress output into non-empty lines. Spinners and download
+/// percentages redraw with a bare `\r`, so that ends a line too.
+fn progress_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split(['\n', '\r'])
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
 }
 
+/// Receives command output one line at a time, without the line ending.
+pub type LineSink<'a> = dyn Fn(&str) + Send + Sync + 'a;
+
+/// Default cap on how much of each of stdout and stderr is kept in memory.
+pub const DEFAULT_MAX_CAPTURE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Runs commands as local child processes.
+///
+/// A child is killed when its token is cancelled or when the future running
+/// it is dropped, so an aborted CLI or a disconnected API client never leaves
+/// multipass working on its own.
+///
+/// At most `max_capture` bytes of each of stdout and stderr are kept; the
+/// rest is still read (so the child never blocks on a full pipe) but dropped,
+/// and the output ends with a `... [truncated N bytes]` marker.
 #[derive(Debug, Clone)]
-pub struct MultipassCli<E>
+pub struct TokioCommandExecutor {
+    max_capture: usize,
+}
+
+impl Default for TokioCommandExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokioCommandExecutor {
+    pub fn new() -> Self {
+        Self {
+            max_capture: DEFAULT_MAX_CAPTURE_BYTES,
+        }
+    }
+
+    pub fn with_max_capture(mut self, max_capture: usize) -> Self {
+        self.max_capture = max_capture;
+        self
+    }
+}
+
+/// Output read by `read_capped`: the kept bytes and how many were dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CappedOutput {
+    pub bytes: Vec<u8>,
+    pub dropped: u64,
+}
+
+impl CappedOutput {
+    /// The kept bytes as text, with a marker saying how much was dropped.
+    pub fn into_text(self) -> String {
+        let mut text = String::from_utf8_lossy(&self.bytes).into_owned();
+        if self.dropped > 0 {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&format!("... [truncated {} bytes]", self.dropped));
+        }
+        text
+    }
+}
+
+/// Reads `reader` to the end, keeping only the first `limit` bytes.
+pub async fn read_capped<R>(reader: &mut R, limit: usize) -> std::io::Result<CappedOutput>
 where
-    E: CommandExecutor,
+    R: tokio::io::AsyncRead + Unpin,
 {
-    executor: E,
+    let mut output = CappedOutput::default();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(output);
+        }
+        let kept = read.min(limit.saturating_sub(output.bytes.len()));
+        output.bytes.extend_from_slice(&chunk[..kept]);
+        output.dropped += (read - kept) as u64;
+    }
 }
 
-impl<E> MultipassCli<E>
+/// Longest stderr line `read_capped_lines` buffers before passing it on.
+const MAX_PROGRESS_LINE_BYTES: usize = 64 * 1024;
+
+/// Like `read_capped`, but also passes every complete line (as split by
+/// `progress_lines`) to `on_line` as soon as it has been read, whether or
+/// not it fits in the kept bytes.
+pub async fn read_capped_lines<R>(
+    reader: &mut R,
+    limit: usize,
+    on_line: &LineSink<'_>,
+) -> std::io::Result<CappedOutput>
 where
-    E: CommandExecutor,
+    R: tokio::io::AsyncRead + Unpin,
 {
-    pub fn new(executor: E) -> Self {
-        Self { executor }
+    let mut output = CappedOutput::default();
+    let mut pending = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            progress_lines(&String::from_utf8_lossy(&pending)).for_each(on_line);
+            return Ok(output);
+        }
+        let kept = read.min(limit.saturating_sub(output.bytes.len()));
+        output.bytes.extend_from_slice(&chunk[..kept]);
+        output.dropped += (read - kept) as u64;
+
+        pending.extend_from_slice(&chunk[..read]);
+        if let Some(end) = pending.iter().rposition(|&b| b == b'\n' || b == b'\r') {
+            let complete: Vec<u8> = pending.drain(..=end).collect();
+            progress_lines(&String::from_utf8_lossy(&complete)).for_each(on_line);
+        }
+        // A runaway line is passed on in pieces rather than held in memory
+        // until it ends.
+        if pending.len() > MAX_PROGRESS_LINE_BYTES {
+            progress_lines(&String::from_utf8_lossy(&pending)).for_each(on_line);
+            pending.clear();
+        }
     }
+}
 
-    async fn run_command(
+#[async_trait]
+impl CommandExecutor for TokioCommandExecutor {
+    async fn run_with_stdin(
         &self,
-        action: &'static str,
-        args: Vec<String>,
-    ) -> Result<CommandOutput, VmError> {
-        let command_preview = format!("multipass {}", args.join(" "));
-        info!(action = action, command = %command_preview, "running multipass command");
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        self.run_with_progress(program, args, stdin, &|_| {}, cancel)
+            .await
+    }
+
+    async fn run_with_progress(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let stdin_mode = if stdin.is_some() {
+            std::process::Stdio::piped()
+        } else {
+            std::process::Stdio::null()
+        };
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(stdin_mode)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| SpawnError::new(program, err))?;
+
+        // Dropping the pipe once everything is written signals EOF to the
+        // child. A child that exits without reading it all is not an error.
+        let stdin_pipe = child.stdin.take();
+        let feed_stdin = async move {
+            if let (Some(mut pipe), Some(bytes)) = (stdin_pipe, stdin) {
+                match pipe.write_all(bytes).await {
+                    Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => return Err(err),
+                    _ => {}
+                }
+            }
+            Ok(())
+        };
+
+        let mut stdout_pipe = child.stdout.take().expect("child stdout should be piped");
+        let mut stderr_pipe = child.stderr.take().expect("child stderr should be piped");
+
+        let (status, stdout, stderr) = tokio::select! {
+            result = async {
+                tokio::try_join!(
+                    child.wait(),
+                    read_capped(&mut stdout_pipe, self.max_capture),
+                    read_capped_lines(&mut stderr_pipe, self.max_capture, on_progress),
+                    feed_stdin,
+                )
+            } => {
+                let (status, stdout, stderr, ()) = result?;
+                (status, stdout, stderr)
+            }
+            _ = cancel.cancelled() => {
+                child.kill().await?;
+                anyhow::bail!("{program} was killed after cancellation");
+            }
+        };
+
+        let truncated = stdout.dropped > 0 || stderr.dropped > 0;
+        Ok(CommandOutput {
+            status_code: status.code().unwrap_or(-1),
+            stdout: stdout.into_text(),
+            stderr: stderr.into_text(),
+            truncated,
+        })
+    }
+
+    async fn run_streaming(
+        &self,
+        program: &str,
+        args: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| SpawnError::new(program, err))?;
+
+        let stdout_pipe = child.stdout.take().expect("child stdout should be piped");
+        let mut stderr_pipe = child.stderr.take().expect("child stderr should be piped");
+        // Split on raw bytes so a stray invalid UTF-8 line doesn't end the stream.
+        let forward_lines = async move {
+            let mut lines = tokio::io::BufReader::new(stdout_pipe).split(b'\n');
+            while let Some(line) = lines.next_segment().await? {
+                on_line(String::from_utf8_lossy(&line).trim_end_matches('\r'));
+            }
+            Ok(())
+        };
+
+        let (status, stderr) = tokio::select! {
+            result = async {
+                tokio::try_join!(
+                    child.wait(),
+                    read_capped(&mut stderr_pipe, self.max_capture),
+                    forward_lines,
+                )
+            } => {
+                let (status, stderr, ()) = result?;
+                (status, stderr)
+            }
+            _ = cancel.cancelled() => {
+                child.kill().await?;
+                anyhow::bail!("{program} was killed after cancellation");
+            }
+        };
+
+        Ok(CommandOutput {
+            status_code: status.code().unwrap_or(-1),
+            stdout: String::new(),
+            truncated: stderr.dropped > 0,
+            stderr: stderr.into_text(),
+        })
+    }
+
+    async fn run_interactive(&self, program: &str, args: &[String]) -> anyhow::Result<i32> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::inherit())
+            .stdout(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::inherit())
+            .spawn()
+            .map_err(|err| SpawnError::new(program, err))?;
+
+        // The child shares our terminal's foreground process group, so the
+        // terminal delivers Ctrl+C to it directly. We only have to survive
+        // the signal ourselves and keep waiting until the child decides
+        // what to do with it.
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break status?,
+                _ = signal::ctrl_c() => {}
+            }
+        };
+        Ok(status.code().unwrap_or(-1))
+    }
+}
+
+/// Connection settings for driving multipass on another machine over SSH.
+#[derive(Debug, Clone)]
+pub struct SshConfig {
+    pub host: String,
+    pub user: Option<String>,
+    pub identity_file: Option<PathBuf>,
+    pub connect_timeout: Duration,
+}
+
+impl SshConfig {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            user: None,
+            identity_file: None,
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+
+    fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+/// The program could not be started at all. Nothing ran, so unlike other
+/// executor errors this one is safe to retry for any action.
+#[derive(Debug, Error)]
+#[error("failed to start {program}: {source}")]
+pub struct SpawnError {
+    pub program: String,
+    #[source]
+    pub source: std::io::Error,
+}
+
+impl SpawnError {
+    fn new(program: &str, source: std::io::Error) -> Self {
+        Self {
+            program: program.to_owned(),
+            source,
+        }
+    }
+}
+
+/// Failure reported by the `ssh` client itself (authentication, host key or
+/// connection problems), as opposed to the remote command failing.
+#[derive(Debug, Error)]
+#[error("ssh to {destination} failed: {stderr}")]
+pub struct SshTransportError {
+    pub destination: String,
+    pub stderr: String,
+}
+
+/// Exit status `ssh` uses for its own errors.
+const SSH_TRANSPORT_FAILURE: i32 = 255;
+
+/// Stderr fragments of ssh's own errors, e.g. `ssh: connect to host ...`.
+const SSH_ERROR_PATTERNS: &[&str] = &[
+    "ssh: ",
+    "permission denied (",
+    "host key verification failed",
+    "connection closed by",
+    "kex_exchange_identification",
+];
+
+/// Runs commands on a remote host through the system `ssh` client.
+#[derive(Debug, Clone)]
+pub struct SshCommandExecutor<E = TokioCommandExecutor>
+where
+    E: CommandExecutor,
+{
+    config: SshConfig,
+    inner: E,
+}
+
+impl SshCommandExecutor {
+    pub fn new(config: SshConfig) -> Self {
+        Self::with_executor(config, TokioCommandExecutor::new())
+    }
+}
+
+impl<E> SshCommandExecutor<E>
+where
+    E: CommandExecutor,
+{
+    /// Uses `inner` to spawn the local `ssh` process.
+    pub fn with_executor(config: SshConfig, inner: E) -> Self {
+        Self { config, inner }
+    }
+
+    /// Turns ssh's own failure status into an `SshTransportError`, so it
+    /// isn't mistaken for the remote command failing. `multipass exec`
+    /// passes through whatever its command exits with, 255 included, so
+    /// there ssh's stderr has to confirm the failure was its own.
+    fn check_transport(
+        &self,
+        args: &[String],
+        output: CommandOutput,
+    ) -> anyhow::Result<CommandOutput> {
+        let ssh_failed = output.status_code == SSH_TRANSPORT_FAILURE
+            && (args.first().is_none_or(|action| action != "exec") || {
+                let stderr = output.stderr.to_lowercase();
+                SSH_ERROR_PATTERNS
+                    .iter()
+                    .any(|pattern| stderr.contains(pattern))
+            });
+        if ssh_failed {
+            return Err(SshTransportError {
+                destination: self.config.destination(),
+                stderr: output.stderr.trim().to_owned(),
+            }
+            .into());
+        }
+        Ok(output)
+    }
+
+    /// Arguments passed to the local `ssh` binary to run `program args...`
+    /// on the remote host.
+    pub fn ssh_args(&self, program: &str, args: &[String]) -> Vec<String> {
+        let mut ssh_args = vec![
+            "-o".to_owned(),
+            "BatchMode=yes".to_owned(),
+            "-o".to_owned(),
+            format!(
+                "ConnectTimeout={}",
+                self.config.connect_timeout.as_secs().max(1)
+            ),
+        ];
+        if let Some(identity_file) = &self.config.identity_file {
+            ssh_args.push("-i".to_owned());
+            ssh_args.push(identity_file.display().to_string());
+        }
+        ssh_args.push(self.config.destination());
+        ssh_args.push("--".to_owned());
+
+        // The remote side runs the command through a shell, so every word
+        // has to be quoted to survive intact.
+        let remote_command = std::iter::once(program)
+            .chain(args.iter().map(String::as_str))
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ");
+        ssh_args.push(remote_command);
+        ssh_args
+    }
+}
+
+#[async_trait]
+impl<E> CommandExecutor for SshCommandExecutor<E>
+where
+    E: CommandExecutor,
+{
+    async fn run_with_stdin(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let output = self
+            .inner
+            .run_with_stdin("ssh", &self.ssh_args(program, args), stdin, cancel)
+            .await?;
+        self.check_transport(args, output)
+    }
+
+    async fn run_streaming(
+        &self,
+        program: &str,
+        args: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let output = self
+            .inner
+            .run_streaming("ssh", &self.ssh_args(program, args), on_line, cancel)
+            .await?;
+        self.check_transport(args, output)
+    }
+
+    async fn run_with_progress(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let output = self
+            .inner
+            .run_with_progress(
+                "ssh",
+                &self.ssh_args(program, args),
+                stdin,
+                on_progress,
+                cancel,
+            )
+            .await?;
+        self.check_transport(args, output)
+    }
+
+    async fn run_interactive(&self, program: &str, args: &[String]) -> anyhow::Result<i32> {
+        // `-t` gives the remote command a terminal of its own.
+        let mut ssh_args = vec!["-t".to_owned()];
+        ssh_args.extend(self.ssh_args(program, args));
+        let status = self.inner.run_interactive("ssh", &ssh_args).await?;
+        if status == SSH_TRANSPORT_FAILURE {
+            // ssh already printed why to the inherited stderr.
+            return Err(SshTransportError {
+                destination: self.config.destination(),
+                stderr: format!("ssh exited with status {}", status),
+            }
+            .into());
+        }
+        Ok(status)
+    }
+}
+
+/// Quotes `word` for a POSIX shell, leaving plain words untouched.
+fn shell_quote(word: &str) -> String {
+    let is_plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+".contains(c));
+    if is_plain {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Actions that can safely be re-run after multipass reported a failure.
+const IDEMPOTENT_ACTIONS: &[&str] = &["list", "info", "networks", "find", "start", "stop"];
+
+/// Retry policy for transient multipass failures (e.g. the daemon socket not
+/// being ready right after multipassd starts).
+///
+/// Failures to run the command at all are retried for every action. Failures
+/// reported by multipass itself are only retried for idempotent actions and
+/// only when stderr matches one of `retryable_patterns`.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub retryable_patterns: Vec<String>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(5),
+            retryable_patterns: vec![
+                "cannot connect to the multipass socket".to_owned(),
+                "timed out".to_owned(),
+                "timeout".to_owned(),
+            ],
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    fn should_retry(&self, action: &str, error: &VmError) -> bool {
+        match error {
+            // Nothing ran, so even `launch` and `delete` can go again.
+            VmError::SpawnFailed(_) => true,
+            // The command may have reached multipassd before the pipe or the
+            // connection failed, so only idempotent actions are safe to re-run.
+            VmError::CommandIo(_) | VmError::Transport(_) => IDEMPOTENT_ACTIONS.contains(&action),
+            VmError::CommandFailed { stderr, .. } => {
+                let stderr = stderr.to_lowercase();
+                IDEMPOTENT_ACTIONS.contains(&action)
+                    && self
+                        .retryable_patterns
+                        .iter()
+                        .any(|pattern| stderr.contains(&pattern.to_lowercase()))
+            }
+            _ => false,
+        }
+    }
+
+    /// Exponential backoff for the given (1-based) retry attempt, capped at
+    /// `max_backoff`, with up to 50% random jitter added.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let base = self
+            .initial_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff);
+        let jitter_ms = rand::random_range(0..=base.as_millis() as u64 / 2);
+        base + Duration::from_millis(jitter_ms)
+    }
+}
+
+/// Runs `command` inside a `multipass` span labelled with `action` and `vm`,
+/// recording how long it took, retries included, and the exit status it
+/// ended with once it is done.
+async fn in_command_span<F>(
+    action: &'static str,
+    vm: Option<&str>,
+    command: F,
+) -> Result<CommandOutput, VmError>
+where
+    F: std::future::Future<Output = Result<CommandOutput, VmError>>,
+{
+    let span = info_span!(
+        "multipass",
+        action,
+        vm,
+        elapsed_ms = field::Empty,
+        status_code = field::Empty,
+    );
+    let started = tokio::time::Instant::now();
+    let result = command.instrument(span.clone()).await;
+    span.record("elapsed_ms", started.elapsed().as_millis() as u64);
+    match &result {
+        Ok(output) => {
+            span.record("status_code", output.status_code);
+        }
+        Err(VmError::CommandFailed { status_code, .. }) => {
+            span.record("status_code", status_code);
+        }
+        Err(_) => {}
+    }
+    result
+}
+
+#[derive(Debug, Clone)]
+pub struct MultipassCli<E>
+where
+    E: CommandExecutor,
+{
+    executor: E,
+    binary: String,
+    retry: RetryConfig,
+    redactor: Redactor,
+    managed_prefix: Option<String>,
+}
+
+impl<E> MultipassCli<E>
+where
+    E: CommandExecutor,
+{
+    pub fn new(executor: E) -> Self {
+        Self::new_with_retry(executor, RetryConfig::default())
+    }
+
+    pub fn new_with_retry(executor: E, retry: RetryConfig) -> Self {
+        Self {
+            executor,
+            binary: "multipass".to_owned(),
+            retry,
+            redactor: Redactor::default(),
+            managed_prefix: None,
+        }
+    }
+
+    /// Runs this multipass executable instead of the one on `PATH`.
+    pub fn with_binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// Replaces the rules used to hide secrets from logs and error messages.
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Restricts this client to VMs whose names start with `prefix`: `list`
+    /// hides everything else and `launch` refuses names without it. Useful
+    /// on shared hosts where other tools also create multipass VMs.
+    pub fn with_managed_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.managed_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Runs a multipass command. `vm` is the instance it acts on, if it acts
+    /// on exactly one, and only labels the command's tracing span.
+    async fn run_command(
+        &self,
+        action: &'static str,
+        vm: Option<&str>,
+        args: Vec<String>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        self.run_command_with_stdin(action, vm, args, None, cancel)
+            .await
+    }
+
+    async fn run_command_with_stdin(
+        &self,
+        action: &'static str,
+        vm: Option<&str>,
+        args: Vec<String>,
+        stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        self.run_command_with_progress(action, vm, args, stdin, None, cancel)
+            .await
+    }
+
+    /// Runs a multipass command, passing its stderr lines to `on_progress`
+    /// (if any) as they arrive. A retried attempt reports its progress again.
+    async fn run_command_with_progress(
+        &self,
+        action: &'static str,
+        vm: Option<&str>,
+        args: Vec<String>,
+        stdin: Option<&[u8]>,
+        on_progress: Option<&LineSink<'_>>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        in_command_span(action, vm, async {
+            self.run_with_retries(action, &args, stdin, on_progress, cancel)
+                .await
+        })
+        .await
+    }
+
+    async fn run_with_retries(
+        &self,
+        action: &'static str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        on_progress: Option<&LineSink<'_>>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        let mut attempt = 0;
+        loop {
+            match self
+                .run_command_once(action, args, stdin, on_progress, cancel)
+                .await
+            {
+                Err(err)
+                    if attempt < self.retry.max_retries
+                        && self.retry.should_retry(action, &err) =>
+                {
+                    attempt += 1;
+                    let delay = self.retry.backoff(attempt);
+                    warn!(
+                        action = action,
+                        attempt = attempt,
+                        max_retries = self.retry.max_retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %err,
+                        "retrying multipass command"
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = cancel.cancelled() => return Err(VmError::Cancelled { action }),
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn run_command_once(
+        &self,
+        action: &'static str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        on_progress: Option<&LineSink<'_>>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        let command_preview = format!(
+            "{} {}",
+            self.binary,
+            self.redactor.redact_args(args).join(" ")
+        );
+        info!(action = action, command = %command_preview, "running multipass command");
+
+        let output = match on_progress {
+            Some(on_progress) => {
+                self.executor
+                    .run_with_progress(&self.binary, args, stdin, on_progress, cancel)
+                    .await
+            }
+            None => {
+                self.executor
+                    .run_with_stdin(&self.binary, args, stdin, cancel)
+                    .await
+            }
+        };
+        let output = output.map_err(|err| self.executor_error(action, args, err, cancel))?;
+        self.check_output(action, args, output)
+    }
+
+    async fn launch_reporting(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: Option<&LineSink<'_>>,
+        cancel: &CancellationToken,
+    ) -> Result<(), VmError> {
+        // Renaming here would leave callers reporting, auditing and waiting
+        // on a name that was never created, so a stray name is refused.
+        if let Some(prefix) = &self.managed_prefix
+            && !spec.name.starts_with(prefix.as_str())
+        {
+            return Err(VmError::InvalidRequest {
+                action: "launch",
+                reason: format!(
+                    "VM name '{}' does not start with the managed prefix '{}'",
+                    spec.name, prefix
+                ),
+            });
+        }
+        let cloud_init = spec.cloud_init.as_deref().map(str::as_bytes);
+        self.run_command_with_progress(
+            "launch",
+            Some(&spec.name),
+            spec.to_args(),
+            cloud_init,
+            on_progress,
+            cancel,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Runs a multipass command that streams stdout to `on_line`. Never
+    /// retried, since some output has already been passed on.
+    async fn run_streaming_command(
+        &self,
+        action: &'static str,
+        vm: Option<&str>,
+        args: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        in_command_span(action, vm, async {
+            self.run_streaming_once(action, args, on_line, cancel).await
+        })
+        .await
+    }
+
+    async fn run_streaming_once(
+        &self,
+        action: &'static str,
+        args: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        let command_preview = format!(
+            "{} {}",
+            self.binary,
+            self.redactor.redact_args(args).join(" ")
+        );
+        info!(action = action, command = %command_preview, "running multipass command");
 
         let output = self
             .executor
-            .run("multipass", &args)
+            .run_streaming(&self.binary, args, on_line, cancel)
             .await
-            .map_err(|err| VmError::CommandIo(err.to_string()))?;
+            .map_err(|err| self.executor_error(action, args, err, cancel))?;
+        self.check_output(action, args, output)
+    }
+
+    /// Maps a failure to run the command at all to a `VmError`.
+    fn executor_error(
+        &self,
+        action: &'static str,
+        args: &[String],
+        err: anyhow::Error,
+        cancel: &CancellationToken,
+    ) -> VmError {
+        if cancel.is_cancelled() {
+            warn!(action = action, "multipass command cancelled");
+            return VmError::Cancelled { action };
+        }
+        if let Some(spawn) = err.downcast_ref::<SpawnError>() {
+            return VmError::SpawnFailed(spawn.to_string());
+        }
+        match err.downcast::<SshTransportError>() {
+            Ok(transport) => {
+                VmError::Transport(self.redactor.redact_output(&transport.to_string(), args))
+            }
+            Err(err) => VmError::CommandIo(err.to_string()),
+        }
+    }
 
+    /// Logs the command's output and turns a non-zero exit into an error.
+    fn check_output(
+        &self,
+        action: &'static str,
+        args: &[String],
+        output: CommandOutput,
+    ) -> Result<CommandOutput, VmError> {
+        if action == "exec" && output.status_code != 0 {
+            return self.check_exec_output(args, output);
+        }
         if output.status_code != 0 {
-            let trimmed_stdout = output.stdout.trim();
+            let trimmed_stdout = self.redactor.redact_output(output.stdout.trim(), args);
             if !trimmed_stdout.is_empty() {
                 debug!(action = action, stdout = %trimmed_stdout, "multipass stdout");
             }
-            let trimmed_stderr = output.stderr.trim();
+            let trimmed_stderr = self.redactor.redact_output(output.stderr.trim(), args);
             if !trimmed_stderr.is_empty() {
                 warn!(action = action, stderr = %trimmed_stderr, "multipass stderr");
             }
             return Err(VmError::CommandFailed {
                 action,
                 status_code: output.status_code,
-                stderr: output.stderr.trim().to_owned(),
+                stderr: trimmed_stderr,
             });
         }
 
-        let trimmed_stderr = output.stderr.trim();
+        let trimmed_stderr = self.redactor.redact_output(output.stderr.trim(), args);
         if !trimmed_stderr.is_empty() {
             debug!(action = action, stderr = %trimmed_stderr, "multipass stderr");
         }
@@ -218,40 +1965,89 @@ where
         Ok(output)
     }
 
+    /// `multipass exec` passes the command's exit status through as its own,
+    /// so a non-zero exit is only an error when multipass itself failed: the
+    /// instance is missing or the daemon's socket can't be reached. Anything
+    /// else, including a `curl` that failed to connect, is returned as the
+    /// command's output.
+    fn check_exec_output(
+        &self,
+        args: &[String],
+        output: CommandOutput,
+    ) -> Result<CommandOutput, VmError> {
+        // args are `exec <name> -- <command>...`
+        let name = args.get(1).map(String::as_str).unwrap_or_default();
+        let stderr = output.stderr.to_lowercase();
+        if stderr.contains(&format!(
+            "instance \"{}\" does not exist",
+            name.to_lowercase()
+        )) {
+            return Err(VmError::NotFound {
+                name: name.to_owned(),
+            });
+        }
+        if stderr.contains(MULTIPASS_SOCKET_ERROR) {
+            let stderr = self.redactor.redact_output(output.stderr.trim(), args);
+            warn!(action = "exec", stderr = %stderr, "multipass stderr");
+            return Err(VmError::CommandFailed {
+                action: "exec",
+                status_code: output.status_code,
+                stderr,
+            });
+        }
+        debug!(
+            action = "exec",
+            status_code = output.status_code,
+            "command in VM exited non-zero"
+        );
+        Ok(output)
+    }
+
     fn parse_status_output(&self, name: &str, output: &str) -> Result<VmStatusResponse, VmError> {
         let value: Value = serde_json::from_str(output).map_err(|err| VmError::InvalidOutput {
             action: "status",
             reason: err.to_string(),
         })?;
 
-        let info = value
+        let errors = multipass_json_errors(&value);
+        let vm = value
             .get("info")
             .and_then(Value::as_object)
-            .ok_or_else(|| VmError::InvalidOutput {
+            .and_then(|info| info.get(name));
+        let Some(vm) = vm else {
+            if !errors.is_empty() {
+                return Err(VmError::PartialFailure {
+                    action: "info",
+                    messages: errors,
+                });
+            }
+            let reason = if value.get("info").is_some_and(Value::is_object) {
+                format!("missing VM entry for {name}")
+            } else {
+                "missing info object".to_owned()
+            };
+            return Err(VmError::InvalidOutput {
                 action: "status",
-                reason: "missing info object".to_owned(),
-            })?;
-
-        let vm = info.get(name).ok_or_else(|| VmError::InvalidOutput {
-            action: "status",
-            reason: format!("missing VM entry for {name}"),
-        })?;
+                reason,
+            });
+        };
+        for error in &errors {
+            warn!(vm = name, "multipass info reported: {}", error);
+        }
 
-        let state =
-            vm.get("state")
-                .and_then(Value::as_str)
-                .ok_or_else(|| VmError::InvalidOutput {
-                    action: "status",
-                    reason: "missing VM state".to_owned(),
-                })?;
+        // Stopped or half-created VMs can come back with most fields missing;
+        // report what is there instead of failing the whole call.
+        let state = match vm.get("state").and_then(Value::as_str) {
+            Some(state) => VmState::from(state),
+            None => {
+                warn!(vm = name, "multipass info has no state; reporting Unknown");
+                VmState::Unknown("Unknown".to_owned())
+            }
+        };
 
         // Extract optional fields
-        let ipv4 = vm.get("ipv4").and_then(Value::as_array).map(|arr| {
-            arr.iter()
-                .filter_map(Value::as_str)
-                .map(String::from)
-                .collect()
-        });
+        let ipv4 = parse_address_list(vm, "ipv4");
+        let ipv6 = parse_address_list(vm, "ipv6");
 
         let release = vm.get("release").and_then(Value::as_str).map(String::from);
         let image_release = vm
@@ -292,8 +2088,9 @@ where
 
         Ok(VmStatusResponse {
             name: name.to_owned(),
-            state: state.to_owned(),
+            state,
             ipv4,
+            ipv6,
             release,
             image_release,
             cpu_count,
@@ -301,23 +2098,33 @@ where
             memory_used,
             disk_total,
             disk_used,
+            warnings: errors,
         })
     }
 
-    fn parse_list_output(&self, output: &str) -> Result<Vec<VmSummary>, VmError> {
+    fn parse_list_output(&self, output: &str) -> Result<VmListing, VmError> {
         let value: Value = serde_json::from_str(output).map_err(|err| VmError::InvalidOutput {
             action: "list",
             reason: err.to_string(),
         })?;
 
-        let list =
-            value
-                .get("list")
-                .and_then(Value::as_array)
-                .ok_or_else(|| VmError::InvalidOutput {
+        let errors = multipass_json_errors(&value);
+        let Some(list) = value.get("list").and_then(Value::as_array) else {
+            if !errors.is_empty() {
+                return Err(VmError::PartialFailure {
                     action: "list",
-                    reason: "missing list array".to_owned(),
-                })?;
+                    messages: errors,
+                });
+            }
+            return Err(VmError::InvalidOutput {
+                action: "list",
+                reason: "missing list array".to_owned(),
+            });
+        };
+        // Whatever did come back is still worth showing, next to the errors.
+        for error in &errors {
+            warn!("multipass list reported: {}", error);
+        }
 
         let mut vms = Vec::with_capacity(list.len());
         for item in list {
@@ -335,12 +2142,8 @@ where
                 }
             })?;
 
-            let ipv4 = item.get("ipv4").and_then(Value::as_array).map(|arr| {
-                arr.iter()
-                    .filter_map(Value::as_str)
-                    .map(String::from)
-                    .collect()
-            });
+            let ipv4 = parse_address_list(item, "ipv4");
+            let ipv6 = parse_address_list(item, "ipv6");
 
             let release = item
                 .get("release")
@@ -349,14 +2152,103 @@ where
 
             vms.push(VmSummary {
                 name: name.to_owned(),
-                state: state.to_owned(),
+                state: state.into(),
                 ipv4,
+                ipv6,
                 release,
             });
         }
 
-        Ok(vms)
-    }
+        Ok(VmListing {
+            vms,
+            warnings: errors,
+        })
+    }
+}
+
+/// The messages in the `errors` array of multipass' JSON output, which it
+/// fills when part of a command failed without failing the whole command.
+fn multipass_json_errors(value: &Value) -> Vec<String> {
+    value
+        .get("errors")
+        .and_then(Value::as_array)
+        .map(|errors| {
+            errors
+                .iter()
+                .map(|error| match error.as_str() {
+                    Some(message) => message.to_owned(),
+                    None => error.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parses `multipass networks --format json`.
+pub fn parse_networks_output(output: &str) -> Result<Vec<NetworkInfo>, VmError> {
+    #[derive(Deserialize)]
+    struct NetworkList {
+        list: Vec<NetworkInfo>,
+    }
+
+    serde_json::from_str::<NetworkList>(output)
+        .map(|networks| networks.list)
+        .map_err(|err| VmError::InvalidOutput {
+            action: "networks",
+            reason: err.to_string(),
+        })
+}
+
+/// Parses `multipass find --format json`, images first and then
+/// blueprints, each sorted by alias. multipass has no description field in
+/// JSON, so it is rebuilt from `os` and `release` the way its table does.
+pub fn parse_find_output(output: &str) -> Result<Vec<ImageInfo>, VmError> {
+    #[derive(Deserialize)]
+    struct FindEntry {
+        #[serde(default)]
+        os: String,
+        #[serde(default)]
+        release: String,
+        #[serde(default)]
+        version: String,
+    }
+
+    #[derive(Deserialize)]
+    struct FindOutput {
+        images: BTreeMap<String, FindEntry>,
+        #[serde(default)]
+        blueprints: BTreeMap<String, FindEntry>,
+    }
+
+    let found =
+        serde_json::from_str::<FindOutput>(output).map_err(|err| VmError::InvalidOutput {
+            action: "find",
+            reason: err.to_string(),
+        })?;
+    let entries = |kind: &str, map: BTreeMap<String, FindEntry>| {
+        map.into_iter()
+            .map(|(alias, entry)| ImageInfo {
+                alias,
+                kind: kind.to_owned(),
+                version: entry.version,
+                description: format!("{} {}", entry.os, entry.release).trim().to_owned(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut images = entries("image", found.images);
+    images.extend(entries("blueprint", found.blueprints));
+    Ok(images)
+}
+
+/// Reads an array of addresses such as `ipv4`/`ipv6`. Older multipass
+/// releases omit `ipv6` entirely, which yields `None`.
+fn parse_address_list(vm: &Value, key: &str) -> Option<Vec<String>> {
+    vm.get(key).and_then(Value::as_array).map(|arr| {
+        arr.iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect()
+    })
 }
 
 #[async_trait]
@@ -364,52 +2256,162 @@ impl<E> Multipass for MultipassCli<E>
 where
     E: CommandExecutor,
 {
-    async fn launch(&self, name: &str) -> Result<(), VmError> {
+    async fn launch(&self, spec: &LaunchSpec, cancel: &CancellationToken) -> Result<(), VmError> {
+        self.launch_reporting(spec, None, cancel).await
+    }
+
+    async fn launch_with_progress(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<(), VmError> {
+        self.launch_reporting(spec, Some(on_progress), cancel).await
+    }
+
+    async fn start(&self, name: &str) -> Result<(), VmError> {
         self.run_command(
-            "launch",
-            vec!["launch".to_owned(), "--name".to_owned(), name.to_owned()],
+            "start",
+            Some(name),
+            vec!["start".to_owned(), name.to_owned()],
+            &CancellationToken::new(),
         )
         .await?;
         Ok(())
     }
 
-    async fn start(&self, name: &str) -> Result<(), VmError> {
-        self.run_command("start", vec!["start".to_owned(), name.to_owned()])
-            .await?;
-        Ok(())
-    }
+    async fn stop(&self, name: &str, opts: &StopOptions) -> Result<(), VmError> {
+        let mut args = vec!["stop".to_owned(), name.to_owned()];
+        if opts.force {
+            args.push("--force".to_owned());
+        }
 
-    async fn stop(&self, name: &str) -> Result<(), VmError> {
-        self.run_command("stop", vec!["stop".to_owned(), name.to_owned()])
-            .await?;
-        Ok(())
+        // The timeout cancels the command, which kills the multipass process
+        let cancel = CancellationToken::new();
+        let timer = opts.timeout.map(|timeout| {
+            let cancel = cancel.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                cancel.cancel();
+            })
+        });
+        let result = self.run_command("stop", Some(name), args, &cancel).await;
+        if let Some(timer) = timer {
+            timer.abort();
+        }
+
+        match (result, opts.timeout) {
+            (Err(VmError::Cancelled { action }), Some(timeout)) => {
+                Err(VmError::TimedOut { action, timeout })
+            }
+            (result, _) => result.map(|_| ()),
+        }
     }
 
     async fn restart(&self, name: &str) -> Result<(), VmError> {
-        self.run_command("restart", vec!["restart".to_owned(), name.to_owned()])
-            .await?;
+        self.run_command(
+            "restart",
+            Some(name),
+            vec!["restart".to_owned(), name.to_owned()],
+            &CancellationToken::new(),
+        )
+        .await?;
         Ok(())
     }
 
     async fn delete(&self, name: &str) -> Result<(), VmError> {
         self.run_command(
             "delete",
+            Some(name),
             vec!["delete".to_owned(), name.to_owned(), "--purge".to_owned()],
+            &CancellationToken::new(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_many(&self, names: &[String], purge: bool) -> Result<(), VmError> {
+        if names.is_empty() {
+            return Err(VmError::InvalidRequest {
+                action: "delete",
+                reason: "no VM names given".to_owned(),
+            });
+        }
+        let mut args = vec!["delete".to_owned()];
+        args.extend(names.iter().cloned());
+        if purge {
+            args.push("--purge".to_owned());
+        }
+        self.run_command("delete", None, args, &CancellationToken::new())
+            .await?;
+        Ok(())
+    }
+
+    async fn clone_vm(&self, source: &str, destination: &str) -> Result<(), VmError> {
+        self.run_command(
+            "clone",
+            Some(source),
+            vec![
+                "clone".to_owned(),
+                source.to_owned(),
+                "--name".to_owned(),
+                destination.to_owned(),
+            ],
+            &CancellationToken::new(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_property(&self, name: &str, key: VmProperty, value: &str) -> Result<(), VmError> {
+        self.run_command(
+            "set",
+            Some(name),
+            vec![
+                "set".to_owned(),
+                format!("{}={}", key.settings_key(name), value),
+            ],
+            &CancellationToken::new(),
         )
         .await?;
         Ok(())
     }
 
+    async fn get_property(&self, name: &str, key: VmProperty) -> Result<String, VmError> {
+        let output = self
+            .run_command(
+                "get",
+                Some(name),
+                vec!["get".to_owned(), key.settings_key(name)],
+                &CancellationToken::new(),
+            )
+            .await?;
+        Ok(output.stdout.trim().to_owned())
+    }
+
+    async fn shell(&self, name: &str) -> Result<i32, VmError> {
+        let args = vec!["shell".to_owned(), name.to_owned()];
+        let command_preview = format!("{} {}", self.binary, args.join(" "));
+        info!(action = "shell", command = %command_preview, "running multipass command");
+        // Never retried: the user may already have typed into the session.
+        self.executor
+            .run_interactive(&self.binary, &args)
+            .await
+            .map_err(|err| self.executor_error("shell", &args, err, &CancellationToken::new()))
+    }
+
     async fn info(&self, name: &str) -> Result<VmStatusResponse, VmError> {
         let output = self
             .run_command(
                 "info",
+                Some(name),
                 vec![
                     "info".to_owned(),
                     name.to_owned(),
                     "--format".to_owned(),
                     "json".to_owned(),
                 ],
+                &CancellationToken::new(),
             )
             .await?;
 
@@ -417,145 +2419,1146 @@ where
     }
 
     async fn list(&self) -> Result<Vec<VmSummary>, VmError> {
+        Ok(self.list_with_warnings().await?.vms)
+    }
+
+    async fn list_with_warnings(&self) -> Result<VmListing, VmError> {
         let output = self
             .run_command(
                 "list",
+                None,
                 vec!["list".to_owned(), "--format".to_owned(), "json".to_owned()],
+                &CancellationToken::new(),
+            )
+            .await?;
+        let mut listing = self.parse_list_output(&output.stdout)?;
+
+        // multipass has no server-side filter, so managed VMs are picked out here
+        if let Some(prefix) = &self.managed_prefix {
+            listing
+                .vms
+                .retain(|vm| vm.name.starts_with(prefix.as_str()));
+        }
+        Ok(listing)
+    }
+
+    async fn networks(&self) -> Result<Vec<NetworkInfo>, VmError> {
+        let output = self
+            .run_command(
+                "networks",
+                None,
+                vec![
+                    "networks".to_owned(),
+                    "--format".to_owned(),
+                    "json".to_owned(),
+                ],
+                &CancellationToken::new(),
+            )
+            .await?;
+
+        parse_networks_output(&output.stdout)
+    }
+
+    async fn find(&self) -> Result<Vec<ImageInfo>, VmError> {
+        let output = self
+            .run_command(
+                "find",
+                None,
+                vec!["find".to_owned(), "--format".to_owned(), "json".to_owned()],
+                &CancellationToken::new(),
+            )
+            .await?;
+
+        parse_find_output(&output.stdout)
+    }
+
+    async fn version(&self) -> Result<MultipassVersions, VmError> {
+        let output = self
+            .run_command(
+                "version",
+                None,
+                vec!["version".to_owned()],
+                &CancellationToken::new(),
+            )
+            .await?;
+
+        Ok(parse_version_output(&output.stdout))
+    }
+
+    async fn exec(&self, name: &str, command: &[String]) -> Result<CommandOutput, VmError> {
+        let mut args = vec!["exec".to_owned(), name.to_owned(), "--".to_owned()];
+        args.extend(command.iter().cloned());
+
+        // Note: exec returns the command output directly, not through JSON,
+        // including the status_code of a command that exited non-zero.
+        self.run_command("exec", Some(name), args, &CancellationToken::new())
+            .await
+    }
+
+    async fn exec_with_stdin(
+        &self,
+        name: &str,
+        command: &[String],
+        stdin: &[u8],
+    ) -> Result<CommandOutput, VmError> {
+        let mut args = vec!["exec".to_owned(), name.to_owned(), "--".to_owned()];
+        args.extend(command.iter().cloned());
+
+        self.run_command_with_stdin(
+            "exec",
+            Some(name),
+            args,
+            Some(stdin),
+            &CancellationToken::new(),
+        )
+        .await
+    }
+
+    async fn exec_streaming(
+        &self,
+        name: &str,
+        command: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, VmError> {
+        let mut args = vec!["exec".to_owned(), name.to_owned(), "--".to_owned()];
+        args.extend(command.iter().cloned());
+
+        self.run_streaming_command("exec", Some(name), &args, on_line, cancel)
+            .await
+    }
+
+    async fn transfer(
+        &self,
+        name: &str,
+        source: &str,
+        destination: &str,
+        cancel: &CancellationToken,
+    ) -> Result<(), VmError> {
+        self.run_command(
+            "transfer",
+            Some(name),
+            vec![
+                "transfer".to_owned(),
+                source.to_owned(),
+                format!("{}:{}", name, destination),
+            ],
+            cancel,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Multipass' own sizing for launches that don't set memory or disk.
+const MULTIPASS_DEFAULT_MEMORY: u64 = 1 << 30;
+const MULTIPASS_DEFAULT_DISK: u64 = 5 << 30;
+
+/// Free memory and disk on the machine running multipass. A value of 0
+/// means it could not be determined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostResources {
+    pub available_memory: u64,
+    /// Free space on the filesystem holding multipass' instance images.
+    pub available_disk: u64,
+}
+
+/// Reads `HostResources` for the launch preflight; swapped out in tests.
+pub trait HostResourceProbe: Send + Sync {
+    fn resources(&self) -> HostResources;
+}
+
+/// Reads host resources with `sysinfo`.
+#[derive(Debug, Clone)]
+pub struct SysinfoProbe {
+    storage_dir: PathBuf,
+}
+
+impl SysinfoProbe {
+    /// Probes the filesystem holding `storage_dir` for free disk space.
+    pub fn new(storage_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            storage_dir: storage_dir.into(),
+        }
+    }
+}
+
+impl Default for SysinfoProbe {
+    /// Probes multipass' default storage location for this platform.
+    fn default() -> Self {
+        let storage_dir = if cfg!(target_os = "macos") {
+            "/var/root/Library/Application Support/multipassd"
+        } else if cfg!(windows) {
+            "C:\\ProgramData\\Multipass"
+        } else {
+            "/var/snap/multipass/common/data/multipassd"
+        };
+        Self::new(storage_dir)
+    }
+}
+
+impl HostResourceProbe for SysinfoProbe {
+    fn resources(&self) -> HostResources {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        // The disk whose mount point is the longest prefix of the storage
+        // directory is the one it lives on.
+        let disks = sysinfo::Disks::new_with_refreshed_list();
+        let available_disk = disks
+            .list()
+            .iter()
+            .filter(|disk| self.storage_dir.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+            .map_or(0, |disk| disk.available_space());
+        HostResources {
+            available_memory: system.available_memory(),
+            available_disk,
+        }
+    }
+}
+
+/// Fails when `spec`, after defaults, clearly won't fit in `host`.
+///
+/// Only a request larger than everything available fails, so a launch that
+/// might work is never blocked. Unparseable sizes and unknown host values
+/// (0) pass; multipass reports those itself.
+pub fn check_host_resources(spec: &LaunchSpec, host: HostResources) -> Result<()> {
+    let requested = |size: &Option<String>, default: u64| match size {
+        Some(size) => crate::util::parse_size(size),
+        None => Some(default),
+    };
+    let checks = [
+        (
+            "memory",
+            requested(&spec.memory, MULTIPASS_DEFAULT_MEMORY),
+            host.available_memory,
+        ),
+        (
+            "disk space",
+            requested(&spec.disk, MULTIPASS_DEFAULT_DISK),
+            host.available_disk,
+        ),
+    ];
+    for (resource, requested, available) in checks {
+        if let Some(requested) = requested
+            && available > 0
+            && requested > available
+        {
+            anyhow::bail!(
+                "not enough host {} to launch VM {}: it needs {} but only {} is free",
+                resource,
+                spec.name,
+                crate::util::format_bytes(requested),
+                crate::util::format_bytes(available)
+            );
+        }
+    }
+    Ok(())
+}
+
+// LocalVmApi: High-level API implementation using Multipass
+#[derive(Clone)]
+pub struct LocalVmApi {
+    multipass: Arc<dyn Multipass>,
+    tags: Option<Arc<TagRegistry>>,
+    launch_defaults: VmDefaults,
+    preflight: Option<Arc<dyn HostResourceProbe>>,
+    audit: Arc<dyn AuditSink>,
+}
+
+impl LocalVmApi {
+    pub fn new(multipass: Arc<dyn Multipass>) -> Self {
+        Self {
+            multipass,
+            tags: None,
+            launch_defaults: VmDefaults::default(),
+            preflight: None,
+            audit: Arc::new(NoopAuditSink),
+        }
+    }
+
+    /// Records every launch, start, stop, restart and delete, successful or
+    /// not, once it finishes.
+    pub fn with_audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    fn record_audit<T>(&self, action: AuditAction, name: &str, result: &Result<T>) {
+        if let Err(err) = self.audit.record(&AuditRecord::new(action, name, result)) {
+            warn!(vm_name = name, "failed to write audit record: {:#}", err);
+        }
+    }
+
+    /// Checks each launch against the host's free memory and disk first,
+    /// failing early instead of deep inside multipass.
+    pub fn with_preflight(mut self, probe: Arc<dyn HostResourceProbe>) -> Self {
+        self.preflight = Some(probe);
+        self
+    }
+
+    /// Fills in sizing that a launch spec leaves unset, e.g. from the
+    /// `[vm]` section of the config file.
+    pub fn with_launch_defaults(mut self, defaults: VmDefaults) -> Self {
+        self.launch_defaults = defaults;
+        self
+    }
+
+    /// Enables `tag`/`untag`/`tags`, backed by the given sidecar registry.
+    pub fn with_tag_registry(mut self, tags: Arc<TagRegistry>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    fn tag_registry(&self) -> Result<&TagRegistry> {
+        self.tags
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no tag registry is configured"))
+    }
+}
+
+/// Keeps the multipass error as the source of the summary so the cause chain
+/// survives into verbose API error bodies.
+fn multipass_error(err: VmError, summary: String) -> anyhow::Error {
+    let message = format!("{summary}: {err}");
+    anyhow::Error::new(err).context(message)
+}
+
+impl LocalVmApi {
+    async fn launch_unaudited(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: Option<&LineSink<'_>>,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        let name = spec.name.as_str();
+        spec.validate().map_err(|err| VmError::InvalidRequest {
+            action: "launch",
+            reason: err.to_string(),
+        })?;
+        debug!(
+            vm_name = name,
+            "launching VM. This may take a couple of minutes."
+        );
+        let spec = self.launch_defaults.apply(spec);
+        if let Some(probe) = &self.preflight {
+            check_host_resources(&spec, probe.resources())?;
+        }
+        let launched = match on_progress {
+            Some(on_progress) => {
+                self.multipass
+                    .launch_with_progress(&spec, on_progress, cancel)
+                    .await
+            }
+            None => self.multipass.launch(&spec, cancel).await,
+        };
+        launched.map_err(|e| multipass_error(e, format!("failed to launch VM {}", name)))?;
+        debug!(vm_name = name, "VM launched successfully");
+        Ok(())
+    }
+
+    async fn start_unaudited(&self, name: &str) -> Result<()> {
+        debug!(vm_name = name, "starting VM");
+        self.multipass
+            .start(name)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to start VM {}", name)))?;
+        debug!(vm_name = name, "VM started successfully");
+        Ok(())
+    }
+
+    async fn stop_unaudited(&self, name: &str, opts: &StopOptions) -> Result<()> {
+        debug!(vm_name = name, force = opts.force, "stopping VM");
+        let result = match self.multipass.stop(name, opts).await {
+            Err(VmError::TimedOut { timeout, .. }) if !opts.force => {
+                warn!(
+                    vm_name = name,
+                    "graceful stop did not finish within {}s, forcing",
+                    timeout.as_secs()
+                );
+                self.multipass.stop(name, &StopOptions::forced()).await
+            }
+            result => result,
+        };
+        result.map_err(|e| multipass_error(e, format!("failed to stop VM {}", name)))?;
+        debug!(vm_name = name, "VM stopped successfully");
+        Ok(())
+    }
+
+    async fn restart_unaudited(&self, name: &str) -> Result<()> {
+        debug!(vm_name = name, "restarting VM");
+        self.multipass
+            .restart(name)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to restart VM {}", name)))?;
+        debug!(vm_name = name, "VM restarted successfully");
+        Ok(())
+    }
+
+    async fn delete_unaudited(&self, name: &str) -> Result<()> {
+        debug!(vm_name = name, "deleting VM");
+        self.multipass
+            .delete(name)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to delete VM {}", name)))?;
+        if let Some(tags) = &self.tags
+            && let Err(err) = tags.remove(name)
+        {
+            warn!(
+                vm_name = name,
+                "failed to clear tags of deleted VM: {:#}", err
+            );
+        }
+        debug!(vm_name = name, "VM deleted successfully");
+        Ok(())
+    }
+
+    async fn delete_many_unaudited(&self, names: &[String]) -> Result<()> {
+        if names.is_empty() {
+            anyhow::bail!("no VM names given to delete");
+        }
+        debug!(vm_names = ?names, "deleting VMs");
+        self.multipass.delete_many(names, true).await.map_err(|e| {
+            multipass_error(e, format!("failed to delete VMs {}", names.join(", ")))
+        })?;
+        if let Some(tags) = &self.tags {
+            for name in names {
+                if let Err(err) = tags.remove(name) {
+                    warn!(
+                        vm_name = name.as_str(),
+                        "failed to clear tags of deleted VM: {:#}", err
+                    );
+                }
+            }
+        }
+        debug!(vm_names = ?names, "VMs deleted successfully");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VmApi for LocalVmApi {
+    async fn launch(&self, spec: &LaunchSpec, cancel: &CancellationToken) -> Result<()> {
+        let result = self.launch_unaudited(spec, None, cancel).await;
+        self.record_audit(AuditAction::Launch, &spec.name, &result);
+        result
+    }
+
+    async fn launch_with_progress(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        let result = self.launch_unaudited(spec, Some(on_progress), cancel).await;
+        self.record_audit(AuditAction::Launch, &spec.name, &result);
+        result
+    }
+
+    async fn start(&self, name: &str) -> Result<()> {
+        let result = self.start_unaudited(name).await;
+        self.record_audit(AuditAction::Start, name, &result);
+        result
+    }
+
+    async fn stop(&self, name: &str, opts: &StopOptions) -> Result<()> {
+        let result = self.stop_unaudited(name, opts).await;
+        self.record_audit(AuditAction::Stop, name, &result);
+        result
+    }
+
+    async fn restart(&self, name: &str) -> Result<()> {
+        let result = self.restart_unaudited(name).await;
+        self.record_audit(AuditAction::Restart, name, &result);
+        result
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let result = self.delete_unaudited(name).await;
+        self.record_audit(AuditAction::Delete, name, &result);
+        result
+    }
+
+    /// Leaves one audit record per VM, all with the batch's outcome.
+    async fn delete_many(&self, names: &[String]) -> Result<()> {
+        let result = self.delete_many_unaudited(names).await;
+        for name in names {
+            self.record_audit(AuditAction::Delete, name, &result);
+        }
+        result
+    }
+
+    async fn rename(
+        &self,
+        name: &str,
+        new_name: &str,
+        opts: &RenameOptions,
+        progress: &(dyn Fn(RenameStep) + Send + Sync),
+    ) -> Result<()> {
+        validate_vm_name(new_name)?;
+        if name == new_name {
+            anyhow::bail!("VM '{}' is already named '{}'", name, new_name);
+        }
+        debug!(vm_name = name, new_name, "renaming VM");
+
+        let info = self
+            .multipass
+            .info(name)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to get info for VM {}", name)))?;
+        if info.state != VmState::Stopped {
+            if !opts.force_stop {
+                anyhow::bail!(
+                    "VM '{}' is {}; stop it first or pass --force-stop",
+                    name,
+                    info.state
+                );
+            }
+            progress(RenameStep::Stopping);
+            self.multipass
+                .stop(name, &StopOptions::default())
+                .await
+                .map_err(|e| multipass_error(e, format!("failed to stop VM {}", name)))?;
+        }
+
+        // Until the clone is confirmed, the original is the only copy: never
+        // delete it on any failure before that point.
+        progress(RenameStep::Cloning);
+        self.multipass.clone_vm(name, new_name).await.map_err(|e| {
+            multipass_error(
+                e,
+                format!(
+                    "failed to clone VM {} to {}; the original was left untouched",
+                    name, new_name
+                ),
             )
-            .await?;
-        self.parse_list_output(&output.stdout)
-    }
+        })?;
 
-    async fn exec(&self, name: &str, command: &[String]) -> Result<CommandOutput, VmError> {
-        let mut args = vec!["exec".to_owned(), name.to_owned(), "--".to_owned()];
-        args.extend(command.iter().cloned());
+        progress(RenameStep::Verifying);
+        self.multipass.info(new_name).await.map_err(|e| {
+            multipass_error(
+                e,
+                format!(
+                    "could not find clone {} of VM {}; the original was left untouched",
+                    new_name, name
+                ),
+            )
+        })?;
 
-        // Note: exec returns the command output directly, not through JSON
-        // So we return the full CommandOutput including status_code
-        self.run_command("exec", args).await
-    }
+        progress(RenameStep::DeletingOriginal);
+        self.multipass.delete(name).await.map_err(|e| {
+            multipass_error(
+                e,
+                format!(
+                    "VM {} was cloned to {} but the original could not be deleted",
+                    name, new_name
+                ),
+            )
+        })?;
 
-    async fn transfer(&self, name: &str, source: &str, destination: &str) -> Result<(), VmError> {
-        self.run_command(
-            "transfer",
-            vec![
-                "transfer".to_owned(),
-                source.to_owned(),
-                format!("{}:{}", name, destination),
-            ],
-        )
-        .await?;
+        if let Some(tags) = &self.tags {
+            let moved = tags
+                .tags(name)
+                .and_then(|old_tags| tags.tag(new_name, &old_tags))
+                .and_then(|_| tags.remove(name));
+            if let Err(err) = moved {
+                warn!(vm_name = name, new_name, "failed to move tags: {:#}", err);
+            }
+        }
+        debug!(vm_name = name, new_name, "VM renamed successfully");
         Ok(())
     }
-}
 
-// LocalVmApi: High-level API implementation using Multipass
-#[derive(Clone)]
-pub struct LocalVmApi {
-    multipass: Arc<dyn Multipass>,
-}
+    async fn set_property(&self, name: &str, key: VmProperty, value: &str) -> Result<()> {
+        key.validate_value(value)
+            .map_err(|err| VmError::InvalidRequest {
+                action: "set",
+                reason: err.to_string(),
+            })?;
+        debug!(
+            vm_name = name,
+            key = key.as_str(),
+            value,
+            "setting VM property"
+        );
+        self.multipass
+            .set_property(name, key, value)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to set {} of VM {}", key, name)))
+    }
 
-impl LocalVmApi {
-    pub fn new(multipass: Arc<dyn Multipass>) -> Self {
-        Self { multipass }
+    async fn get_property(&self, name: &str, key: VmProperty) -> Result<String> {
+        debug!(vm_name = name, key = key.as_str(), "getting VM property");
+        self.multipass
+            .get_property(name, key)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to get {} of VM {}", key, name)))
     }
-}
 
-#[async_trait]
-impl VmApi for LocalVmApi {
-    async fn launch(&self, name: &str) -> Result<()> {
-        info!(
-            vm_name = name,
-            "launching VM. This may take a couple of minutes."
-        );
+    async fn shell(&self, name: &str) -> Result<i32> {
+        debug!(vm_name = name, "opening shell in VM");
         self.multipass
-            .launch(name)
+            .shell(name)
             .await
-            .map_err(|e| anyhow::anyhow!("failed to launch VM {}: {}", name, e))?;
-        info!(vm_name = name, "VM launched successfully");
-        Ok(())
+            .map_err(|e| multipass_error(e, format!("failed to open a shell in VM {}", name)))
     }
 
-    async fn start(&self, name: &str) -> Result<()> {
-        info!(vm_name = name, "starting VM");
+    async fn info(&self, name: &str) -> Result<VmStatusResponse> {
+        debug!(vm_name = name, "getting VM info");
         self.multipass
-            .start(name)
+            .info(name)
             .await
-            .map_err(|e| anyhow::anyhow!("failed to start VM {}: {}", name, e))?;
-        info!(vm_name = name, "VM started successfully");
-        Ok(())
+            .map_err(|e| multipass_error(e, format!("failed to get info for VM {}", name)))
     }
 
-    async fn stop(&self, name: &str) -> Result<()> {
-        info!(vm_name = name, "stopping VM");
+    async fn list(&self) -> Result<Vec<VmSummary>> {
+        debug!("listing VMs");
         self.multipass
-            .stop(name)
+            .list()
             .await
-            .map_err(|e| anyhow::anyhow!("failed to stop VM {}: {}", name, e))?;
-        info!(vm_name = name, "VM stopped successfully");
-        Ok(())
+            .map_err(|e| multipass_error(e, "failed to list VMs from multipass".to_owned()))
     }
 
-    async fn restart(&self, name: &str) -> Result<()> {
-        info!(vm_name = name, "restarting VM");
+    async fn list_with_warnings(&self) -> Result<VmListing> {
+        debug!("listing VMs");
         self.multipass
-            .restart(name)
+            .list_with_warnings()
             .await
-            .map_err(|e| anyhow::anyhow!("failed to restart VM {}: {}", name, e))?;
-        info!(vm_name = name, "VM restarted successfully");
-        Ok(())
+            .map_err(|e| multipass_error(e, "failed to list VMs from multipass".to_owned()))
     }
 
-    async fn delete(&self, name: &str) -> Result<()> {
-        info!(vm_name = name, "deleting VM");
+    async fn networks(&self) -> Result<Vec<NetworkInfo>> {
+        debug!("listing networks");
         self.multipass
-            .delete(name)
+            .networks()
             .await
-            .map_err(|e| anyhow::anyhow!("failed to delete VM {}: {}", name, e))?;
-        info!(vm_name = name, "VM deleted successfully");
-        Ok(())
+            .map_err(|e| multipass_error(e, "failed to list networks from multipass".to_owned()))
     }
 
-    async fn info(&self, name: &str) -> Result<VmStatusResponse> {
-        info!(vm_name = name, "getting VM info");
+    async fn find(&self) -> Result<Vec<ImageInfo>> {
+        debug!("listing images");
         self.multipass
-            .info(name)
+            .find()
             .await
-            .map_err(|e| anyhow::anyhow!("failed to get info for VM {}: {}", name, e))
+            .map_err(|e| multipass_error(e, "failed to list images from multipass".to_owned()))
     }
 
-    async fn list(&self) -> Result<Vec<VmSummary>> {
-        info!("listing VMs");
+    async fn version(&self) -> Result<MultipassVersions> {
         self.multipass
-            .list()
+            .version()
             .await
-            .map_err(|e| anyhow::anyhow!("failed to list VMs from multipass: {}", e))
+            .map_err(|e| multipass_error(e, "failed to get the multipass version".to_owned()))
+    }
+
+    async fn tag(&self, name: &str, tags: &[String]) -> Result<Vec<String>> {
+        debug!(vm_name = name, tags = ?tags, "tagging VM");
+        self.tag_registry()?.tag(name, tags)
+    }
+
+    async fn untag(&self, name: &str, tags: &[String]) -> Result<Vec<String>> {
+        debug!(vm_name = name, tags = ?tags, "untagging VM");
+        self.tag_registry()?.untag(name, tags)
+    }
+
+    async fn tags(&self, name: &str) -> Result<Vec<String>> {
+        self.tag_registry()?.tags(name)
     }
 
     async fn exec(&self, name: &str, command: &[String]) -> Result<CommandOutput> {
-        info!(vm_name = name, command = ?command, "executing command in VM");
+        debug!(
+            vm_name = name,
+            command = ?Redactor::default().redact_command(command),
+            "executing command in VM"
+        );
         self.multipass
             .exec(name, command)
             .await
-            .map_err(|e| anyhow::anyhow!("failed to exec command in VM {}: {}", name, e))
+            .map_err(|e| multipass_error(e, format!("failed to exec command in VM {}", name)))
+    }
+
+    async fn exec_with_stdin(
+        &self,
+        name: &str,
+        command: &[String],
+        stdin: &[u8],
+    ) -> Result<CommandOutput> {
+        debug!(
+            vm_name = name,
+            command = ?Redactor::default().redact_command(command),
+            stdin_bytes = stdin.len(),
+            "executing command in VM"
+        );
+        self.multipass
+            .exec_with_stdin(name, command, stdin)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to exec command in VM {}", name)))
+    }
+
+    async fn exec_streaming(
+        &self,
+        name: &str,
+        command: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput> {
+        debug!(
+            vm_name = name,
+            command = ?Redactor::default().redact_command(command),
+            "streaming command output from VM"
+        );
+        self.multipass
+            .exec_streaming(name, command, on_line, cancel)
+            .await
+            .map_err(|e| multipass_error(e, format!("failed to exec command in VM {}", name)))
     }
 
-    async fn transfer(&self, name: &str, source: &str, destination: &str) -> Result<()> {
-        info!(
+    async fn transfer(
+        &self,
+        name: &str,
+        source: &str,
+        destination: &str,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        debug!(
             vm_name = name,
             source = source,
             dest = destination,
             "transferring file to VM"
         );
         self.multipass
-            .transfer(name, source, destination)
+            .transfer(name, source, destination, cancel)
             .await
-            .map_err(|e| anyhow::anyhow!("failed to transfer file to VM {}: {}", name, e))?;
-        info!(vm_name = name, "file transferred successfully");
+            .map_err(|e| multipass_error(e, format!("failed to transfer file to VM {}", name)))?;
+        debug!(vm_name = name, "file transferred successfully");
         Ok(())
     }
 }
 
+// ============================================================================
+// Readiness - Waiting for a VM to become usable
+// ============================================================================
+
+/// Default `--wait-timeout` for `vm launch` and `vm start`, in seconds.
+pub const DEFAULT_WAIT_TIMEOUT_SECS: u64 = 300;
+
+/// How `wait_for_ready` polls `VmApi::info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitOptions {
+    pub timeout: Duration,
+    pub interval: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(DEFAULT_WAIT_TIMEOUT_SECS),
+            interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl WaitOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// A VM did not reach the awaited condition in time.
+#[derive(Debug, Error)]
+#[error(
+    "timed out after {}s waiting for VM '{name}' to {condition} (last state: {last_state})",
+    timeout.as_secs()
+)]
+pub struct WaitTimeout {
+    pub name: String,
+    pub condition: String,
+    pub last_state: String,
+    pub timeout: Duration,
+}
+
+/// Polls until the VM is `Running` with at least one IPv4 address, which is
+/// when cloud-init has finished enough for the VM to be reachable. Returns the
+/// last observed status.
+pub async fn wait_for_ready(
+    api: &dyn VmApi,
+    name: &str,
+    opts: &WaitOptions,
+) -> Result<VmStatusResponse> {
+    let deadline = tokio::time::Instant::now() + opts.timeout;
+    loop {
+        let info = api.info(name).await?;
+        let has_ipv4 = info.ipv4.as_ref().is_some_and(|ips| !ips.is_empty());
+        if info.state == VmState::Running && has_ipv4 {
+            return Ok(info);
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            let ipv4 = if has_ipv4 {
+                "IPv4 assigned"
+            } else {
+                "no IPv4 address"
+            };
+            return Err(WaitTimeout {
+                name: name.to_owned(),
+                condition: "be ready".to_owned(),
+                last_state: format!("{}, {}", info.state, ipv4),
+                timeout: opts.timeout,
+            }
+            .into());
+        }
+        tokio::time::sleep(opts.interval.min(deadline - now)).await;
+    }
+}
+
+/// Polls until the VM reports `target` and returns its status. `on_change`
+/// sees the first state observed and every change after it. Errors from
+/// `info`, such as an unknown VM, end the wait at once.
+pub async fn wait_for_state(
+    api: &dyn VmApi,
+    name: &str,
+    target: &VmState,
+    opts: &WaitOptions,
+    on_change: &(dyn Fn(&VmState) + Send + Sync),
+) -> Result<VmStatusResponse> {
+    poll_state(name, target, opts, on_change, || async {
+        let info = api.info(name).await?;
+        Ok((info.state.clone(), info))
+    })
+    .await
+}
+
+/// Polls `list` until the VM is gone from it, or listed as `Deleted` after
+/// a delete without purge. `on_change` is called as in `wait_for_state`,
+/// with `Deleted` once the VM has disappeared.
+pub async fn wait_for_deletion(
+    api: &dyn VmApi,
+    name: &str,
+    opts: &WaitOptions,
+    on_change: &(dyn Fn(&VmState) + Send + Sync),
+) -> Result<()> {
+    poll_state(name, &VmState::Deleted, opts, on_change, || async {
+        let state = api
+            .list()
+            .await?
+            .into_iter()
+            .find(|vm| vm.name == name)
+            .map_or(VmState::Deleted, |vm| vm.state);
+        Ok((state, ()))
+    })
+    .await
+}
+
+/// The loop behind `wait_for_state` and `wait_for_deletion`: runs `observe`
+/// every `opts.interval` until the state it reports is `target`, and fails
+/// with a `WaitTimeout` once `opts.timeout` has passed.
+async fn poll_state<T, F, Fut>(
+    name: &str,
+    target: &VmState,
+    opts: &WaitOptions,
+    on_change: &(dyn Fn(&VmState) + Send + Sync),
+    mut observe: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(VmState, T)>>,
+{
+    let deadline = tokio::time::Instant::now() + opts.timeout;
+    let mut last: Option<VmState> = None;
+    loop {
+        let (state, observed) = observe().await?;
+        if last.as_ref() != Some(&state) {
+            on_change(&state);
+            last = Some(state.clone());
+        }
+        if &state == target {
+            return Ok(observed);
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Err(WaitTimeout {
+                name: name.to_owned(),
+                condition: format!("reach {target}"),
+                last_state: state.to_string(),
+                timeout: opts.timeout,
+            }
+            .into());
+        }
+        tokio::time::sleep(opts.interval.min(deadline - now)).await;
+    }
+}
+
+/// Details of every listed VM. A VM that is deleted between `list` and its
+/// `info` call is skipped with a warning; any other error is returned.
+pub async fn info_all(api: &dyn VmApi) -> Result<Vec<VmStatusResponse>> {
+    let mut infos = Vec::new();
+    for vm in api.list().await? {
+        match api.info(&vm.name).await {
+            Ok(info) => infos.push(info),
+            Err(err)
+                if err
+                    .chain()
+                    .filter_map(|cause| cause.downcast_ref::<VmError>())
+                    .any(VmError::is_not_found) =>
+            {
+                warn!(
+                    vm_name = vm.name.as_str(),
+                    "VM disappeared before its info was read; skipping"
+                );
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(infos)
+}
+
+/// Number of deletes `prune_vms` runs at the same time by default.
+pub const DEFAULT_PRUNE_CONCURRENCY: usize = 4;
+
+/// Number of launches `launch_vms` runs at the same time by default.
+pub const DEFAULT_LAUNCH_CONCURRENCY: usize = 4;
+
+/// Outcome for one VM of a batch operation; `error` is `None` on success.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VmBatchResult {
+    pub name: String,
+    pub error: Option<String>,
+}
+
+/// Which listed VMs `vm prune` deletes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneSelection {
+    /// Only VMs whose name starts with this prefix.
+    pub prefix: String,
+    /// Also select suspended VMs.
+    pub include_suspended: bool,
+    /// Select VMs in any state, running ones included.
+    pub any_state: bool,
+}
+
+impl PruneSelection {
+    /// Whether `vm` should be pruned. Without `any_state` only stopped
+    /// (and, if asked, suspended) VMs qualify.
+    pub fn matches(&self, vm: &VmSummary) -> bool {
+        let state_ok = self.any_state
+            || vm.state == VmState::Stopped
+            || (self.include_suspended && vm.state == VmState::Suspended);
+        state_ok && vm.name.starts_with(&self.prefix)
+    }
+}
+
+/// A `--name` glob selecting VMs for `vm list` and the bulk commands.
+/// Matching is anchored to the whole name and case-sensitive, so
+/// `agent-*` matches neither `my-agent-1` nor `Agent-1`.
+#[derive(Debug, Clone)]
+pub struct NamePattern(GlobMatcher);
+
+impl NamePattern {
+    /// Compiles `pattern`, failing on malformed globs such as `agent-[`.
+    pub fn new(pattern: &str) -> Result<Self> {
+        let glob = Glob::new(pattern)
+            .map_err(|e| anyhow::anyhow!("invalid name pattern '{}': {}", pattern, e.kind()))?;
+        Ok(Self(glob.compile_matcher()))
+    }
+
+    /// The pattern as given on the command line.
+    pub fn as_str(&self) -> &str {
+        self.0.glob().glob()
+    }
+
+    /// Whether `name` matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.is_match(name)
+    }
+
+    /// Keeps the VMs whose names match, in their original order.
+    pub fn filter(&self, vms: Vec<VmSummary>) -> Vec<VmSummary> {
+        vms.into_iter()
+            .filter(|vm| self.matches(&vm.name))
+            .collect()
+    }
+}
+
+/// Deletes every named VM, `concurrency` at a time. A failed delete is
+/// recorded against its VM and does not stop the others. `progress` sees
+/// each result as its delete finishes; the returned results are in the
+/// order of `names`.
+pub async fn prune_vms(
+    api: &dyn VmApi,
+    names: &[String],
+    concurrency: usize,
+    progress: &(dyn Fn(&VmBatchResult) + Send + Sync),
+) -> Vec<VmBatchResult> {
+    stream::iter(names)
+        .map(|name| async move {
+            let result = VmBatchResult {
+                name: name.clone(),
+                error: api.delete(name).await.err().map(|e| e.to_string()),
+            };
+            progress(&result);
+            result
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Prefix `vm launch --auto` puts before the random part of a name.
+pub const DEFAULT_NAME_PREFIX: &str = "agent";
+
+const SHORT_ID_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+const SHORT_ID_LEN: usize = 6;
+
+/// A name like `agent-k3x9qa` that is neither in `taken` nor the base of a
+/// name in it (such as `agent-k3x9qa-1` from `--count`). Takes the random
+/// source as an argument so tests can seed it.
+pub fn generate_vm_name<R: rand::Rng + ?Sized>(
+    prefix: &str,
+    taken: &[String],
+    rng: &mut R,
+) -> String {
+    loop {
+        let id: String = (0..SHORT_ID_LEN)
+            .map(|_| SHORT_ID_ALPHABET[rng.random_range(0..SHORT_ID_ALPHABET.len())] as char)
+            .collect();
+        let name = format!("{prefix}-{id}");
+        let clashes = taken.iter().any(|existing| {
+            existing
+                .strip_prefix(name.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        });
+        if !clashes {
+            return name;
+        }
+    }
+}
+
+/// `base-1` through `base-count`, the names `vm launch --count` creates.
+pub fn numbered_vm_names(base: &str, count: u32) -> Vec<String> {
+    (1..=count).map(|n| format!("{base}-{n}")).collect()
+}
+
+/// What `run_script` passes to the script and whether it cleans up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptOptions {
+    pub args: Vec<String>,
+    /// Leave the uploaded copy in the VM instead of deleting it.
+    pub keep: bool,
+}
+
+/// A fresh path under `/tmp` in the VM for an upload of `script`, keeping
+/// its file name so it shows up recognizably in `ps` and error messages.
+pub fn remote_script_path(script: &std::path::Path) -> String {
+    let file_name = script
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "script".to_owned());
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    format!("/tmp/safepaw-{}-{}", &id[..8], file_name)
+}
+
+/// Uploads `script` into `name`, makes it executable and runs it with
+/// `opts.args`, passing its stdout and stderr lines to `on_line` as they
+/// arrive. The upload is removed afterwards unless `opts.keep` is set;
+/// failing to remove it is only logged. Returns the script's exit status.
+pub async fn run_script(
+    api: &dyn VmApi,
+    name: &str,
+    script: &std::path::Path,
+    opts: &ScriptOptions,
+    on_line: &LineSink<'_>,
+    cancel: &CancellationToken,
+) -> Result<i32> {
+    let remote = remote_script_path(script);
+    api.transfer(name, &script.to_string_lossy(), &remote, cancel)
+        .await?;
+    let result = run_uploaded_script(api, name, &remote, opts, on_line, cancel).await;
+    if opts.keep {
+        info!(vm_name = name, path = %remote, "kept uploaded script");
+    } else {
+        let removed = api
+            .exec(name, &["rm".to_owned(), "-f".to_owned(), remote.clone()])
+            .await;
+        if !matches!(removed, Ok(ref output) if output.status_code == 0) {
+            warn!(vm_name = name, path = %remote, "failed to remove uploaded script");
+        }
+    }
+    result
+}
+
+async fn run_uploaded_script(
+    api: &dyn VmApi,
+    name: &str,
+    remote: &str,
+    opts: &ScriptOptions,
+    on_line: &LineSink<'_>,
+    cancel: &CancellationToken,
+) -> Result<i32> {
+    let chmod = api
+        .exec(
+            name,
+            &["chmod".to_owned(), "+x".to_owned(), remote.to_owned()],
+        )
+        .await?;
+    if chmod.status_code != 0 {
+        anyhow::bail!(
+            "failed to make {} executable: {}",
+            remote,
+            chmod.stderr.trim()
+        );
+    }
+
+    // Only stdout is streamed, so fold stderr into it through a shell.
+    let mut command = vec![
+        "sh".to_owned(),
+        "-c".to_owned(),
+        "\"$0\" \"$@\" 2>&1".to_owned(),
+        remote.to_owned(),
+    ];
+    command.extend(opts.args.iter().cloned());
+    let output = api.exec_streaming(name, &command, on_line, cancel).await?;
+    Ok(output.status_code)
+}
+
+/// Launches every spec, `concurrency` at a time, and waits for each VM to
+/// be ready when `wait` is given. `progress` sees each result as soon as its
+/// VM finishes; a failure does not stop the other launches. The returned
+/// results are in the order of `specs`.
+pub async fn launch_vms(
+    api: &dyn VmApi,
+    specs: &[LaunchSpec],
+    concurrency: usize,
+    wait: Option<&WaitOptions>,
+    cancel: &CancellationToken,
+    progress: &(dyn Fn(&VmBatchResult) + Send + Sync),
+) -> Vec<VmBatchResult> {
+    let mut results: Vec<(usize, VmBatchResult)> = stream::iter(specs.iter().enumerate())
+        .map(|(index, spec)| async move {
+            let mut outcome = api.launch(spec, cancel).await;
+            if let (Ok(()), Some(wait)) = (&outcome, wait) {
+                outcome = wait_for_ready(api, &spec.name, wait).await.map(|_| ());
+            }
+            let result = VmBatchResult {
+                name: spec.name.clone(),
+                error: outcome.err().map(|e| e.to_string()),
+            };
+            progress(&result);
+            (index, result)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
 // ============================================================================
 // Unified Handlers - Used by both CLI and REST API
 // ============================================================================
@@ -565,24 +3568,71 @@ pub mod handlers {
     use super::*;
     use crate::util::HandlerResult;
 
-    pub async fn launch_vm(api: &dyn VmApi, name: &str) -> HandlerResult<()> {
-        match api.launch(name).await {
+    pub async fn launch_vm(
+        api: &dyn VmApi,
+        spec: &LaunchSpec,
+        cancel: &CancellationToken,
+    ) -> HandlerResult<()> {
+        launch_result(&spec.name, api.launch(spec, cancel).await)
+    }
+
+    /// Like `launch_vm`, passing multipass progress lines to `on_progress`.
+    pub async fn launch_vm_with_progress(
+        api: &dyn VmApi,
+        spec: &LaunchSpec,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> HandlerResult<()> {
+        let result = api.launch_with_progress(spec, on_progress, cancel).await;
+        launch_result(&spec.name, result)
+    }
+
+    fn launch_result(name: &str, result: anyhow::Result<()>) -> HandlerResult<()> {
+        match result {
             Ok(_) => HandlerResult::ok_with_message(format!("VM '{}' launched successfully", name)),
-            Err(e) => HandlerResult::err(format!("Failed to launch VM '{}': {}", name, e)),
+            Err(e) => {
+                HandlerResult::from_error(format!("Failed to launch VM '{}': {}", name, e), e)
+            }
         }
     }
 
     pub async fn start_vm(api: &dyn VmApi, name: &str) -> HandlerResult<()> {
         match api.start(name).await {
             Ok(_) => HandlerResult::ok_with_message(format!("VM '{}' started successfully", name)),
-            Err(e) => HandlerResult::err(format!("Failed to start VM '{}': {}", name, e)),
+            Err(e) => HandlerResult::from_error(format!("Failed to start VM '{}': {}", name, e), e),
         }
     }
 
-    pub async fn stop_vm(api: &dyn VmApi, name: &str) -> HandlerResult<()> {
-        match api.stop(name).await {
+    pub async fn stop_vm(api: &dyn VmApi, name: &str, opts: &StopOptions) -> HandlerResult<()> {
+        match api.stop(name, opts).await {
             Ok(_) => HandlerResult::ok_with_message(format!("VM '{}' stopped successfully", name)),
-            Err(e) => HandlerResult::err(format!("Failed to stop VM '{}': {}", name, e)),
+            Err(e) => HandlerResult::from_error(format!("Failed to stop VM '{}': {}", name, e), e),
+        }
+    }
+
+    /// Starts the VM unless it is already running.
+    pub async fn start_vm_if_needed(api: &dyn VmApi, name: &str) -> HandlerResult<()> {
+        match api.info(name).await {
+            Ok(info) if info.state == VmState::Running => {
+                HandlerResult::ok_with_message(format!("VM '{}' is already running", name))
+            }
+            Ok(_) => start_vm(api, name).await,
+            Err(e) => HandlerResult::from_error(format!("Failed to start VM '{}': {}", name, e), e),
+        }
+    }
+
+    /// Stops the VM unless it is already stopped.
+    pub async fn stop_vm_if_needed(
+        api: &dyn VmApi,
+        name: &str,
+        opts: &StopOptions,
+    ) -> HandlerResult<()> {
+        match api.info(name).await {
+            Ok(info) if info.state == VmState::Stopped => {
+                HandlerResult::ok_with_message(format!("VM '{}' is already stopped", name))
+            }
+            Ok(_) => stop_vm(api, name, opts).await,
+            Err(e) => HandlerResult::from_error(format!("Failed to stop VM '{}': {}", name, e), e),
         }
     }
 
@@ -591,90 +3641,307 @@ pub mod handlers {
             Ok(_) => {
                 HandlerResult::ok_with_message(format!("VM '{}' restarted successfully", name))
             }
-            Err(e) => HandlerResult::err(format!("Failed to restart VM '{}': {}", name, e)),
+            Err(e) => {
+                HandlerResult::from_error(format!("Failed to restart VM '{}': {}", name, e), e)
+            }
         }
     }
 
     pub async fn delete_vm(api: &dyn VmApi, name: &str) -> HandlerResult<()> {
         match api.delete(name).await {
             Ok(_) => HandlerResult::ok_with_message(format!("VM '{}' deleted successfully", name)),
-            Err(e) => HandlerResult::err(format!("Failed to delete VM '{}': {}", name, e)),
+            Err(e) => {
+                HandlerResult::from_error(format!("Failed to delete VM '{}': {}", name, e), e)
+            }
+        }
+    }
+
+    pub async fn delete_vms(api: &dyn VmApi, names: &[String]) -> HandlerResult<()> {
+        match api.delete_many(names).await {
+            Ok(_) => HandlerResult::ok_with_message(format!(
+                "Deleted {} VMs: {}",
+                names.len(),
+                names.join(", ")
+            )),
+            Err(e) => HandlerResult::from_error(
+                format!("Failed to delete VMs {}: {}", names.join(", "), e),
+                e,
+            ),
+        }
+    }
+
+    pub async fn rename_vm(
+        api: &dyn VmApi,
+        name: &str,
+        new_name: &str,
+        opts: &RenameOptions,
+        progress: &(dyn Fn(RenameStep) + Send + Sync),
+    ) -> HandlerResult<()> {
+        match api.rename(name, new_name, opts, progress).await {
+            Ok(_) => {
+                HandlerResult::ok_with_message(format!("VM '{}' renamed to '{}'", name, new_name))
+            }
+            Err(e) => HandlerResult::from_error(
+                format!("Failed to rename VM '{}' to '{}': {}", name, new_name, e),
+                e,
+            ),
+        }
+    }
+
+    pub async fn set_vm_property(
+        api: &dyn VmApi,
+        name: &str,
+        key: VmProperty,
+        value: &str,
+    ) -> HandlerResult<()> {
+        match api.set_property(name, key, value).await {
+            Ok(_) => {
+                HandlerResult::ok_with_message(format!("Set {} of VM '{}' to {}", key, name, value))
+            }
+            Err(e) => HandlerResult::from_error(
+                format!("Failed to set {} of VM '{}': {}", key, name, e),
+                e,
+            ),
+        }
+    }
+
+    pub async fn get_vm_property(
+        api: &dyn VmApi,
+        name: &str,
+        key: VmProperty,
+    ) -> HandlerResult<String> {
+        match api.get_property(name, key).await {
+            Ok(value) => HandlerResult::ok(value, format!("Retrieved {} of VM '{}'", key, name)),
+            Err(e) => HandlerResult::from_error(
+                format!("Failed to get {} of VM '{}': {}", key, name, e),
+                e,
+            ),
         }
     }
 
     pub async fn get_vm_info(api: &dyn VmApi, name: &str) -> HandlerResult<VmStatusResponse> {
         match api.info(name).await {
             Ok(info) => HandlerResult::ok(info, format!("Retrieved info for VM '{}'", name)),
-            Err(e) => HandlerResult::err(format!("Failed to get info for VM '{}': {}", name, e)),
+            Err(e) => {
+                HandlerResult::from_error(format!("Failed to get info for VM '{}': {}", name, e), e)
+            }
+        }
+    }
+
+    pub async fn vm_logs(
+        api: &dyn VmApi,
+        name: &str,
+        source: LogSource,
+        lines: usize,
+    ) -> HandlerResult<String> {
+        match api.exec(name, &source.command(lines, false)).await {
+            Ok(output) if output.status_code == 0 => {
+                HandlerResult::ok(output.stdout, format!("Fetched logs for VM '{}'", name))
+            }
+            Ok(output) => HandlerResult::err(format!(
+                "Failed to fetch logs for VM '{}': '{}' exited with status {}: {}",
+                name,
+                source.command(lines, false).join(" "),
+                output.status_code,
+                output.stderr.trim()
+            )),
+            Err(e) => HandlerResult::from_error(
+                format!("Failed to fetch logs for VM '{}': {}", name, e),
+                e,
+            ),
         }
     }
 
-    pub async fn list_vms(api: &dyn VmApi) -> HandlerResult<Vec<VmSummary>> {
-        match api.list().await {
-            Ok(vms) => {
-                let count = vms.len();
-                HandlerResult::ok(vms, format!("Found {} VM(s)", count))
+    pub async fn exec_in_vm(
+        api: &dyn VmApi,
+        name: &str,
+        command: &[String],
+        stdin: Option<&[u8]>,
+    ) -> HandlerResult<CommandOutput> {
+        let result = match stdin {
+            Some(stdin) => api.exec_with_stdin(name, command, stdin).await,
+            None => api.exec(name, command).await,
+        };
+        match result {
+            Ok(output) => HandlerResult::ok(output, format!("Ran command in VM '{}'", name)),
+            Err(e) => HandlerResult::from_error(
+                format!("Failed to run command in VM '{}': {}", name, e),
+                e,
+            ),
+        }
+    }
+
+    pub async fn list_vms(api: &dyn VmApi) -> HandlerResult<VmListing> {
+        match api.list_with_warnings().await {
+            Ok(listing) => {
+                let count = listing.vms.len();
+                HandlerResult::ok(listing, format!("Found {} VM(s)", count))
+            }
+            Err(e) => HandlerResult::from_error(format!("Failed to list VMs: {}", e), e),
+        }
+    }
+
+    pub async fn tag_vm(
+        api: &dyn VmApi,
+        name: &str,
+        tags: &[String],
+    ) -> HandlerResult<Vec<String>> {
+        match api.tag(name, tags).await {
+            Ok(tags) => HandlerResult::ok(tags, format!("Tagged VM '{}'", name)),
+            Err(e) => HandlerResult::from_error(format!("Failed to tag VM '{}': {}", name, e), e),
+        }
+    }
+
+    pub async fn untag_vm(
+        api: &dyn VmApi,
+        name: &str,
+        tags: &[String],
+    ) -> HandlerResult<Vec<String>> {
+        match api.untag(name, tags).await {
+            Ok(tags) => HandlerResult::ok(tags, format!("Untagged VM '{}'", name)),
+            Err(e) => HandlerResult::from_error(format!("Failed to untag VM '{}': {}", name, e), e),
+        }
+    }
+
+    /// Lists VMs that carry every one of `tags`.
+    pub async fn list_vms_tagged(api: &dyn VmApi, tags: &[String]) -> HandlerResult<VmListing> {
+        let VmListing { vms, warnings } = match api.list_with_warnings().await {
+            Ok(listing) => listing,
+            Err(e) => return HandlerResult::from_error(format!("Failed to list VMs: {}", e), e),
+        };
+
+        let mut tagged = Vec::with_capacity(vms.len());
+        for vm in vms {
+            match api.tags(&vm.name).await {
+                Ok(vm_tags) if tags.iter().all(|tag| vm_tags.contains(tag)) => tagged.push(vm),
+                Ok(_) => {}
+                Err(e) => {
+                    return HandlerResult::from_error(
+                        format!("Failed to read tags of VM '{}': {}", vm.name, e),
+                        e,
+                    );
+                }
+            }
+        }
+        let count = tagged.len();
+        HandlerResult::ok(
+            VmListing {
+                vms: tagged,
+                warnings,
+            },
+            format!("Found {} VM(s)", count),
+        )
+    }
+
+    pub async fn list_networks(api: &dyn VmApi) -> HandlerResult<Vec<NetworkInfo>> {
+        match api.networks().await {
+            Ok(networks) => {
+                let count = networks.len();
+                HandlerResult::ok(networks, format!("Found {} network(s)", count))
+            }
+            Err(e) => HandlerResult::from_error(format!("Failed to list networks: {}", e), e),
+        }
+    }
+
+    pub async fn list_images(api: &dyn VmApi) -> HandlerResult<Vec<ImageInfo>> {
+        match api.find().await {
+            Ok(images) => {
+                let count = images.len();
+                HandlerResult::ok(images, format!("Found {} image(s)", count))
             }
-            Err(e) => HandlerResult::err(format!("Failed to list VMs: {}", e)),
+            Err(e) => HandlerResult::from_error(format!("Failed to list images: {}", e), e),
         }
     }
 }
 
+/// Runs a cancellable operation on its own task so that dropping the caller
+/// (e.g. an HTTP client disconnecting mid-request) cancels the token and lets
+/// the executor kill the underlying multipass process. Cancelling `parent`
+/// cancels the operation too.
+pub(crate) async fn run_until_disconnect<F, Fut, T>(
+    parent: &CancellationToken,
+    operation: F,
+) -> Result<T>
+where
+    F: FnOnce(CancellationToken) -> Fut,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let cancel = parent.child_token();
+    let disconnect_guard = cancel.clone().drop_guard();
+    let result = tokio::spawn(operation(cancel)).await;
+    disconnect_guard.disarm();
+    result.map_err(|err| anyhow::anyhow!("VM operation task failed: {}", err))
+}
+
 #[derive(Clone)]
 struct VmApiState {
     multipass: Arc<dyn Multipass>,
 }
 
+/// The original `/v1/vm` API. Failures use the same `ApiErrorBody` envelope
+/// as `server::create_api_router`.
 pub fn app(multipass: Arc<dyn Multipass>) -> Router {
     Router::new()
         .route("/v1/vm", post(spawn_vm).get(list_vms))
         .route("/v1/vm/", post(spawn_vm).get(list_vms))
         .route("/v1/vm/{name}", get(get_vm_status).delete(terminate_vm))
         .route("/v1/vm/{name}/", get(get_vm_status).delete(terminate_vm))
+        .layer(middleware::from_fn(with_request_id))
         .with_state(VmApiState { multipass })
 }
 
 async fn spawn_vm(
     State(state): State<VmApiState>,
     Json(request): Json<SpawnVmRequest>,
-) -> Result<StatusCode, StatusCode> {
-    state
-        .multipass
-        .launch(&request.name)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<StatusCode, ApiError> {
+    let multipass = state.multipass.clone();
+    run_until_disconnect(&CancellationToken::new(), |cancel| async move {
+        multipass
+            .launch(&LaunchSpec::new(request.name), &cancel)
+            .await
+    })
+    .await
+    .map_err(|err| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::Internal,
+            err.to_string(),
+        )
+    })?
+    .map_err(|err| ApiError::from_vm_error(&err))?;
     Ok(StatusCode::CREATED)
 }
 
-async fn list_vms(State(state): State<VmApiState>) -> Result<Json<Vec<VmSummary>>, StatusCode> {
+async fn list_vms(State(state): State<VmApiState>) -> Result<Json<Vec<VmSummary>>, ApiError> {
     let vms = state
         .multipass
         .list()
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|err| ApiError::from_vm_error(&err))?;
     Ok(Json(vms))
 }
 
 async fn get_vm_status(
     State(state): State<VmApiState>,
     Path(name): Path<String>,
-) -> Result<Json<VmStatusResponse>, StatusCode> {
+) -> Result<Json<VmStatusResponse>, ApiError> {
     let status = state
         .multipass
         .info(&name)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|err| ApiError::from_vm_error(&err))?;
     Ok(Json(status))
 }
 
 async fn terminate_vm(
     State(state): State<VmApiState>,
     Path(name): Path<String>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, ApiError> {
     state
         .multipass
-        .stop(&name)
+        .stop(&name, &StopOptions::default())
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|err| ApiError::from_vm_error(&err))?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -42,6 +42,7 @@ async fn is_agent_installed_returns_true_when_picoclaw_found() {
             status_code: 0,
             stdout: "/usr/local/bin/picoclaw\n".to_owned(),
             stderr: String::new(),
+            truncated: false,
         })));
 
     let installed = agent_manager
@@ -59,6 +60,7 @@ async fn is_agent_installed_returns_false_when_picoclaw_not_found() {
             status_code: 1,
             stdout: String::new(),
             stderr: String::new(),
+            truncated: false,
         })));
 
     let installed = agent_manager
@@ -134,6 +136,7 @@ async fn install_agent_fails_when_script_execution_fails() {
             status_code: 1,
             stdout: String::new(),
             stderr: "Installation failed\n".to_owned(),
+            truncated: false,
         })));
 
     let result = agent_manager
@@ -156,6 +159,7 @@ async fn onboard_agent_fails_when_agent_not_installed() {
             status_code: 1,
             stdout: String::new(),
             stderr: String::new(),
+            truncated: false,
         })));
 
     let result = agent_manager
@@ -177,6 +181,7 @@ async fn onboard_agent_creates_ready_instance_and_persists_it() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -237,6 +242,7 @@ async fn onboard_agent_without_optional_fields_uses_defaults() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -275,6 +281,7 @@ async fn stop_agent_updates_status_in_store() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -309,6 +316,7 @@ async fn delete_agent_removes_from_store() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -339,6 +347,7 @@ async fn agents_are_isolated_per_vm() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -347,6 +356,7 @@ async fn agents_are_isolated_per_vm() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
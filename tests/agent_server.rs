@@ -57,7 +57,8 @@ async fn test_install_agent_success() {
 
     assert_eq!(response.status(), StatusCode::OK);
     let body = response_body_to_json(response.into_body()).await;
-    assert_eq!(body["success"], true);
+    assert_eq!(body["name"], "test-vm");
+    assert_eq!(body["action"], "install_agent");
     assert!(body["message"].as_str().unwrap().contains("installed"));
 }
 
@@ -68,6 +69,7 @@ async fn test_install_agent_failure() {
             status_code: 1,
             stdout: String::new(),
             stderr: "Installation failed\n".to_owned(),
+            truncated: false,
         })));
 
     let request = Request::builder()
@@ -86,8 +88,8 @@ async fn test_install_agent_failure() {
 
     assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
     let body = response_body_to_json(response.into_body()).await;
-    assert_eq!(body["success"], false);
-    assert!(body["error"].as_str().unwrap().contains("Failed"));
+    assert_eq!(body["code"], "internal");
+    assert!(body["message"].as_str().unwrap().contains("Failed"));
     assert_eq!(body["details"]["code"], "agent_install_failed");
     assert_eq!(body["details"]["operation"], "install_agent");
     assert_eq!(body["details"]["vm_name"], "test-vm");
@@ -100,6 +102,7 @@ async fn test_check_agent_installed_true() {
             status_code: 0,
             stdout: "/usr/local/bin/picoclaw\n".to_owned(),
             stderr: String::new(),
+            truncated: false,
         })));
 
     let request = Request::builder()
@@ -130,6 +133,7 @@ async fn test_onboard_agent_success() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -172,6 +176,7 @@ async fn test_onboard_agent_not_installed() {
             status_code: 1,
             stdout: String::new(),
             stderr: String::new(),
+            truncated: false,
         })));
 
     let request = Request::builder()
@@ -192,8 +197,8 @@ async fn test_onboard_agent_not_installed() {
 
     assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
     let body = response_body_to_json(response.into_body()).await;
-    assert_eq!(body["success"], false);
-    assert!(body["error"].as_str().unwrap().contains("not installed"));
+    assert_eq!(body["code"], "internal");
+    assert!(body["message"].as_str().unwrap().contains("not installed"));
     assert_eq!(body["details"]["code"], "agent_onboard_failed");
     assert_eq!(body["details"]["operation"], "onboard_agent");
     assert_eq!(body["details"]["vm_name"], "test-vm");
@@ -208,6 +213,7 @@ async fn test_list_and_get_agents_after_onboard() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -270,6 +276,7 @@ async fn test_full_agent_lifecycle() {
                 status_code: 0,
                 stdout: "/usr/local/bin/picoclaw\n".to_owned(),
                 stderr: String::new(),
+                truncated: false,
             }))
             .with_exec_response(Ok(CommandOutput::success(
                 "==> picoclaw onboarding complete\n",
@@ -319,6 +326,9 @@ async fn test_full_agent_lifecycle() {
         .unwrap();
     let stop_response = router.clone().oneshot(stop_request).await.unwrap();
     assert_eq!(stop_response.status(), StatusCode::OK);
+    let stop_body = response_body_to_json(stop_response.into_body()).await;
+    assert_eq!(stop_body["name"], agent_id);
+    assert_eq!(stop_body["action"], "stop_agent");
 
     let delete_request = Request::builder()
         .method("DELETE")
@@ -354,8 +364,8 @@ async fn test_get_agent_not_found() {
 
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
     let body = response_body_to_json(response.into_body()).await;
-    assert_eq!(body["success"], false);
-    assert!(body["error"].as_str().unwrap().contains("not found"));
+    assert_eq!(body["code"], "agent_not_found");
+    assert!(body["message"].as_str().unwrap().contains("not found"));
     assert_eq!(body["details"]["code"], "agent_get_failed");
     assert_eq!(body["details"]["agent_id"], "nonexistent-id");
 }
@@ -380,12 +390,11 @@ async fn test_invalid_agent_payload_returns_json_error_details() {
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     let body = response_body_to_json(response.into_body()).await;
-    assert_eq!(body["success"], false);
-    assert_eq!(body["details"]["code"], "agent_request_invalid");
+    assert_eq!(body["code"], "validation_failed");
     assert_eq!(body["details"]["operation"], "check_agent_installed");
     assert_eq!(body["details"]["vm_name"], "test-vm");
     assert!(
-        body["error"]
+        body["message"]
             .as_str()
             .unwrap()
             .contains("Invalid agent request")
@@ -406,8 +415,7 @@ async fn test_unknown_api_route_returns_json_not_found() {
 
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
     let body = response_body_to_json(response.into_body()).await;
-    assert_eq!(body["success"], false);
-    assert_eq!(body["details"]["code"], "route_not_found");
+    assert_eq!(body["code"], "route_not_found");
     assert_eq!(body["details"]["method"], "POST");
     assert_eq!(body["details"]["path"], "/agents/test-vm/unsupported/path");
 }
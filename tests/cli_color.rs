@@ -0,0 +1,118 @@
+use std::ffi::OsStr;
+
+use safepaw::cli::{
+    CommandResult, OutputFormat, RenderOptions, STATE_GREEN, STATE_RED, STATE_YELLOW, build_cli,
+    render_table, use_color,
+};
+use safepaw::vm::{VmStatusResponse, VmSummary};
+
+const RESET: &str = "\x1b[0m";
+
+fn fleet() -> Vec<VmSummary> {
+    vec![
+        VmSummary::minimal("web", "Running"),
+        VmSummary::minimal("db", "Starting"),
+        VmSummary::minimal("cache", "Stopped"),
+        VmSummary::minimal("old", "Restarting"),
+    ]
+}
+
+fn colored(format: OutputFormat) -> RenderOptions {
+    RenderOptions {
+        format,
+        color: true,
+        ..RenderOptions::default()
+    }
+}
+
+fn strip_ansi(line: &str) -> String {
+    [STATE_GREEN, STATE_YELLOW, STATE_RED, RESET]
+        .iter()
+        .fold(line.to_owned(), |line, code| line.replace(code, ""))
+}
+
+#[test]
+fn table_colors_only_the_state_column() {
+    let lines = CommandResult::Summaries(fleet()).render(&colored(OutputFormat::Text));
+
+    assert_eq!(lines[0], "NAME   STATE       IPV4  RELEASE");
+    assert_eq!(
+        lines[1],
+        format!("web    {STATE_GREEN}Running{RESET}     -     -")
+    );
+    assert_eq!(
+        lines[2],
+        format!("db     {STATE_YELLOW}Starting{RESET}    -     -")
+    );
+    assert_eq!(
+        lines[3],
+        format!("cache  {STATE_RED}Stopped{RESET}     -     -")
+    );
+    assert_eq!(
+        lines[4],
+        format!("old    {STATE_YELLOW}Restarting{RESET}  -     -")
+    );
+}
+
+#[test]
+fn stripped_colored_table_matches_the_plain_table() {
+    let colored_lines = CommandResult::Summaries(fleet()).render(&colored(OutputFormat::Text));
+    let stripped: Vec<String> = colored_lines.iter().map(|line| strip_ansi(line)).collect();
+
+    assert_eq!(stripped, render_table(fleet()));
+    assert_eq!(
+        CommandResult::Summaries(fleet()).into_lines(),
+        render_table(fleet())
+    );
+}
+
+#[test]
+fn info_colors_the_state_line() {
+    let info = VmStatusResponse::minimal("web", "Suspended");
+
+    let lines = CommandResult::Info(Box::new(info.clone())).render(&colored(OutputFormat::Text));
+    let plain = CommandResult::Info(Box::new(info)).into_lines();
+
+    assert_eq!(lines[1], format!("State: {STATE_YELLOW}Suspended{RESET}"));
+    assert_eq!(plain[1], "State: Suspended");
+    let stripped: Vec<String> = lines.iter().map(|line| strip_ansi(line)).collect();
+    assert_eq!(stripped, plain);
+}
+
+#[test]
+fn plain_and_json_output_never_contain_escape_codes() {
+    for format in [OutputFormat::Plain, OutputFormat::Json] {
+        let lines = CommandResult::Summaries(fleet()).render(&colored(format));
+        let info = CommandResult::Info(Box::new(VmStatusResponse::minimal("web", "Running")))
+            .render(&colored(format));
+
+        for line in lines.iter().chain(&info) {
+            assert!(!line.contains('\x1b'), "{format:?} leaked ANSI: {line:?}");
+        }
+    }
+}
+
+#[test]
+fn color_needs_a_terminal_and_no_opt_out() {
+    assert!(use_color(false, None, true));
+    assert!(use_color(false, Some(OsStr::new("")), true));
+    assert!(!use_color(false, None, false));
+    assert!(!use_color(true, None, true));
+    assert!(!use_color(false, Some(OsStr::new("1")), true));
+}
+
+#[test]
+fn no_color_flag_is_global() {
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "list", "--no-color"])
+        .expect("--no-color should be accepted after the subcommand");
+    let vm_matches = matches.subcommand_matches("vm").unwrap();
+
+    assert!(!RenderOptions::from_matches(vm_matches).color);
+
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "--no-color", "vm", "info", "web"])
+            .is_ok()
+    );
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::FakeVmApi;
+use safepaw::cli::{build_cli, complete_vm_names};
+use safepaw::vm::VmSummary;
+
+#[tokio::test]
+async fn complete_vm_names_prints_listed_names() {
+    let api = FakeVmApi::default().with_list_response(vec![
+        VmSummary::minimal("agent-1", "Running"),
+        VmSummary::minimal("agent-2", "Stopped"),
+    ]);
+
+    assert_eq!(complete_vm_names(&api).await, vec!["agent-1", "agent-2"]);
+    assert_eq!(api.calls(), vec!["list"]);
+}
+
+#[tokio::test]
+async fn complete_vm_names_is_empty_when_multipass_is_unreachable() {
+    let api = FakeVmApi::default().with_list_error("cannot connect to the multipass socket");
+
+    assert!(complete_vm_names(&api).await.is_empty());
+}
+
+#[test]
+fn complete_vm_names_subcommand_is_hidden_from_help() {
+    let mut cli = build_cli();
+    let help = cli.render_help().to_string();
+
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "__complete-vm-names"])
+            .is_ok()
+    );
+    assert!(!help.contains("__complete-vm-names"));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{FakeVmApi, ScriptedConfirm};
+use safepaw::cli::{CommandResult, build_cli, run_vm_subcommand_with};
+
+async fn run_delete(
+    args: &[&str],
+    api: &FakeVmApi,
+    confirm: &ScriptedConfirm,
+) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand_with(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        api,
+        confirm,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn delete_proceeds_when_user_confirms() {
+    let api = FakeVmApi::default();
+    let confirm = ScriptedConfirm::answering(true);
+
+    let lines = run_delete(&["safeclaw", "vm", "delete", "agent-1"], &api, &confirm)
+        .await
+        .expect("delete should succeed")
+        .into_lines();
+
+    assert_eq!(lines, vec!["VM 'agent-1' deleted successfully"]);
+    assert_eq!(
+        confirm.prompts(),
+        vec!["Delete VM 'agent-1'? This cannot be undone."]
+    );
+    assert_eq!(api.calls(), vec!["delete:agent-1"]);
+}
+
+#[tokio::test]
+async fn delete_is_aborted_when_user_declines() {
+    let api = FakeVmApi::default();
+    let confirm = ScriptedConfirm::answering(false);
+
+    let lines = run_delete(&["safeclaw", "vm", "delete", "agent-1"], &api, &confirm)
+        .await
+        .expect("declining is not an error")
+        .into_lines();
+
+    assert_eq!(lines, vec!["Aborted; VM 'agent-1' was not deleted"]);
+    assert!(api.calls().is_empty());
+}
+
+#[tokio::test]
+async fn delete_with_yes_skips_the_prompt() {
+    for flag in ["--yes", "-y"] {
+        let api = FakeVmApi::default();
+        let confirm = ScriptedConfirm::non_interactive();
+
+        run_delete(
+            &["safeclaw", "vm", "delete", "agent-1", flag],
+            &api,
+            &confirm,
+        )
+        .await
+        .expect("delete should succeed");
+
+        assert!(confirm.prompts().is_empty());
+        assert_eq!(api.calls(), vec!["delete:agent-1"]);
+    }
+}
+
+#[tokio::test]
+async fn delete_without_terminal_requires_yes() {
+    let api = FakeVmApi::default();
+    let confirm = ScriptedConfirm::non_interactive();
+
+    let err = run_delete(&["safeclaw", "vm", "delete", "agent-1"], &api, &confirm)
+        .await
+        .expect_err("delete should refuse without a terminal");
+
+    assert!(err.to_string().contains("pass --yes"));
+    assert!(confirm.prompts().is_empty());
+    assert!(api.calls().is_empty());
+}
+
+#[tokio::test]
+async fn delete_with_several_names_confirms_once_and_deletes_together() {
+    let api = FakeVmApi::default();
+    let confirm = ScriptedConfirm::answering(true);
+
+    let lines = run_delete(
+        &["safeclaw", "vm", "delete", "agent-1", "agent-2"],
+        &api,
+        &confirm,
+    )
+    .await
+    .expect("delete should succeed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["Deleted 2 VMs: agent-1, agent-2"]);
+    assert_eq!(
+        confirm.prompts(),
+        vec!["Delete 2 VMs (agent-1, agent-2)? This cannot be undone."]
+    );
+    assert_eq!(api.calls(), vec!["delete:agent-1,agent-2"]);
+}
@@ -0,0 +1,170 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use std::time::Duration;
+
+use safepaw::cli::{
+    EXIT_BACKEND, EXIT_FAILURE, EXIT_MULTIPASS_UNAVAILABLE, EXIT_TIMEOUT, EXIT_USAGE,
+    EXIT_VM_NOT_FOUND, exit_code,
+};
+use safepaw::util::HandlerResult;
+use safepaw::vm::{VmError, WaitTimeout};
+
+fn binary_path() -> String {
+    std::env::var("NEXTEST_BIN_EXE_safeclaw")
+        .or_else(|_| std::env::var("CARGO_BIN_EXE_safeclaw"))
+        .or_else(|_| std::env::var("NEXTEST_BIN_EXE_safepaw"))
+        .or_else(|_| std::env::var("CARGO_BIN_EXE_safepaw"))
+        .unwrap_or_else(|_| "target/debug/safeclaw".to_owned())
+}
+
+/// Runs the binary with only `bin_dir` on PATH, so `multipass` resolves to
+/// whatever fake the test put there (or to nothing).
+fn run_with_path(bin_dir: &Path, args: &[&str]) -> Output {
+    Command::new(binary_path())
+        .args(args)
+        .env("PATH", bin_dir)
+        .env("HOME", bin_dir)
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("failed to execute binary")
+}
+
+fn write_fake_multipass(dir: &Path, script: &str) {
+    let path = dir.join("multipass");
+    std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn missing_vm_exits_with_not_found_code() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    write_fake_multipass(
+        temp_dir.path(),
+        r#"echo 'info failed: instance "ghost" does not exist' >&2; exit 2"#,
+    );
+
+    let output = run_with_path(temp_dir.path(), &["vm", "info", "ghost"]);
+
+    assert_eq!(output.status.code(), Some(EXIT_VM_NOT_FOUND));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not exist"), "stderr: {stderr}");
+}
+
+#[test]
+fn missing_multipass_exits_with_unavailable_code() {
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    let output = run_with_path(temp_dir.path(), &["vm", "info", "agent-1"]);
+
+    assert_eq!(output.status.code(), Some(EXIT_MULTIPASS_UNAVAILABLE));
+}
+
+#[test]
+fn multipass_failures_exit_with_backend_code() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    write_fake_multipass(
+        temp_dir.path(),
+        r#"echo 'info failed: unexpected internal error' >&2; exit 2"#,
+    );
+
+    let output = run_with_path(temp_dir.path(), &["vm", "info", "agent-1"]);
+
+    assert_eq!(output.status.code(), Some(EXIT_BACKEND));
+}
+
+#[test]
+fn daemon_side_failures_exit_with_backend_code() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    write_fake_multipass(
+        temp_dir.path(),
+        r#"echo 'info failed: multipassd: instance image is corrupt' >&2; exit 2"#,
+    );
+
+    let output = run_with_path(temp_dir.path(), &["vm", "info", "agent-1"]);
+
+    assert_eq!(output.status.code(), Some(EXIT_BACKEND));
+}
+
+#[test]
+fn usage_errors_exit_with_usage_code() {
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // Rejected by clap
+    let output = run_with_path(temp_dir.path(), &["vm", "launch"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+
+    // Rejected after parsing
+    let output = run_with_path(temp_dir.path(), &["vm", "--mode", "remote", "list"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--ssh-host is required"));
+}
+
+#[test]
+fn partial_list_succeeds_and_warns_on_stderr() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    write_fake_multipass(
+        temp_dir.path(),
+        r#"echo '{"errors":["instance \"agent-2\" is in an unknown state"],"list":[{"name":"agent-1","state":"Running"}]}'"#,
+    );
+
+    let output = run_with_path(temp_dir.path(), &["vm", "list", "-o", "json"]);
+
+    assert!(output.status.success());
+    let vms: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(vms[0]["name"], "agent-1");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(r#"warning: instance "agent-2" is in an unknown state"#),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn long_help_documents_exit_codes() {
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    let output = run_with_path(temp_dir.path(), &["--help"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Exit codes:"));
+    assert!(stdout.contains("3  VM not found"));
+    assert!(stdout.contains("7  multipass ran but failed"));
+}
+
+#[test]
+fn exit_code_looks_through_handler_errors() {
+    let not_found = anyhow::Error::new(VmError::CommandFailed {
+        action: "start",
+        status_code: 2,
+        stderr: "start failed: instance \"ghost\" does not exist".to_owned(),
+    })
+    .context("failed to start VM ghost");
+    let err = HandlerResult::<()>::from_error("Failed to start VM 'ghost'", not_found).into_error();
+
+    assert_eq!(err.to_string(), "Failed to start VM 'ghost'");
+    assert_eq!(exit_code(&err), EXIT_VM_NOT_FOUND);
+}
+
+#[test]
+fn exit_code_maps_timeouts_and_unknown_errors() {
+    let wait = anyhow::Error::new(WaitTimeout {
+        name: "agent-1".to_owned(),
+        condition: "be ready".to_owned(),
+        last_state: "Starting".to_owned(),
+        timeout: Duration::from_secs(5),
+    });
+    assert_eq!(exit_code(&wait), EXIT_TIMEOUT);
+
+    let stop = anyhow::Error::new(VmError::TimedOut {
+        action: "stop",
+        timeout: Duration::from_secs(5),
+    });
+    assert_eq!(exit_code(&stop), EXIT_TIMEOUT);
+
+    assert_eq!(exit_code(&anyhow::anyhow!("boom")), EXIT_FAILURE);
+}
@@ -0,0 +1,46 @@
+use safepaw::cli::{build_cli, render_man_page, write_man_pages};
+
+#[test]
+fn root_man_page_is_roff() {
+    let page = String::from_utf8(render_man_page(&[]).expect("root man page should render"))
+        .expect("man page should be UTF-8");
+    assert!(page.contains(".TH"));
+    assert!(page.contains("SAFEPAW"));
+}
+
+#[test]
+fn vm_subcommand_man_page_uses_long_about() {
+    let page = String::from_utf8(render_man_page(&["vm", "launch"]).unwrap()).unwrap();
+    assert!(page.contains("safepaw\\-vm\\-launch"));
+    assert!(page.contains("\\-\\-wait"));
+}
+
+#[test]
+fn unknown_subcommand_is_rejected() {
+    let err = render_man_page(&["vm", "nope"]).unwrap_err();
+    assert!(err.to_string().contains("no such command"));
+}
+
+#[test]
+fn gen_man_writes_root_and_vm_pages_into_a_new_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let out_dir = dir.path().join("man1");
+
+    let written = write_man_pages(&out_dir).expect("man pages should be written");
+
+    assert_eq!(written[0], out_dir.join("safepaw.1"));
+    assert!(written.contains(&out_dir.join("safepaw-vm.1")));
+    assert!(written.contains(&out_dir.join("safepaw-vm-launch.1")));
+    assert!(written.iter().all(|path| path.exists()));
+    let root = std::fs::read_to_string(out_dir.join("safepaw.1")).unwrap();
+    assert!(root.contains("Agents for the paranoid."));
+}
+
+#[test]
+fn gen_man_is_hidden() {
+    assert!(
+        build_cli()
+            .get_subcommands()
+            .any(|sub| sub.get_name() == "gen-man" && sub.is_hide_set())
+    );
+}
@@ -0,0 +1,95 @@
+use safepaw::cli::{build_cli, resolve_log_filter};
+
+fn filter_for(args: &[&str]) -> String {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    resolve_log_filter(
+        matches.get_flag("quiet"),
+        matches.get_count("verbose"),
+        "info",
+        None,
+    )
+}
+
+#[test]
+fn default_filter_is_info() {
+    assert_eq!(filter_for(&["safepaw", "vm", "list"]), "safepaw=info");
+}
+
+#[test]
+fn verbose_flags_raise_the_level() {
+    assert_eq!(
+        filter_for(&["safepaw", "-v", "vm", "list"]),
+        "safepaw=debug"
+    );
+    assert_eq!(
+        filter_for(&["safepaw", "vm", "list", "-vv"]),
+        "safepaw=trace"
+    );
+    assert_eq!(
+        filter_for(&["safepaw", "--verbose", "--verbose", "vm", "list"]),
+        "safepaw=trace"
+    );
+}
+
+#[test]
+fn quiet_flag_only_keeps_warnings() {
+    assert_eq!(filter_for(&["safepaw", "-q", "vm", "list"]), "safepaw=warn");
+    assert_eq!(
+        filter_for(&["safepaw", "vm", "list", "--quiet"]),
+        "safepaw=warn"
+    );
+}
+
+#[test]
+fn resolves_each_flag_combination() {
+    assert_eq!(resolve_log_filter(false, 0, "info", None), "safepaw=info");
+    assert_eq!(resolve_log_filter(true, 0, "info", None), "safepaw=warn");
+    assert_eq!(resolve_log_filter(false, 1, "info", None), "safepaw=debug");
+    assert_eq!(resolve_log_filter(false, 2, "info", None), "safepaw=trace");
+    assert_eq!(resolve_log_filter(false, 5, "info", None), "safepaw=trace");
+}
+
+#[test]
+fn rust_log_overrides_flags_when_set() {
+    assert_eq!(resolve_log_filter(true, 0, "info", Some("debug")), "debug");
+    assert_eq!(
+        resolve_log_filter(false, 2, "info", Some("safepaw=warn,tower_http=debug")),
+        "safepaw=warn,tower_http=debug"
+    );
+    assert_eq!(
+        resolve_log_filter(false, 1, "info", Some("")),
+        "safepaw=debug"
+    );
+    assert_eq!(
+        resolve_log_filter(true, 0, "info", Some("  ")),
+        "safepaw=warn"
+    );
+}
+
+#[test]
+fn quiet_and_verbose_conflict() {
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "-q", "-v", "vm", "list"])
+            .is_err()
+    );
+}
+
+#[test]
+fn global_verbose_flag_applies_to_agent_commands() {
+    build_cli().debug_assert();
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "agent", "list", "--vm", "dev", "-v"])
+        .expect("failed to parse CLI args");
+    assert_eq!(matches.get_count("verbose"), 1);
+}
+
+#[test]
+fn configured_level_applies_without_flags() {
+    assert_eq!(resolve_log_filter(false, 0, "debug", None), "safepaw=debug");
+    assert_eq!(resolve_log_filter(true, 0, "debug", None), "safepaw=warn");
+    assert_eq!(resolve_log_filter(false, 1, "error", None), "safepaw=debug");
+    assert_eq!(resolve_log_filter(false, 0, "debug", Some("warn")), "warn");
+}
@@ -1,8 +1,13 @@
 mod common;
 
-use common::FakeVmApi;
-use safepaw::cli::{build_cli, run_vm_subcommand};
-use safepaw::vm::VmSummary;
+use std::sync::Arc;
+
+use common::{FakeVmApi, multipass_cli_with_outputs};
+use safepaw::cli::{
+    CommandResult, OutputFormat, RenderOptions, build_cli, render_table, run_vm_subcommand,
+};
+use safepaw::vm::{CommandOutput, LocalVmApi, VmStatusResponse, VmSummary};
+use serde_json::json;
 
 #[tokio::test]
 async fn vm_launch_command_produces_expected_output_and_call() {
@@ -21,7 +26,8 @@ async fn vm_launch_command_produces_expected_output_and_call() {
         &api,
     )
     .await
-    .expect("launch command failed");
+    .expect("launch command failed")
+    .into_lines();
 
     assert_eq!(lines, vec!["VM 'agent-1' launched successfully"]);
     assert_eq!(api.calls(), vec!["launch:agent-1"]);
@@ -41,12 +47,141 @@ async fn vm_info_command_produces_expected_output_and_call() {
         &api,
     )
     .await
-    .expect("info command failed");
+    .expect("info command failed")
+    .into_lines();
 
     assert_eq!(lines, vec!["Name:  agent-1", "State: Running"]);
     assert_eq!(api.calls(), vec!["info:agent-1"]);
 }
 
+#[tokio::test]
+async fn list_and_info_return_typed_results() {
+    let api =
+        FakeVmApi::default().with_list_response(vec![VmSummary::minimal("agent-1", "Running")]);
+    let run = |args: &'static [&'static str]| {
+        let api = &api;
+        async move {
+            let matches = build_cli().try_get_matches_from(args).unwrap();
+            run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api)
+                .await
+                .unwrap()
+        }
+    };
+
+    assert_eq!(
+        run(&["safepaw", "vm", "list", "-o", "json"]).await,
+        CommandResult::Summaries(vec![VmSummary::minimal("agent-1", "Running")])
+    );
+    assert_eq!(
+        run(&["safepaw", "vm", "info", "test-vm"]).await,
+        CommandResult::Info(Box::new(VmStatusResponse::minimal("test-vm", "Running")))
+    );
+}
+
+#[test]
+fn empty_results_render_nothing_and_empty_lists_say_so() {
+    assert!(
+        CommandResult::Empty
+            .render(&RenderOptions::default())
+            .is_empty()
+    );
+    assert_eq!(
+        CommandResult::Summaries(vec![]).into_lines(),
+        vec!["No VMs found"]
+    );
+    let json = RenderOptions {
+        format: OutputFormat::Json,
+        ..RenderOptions::default()
+    };
+    assert_eq!(CommandResult::Summaries(vec![]).render(&json), vec!["[]"]);
+}
+
+fn sized_status() -> VmStatusResponse {
+    VmStatusResponse {
+        memory_total: Some(2 * 1024 * 1024 * 1024),
+        memory_used: Some(512 * 1024 * 1024),
+        disk_total: Some(9_663_676_416),
+        disk_used: Some(1_503_238_553),
+        ..VmStatusResponse::minimal("agent-1", "Running")
+    }
+}
+
+/// Runs a `vm` command and renders it the way `main.rs` prints it.
+async fn rendered_lines(args: &[&str], api: &FakeVmApi) -> Vec<String> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    let vm_matches = matches.subcommand_matches("vm").unwrap();
+    run_vm_subcommand(vm_matches, api)
+        .await
+        .expect("command failed")
+        .render(&RenderOptions::from_matches(vm_matches))
+}
+
+#[tokio::test]
+async fn vm_info_command_formats_sizes_for_humans() {
+    let api = FakeVmApi::default().with_info_response(sized_status());
+
+    let lines = rendered_lines(&["safeclaw", "vm", "info", "agent-1"], &api).await;
+
+    assert_eq!(
+        lines[2..],
+        [
+            "Memory: 512.0 MiB / 2.0 GiB (25.0%)",
+            "Disk:   1.4 GiB / 9.0 GiB (15.6%)",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vm_info_command_prints_multipass_warnings_last() {
+    let api = FakeVmApi::default().with_info_response(VmStatusResponse {
+        warnings: vec!["mount /data could not be inspected".to_owned()],
+        ..VmStatusResponse::minimal("agent-1", "Running")
+    });
+
+    let text = rendered_lines(&["safeclaw", "vm", "info", "agent-1"], &api).await;
+    let json = rendered_lines(&["safeclaw", "vm", "info", "agent-1", "-o", "json"], &api).await;
+
+    assert_eq!(
+        text.last().unwrap(),
+        "Warning: mount /data could not be inspected"
+    );
+    let json: serde_json::Value = serde_json::from_str(&json[0]).unwrap();
+    assert_eq!(
+        json["warnings"],
+        serde_json::json!(["mount /data could not be inspected"])
+    );
+}
+
+#[tokio::test]
+async fn vm_info_command_prints_raw_bytes() {
+    let api = FakeVmApi::default().with_info_response(sized_status());
+
+    let lines = rendered_lines(&["safeclaw", "vm", "info", "agent-1", "--bytes"], &api).await;
+
+    assert_eq!(
+        lines[2..],
+        [
+            "Memory: 536870912 / 2147483648 (25.0%)",
+            "Disk:   1503238553 / 9663676416 (15.6%)",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vm_info_command_skips_percentage_for_zero_totals() {
+    let api = FakeVmApi::default().with_info_response(VmStatusResponse {
+        disk_total: Some(0),
+        disk_used: Some(0),
+        ..VmStatusResponse::minimal("agent-1", "Running")
+    });
+
+    let lines = rendered_lines(&["safeclaw", "vm", "info", "agent-1"], &api).await;
+
+    assert_eq!(lines[2], "Disk:   0 B / 0 B");
+}
+
 #[tokio::test]
 async fn vm_list_command_produces_expected_output_and_call() {
     let api = FakeVmApi::default().with_list_response(vec![
@@ -64,12 +199,179 @@ async fn vm_list_command_produces_expected_output_and_call() {
         &api,
     )
     .await
-    .expect("list command failed");
+    .expect("list command failed")
+    .into_lines();
 
-    assert_eq!(lines, vec!["agent-1 | Running", "agent-2 | Stopped"]);
+    assert_eq!(
+        lines,
+        vec![
+            "NAME     STATE    IPV4  RELEASE",
+            "agent-1  Running  -     -",
+            "agent-2  Stopped  -     -",
+        ]
+    );
     assert_eq!(api.calls(), vec!["list"]);
 }
 
+#[tokio::test]
+async fn vm_list_plain_output_keeps_pipe_separated_lines() {
+    let api = FakeVmApi::default().with_list_response(vec![
+        VmSummary::minimal("agent-1", "Running"),
+        VmSummary::minimal("agent-2", "Stopped"),
+    ]);
+    let lines = rendered_lines(&["safeclaw", "vm", "list", "--output", "plain"], &api).await;
+
+    assert_eq!(lines, vec!["agent-1 | Running", "agent-2 | Stopped"]);
+}
+
+fn unsorted_list() -> FakeVmApi {
+    FakeVmApi::default().with_list_response(vec![
+        VmSummary::minimal("charlie", "Running"),
+        VmSummary::minimal("alpha", "Stopped"),
+        VmSummary::minimal("bravo", "Running"),
+    ])
+}
+
+#[tokio::test]
+async fn vm_list_sort_orders_by_name_or_state() {
+    let api = unsorted_list();
+
+    assert_eq!(
+        rendered_lines(&["safepaw", "vm", "list", "-o", "plain"], &api).await,
+        vec!["charlie | Running", "alpha | Stopped", "bravo | Running"]
+    );
+    assert_eq!(
+        rendered_lines(
+            &["safepaw", "vm", "list", "-o", "plain", "--sort", "name"],
+            &api
+        )
+        .await,
+        vec!["alpha | Stopped", "bravo | Running", "charlie | Running"]
+    );
+    assert_eq!(
+        rendered_lines(
+            &["safepaw", "vm", "list", "-o", "plain", "--sort", "state"],
+            &api
+        )
+        .await,
+        vec!["bravo | Running", "charlie | Running", "alpha | Stopped"]
+    );
+}
+
+fn mixed_states() -> FakeVmApi {
+    FakeVmApi::default().with_list_response(vec![
+        VmSummary::minimal("alpha", "Running"),
+        VmSummary::minimal("bravo", "Stopped"),
+        VmSummary::minimal("charlie", "Suspended"),
+        VmSummary::minimal("delta", "Running"),
+    ])
+}
+
+#[tokio::test]
+async fn vm_list_state_filter_is_case_insensitive() {
+    let api = mixed_states();
+
+    assert_eq!(
+        rendered_lines(&["safepaw", "vm", "list", "--state", "RUNNING"], &api).await,
+        vec![
+            "NAME   STATE    IPV4  RELEASE",
+            "alpha  Running  -     -",
+            "delta  Running  -     -",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vm_list_repeated_state_flags_match_any_state() {
+    let api = mixed_states();
+
+    assert_eq!(
+        rendered_lines(
+            &[
+                "safepaw",
+                "vm",
+                "list",
+                "-o",
+                "plain",
+                "--state",
+                "stopped",
+                "--state",
+                "Suspended",
+            ],
+            &api
+        )
+        .await,
+        vec![
+            "# state: stopped, suspended",
+            "bravo | Stopped",
+            "charlie | Suspended"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vm_list_state_filter_can_match_nothing() {
+    let api = mixed_states();
+
+    assert_eq!(
+        rendered_lines(&["safepaw", "vm", "list", "--state", "deleted"], &api).await,
+        vec!["No VMs found"]
+    );
+}
+
+#[test]
+fn vm_list_rejects_unknown_states_with_the_valid_ones() {
+    let err = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "list", "--state", "sleeping"])
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains("sleeping"));
+    assert!(err.contains("running, stopped, suspended, starting, deleted"));
+}
+
+#[test]
+fn vm_list_sort_rejects_unknown_keys_and_watch() {
+    for args in [
+        &["safepaw", "vm", "list", "--sort", "ip"][..],
+        &["safepaw", "vm", "list", "--sort", "name", "--watch"][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err());
+    }
+}
+
+#[test]
+fn render_table_aligns_columns_to_widest_cell() {
+    let mut long = VmSummary::minimal("research-agent-long", "Running");
+    long.ipv4 = Some(vec!["10.0.0.12".to_owned()]);
+    long.release = Some("24.04 LTS".to_owned());
+
+    let lines = render_table(vec![long, VmSummary::minimal("a", "Stopped")]);
+
+    assert_eq!(
+        lines,
+        vec![
+            "NAME                 STATE    IPV4       RELEASE",
+            "research-agent-long  Running  10.0.0.12  24.04 LTS",
+            "a                    Stopped  -          -",
+        ]
+    );
+}
+
+#[test]
+fn render_table_truncates_long_ip_lists() {
+    let mut vm = VmSummary::minimal("agent-1", "Running");
+    vm.ipv4 = Some(vec![
+        "10.0.0.5".to_owned(),
+        "172.17.0.1".to_owned(),
+        "192.168.64.2".to_owned(),
+    ]);
+
+    let lines = render_table(vec![vm]);
+
+    assert_eq!(lines[1], "agent-1  Running  10.0.0.5 +2 more  -");
+}
+
 #[tokio::test]
 async fn vm_stop_command_produces_expected_output_and_call() {
     let api = FakeVmApi::default();
@@ -84,8 +386,522 @@ async fn vm_stop_command_produces_expected_output_and_call() {
         &api,
     )
     .await
-    .expect("stop command failed");
+    .expect("stop command failed")
+    .into_lines();
 
     assert_eq!(lines, vec!["VM 'agent-1' stopped successfully"]);
     assert_eq!(api.calls(), vec!["stop:agent-1"]);
 }
+
+#[tokio::test]
+async fn vm_start_if_needed_skips_running_vm() {
+    let api = FakeVmApi::default();
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "start", "agent-1", "--if-needed"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("start command failed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["VM 'agent-1' is already running"]);
+    assert_eq!(api.calls(), vec!["info:agent-1"]);
+}
+
+#[tokio::test]
+async fn vm_start_if_needed_starts_stopped_vm() {
+    let api =
+        FakeVmApi::default().with_info_response(VmStatusResponse::minimal("agent-1", "Stopped"));
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "start", "agent-1", "--if-needed"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("start command failed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["VM 'agent-1' started successfully"]);
+    assert_eq!(api.calls(), vec!["info:agent-1", "start:agent-1"]);
+}
+
+#[tokio::test]
+async fn vm_stop_if_needed_skips_stopped_vm_and_stops_running_vm() {
+    let stopped =
+        FakeVmApi::default().with_info_response(VmStatusResponse::minimal("agent-1", "Stopped"));
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "stop", "agent-1", "--if-needed"])
+        .expect("failed to parse CLI args");
+    let vm_matches = matches
+        .subcommand_matches("vm")
+        .expect("missing vm subcommand");
+
+    let lines = run_vm_subcommand(vm_matches, &stopped)
+        .await
+        .expect("stop command failed")
+        .into_lines();
+    assert_eq!(lines, vec!["VM 'agent-1' is already stopped"]);
+    assert_eq!(stopped.calls(), vec!["info:agent-1"]);
+
+    let running = FakeVmApi::default();
+    let lines = run_vm_subcommand(vm_matches, &running)
+        .await
+        .expect("stop command failed")
+        .into_lines();
+    assert_eq!(lines, vec!["VM 'agent-1' stopped successfully"]);
+    assert_eq!(running.calls(), vec!["info:agent-1", "stop:agent-1"]);
+}
+
+fn ready_status(name: &str, ip: &str) -> VmStatusResponse {
+    let mut status = VmStatusResponse::minimal(name, "Running");
+    status.ipv4 = Some(vec![ip.to_owned()]);
+    status
+}
+
+#[tokio::test(start_paused = true)]
+async fn vm_start_wait_polls_until_ready() {
+    let api = FakeVmApi::default()
+        .with_info_sequence(vec![
+            VmStatusResponse::minimal("agent-1", "Starting"),
+            VmStatusResponse::minimal("agent-1", "Running"),
+        ])
+        .with_info_response(ready_status("agent-1", "10.0.0.7"));
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "start", "agent-1", "--wait"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("start command failed")
+    .into_lines();
+
+    assert_eq!(
+        lines,
+        vec![
+            "VM 'agent-1' started successfully",
+            "VM 'agent-1' is ready at 10.0.0.7"
+        ]
+    );
+    assert_eq!(
+        api.calls(),
+        vec![
+            "start:agent-1",
+            "info:agent-1",
+            "info:agent-1",
+            "info:agent-1"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vm_stop_wait_reports_last_state_on_timeout() {
+    let api = FakeVmApi::default();
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "stop",
+            "agent-1",
+            "--wait",
+            "--timeout",
+            "0",
+        ])
+        .expect("failed to parse CLI args");
+
+    let err = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect_err("wait should time out");
+
+    assert_eq!(
+        err.to_string(),
+        "timed out after 0s waiting for VM 'agent-1' to reach Stopped (last state: Running)"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn vm_launch_wait_times_out_without_ipv4() {
+    let api = FakeVmApi::default();
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "launch",
+            "agent-1",
+            "--wait",
+            "--wait-timeout",
+            "10",
+        ])
+        .expect("failed to parse CLI args");
+
+    let err = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect_err("wait should time out");
+
+    assert_eq!(
+        err.to_string(),
+        "timed out after 10s waiting for VM 'agent-1' to be ready (last state: Running, no IPv4 address)"
+    );
+    // One poll every 2s over a 10s window, plus the final check.
+    let polls = api.calls().iter().filter(|c| *c == "info:agent-1").count();
+    assert_eq!(polls, 6);
+}
+
+#[test]
+fn vm_launch_wait_timeout_defaults_and_accepts_timeout_alias() {
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "launch", "agent-1", "--wait"])
+        .expect("failed to parse CLI args");
+    let launch = matches
+        .subcommand_matches("vm")
+        .and_then(|vm| vm.subcommand_matches("launch"))
+        .unwrap();
+    assert_eq!(launch.get_one::<u64>("wait-timeout"), Some(&300));
+
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "start",
+            "agent-1",
+            "--wait",
+            "--timeout",
+            "5",
+        ])
+        .expect("failed to parse CLI args");
+    let start = matches
+        .subcommand_matches("vm")
+        .and_then(|vm| vm.subcommand_matches("start"))
+        .unwrap();
+    assert_eq!(start.get_one::<u64>("wait-timeout"), Some(&5));
+}
+
+const RUNNING_INFO: &str = r#"{"errors":[],"info":{"agent-1":{"state":"Running"}}}"#;
+
+#[tokio::test]
+async fn vm_logs_command_runs_journalctl_in_vm() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(RUNNING_INFO),
+        CommandOutput::success("Oct 17 boot line\nOct 17 cloud-init finished\n"),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw", "vm", "logs", "agent-1", "--lines", "20", "--source", "journal",
+        ])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("logs command failed")
+    .into_lines();
+
+    assert_eq!(
+        lines,
+        vec!["Oct 17 boot line", "Oct 17 cloud-init finished"]
+    );
+    assert_eq!(
+        fake.calls()[1],
+        vec![
+            "multipass".to_owned(),
+            "exec".to_owned(),
+            "agent-1".to_owned(),
+            "--".to_owned(),
+            "journalctl".to_owned(),
+            "--no-pager".to_owned(),
+            "-n".to_owned(),
+            "20".to_owned()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vm_logs_command_tails_cloud_init_output_by_default() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(RUNNING_INFO),
+        CommandOutput::success("Cloud-init v. 24.1 finished\n"),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "logs", "agent-1"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("logs command failed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["Cloud-init v. 24.1 finished"]);
+    assert_eq!(
+        fake.calls()[1][4..],
+        ["tail", "-n", "100", "/var/log/cloud-init-output.log"]
+    );
+}
+
+#[tokio::test]
+async fn vm_exec_command_passes_trailing_args_without_stdin() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("ok\n")]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw", "vm", "exec", "agent-1", "--", "ls", "-la", "/tmp",
+        ])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("exec command failed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["ok"]);
+    assert_eq!(
+        fake.calls(),
+        vec![vec![
+            "multipass".to_owned(),
+            "exec".to_owned(),
+            "agent-1".to_owned(),
+            "--".to_owned(),
+            "ls".to_owned(),
+            "-la".to_owned(),
+            "/tmp".to_owned()
+        ]]
+    );
+    assert_eq!(fake.stdins(), vec![None]);
+}
+
+async fn run_json(args: &[&str], api: &FakeVmApi) -> serde_json::Value {
+    let lines = rendered_lines(args, api).await;
+    serde_json::from_str(&lines.join("\n")).expect("output should be JSON")
+}
+
+#[tokio::test]
+async fn vm_launch_command_json_output() {
+    let api = FakeVmApi::default();
+
+    let value = run_json(&["safeclaw", "vm", "-o", "json", "launch", "agent-1"], &api).await;
+
+    assert_eq!(
+        value,
+        json!({"ok": true, "action": "launch", "name": "agent-1"})
+    );
+    assert_eq!(api.calls(), vec!["launch:agent-1"]);
+}
+
+#[tokio::test]
+async fn vm_info_command_json_output() {
+    let api = FakeVmApi::default();
+
+    let value = run_json(
+        &["safeclaw", "vm", "info", "agent-1", "--output", "json"],
+        &api,
+    )
+    .await;
+
+    assert_eq!(value, json!({"name": "agent-1", "state": "Running"}));
+    assert_eq!(api.calls(), vec!["info:agent-1"]);
+}
+
+#[tokio::test]
+async fn vm_list_command_json_output() {
+    let api = FakeVmApi::default().with_list_response(vec![
+        VmSummary::minimal("agent-1", "Running"),
+        VmSummary::minimal("agent-2", "Stopped"),
+    ]);
+
+    let value = run_json(&["safeclaw", "vm", "list", "-o", "json"], &api).await;
+
+    assert_eq!(
+        value,
+        json!([
+            {"name": "agent-1", "state": "Running"},
+            {"name": "agent-2", "state": "Stopped"}
+        ])
+    );
+    assert_eq!(api.calls(), vec!["list"]);
+}
+
+#[tokio::test]
+async fn vm_stop_command_json_output() {
+    let api = FakeVmApi::default();
+
+    let value = run_json(&["safeclaw", "vm", "stop", "agent-1", "-o", "json"], &api).await;
+
+    assert_eq!(
+        value,
+        json!({"ok": true, "action": "stop", "name": "agent-1"})
+    );
+    assert_eq!(api.calls(), vec!["stop:agent-1"]);
+}
+
+#[tokio::test]
+async fn vm_launch_passes_repeated_network_flags() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "launch",
+            "agent-1",
+            "--network",
+            "en0",
+            "--network",
+            "bridge0",
+        ])
+        .expect("failed to parse CLI args");
+
+    run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("launch command failed");
+
+    assert_eq!(
+        fake.calls()[0][4..],
+        ["--network", "en0", "--network", "bridge0"].map(String::from)
+    );
+}
+
+#[tokio::test]
+async fn vm_networks_lists_host_networks() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        r#"{"list":[{"description":"Wi-Fi","name":"en0","type":"wifi"}]}"#,
+    )]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "networks"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("networks command failed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["en0 | wifi | Wi-Fi"]);
+}
+
+#[tokio::test]
+async fn vm_images_lists_images_and_blueprints() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        include_str!("fixtures/multipass_find.json"),
+    )]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "images"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("images command failed")
+    .into_lines();
+
+    assert_eq!(
+        lines,
+        vec![
+            "22.04 | image | 20240821 | Ubuntu 22.04 LTS",
+            "24.04 | image | 20240821 | Ubuntu 24.04 LTS",
+            "docker | blueprint | 0.4 | ",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn list_jsonl_emits_one_compact_object_per_vm() {
+    let api = FakeVmApi::default().with_list_response(vec![
+        VmSummary::minimal("agent-1", "Running"),
+        VmSummary::minimal("agent-2", "Stopped"),
+        VmSummary::minimal("agent-3", "Suspended"),
+    ]);
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "-o", "jsonl", "list"])
+        .expect("failed to parse CLI args");
+    let vm_matches = matches.subcommand_matches("vm").unwrap();
+
+    let lines = run_vm_subcommand(vm_matches, &api)
+        .await
+        .expect("list command failed")
+        .render(&RenderOptions::from_matches(vm_matches));
+
+    assert_eq!(lines.len(), 3);
+    let names: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let value: serde_json::Value =
+                serde_json::from_str(line).expect("each line is a JSON document");
+            value["name"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    assert_eq!(names, vec!["agent-1", "agent-2", "agent-3"]);
+    assert_eq!(lines[0], r#"{"name":"agent-1","state":"Running"}"#);
+}
+
+#[test]
+fn jsonl_keeps_single_documents_on_one_line() {
+    let jsonl = RenderOptions {
+        format: OutputFormat::JsonLines,
+        ..RenderOptions::default()
+    };
+
+    assert!(CommandResult::Summaries(vec![]).render(&jsonl).is_empty());
+    assert_eq!(
+        CommandResult::Json(json!({"ok": true, "action": "start", "name": "a"})).render(&jsonl),
+        vec![r#"{"action":"start","name":"a","ok":true}"#]
+    );
+    assert_eq!(
+        CommandResult::Info(Box::new(VmStatusResponse::minimal("a", "Running"))).render(&jsonl),
+        vec![r#"{"name":"a","state":"Running"}"#]
+    );
+}
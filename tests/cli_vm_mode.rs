@@ -1,4 +1,4 @@
-use safepaw::cli::{VmMode, build_cli, resolve_vm_mode};
+use safepaw::cli::{VmMode, build_cli, resolve_ssh_config, resolve_vm_mode};
 
 #[test]
 fn vm_mode_defaults_to_local() {
@@ -27,3 +27,44 @@ fn vm_mode_can_be_set_to_network() {
 
     assert_eq!(mode, VmMode::Network);
 }
+
+#[test]
+fn vm_mode_remote_builds_ssh_config() {
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "--mode",
+            "remote",
+            "--ssh-host",
+            "lab.local",
+            "--ssh-user",
+            "ops",
+            "list",
+        ])
+        .expect("failed to parse CLI args");
+
+    let vm_matches = matches
+        .subcommand_matches("vm")
+        .expect("missing vm subcommand");
+    let config = resolve_ssh_config(vm_matches).expect("failed to resolve ssh config");
+
+    assert_eq!(resolve_vm_mode(vm_matches).unwrap(), VmMode::Remote);
+    assert_eq!(config.host, "lab.local");
+    assert_eq!(config.user.as_deref(), Some("ops"));
+    assert_eq!(config.connect_timeout.as_secs(), 10);
+}
+
+#[test]
+fn vm_mode_remote_requires_ssh_host() {
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "--mode", "remote", "list"])
+        .expect("failed to parse CLI args");
+
+    let vm_matches = matches
+        .subcommand_matches("vm")
+        .expect("missing vm subcommand");
+    let err = resolve_ssh_config(vm_matches).expect_err("ssh host should be required");
+
+    assert!(err.to_string().contains("--ssh-host"));
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use std::time::Duration;
+
+use common::FakeVmApi;
+use safepaw::cli::{
+    RenderSink, SizeFormat, TopSort, WATCH_HIGHLIGHT, build_cli, watch_vm_info, watch_vm_list,
+    watch_vm_top,
+};
+use safepaw::vm::{VmStatusResponse, VmSummary};
+use tokio_util::sync::CancellationToken;
+
+/// Records frames and stops the watch after `limit` of them.
+struct RecordingSink {
+    frames: Vec<Vec<String>>,
+    limit: usize,
+    cancel: CancellationToken,
+}
+
+impl RecordingSink {
+    fn new(limit: usize, cancel: CancellationToken) -> Self {
+        Self {
+            frames: Vec::new(),
+            limit,
+            cancel,
+        }
+    }
+}
+
+impl RenderSink for RecordingSink {
+    fn render(&mut self, frame: &[String]) -> anyhow::Result<()> {
+        self.frames.push(frame.to_vec());
+        if self.frames.len() >= self.limit {
+            self.cancel.cancel();
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn watch_vm_info_redraws_until_cancelled() {
+    let api = FakeVmApi::default()
+        .with_info_sequence(vec![VmStatusResponse::minimal("agent-1", "Starting")])
+        .with_info_response(VmStatusResponse::minimal("agent-1", "Running"));
+    let cancel = CancellationToken::new();
+    let mut sink = RecordingSink::new(2, cancel.clone());
+
+    watch_vm_info(
+        &api,
+        "agent-1",
+        SizeFormat::Human,
+        Duration::from_secs(2),
+        &mut sink,
+        &cancel,
+    )
+    .await
+    .expect("watch should stop cleanly");
+
+    assert_eq!(sink.frames.len(), 2);
+    assert_eq!(sink.frames[0][..2], ["Name:  agent-1", "State: Starting"]);
+    assert_eq!(sink.frames[1][..2], ["Name:  agent-1", "State: Running"]);
+    assert_eq!(api.calls(), vec!["info:agent-1", "info:agent-1"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn watch_vm_top_redraws_from_list_and_info() {
+    let api = FakeVmApi::default()
+        .with_list_response(vec![
+            VmSummary::minimal("agent-1", "Running"),
+            VmSummary::minimal("agent-2", "Running"),
+        ])
+        .with_info_response(VmStatusResponse::minimal("agent-1", "Running"));
+    let cancel = CancellationToken::new();
+    let mut sink = RecordingSink::new(2, cancel.clone());
+
+    watch_vm_top(
+        &api,
+        TopSort::Memory,
+        Duration::from_secs(2),
+        &mut sink,
+        &cancel,
+    )
+    .await
+    .expect("watch should stop cleanly");
+
+    assert_eq!(sink.frames.len(), 2);
+    assert_eq!(
+        sink.frames[0],
+        vec![
+            "NAME     STATE    CPUS  MEM  DISK",
+            "agent-1  Running  -     -    -",
+            "agent-2  Running  -     -    -",
+        ]
+    );
+    assert_eq!(
+        api.calls(),
+        vec![
+            "list",
+            "info:agent-1",
+            "info:agent-2",
+            "list",
+            "info:agent-1",
+            "info:agent-2",
+        ]
+    );
+}
+
+#[test]
+fn watch_interval_defaults_to_two_seconds_and_requires_watch() {
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "info", "agent-1", "--watch"])
+        .unwrap();
+    let info = matches
+        .subcommand_matches("vm")
+        .and_then(|vm| vm.subcommand_matches("info"))
+        .unwrap();
+    assert_eq!(info.get_one::<u64>("interval"), Some(&2));
+
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "info", "agent-1", "--interval", "5"])
+            .is_err()
+    );
+    assert!(
+        build_cli()
+            .try_get_matches_from([
+                "safepaw",
+                "vm",
+                "info",
+                "agent-1",
+                "--watch",
+                "--interval",
+                "0"
+            ])
+            .is_err()
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn watch_vm_list_highlights_changes_and_marks_stale_data() {
+    let api = FakeVmApi::default().with_list_sequence(vec![
+        Ok(vec![
+            VmSummary::minimal("agent-1", "Starting"),
+            VmSummary::minimal("agent-2", "Running"),
+        ]),
+        Ok(vec![
+            VmSummary::minimal("agent-1", "Running"),
+            VmSummary::minimal("agent-2", "Running"),
+        ]),
+        Err("multipass socket unavailable".to_owned()),
+    ]);
+    let cancel = CancellationToken::new();
+    let mut sink = RecordingSink::new(3, cancel.clone());
+
+    watch_vm_list(&api, &[], Duration::from_secs(2), &mut sink, &cancel)
+        .await
+        .expect("watch should stop cleanly");
+
+    let [first, second, third] = &sink.frames[..] else {
+        panic!("expected three frames, got {:?}", sink.frames);
+    };
+
+    // Nothing to compare against on the first poll
+    assert!(first.iter().all(|line| !line.contains(WATCH_HIGHLIGHT)));
+    assert_eq!(first[0], "NAME     STATE     IPV4  RELEASE");
+
+    assert!(second[1].starts_with(WATCH_HIGHLIGHT));
+    assert!(second[1].contains("agent-1  Running"));
+    assert!(!second[2].contains(WATCH_HIGHLIGHT));
+
+    // The failed poll keeps the last table and says it is stale
+    assert!(third[1].contains("agent-1  Running"));
+    assert!(
+        third[1..3]
+            .iter()
+            .all(|line| !line.contains(WATCH_HIGHLIGHT))
+    );
+    assert_eq!(
+        third.last().unwrap(),
+        "[stale] Failed to list VMs: multipass socket unavailable"
+    );
+}
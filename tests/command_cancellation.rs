@@ -0,0 +1,254 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use safepaw::vm::{
+    CommandExecutor, CommandOutput, LaunchSpec, Multipass, MultipassCli, TokioCommandExecutor,
+    VmError, read_capped,
+};
+use tokio_util::sync::CancellationToken;
+
+// ============================================================================
+// HangingExecutor - runs until cancelled, recording that a kill was attempted
+// ============================================================================
+
+#[derive(Clone, Default)]
+struct HangingExecutor {
+    killed: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl CommandExecutor for HangingExecutor {
+    async fn run_with_stdin(
+        &self,
+        program: &str,
+        _args: &[String],
+        _stdin: Option<&[u8]>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        cancel.cancelled().await;
+        self.killed.store(true, Ordering::SeqCst);
+        anyhow::bail!("{program} was killed after cancellation")
+    }
+}
+
+#[tokio::test]
+async fn tokio_executor_kills_child_when_token_is_cancelled() {
+    let cancel = CancellationToken::new();
+    let trigger = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        trigger.cancel();
+    });
+
+    let started = Instant::now();
+    let result = TokioCommandExecutor::new()
+        .run("sleep", &["30".to_owned()], &cancel)
+        .await;
+
+    let err = result.expect_err("cancelled command should fail");
+    assert!(err.to_string().contains("killed"));
+    assert!(
+        started.elapsed() < Duration::from_secs(10),
+        "child should be killed instead of running to completion"
+    );
+}
+
+#[tokio::test]
+async fn tokio_executor_captures_output_when_not_cancelled() {
+    let output = TokioCommandExecutor::new()
+        .run(
+            "sh",
+            &["-c".to_owned(), "echo out; echo err >&2; exit 3".to_owned()],
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.status_code, 3);
+    assert_eq!(output.stdout, "out\n");
+    assert_eq!(output.stderr, "err\n");
+}
+
+#[tokio::test]
+async fn multipass_launch_returns_cancelled_error_and_kills_child() {
+    let executor = HangingExecutor::default();
+    let multipass = MultipassCli::new(executor.clone());
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+
+    let err = multipass
+        .launch(&LaunchSpec::new("agent-1"), &cancel)
+        .await
+        .expect_err("launch should be cancelled");
+
+    assert!(matches!(err, VmError::Cancelled { action: "launch" }));
+    assert!(executor.killed.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn tokio_executor_pipes_stdin_to_child() {
+    let output = TokioCommandExecutor::new()
+        .run_with_stdin(
+            "cat",
+            &[],
+            Some(b"hello from stdin\n"),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.status_code, 0);
+    assert_eq!(output.stdout, "hello from stdin\n");
+}
+
+#[tokio::test]
+async fn read_capped_keeps_the_head_of_large_output() {
+    let large = vec![b'x'; 1024 * 1024];
+
+    let output = read_capped(&mut large.as_slice(), 1000)
+        .await
+        .expect("in-memory read should work");
+
+    assert_eq!(output.bytes.len(), 1000);
+    assert_eq!(output.dropped, 1024 * 1024 - 1000);
+    let text = output.into_text();
+    assert!(text.starts_with("xxxx"));
+    assert!(text.ends_with("\n... [truncated 1047576 bytes]"));
+}
+
+#[tokio::test]
+async fn read_capped_leaves_small_output_untouched() {
+    let output = read_capped(&mut b"short\n".as_slice(), 1000).await.unwrap();
+
+    assert_eq!(output.dropped, 0);
+    assert_eq!(output.into_text(), "short\n");
+}
+
+#[tokio::test]
+async fn tokio_executor_truncates_output_over_the_cap() {
+    let output = TokioCommandExecutor::new()
+        .with_max_capture(10)
+        .run(
+            "sh",
+            &[
+                "-c".to_owned(),
+                "printf 0123456789abcdef; echo err >&2".to_owned(),
+            ],
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("command should run");
+
+    assert!(output.truncated);
+    assert_eq!(output.stdout, "0123456789\n... [truncated 6 bytes]");
+    assert_eq!(output.stderr, "err\n");
+}
+
+#[tokio::test]
+async fn tokio_executor_streams_lines_as_they_arrive() {
+    let lines = std::sync::Mutex::new(Vec::new());
+
+    let output = TokioCommandExecutor::new()
+        .run_streaming(
+            "sh",
+            &[
+                "-c".to_owned(),
+                "echo one; echo err >&2; printf 'two\\r\\nthree'".to_owned(),
+            ],
+            &|line: &str| lines.lock().unwrap().push(line.to_owned()),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("command should run");
+
+    assert_eq!(lines.into_inner().unwrap(), vec!["one", "two", "three"]);
+    assert_eq!(output.stdout, "");
+    assert_eq!(output.stderr, "err\n");
+}
+
+#[tokio::test]
+async fn tokio_executor_kills_a_streaming_child_when_cancelled() {
+    let cancel = CancellationToken::new();
+    let trigger = cancel.clone();
+    let lines = std::sync::Mutex::new(Vec::new());
+
+    let result = TokioCommandExecutor::new()
+        .run_streaming(
+            "sh",
+            &["-c".to_owned(), "echo ready; sleep 30".to_owned()],
+            &|line: &str| {
+                lines.lock().unwrap().push(line.to_owned());
+                trigger.cancel();
+            },
+            &cancel,
+        )
+        .await;
+
+    assert!(
+        result
+            .expect_err("cancelled")
+            .to_string()
+            .contains("killed")
+    );
+    assert_eq!(lines.into_inner().unwrap(), vec!["ready"]);
+}
+
+/// Whether `pid` has exited. A killed child may linger as a zombie until
+/// tokio reaps it, which counts as exited.
+#[cfg(target_os = "linux")]
+fn process_exited(pid: &str) -> bool {
+    match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        Ok(stat) => stat
+            .rsplit_once(')')
+            .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn dropping_the_run_future_kills_the_child() {
+    let temp_dir = tempfile::tempdir().expect("temp dir should be created");
+    let pid_file = temp_dir.path().join("child.pid");
+    let script = format!("echo $$ > {}; exec sleep 30", pid_file.display());
+    let executor = TokioCommandExecutor::new();
+    let cancel = CancellationToken::new();
+    let args = ["-c".to_owned(), script];
+
+    let run = executor.run("sh", &args, &cancel);
+    let pid = {
+        tokio::pin!(run);
+        let started = Instant::now();
+        loop {
+            tokio::select! {
+                _ = &mut run => panic!("sleep should not finish"),
+                _ = tokio::time::sleep(Duration::from_millis(20)) => {}
+            }
+            if let Ok(pid) = std::fs::read_to_string(&pid_file)
+                && !pid.trim().is_empty()
+            {
+                break pid.trim().to_owned();
+            }
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "child never started"
+            );
+        }
+        // `run` is dropped here, without the token ever being cancelled.
+    };
+
+    let started = Instant::now();
+    while !process_exited(&pid) {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "child {pid} kept running after its future was dropped"
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use common::FakeExecutor;
+use safepaw::redact::Redactor;
+use safepaw::vm::{CommandOutput, Multipass, MultipassCli, RetryConfig, VmError};
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+// ============================================================================
+// Redactor rules
+// ============================================================================
+
+#[test]
+fn redacts_value_after_sensitive_flag() {
+    let redactor = Redactor::default();
+
+    assert_eq!(
+        redactor.redact_args(&strings(&[
+            "launch",
+            "--cloud-init",
+            "users: [{passwd: hunter2}]",
+            "--name",
+            "agent-1"
+        ])),
+        strings(&["launch", "--cloud-init", "***", "--name", "agent-1"])
+    );
+    assert_eq!(
+        redactor.redact_args(&strings(&["launch", "--cloud-init=inline-data"])),
+        strings(&["launch", "--cloud-init=***"])
+    );
+}
+
+#[test]
+fn redacts_patterns_and_env_assignments_in_exec() {
+    let redactor = Redactor::default();
+
+    assert_eq!(
+        redactor.redact_args(&strings(&[
+            "exec",
+            "agent-1",
+            "--",
+            "env",
+            "OPENROUTER_API_KEY=sk-123",
+            "curl",
+            "https://host/hook?Token=abc&x=1",
+        ])),
+        strings(&[
+            "exec",
+            "agent-1",
+            "--",
+            "env",
+            "OPENROUTER_API_KEY=***",
+            "curl",
+            "https://host/hook?Token=***&x=1",
+        ])
+    );
+}
+
+#[test]
+fn custom_flags_and_patterns_extend_defaults() {
+    let redactor = Redactor::default()
+        .with_flag("--auth")
+        .with_pattern("session=");
+
+    assert_eq!(
+        redactor.redact_args(&strings(&[
+            "set",
+            "--auth",
+            "abc",
+            "session=xyz",
+            "name=ok"
+        ])),
+        strings(&["set", "--auth", "***", "session=***", "name=ok"])
+    );
+}
+
+// ============================================================================
+// MultipassCli integration: logs and error messages
+// ============================================================================
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn multipass_logs_and_errors_do_not_leak_secrets() {
+    let logs = SharedBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let fake = FakeExecutor::new(vec![CommandOutput {
+        status_code: 1,
+        stdout: String::new(),
+        stderr: "cannot connect to the multipass socket: 'API_TOKEN=s3cr3t' rejected, password=hunter2\n"
+            .to_owned(),
+        truncated: false,
+    }]);
+    let multipass = MultipassCli::new_with_retry(fake.clone(), RetryConfig::disabled());
+
+    let err = multipass
+        .exec(
+            "agent-1",
+            &strings(&["env", "API_TOKEN=s3cr3t", "deploy", "--password=hunter2"]),
+        )
+        .await
+        .expect_err("exec should fail");
+
+    // The executor still sees the real values.
+    assert_eq!(fake.calls()[0][5], "API_TOKEN=s3cr3t");
+
+    match err {
+        VmError::CommandFailed { stderr, .. } => {
+            assert!(!stderr.contains("s3cr3t"), "stderr leaked: {stderr}");
+            assert!(!stderr.contains("hunter2"), "stderr leaked: {stderr}");
+            assert!(stderr.contains("***"));
+        }
+        other => panic!("expected command failure, got {other:?}"),
+    }
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    assert!(logs.contains("running multipass command"));
+    assert!(logs.contains("API_TOKEN=***"));
+    assert!(!logs.contains("s3cr3t"), "logs leaked: {logs}");
+    assert!(!logs.contains("hunter2"), "logs leaked: {logs}");
+}
@@ -4,13 +4,26 @@
 
 use std::{
     collections::VecDeque,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
 };
 
 use async_trait::async_trait;
+use axum::Router;
+use safepaw::agent::LocalAgentManager;
+use safepaw::cli::Confirm;
+use safepaw::db::SafePawDb;
+use safepaw::doctor::MultipassVersions;
+use safepaw::server::{AppState, create_api_router};
 use safepaw::vm::{
-    CommandExecutor, CommandOutput, Multipass, MultipassCli, VmApi, VmStatusResponse, VmSummary,
+    CommandExecutor, CommandOutput, ImageInfo, LaunchSpec, LineSink, Multipass, MultipassCli,
+    RetryConfig, VmApi, VmListing, VmStatusResponse, VmSummary,
 };
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
 
 // ============================================================================
 // FakeExecutor - Mock CommandExecutor for testing
@@ -19,6 +32,7 @@ use safepaw::vm::{
 #[derive(Clone)]
 pub struct FakeExecutor {
     calls: Arc<Mutex<Vec<Vec<String>>>>,
+    stdins: Arc<Mutex<Vec<Option<Vec<u8>>>>>,
     outputs: Arc<Mutex<VecDeque<CommandOutput>>>,
 }
 
@@ -26,6 +40,7 @@ impl FakeExecutor {
     pub fn new(outputs: Vec<CommandOutput>) -> Self {
         Self {
             calls: Arc::new(Mutex::new(Vec::new())),
+            stdins: Arc::new(Mutex::new(Vec::new())),
             outputs: Arc::new(Mutex::new(outputs.into())),
         }
     }
@@ -33,16 +48,31 @@ impl FakeExecutor {
     pub fn calls(&self) -> Vec<Vec<String>> {
         self.calls.lock().expect("poisoned calls mutex").clone()
     }
+
+    /// Stdin passed to each call, in call order.
+    pub fn stdins(&self) -> Vec<Option<Vec<u8>>> {
+        self.stdins.lock().expect("poisoned stdins mutex").clone()
+    }
 }
 
 #[async_trait]
 impl CommandExecutor for FakeExecutor {
-    async fn run(&self, program: &str, args: &[String]) -> anyhow::Result<CommandOutput> {
+    async fn run_with_stdin(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+        _cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
         let mut call = Vec::with_capacity(args.len() + 1);
         call.push(program.to_owned());
         call.extend(args.iter().cloned());
 
         self.calls.lock().expect("poisoned calls mutex").push(call);
+        self.stdins
+            .lock()
+            .expect("poisoned stdins mutex")
+            .push(stdin.map(<[u8]>::to_vec));
 
         self.outputs
             .lock()
@@ -50,6 +80,14 @@ impl CommandExecutor for FakeExecutor {
             .pop_front()
             .ok_or_else(|| anyhow::anyhow!("no fake output available"))
     }
+
+    /// Records the call like any other and returns the next output's status.
+    async fn run_interactive(&self, program: &str, args: &[String]) -> anyhow::Result<i32> {
+        let output = self
+            .run_with_stdin(program, args, None, &CancellationToken::new())
+            .await?;
+        Ok(output.status_code)
+    }
 }
 
 // ============================================================================
@@ -71,10 +109,13 @@ struct FakeMultipassResponses {
     stop: VecDeque<Result<(), safepaw::vm::VmError>>,
     restart: VecDeque<Result<(), safepaw::vm::VmError>>,
     delete: VecDeque<Result<(), safepaw::vm::VmError>>,
+    clone: VecDeque<Result<(), safepaw::vm::VmError>>,
     info: VecDeque<Result<VmStatusResponse, safepaw::vm::VmError>>,
     list: VecDeque<Result<Vec<VmSummary>, safepaw::vm::VmError>>,
     exec: VecDeque<Result<CommandOutput, safepaw::vm::VmError>>,
     transfer: VecDeque<Result<(), safepaw::vm::VmError>>,
+    version: VecDeque<Result<MultipassVersions, safepaw::vm::VmError>>,
+    find: VecDeque<Result<Vec<ImageInfo>, safepaw::vm::VmError>>,
 }
 
 impl Default for FakeMultipass {
@@ -111,6 +152,21 @@ impl FakeMultipass {
         self
     }
 
+    pub fn with_stop_response(self, response: Result<(), safepaw::vm::VmError>) -> Self {
+        self.responses.lock().unwrap().stop.push_back(response);
+        self
+    }
+
+    pub fn with_delete_response(self, response: Result<(), safepaw::vm::VmError>) -> Self {
+        self.responses.lock().unwrap().delete.push_back(response);
+        self
+    }
+
+    pub fn with_clone_response(self, response: Result<(), safepaw::vm::VmError>) -> Self {
+        self.responses.lock().unwrap().clone.push_back(response);
+        self
+    }
+
     pub fn with_info_response(
         self,
         response: Result<VmStatusResponse, safepaw::vm::VmError>,
@@ -137,6 +193,22 @@ impl FakeMultipass {
         self
     }
 
+    pub fn with_version_response(
+        self,
+        response: Result<MultipassVersions, safepaw::vm::VmError>,
+    ) -> Self {
+        self.responses.lock().unwrap().version.push_back(response);
+        self
+    }
+
+    pub fn with_find_response(
+        self,
+        response: Result<Vec<ImageInfo>, safepaw::vm::VmError>,
+    ) -> Self {
+        self.responses.lock().unwrap().find.push_back(response);
+        self
+    }
+
     pub fn calls(&self) -> Vec<String> {
         self.calls.lock().unwrap().clone()
     }
@@ -148,8 +220,12 @@ impl FakeMultipass {
 
 #[async_trait]
 impl Multipass for FakeMultipass {
-    async fn launch(&self, name: &str) -> Result<(), safepaw::vm::VmError> {
-        self.record_call(format!("launch:{}", name));
+    async fn launch(
+        &self,
+        spec: &LaunchSpec,
+        _cancel: &CancellationToken,
+    ) -> Result<(), safepaw::vm::VmError> {
+        self.record_call(format!("launch:{}", spec.name));
         self.responses
             .lock()
             .unwrap()
@@ -168,8 +244,16 @@ impl Multipass for FakeMultipass {
             .unwrap_or(Ok(()))
     }
 
-    async fn stop(&self, name: &str) -> Result<(), safepaw::vm::VmError> {
-        self.record_call(format!("stop:{}", name));
+    async fn stop(
+        &self,
+        name: &str,
+        opts: &safepaw::vm::StopOptions,
+    ) -> Result<(), safepaw::vm::VmError> {
+        if opts.force {
+            self.record_call(format!("stop:{}:force", name));
+        } else {
+            self.record_call(format!("stop:{}", name));
+        }
         self.responses
             .lock()
             .unwrap()
@@ -198,6 +282,30 @@ impl Multipass for FakeMultipass {
             .unwrap_or(Ok(()))
     }
 
+    async fn delete_many(
+        &self,
+        names: &[String],
+        _purge: bool,
+    ) -> Result<(), safepaw::vm::VmError> {
+        self.record_call(format!("delete:{}", names.join(",")));
+        self.responses
+            .lock()
+            .unwrap()
+            .delete
+            .pop_front()
+            .unwrap_or(Ok(()))
+    }
+
+    async fn clone_vm(&self, source: &str, destination: &str) -> Result<(), safepaw::vm::VmError> {
+        self.record_call(format!("clone:{}:{}", source, destination));
+        self.responses
+            .lock()
+            .unwrap()
+            .clone
+            .pop_front()
+            .unwrap_or(Ok(()))
+    }
+
     async fn info(&self, name: &str) -> Result<VmStatusResponse, safepaw::vm::VmError> {
         self.record_call(format!("info:{}", name));
         self.responses
@@ -244,6 +352,7 @@ impl Multipass for FakeMultipass {
         _name: &str,
         _source: &str,
         _destination: &str,
+        _cancel: &CancellationToken,
     ) -> Result<(), safepaw::vm::VmError> {
         self.responses
             .lock()
@@ -252,12 +361,40 @@ impl Multipass for FakeMultipass {
             .pop_front()
             .unwrap_or(Ok(()))
     }
+
+    async fn version(&self) -> Result<MultipassVersions, safepaw::vm::VmError> {
+        self.record_call("version".to_owned());
+        self.responses
+            .lock()
+            .unwrap()
+            .version
+            .pop_front()
+            .unwrap_or_else(|| {
+                Ok(MultipassVersions {
+                    client: Some("1.14.0".to_owned()),
+                    daemon: Some("1.14.0".to_owned()),
+                })
+            })
+    }
+
+    async fn find(&self) -> Result<Vec<ImageInfo>, safepaw::vm::VmError> {
+        self.record_call("find".to_owned());
+        self.responses
+            .lock()
+            .unwrap()
+            .find
+            .pop_front()
+            .unwrap_or(Ok(vec![]))
+    }
 }
 
 // ============================================================================
 // FakeVmApi - Mock VmApi trait for testing
 // ============================================================================
 
+/// A scripted `list` result; `Err` holds the error message.
+type ListResult = Result<Vec<VmSummary>, String>;
+
 #[derive(Clone)]
 pub struct FakeVmApi {
     calls: Arc<Mutex<Vec<String>>>,
@@ -265,7 +402,16 @@ pub struct FakeVmApi {
     exec_responses: Arc<Mutex<VecDeque<anyhow::Result<CommandOutput>>>>,
     transfer_responses: Arc<Mutex<VecDeque<anyhow::Result<()>>>>,
     info_response: VmStatusResponse,
+    info_sequence: Arc<Mutex<VecDeque<VmStatusResponse>>>,
     list_response: Vec<VmSummary>,
+    list_error: Option<String>,
+    list_sequence: Arc<Mutex<VecDeque<ListResult>>>,
+    list_warnings: Vec<String>,
+    launch_delay: Duration,
+    launch_failures: Vec<String>,
+    launch_progress: Vec<String>,
+    launches_in_flight: Arc<AtomicUsize>,
+    max_launches_in_flight: Arc<AtomicUsize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -288,10 +434,42 @@ impl FakeVmApi {
             exec_responses: Arc::new(Mutex::new(VecDeque::new())),
             transfer_responses: Arc::new(Mutex::new(VecDeque::new())),
             info_response: VmStatusResponse::minimal("test-vm", "Running"),
+            info_sequence: Arc::new(Mutex::new(VecDeque::new())),
             list_response: vec![],
+            list_error: None,
+            list_sequence: Arc::new(Mutex::new(VecDeque::new())),
+            list_warnings: vec![],
+            launch_delay: Duration::ZERO,
+            launch_failures: vec![],
+            launch_progress: vec![],
+            launches_in_flight: Arc::new(AtomicUsize::new(0)),
+            max_launches_in_flight: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Makes every `launch` take `delay` so concurrent launches overlap.
+    pub fn with_launch_delay(mut self, delay: Duration) -> Self {
+        self.launch_delay = delay;
+        self
+    }
+
+    /// Lines `launch_with_progress` reports before launching.
+    pub fn with_launch_progress(mut self, lines: &[&str]) -> Self {
+        self.launch_progress = lines.iter().map(|line| line.to_string()).collect();
+        self
+    }
+
+    /// Makes launching `name` fail.
+    pub fn with_launch_failure(mut self, name: impl Into<String>) -> Self {
+        self.launch_failures.push(name.into());
+        self
+    }
+
+    /// The most launches that were ever running at the same time.
+    pub fn max_concurrent_launches(&self) -> usize {
+        self.max_launches_in_flight.load(Ordering::SeqCst)
+    }
+
     pub fn with_exec_response(self, response: anyhow::Result<CommandOutput>) -> Self {
         self.exec_responses.lock().unwrap().push_back(response);
         self
@@ -307,11 +485,37 @@ impl FakeVmApi {
         self
     }
 
+    /// Responses returned by successive `info` calls before falling back to
+    /// the fixed `info_response`.
+    pub fn with_info_sequence(self, responses: Vec<VmStatusResponse>) -> Self {
+        self.info_sequence.lock().unwrap().extend(responses);
+        self
+    }
+
     pub fn with_list_response(mut self, response: Vec<VmSummary>) -> Self {
         self.list_response = response;
         self
     }
 
+    /// Makes every `list` call fail with `message`.
+    /// Results returned by successive `list` calls (`Err` holds the error
+    /// message) before falling back to the fixed response.
+    pub fn with_list_sequence(self, responses: Vec<ListResult>) -> Self {
+        self.list_sequence.lock().unwrap().extend(responses);
+        self
+    }
+
+    pub fn with_list_error(mut self, message: impl Into<String>) -> Self {
+        self.list_error = Some(message.into());
+        self
+    }
+
+    /// Errors `list_with_warnings` reports next to the listed VMs.
+    pub fn with_list_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.list_warnings = warnings;
+        self
+    }
+
     pub fn calls(&self) -> Vec<String> {
         self.calls.lock().unwrap().clone()
     }
@@ -327,17 +531,45 @@ impl FakeVmApi {
 
 #[async_trait]
 impl VmApi for FakeVmApi {
-    async fn launch(&self, name: &str) -> anyhow::Result<()> {
-        self.record_call(format!("launch:{}", name));
+    async fn launch(&self, spec: &LaunchSpec, cancel: &CancellationToken) -> anyhow::Result<()> {
+        self.record_call(format!("launch:{}", spec.name));
+        let in_flight = self.launches_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_launches_in_flight
+            .fetch_max(in_flight, Ordering::SeqCst);
+        // Biased so a zero delay wins over an already cancelled token.
+        let cancelled = tokio::select! {
+            biased;
+            _ = tokio::time::sleep(self.launch_delay) => false,
+            _ = cancel.cancelled() => true,
+        };
+        self.launches_in_flight.fetch_sub(1, Ordering::SeqCst);
+        if cancelled {
+            anyhow::bail!("launch of '{}' was cancelled", spec.name);
+        }
+        if self.launch_failures.contains(&spec.name) {
+            anyhow::bail!("launch of '{}' failed", spec.name);
+        }
         Ok(())
     }
 
+    async fn launch_with_progress(
+        &self,
+        spec: &LaunchSpec,
+        on_progress: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        self.launch_progress
+            .iter()
+            .for_each(|line| on_progress(line));
+        self.launch(spec, cancel).await
+    }
+
     async fn start(&self, name: &str) -> anyhow::Result<()> {
         self.record_call(format!("start:{}", name));
         Ok(())
     }
 
-    async fn stop(&self, name: &str) -> anyhow::Result<()> {
+    async fn stop(&self, name: &str, _opts: &safepaw::vm::StopOptions) -> anyhow::Result<()> {
         self.record_call(format!("stop:{}", name));
         Ok(())
     }
@@ -352,19 +584,42 @@ impl VmApi for FakeVmApi {
         Ok(())
     }
 
+    async fn delete_many(&self, names: &[String]) -> anyhow::Result<()> {
+        self.record_call(format!("delete:{}", names.join(",")));
+        Ok(())
+    }
+
     async fn info(&self, name: &str) -> anyhow::Result<VmStatusResponse> {
         self.record_call(format!("info:{}", name));
         // Return a response with the actual VM name instead of the default "test-vm"
-        let mut response = self.info_response.clone();
+        let mut response = self
+            .info_sequence
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| self.info_response.clone());
         response.name = name.to_owned();
         Ok(response)
     }
 
     async fn list(&self) -> anyhow::Result<Vec<VmSummary>> {
         self.record_call("list".to_owned());
+        if let Some(response) = self.list_sequence.lock().unwrap().pop_front() {
+            return response.map_err(|message| anyhow::anyhow!(message));
+        }
+        if let Some(message) = &self.list_error {
+            anyhow::bail!("{message}");
+        }
         Ok(self.list_response.clone())
     }
 
+    async fn list_with_warnings(&self) -> anyhow::Result<VmListing> {
+        Ok(VmListing {
+            vms: self.list().await?,
+            warnings: self.list_warnings.clone(),
+        })
+    }
+
     async fn exec(&self, name: &str, command: &[String]) -> anyhow::Result<CommandOutput> {
         self.exec_calls.lock().unwrap().push(ExecCall {
             vm_name: name.to_owned(),
@@ -377,7 +632,34 @@ impl VmApi for FakeVmApi {
             .unwrap_or_else(|| Ok(CommandOutput::success("")))
     }
 
-    async fn transfer(&self, _name: &str, _source: &str, _destination: &str) -> anyhow::Result<()> {
+    /// Replays the next scripted exec output line by line. Commands with
+    /// `-f` then keep "following" until `cancel` fires, like `tail -f`.
+    async fn exec_streaming(
+        &self,
+        name: &str,
+        command: &[String],
+        on_line: &LineSink<'_>,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let output = self.exec(name, command).await?;
+        output.stdout.lines().for_each(on_line);
+        if command.iter().any(|arg| arg == "-f") {
+            cancel.cancelled().await;
+            anyhow::bail!("exec in {} was cancelled", name);
+        }
+        Ok(CommandOutput {
+            stdout: String::new(),
+            ..output
+        })
+    }
+
+    async fn transfer(
+        &self,
+        _name: &str,
+        _source: &str,
+        _destination: &str,
+        _cancel: &CancellationToken,
+    ) -> anyhow::Result<()> {
         self.transfer_responses
             .lock()
             .unwrap()
@@ -394,6 +676,80 @@ pub fn multipass_cli_with_outputs(
     outputs: Vec<CommandOutput>,
 ) -> (MultipassCli<FakeExecutor>, FakeExecutor) {
     let fake = FakeExecutor::new(outputs);
-    let cli = MultipassCli::new(fake.clone());
+    // Retries are disabled so each scripted output maps to exactly one call.
+    let cli = MultipassCli::new_with_retry(fake.clone(), RetryConfig::disabled());
     (cli, fake)
 }
+
+/// An `AppState` around `vm_api`, with its agents in a fresh DB under the
+/// returned temp dir.
+pub fn build_state(vm_api: impl VmApi + 'static) -> (TempDir, AppState) {
+    let temp_dir = tempfile::tempdir().expect("temp dir should be created");
+    let db = Arc::new(
+        SafePawDb::open(temp_dir.path().join("safepaw.data")).expect("DB should initialize"),
+    );
+    let vm_api: Arc<dyn VmApi> = Arc::new(vm_api);
+    let agent_manager = Arc::new(LocalAgentManager::new_with_db(vm_api.clone(), db));
+
+    (temp_dir, AppState::new(vm_api, agent_manager as Arc<_>))
+}
+
+/// The API router around `vm_api`.
+pub fn build_app(vm_api: impl VmApi + 'static) -> (TempDir, Router) {
+    build_app_with(vm_api, |state| state)
+}
+
+/// The API router around `vm_api`, after `configure` has applied the state
+/// tweaks a test needs: `with_cors`, `with_jobs`, `with_rate_limits`,
+/// `with_info_cache` and so on.
+pub fn build_app_with(
+    vm_api: impl VmApi + 'static,
+    configure: impl FnOnce(AppState) -> AppState,
+) -> (TempDir, Router) {
+    let (temp_dir, state) = build_state(vm_api);
+    (temp_dir, create_api_router(configure(state)))
+}
+
+// ============================================================================
+// ScriptedConfirm - Mock Confirm prompt for testing
+// ============================================================================
+
+/// Answers prompts from a script and records what was asked.
+pub struct ScriptedConfirm {
+    interactive: bool,
+    answer: bool,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl ScriptedConfirm {
+    pub fn answering(answer: bool) -> Self {
+        Self {
+            interactive: true,
+            answer,
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn non_interactive() -> Self {
+        Self {
+            interactive: false,
+            answer: false,
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+impl Confirm for ScriptedConfirm {
+    fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    fn confirm(&self, prompt: &str) -> anyhow::Result<bool> {
+        self.prompts.lock().unwrap().push(prompt.to_owned());
+        Ok(self.answer)
+    }
+}
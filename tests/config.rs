@@ -0,0 +1,283 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::FakeExecutor;
+use safepaw::cli::effective_config;
+use safepaw::config::{Config, DEFAULT_CONFIG_TOML, VmDefaults, init_config};
+use safepaw::vm::{CommandOutput, LaunchSpec, LocalVmApi, MultipassCli, VmApi};
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+fn success() -> CommandOutput {
+    CommandOutput {
+        status_code: 0,
+        stdout: String::new(),
+        stderr: String::new(),
+        truncated: false,
+    }
+}
+
+#[test]
+fn init_writes_a_config_the_loader_accepts() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("config.toml");
+
+    init_config(&path, false).expect("init should write the file");
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG_TOML);
+    assert_eq!(Config::load(&path).unwrap(), Config::default());
+}
+
+#[test]
+fn init_refuses_to_overwrite_without_force() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[server]\nui_port = 9000\n").unwrap();
+
+    let err = init_config(&path, false).unwrap_err();
+    assert!(err.to_string().starts_with("config file already exists: "));
+    assert_eq!(Config::load(&path).unwrap().server.ui_port, 9000);
+
+    init_config(&path, true).expect("--force should overwrite");
+    assert_eq!(Config::load(&path).unwrap(), Config::default());
+}
+
+#[test]
+fn missing_settings_fall_back_to_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[vm]\ncpus = 4\n\n[log]\nlevel = \"debug\"\n").unwrap();
+
+    let config = Config::load(&path).unwrap();
+
+    assert_eq!(config.vm.cpus, Some(4));
+    assert_eq!(config.log.level, "debug");
+    assert_eq!(config.server, Config::default().server);
+    assert_eq!(config.multipass.binary, "multipass");
+}
+
+fn issues_in(text: &str) -> Vec<String> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, text).unwrap();
+    Config::load(&path)
+        .expect("file should parse")
+        .validate()
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn the_default_config_has_no_issues() {
+    assert!(Config::default().validate().is_empty());
+    assert!(issues_in(DEFAULT_CONFIG_TOML).is_empty());
+}
+
+#[test]
+fn unknown_keys_are_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[server]\nport = 1\n[profiles]\nx = 1\n").unwrap();
+
+    let issues = Config::load(&path).unwrap().validate();
+
+    assert_eq!(issues.len(), 2);
+    assert!(issues.iter().all(|issue| !issue.is_error()));
+    assert_eq!(
+        issues.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "warning: profiles: unknown key, ignored",
+            "warning: server.port: unknown key, ignored",
+        ]
+    );
+}
+
+#[test]
+fn unusable_ports_are_errors() {
+    assert_eq!(
+        issues_in("[server]\nui_port = 0\n"),
+        vec!["error: server.ui_port: port must be between 1 and 65535"]
+    );
+    assert_eq!(
+        issues_in("[server]\nui_port = 9000\napi_port = 9000\n"),
+        vec!["error: server.api_port: same port as server.ui_port (9000)"]
+    );
+    // The API port is unused when everything is served on the UI port.
+    assert!(
+        issues_in("[server]\nui_port = 9000\napi_port = 9000\nsingle_port = true\n").is_empty()
+    );
+    assert_eq!(
+        issues_in("[server]\nhost = \"localhost\"\n"),
+        vec!["error: server.host: 'localhost' is not an IP address"]
+    );
+}
+
+#[test]
+fn out_of_range_ports_fail_to_parse() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[server]\napi_port = 70000\n").unwrap();
+
+    let err = Config::load(&path).unwrap_err();
+
+    assert!(err.to_string().starts_with("invalid config file"));
+}
+
+#[test]
+fn malformed_sizes_are_errors() {
+    for size in ["512M", "4G", "20GiB", "1.5g", "1024", "2TB"] {
+        assert!(
+            issues_in(&format!("[vm]\nmemory = \"{size}\"\n")).is_empty(),
+            "{size} should be accepted"
+        );
+    }
+
+    assert_eq!(
+        issues_in("[vm]\nmemory = \"4 gigs\"\ndisk = \"0G\"\ncpus = 0\n"),
+        vec![
+            "error: vm.cpus: must be at least 1",
+            "error: vm.memory: '4 gigs' is not a size like 512M, 4G or 20GiB",
+            "error: vm.disk: '0G' is not a size like 512M, 4G or 20GiB",
+        ]
+    );
+}
+
+#[test]
+fn bad_log_level_and_binary_are_errors() {
+    assert_eq!(
+        issues_in("[log]\nlevel = \"loud\"\n"),
+        vec!["error: log.level: 'loud' is not one of error, warn, info, debug, trace"]
+    );
+    assert_eq!(
+        issues_in("[multipass]\nbinary = \"\"\n"),
+        vec!["error: multipass.binary: must not be empty"]
+    );
+}
+
+#[test]
+fn managed_prefix_must_be_able_to_start_a_vm_name() {
+    assert!(issues_in("[multipass]\nmanaged_prefix = \"sp-\"\n").is_empty());
+    assert_eq!(
+        issues_in("[multipass]\nmanaged_prefix = \"SP_\"\n"),
+        vec![
+            "error: multipass.managed_prefix: 'SP_' cannot start a VM name; use lowercase \
+             letters, digits and hyphens, starting with a letter"
+        ]
+    );
+}
+
+#[test]
+fn audit_section_parses_and_rejects_an_empty_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        "[audit]\nenabled = true\npath = \"/tmp/audit.log\"\n",
+    )
+    .unwrap();
+    let config = Config::load(&path).unwrap();
+
+    assert!(config.audit.enabled);
+    assert_eq!(
+        config.audit.path.as_deref(),
+        Some(std::path::Path::new("/tmp/audit.log"))
+    );
+    assert_eq!(
+        issues_in("[audit]\npath = \"\"\n"),
+        vec!["error: audit.path: must not be empty"]
+    );
+}
+
+#[test]
+fn vm_defaults_only_fill_unset_fields() {
+    let defaults = VmDefaults {
+        cpus: Some(2),
+        memory: Some("4G".to_owned()),
+        ..VmDefaults::default()
+    };
+    let spec = LaunchSpec {
+        memory: Some("8G".to_owned()),
+        ..LaunchSpec::new("agent-1")
+    };
+
+    let filled = defaults.apply(&spec);
+
+    assert_eq!(filled.cpus, Some(2));
+    assert_eq!(filled.memory.as_deref(), Some("8G"));
+    assert_eq!(filled.disk, None);
+    assert_eq!(filled.name, "agent-1");
+}
+
+#[tokio::test]
+async fn configured_binary_and_sizing_reach_multipass() {
+    let executor = FakeExecutor::new(vec![success()]);
+    let multipass = MultipassCli::new(executor.clone()).with_binary("/opt/multipass/bin/multipass");
+    let api = LocalVmApi::new(Arc::new(multipass)).with_launch_defaults(VmDefaults {
+        cpus: Some(2),
+        ..VmDefaults::default()
+    });
+
+    api.launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect("launch should succeed");
+
+    assert_eq!(
+        executor.calls(),
+        vec![vec![
+            "/opt/multipass/bin/multipass",
+            "launch",
+            "--name",
+            "agent-1",
+            "--cpus",
+            "2",
+        ]]
+    );
+}
+
+#[test]
+fn effective_config_merges_the_file_and_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("multipass"), "").unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        "[server]\napi_port = 9999\napi_token = \"hunter2\"\n\n[vm]\ncpus = 4\n",
+    )
+    .unwrap();
+    let config = Config::load(&path).unwrap();
+    let search_path = dir.path().display().to_string();
+    let env = |name: &str| match name {
+        "SAFEPAW_CORS_ORIGINS" => Some("https://paw.example".to_owned()),
+        "PATH" => Some(search_path.clone()),
+        _ => None,
+    };
+
+    let shown = effective_config(&config, Some(&path), "safepaw=debug", &env).unwrap();
+
+    assert_eq!(shown["path"], path.display().to_string());
+    assert_eq!(shown["server"]["api_port"], 9999);
+    assert_eq!(shown["server"]["ui_port"], 8888);
+    assert_eq!(shown["server"]["cors_origins"], "https://paw.example");
+    assert_eq!(shown["vm"]["cpus"], 4);
+    assert_eq!(shown["vm"]["memory"], Value::Null);
+    assert_eq!(
+        shown["multipass"]["path"],
+        dir.path().join("multipass").display().to_string()
+    );
+    assert_eq!(shown["log"]["filter"], "safepaw=debug");
+    // Unknown keys, which could hold anything, are not echoed.
+    assert!(!shown.to_string().contains("hunter2"));
+}
+
+#[test]
+fn effective_config_without_a_file_shows_the_defaults() {
+    let shown = effective_config(&Config::default(), None, "safepaw=info", &|_| None).unwrap();
+
+    assert_eq!(shown["path"], Value::Null);
+    assert_eq!(shown["server"]["host"], "0.0.0.0");
+    assert_eq!(shown["server"]["cors_origins"], Value::Null);
+    assert_eq!(shown["multipass"]["binary"], "multipass");
+    assert_eq!(shown["multipass"]["path"], Value::Null);
+}
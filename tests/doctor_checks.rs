@@ -0,0 +1,215 @@
+mod common;
+
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common::{FakeExecutor, FakeMultipass};
+use safepaw::doctor::{
+    CheckOutcome, CheckStatus, DiagnosticCheck, DiskSpaceCheck, MultipassBinaryCheck,
+    MultipassDaemonCheck, MultipassListCheck, PortCheck, parse_df_available, parse_version_output,
+    run_checks,
+};
+use safepaw::vm::{CommandOutput, VmError};
+
+const VERSION_OUTPUT: &str = "multipass   1.14.0\nmultipassd  1.14.0\n";
+const DF_OUTPUT: &str = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                         /dev/sda1        102400000  40000000  62400000      40% /\n";
+
+fn failed(stderr: &str) -> CommandOutput {
+    CommandOutput {
+        status_code: 2,
+        stdout: "multipass   1.14.0\n".to_owned(),
+        stderr: stderr.to_owned(),
+        truncated: false,
+    }
+}
+
+#[test]
+fn parses_client_and_daemon_versions() {
+    let versions = parse_version_output(VERSION_OUTPUT);
+    assert_eq!(versions.client.as_deref(), Some("1.14.0"));
+    assert_eq!(versions.daemon.as_deref(), Some("1.14.0"));
+
+    let client_only = parse_version_output("multipass   1.14.0\n");
+    assert_eq!(client_only.daemon, None);
+}
+
+#[test]
+fn parses_available_space_from_df() {
+    assert_eq!(parse_df_available(DF_OUTPUT), Some(62_400_000 * 1024));
+    assert_eq!(parse_df_available("garbage"), None);
+}
+
+#[tokio::test]
+async fn binary_check_reports_version_or_missing_binary() {
+    let executor = FakeExecutor::new(vec![CommandOutput::success(VERSION_OUTPUT)]);
+    let outcome = MultipassBinaryCheck::new(executor.clone()).run().await;
+    assert_eq!(outcome, CheckOutcome::pass("multipass 1.14.0"));
+    assert_eq!(executor.calls(), vec![vec!["multipass", "version"]]);
+
+    // No queued output makes the fake executor fail like a missing binary.
+    let outcome = MultipassBinaryCheck::new(FakeExecutor::new(vec![]))
+        .run()
+        .await;
+    assert_eq!(outcome.status, CheckStatus::Fail);
+    assert!(outcome.hint.unwrap().contains("Install multipass"));
+}
+
+#[tokio::test]
+async fn version_checks_run_the_configured_binary() {
+    let executor = FakeExecutor::new(vec![
+        CommandOutput::success(VERSION_OUTPUT),
+        CommandOutput::success(VERSION_OUTPUT),
+    ]);
+
+    let binary = MultipassBinaryCheck::new(executor.clone())
+        .with_binary("/opt/multipass/bin/multipass")
+        .run()
+        .await;
+    let daemon = MultipassDaemonCheck::new(executor.clone())
+        .with_binary("/opt/multipass/bin/multipass")
+        .run()
+        .await;
+
+    assert_eq!(binary.status, CheckStatus::Pass);
+    assert_eq!(daemon.status, CheckStatus::Pass);
+    assert_eq!(
+        executor.calls(),
+        vec![
+            vec!["/opt/multipass/bin/multipass", "version"],
+            vec!["/opt/multipass/bin/multipass", "version"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn daemon_check_fails_without_multipassd_line() {
+    let outcome = MultipassDaemonCheck::new(FakeExecutor::new(vec![CommandOutput::success(
+        VERSION_OUTPUT,
+    )]))
+    .run()
+    .await;
+    assert_eq!(outcome, CheckOutcome::pass("multipassd 1.14.0"));
+
+    let outcome = MultipassDaemonCheck::new(FakeExecutor::new(vec![failed(
+        "cannot connect to the multipass socket",
+    )]))
+    .run()
+    .await;
+    assert_eq!(outcome.status, CheckStatus::Fail);
+    assert!(
+        outcome
+            .detail
+            .contains("cannot connect to the multipass socket")
+    );
+    assert!(outcome.hint.unwrap().contains("Start the daemon"));
+}
+
+#[tokio::test]
+async fn list_check_reports_instances_or_error() {
+    let multipass = FakeMultipass::new().with_list_response(Ok(vec![]));
+    let outcome = MultipassListCheck::new(Arc::new(multipass)).run().await;
+    assert_eq!(outcome, CheckOutcome::pass("0 instance(s)"));
+
+    let multipass = FakeMultipass::new().with_list_response(Err(VmError::InvalidOutput {
+        action: "list",
+        reason: "expected value".to_owned(),
+    }));
+    let outcome = MultipassListCheck::new(Arc::new(multipass)).run().await;
+    assert_eq!(outcome.status, CheckStatus::Fail);
+}
+
+#[tokio::test]
+async fn port_check_fails_when_port_is_taken() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let outcome = PortCheck::new("ui", "127.0.0.1", port).run().await;
+    assert_eq!(outcome.status, CheckStatus::Fail);
+    assert!(outcome.detail.contains("already in use"));
+    assert!(outcome.hint.unwrap().contains("--ui-port"));
+
+    drop(listener);
+    let outcome = PortCheck::new("ui", "127.0.0.1", port).run().await;
+    assert_eq!(outcome.status, CheckStatus::Pass);
+}
+
+#[tokio::test]
+async fn disk_check_applies_thresholds() {
+    let gib = 1024 * 1024 * 1024;
+    let check = |warn_below, fail_below| {
+        DiskSpaceCheck::new(
+            FakeExecutor::new(vec![CommandOutput::success(DF_OUTPUT)]),
+            "/definitely/missing/multipassd",
+        )
+        .with_thresholds(warn_below, fail_below)
+    };
+
+    // DF_OUTPUT reports about 59.5 GiB available.
+    assert_eq!(
+        check(20 * gib, 5 * gib).run().await.status,
+        CheckStatus::Pass
+    );
+    assert_eq!(
+        check(80 * gib, 5 * gib).run().await.status,
+        CheckStatus::Warn
+    );
+    assert_eq!(
+        check(100 * gib, 80 * gib).run().await.status,
+        CheckStatus::Fail
+    );
+
+    let executor = FakeExecutor::new(vec![CommandOutput::success(DF_OUTPUT)]);
+    DiskSpaceCheck::new(executor.clone(), "/definitely/missing/multipassd")
+        .run()
+        .await;
+    assert_eq!(executor.calls(), vec![vec!["df", "-Pk", "/"]]);
+}
+
+#[tokio::test]
+async fn disk_check_warns_when_df_fails() {
+    let outcome = DiskSpaceCheck::new(FakeExecutor::new(vec![]), "/")
+        .run()
+        .await;
+    assert_eq!(outcome.status, CheckStatus::Warn);
+}
+
+struct StaticCheck(&'static str, CheckOutcome);
+
+#[async_trait]
+impl DiagnosticCheck for StaticCheck {
+    fn name(&self) -> String {
+        self.0.to_owned()
+    }
+
+    async fn run(&self) -> CheckOutcome {
+        self.1.clone()
+    }
+}
+
+#[tokio::test]
+async fn report_lists_each_check_with_hints_and_counts_failures() {
+    let checks: Vec<Box<dyn DiagnosticCheck>> = vec![
+        Box::new(StaticCheck("first", CheckOutcome::pass("ok"))),
+        Box::new(StaticCheck(
+            "second",
+            CheckOutcome::warn("low", "free some space"),
+        )),
+        Box::new(StaticCheck("third", CheckOutcome::fail("broken", "fix it"))),
+    ];
+
+    let report = run_checks(&checks).await;
+    assert_eq!(report.failures(), 1);
+    assert_eq!(
+        report.into_lines(),
+        vec![
+            "[PASS] first: ok",
+            "[WARN] second: low",
+            "       hint: free some space",
+            "[FAIL] third: broken",
+            "       hint: fix it",
+            "1 passed, 1 warnings, 1 failed",
+        ]
+    );
+}
@@ -99,3 +99,251 @@ async fn test_pixi_library_is_embedded() {
     // Verify it's the minified PixiJS library (should be substantial in size)
     assert!(body.len() > 100_000, "PixiJS library should be embedded");
 }
+
+#[tokio::test]
+async fn test_ui_dir_overrides_embedded_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ui_dir = temp_dir.path().join("ui");
+    std::fs::create_dir(&ui_dir).unwrap();
+    std::fs::write(ui_dir.join("index.html"), "<h1>local build</h1>").unwrap();
+    std::fs::write(temp_dir.path().join("secret.txt"), "do not serve").unwrap();
+
+    let app = safepaw::server::create_ui_router_with_dir(&ui_dir).unwrap();
+
+    // File present on disk wins over the embedded copy
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&body[..], b"<h1>local build</h1>");
+
+    // Missing on disk falls back to the embedded asset
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/app.js")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Paths escaping the UI directory are never read from disk
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/../secret.txt")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&body).contains("do not serve"));
+}
+
+#[tokio::test]
+async fn test_parent_directory_segments_are_rejected() {
+    let app = safepaw::server::create_ui_router();
+
+    for uri in [
+        "/../Cargo.toml",
+        "/assets/../../src/main.rs",
+        "/assets/tiles/../../app.js",
+    ] {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 400, "{uri} should be rejected");
+    }
+}
+
+#[tokio::test]
+async fn test_static_assets_are_cacheable_with_etag() {
+    let app = safepaw::server::create_ui_router();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/app.js")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("cache-control").unwrap(),
+        "public, max-age=3600"
+    );
+    let etag = response
+        .headers()
+        .get("etag")
+        .expect("asset should have an ETag")
+        .clone();
+    let etag = etag.to_str().unwrap().to_owned();
+    assert!(etag.starts_with("W/\""), "ETag should be weak: {etag}");
+    assert_eq!(etag.len(), 68, "ETag should be a quoted SHA-256 hex digest");
+
+    // A matching If-None-Match revalidates without a body, even when a cache
+    // dropped the weak marker or sent several tags
+    let opaque = etag.trim_start_matches("W/").to_owned();
+    for if_none_match in [etag.clone(), opaque.clone(), format!("\"other\", {opaque}")] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/app.js")
+                    .header("if-none-match", &if_none_match)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 304, "{if_none_match}");
+    }
+
+    // index.html must always be revalidated
+    let response = app
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-cache");
+}
+
+#[tokio::test]
+async fn test_pixi_library_is_gzip_compressed_when_accepted() {
+    let app = safepaw::server::create_ui_router();
+
+    let uncompressed = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/pixi.min@v8.16.0.js")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(uncompressed.headers().get("content-encoding").is_none());
+    let uncompressed = axum::body::to_bytes(uncompressed.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/pixi.min@v8.16.0.js")
+                .header("accept-encoding", "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers().get("content-encoding").unwrap(), "gzip");
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(
+        body.len() < uncompressed.len() / 2,
+        "compressed bundle should be much smaller"
+    );
+}
+
+async fn get_pixi(headers: &[(&str, &str)]) -> axum::response::Response {
+    let mut request = Request::builder().uri("/pixi.min@v8.16.0.js");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    safepaw::server::create_ui_router()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pixi_library_reports_its_length_and_accepts_ranges() {
+    let response = get_pixi(&[]).await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers().get("accept-ranges").unwrap(), "bytes");
+    let length: usize = response.headers()["content-length"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(length, body.len());
+}
+
+#[tokio::test]
+async fn test_pixi_library_serves_byte_ranges() {
+    let full = axum::body::to_bytes(get_pixi(&[]).await.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    let response = get_pixi(&[("range", "bytes=0-99"), ("accept-encoding", "gzip")]).await;
+
+    assert_eq!(response.status(), 206);
+    assert_eq!(
+        response.headers().get("content-range").unwrap(),
+        &format!("bytes 0-99/{}", full.len())
+    );
+    assert_eq!(response.headers().get("content-length").unwrap(), "100");
+    assert!(response.headers().get("content-encoding").is_none());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body.len(), 100);
+    assert_eq!(body, full.slice(0..100));
+
+    let tail = get_pixi(&[("range", "bytes=-10")]).await;
+    assert_eq!(tail.status(), 206);
+    let tail = axum::body::to_bytes(tail.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(tail, full.slice(full.len() - 10..));
+}
+
+#[tokio::test]
+async fn test_unusable_ranges_fall_back_or_are_rejected() {
+    let past_the_end = get_pixi(&[("range", "bytes=999999999-")]).await;
+    assert_eq!(past_the_end.status(), 416);
+    assert!(
+        past_the_end.headers()["content-range"]
+            .to_str()
+            .unwrap()
+            .starts_with("bytes */")
+    );
+
+    // Several ranges at once and stale If-Range validators get the whole file.
+    let several = get_pixi(&[("range", "bytes=0-1,5-6")]).await;
+    assert_eq!(several.status(), 200);
+    let stale = get_pixi(&[("range", "bytes=0-99"), ("if-range", "\"stale\"")]).await;
+    assert_eq!(stale.status(), 200);
+    // The ETag is weak, so it can't validate a range even when it matches
+    let etag = get_pixi(&[]).await.headers()["etag"].clone();
+    let etag = etag.to_str().unwrap();
+    let weak = get_pixi(&[("range", "bytes=0-99"), ("if-range", etag)]).await;
+    assert_eq!(weak.status(), 200);
+}
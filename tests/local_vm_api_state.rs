@@ -4,7 +4,11 @@ use std::{
 };
 
 use async_trait::async_trait;
-use safepaw::vm::{LocalVmApi, Multipass, VmApi, VmError, VmStatusResponse, VmSummary};
+use safepaw::vm::{
+    LaunchSpec, LocalVmApi, Multipass, StopOptions, VmApi, VmError, VmState, VmStatusResponse,
+    VmSummary,
+};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Default)]
 struct FakeState {
@@ -44,12 +48,12 @@ impl FakeMultipass {
 
 #[async_trait]
 impl Multipass for FakeMultipass {
-    async fn launch(&self, name: &str) -> Result<(), VmError> {
+    async fn launch(&self, spec: &LaunchSpec, _cancel: &CancellationToken) -> Result<(), VmError> {
         self.state
             .lock()
             .expect("poisoned fake state")
             .calls
-            .push(format!("launch:{name}"));
+            .push(format!("launch:{}", spec.name));
         Ok(())
     }
 
@@ -62,7 +66,7 @@ impl Multipass for FakeMultipass {
         Ok(())
     }
 
-    async fn stop(&self, name: &str) -> Result<(), VmError> {
+    async fn stop(&self, name: &str, _opts: &StopOptions) -> Result<(), VmError> {
         self.state
             .lock()
             .expect("poisoned fake state")
@@ -118,6 +122,7 @@ impl Multipass for FakeMultipass {
         _name: &str,
         _source: &str,
         _destination: &str,
+        _cancel: &CancellationToken,
     ) -> Result<(), VmError> {
         Ok(())
     }
@@ -128,7 +133,9 @@ async fn launch_calls_multipass() {
     let fake = FakeMultipass::default();
     let api = LocalVmApi::new(Arc::new(fake.clone()));
 
-    api.launch("agent-1").await.expect("launch should succeed");
+    api.launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect("launch should succeed");
 
     assert_eq!(fake.calls(), vec!["launch:agent-1"]);
 }
@@ -141,7 +148,7 @@ async fn info_returns_vm_info() {
     let info = api.info("agent-1").await.expect("info should succeed");
 
     assert_eq!(info.name, "agent-1");
-    assert_eq!(info.state, "Running");
+    assert_eq!(info.state, VmState::Running);
     assert_eq!(fake.calls(), vec!["info:agent-1"]);
 }
 
@@ -158,9 +165,9 @@ async fn list_returns_vms_from_multipass() {
     assert_eq!(fake.calls(), vec!["list"]);
     assert_eq!(listed.len(), 2);
     assert_eq!(listed[0].name, "agent-1");
-    assert_eq!(listed[0].state, "Running");
+    assert_eq!(listed[0].state, VmState::Running);
     assert_eq!(listed[1].name, "agent-2");
-    assert_eq!(listed[1].state, "Stopped");
+    assert_eq!(listed[1].state, VmState::Stopped);
 }
 
 #[tokio::test]
@@ -168,7 +175,9 @@ async fn stop_stops_vm() {
     let fake = FakeMultipass::default();
     let api = LocalVmApi::new(Arc::new(fake.clone()));
 
-    api.stop("agent-1").await.expect("stop should succeed");
+    api.stop("agent-1", &StopOptions::default())
+        .await
+        .expect("stop should succeed");
 
     assert_eq!(fake.calls(), vec!["stop:agent-1"]);
 }
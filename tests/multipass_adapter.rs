@@ -1,7 +1,11 @@
 mod common;
 
 use common::multipass_cli_with_outputs;
-use safepaw::vm::{CommandOutput, Multipass};
+use safepaw::vm::{
+    CommandOutput, ImageInfo, LaunchSpec, Multipass, NetworkInfo, StopOptions, VmError, VmProperty,
+    VmState, parse_find_output, parse_networks_output,
+};
+use tokio_util::sync::CancellationToken;
 
 #[tokio::test]
 async fn launch_info_list_and_stop_flow_maps_to_multipass_commands() {
@@ -17,20 +21,23 @@ async fn launch_info_list_and_stop_flow_maps_to_multipass_commands() {
     ]);
 
     multipass
-        .launch("agent-1")
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
         .await
         .expect("launch should work");
     let info = multipass.info("agent-1").await.expect("info should work");
     let listed = multipass.list().await.expect("list should work");
-    multipass.stop("agent-1").await.expect("stop should work");
+    multipass
+        .stop("agent-1", &StopOptions::default())
+        .await
+        .expect("stop should work");
 
     assert_eq!(info.name, "agent-1");
-    assert_eq!(info.state, "Running");
+    assert_eq!(info.state, VmState::Running);
     assert_eq!(listed.len(), 2);
     assert_eq!(listed[0].name, "agent-1");
-    assert_eq!(listed[0].state, "Running");
+    assert_eq!(listed[0].state, VmState::Running);
     assert_eq!(listed[1].name, "agent-2");
-    assert_eq!(listed[1].state, "Stopped");
+    assert_eq!(listed[1].state, VmState::Stopped);
 
     assert_eq!(
         fake.calls(),
@@ -69,12 +76,580 @@ async fn launch_returns_error_when_multipass_command_fails() {
         status_code: 1,
         stdout: String::new(),
         stderr: "launch failed".to_owned(),
+        truncated: false,
     }]);
 
     let err = multipass
-        .launch("agent-1")
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
         .await
         .expect_err("launch should fail");
     assert!(err.to_string().contains("launch"));
     assert!(err.to_string().contains("launch failed"));
 }
+
+#[tokio::test]
+async fn launch_passes_resource_flags_and_image() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let spec = LaunchSpec {
+        name: "agent-1".to_owned(),
+        cpus: Some(2),
+        memory: Some("4G".to_owned()),
+        disk: Some("20G".to_owned()),
+        image: Some("24.04".to_owned()),
+        networks: vec![],
+        cloud_init: None,
+    };
+
+    multipass
+        .launch(&spec, &CancellationToken::new())
+        .await
+        .expect("launch should work");
+
+    assert_eq!(
+        fake.calls(),
+        vec![vec![
+            "multipass".to_owned(),
+            "launch".to_owned(),
+            "--name".to_owned(),
+            "agent-1".to_owned(),
+            "--cpus".to_owned(),
+            "2".to_owned(),
+            "--memory".to_owned(),
+            "4G".to_owned(),
+            "--disk".to_owned(),
+            "20G".to_owned(),
+            "24.04".to_owned()
+        ]]
+    );
+}
+
+#[tokio::test]
+async fn launch_feeds_cloud_init_on_stdin() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let spec = LaunchSpec {
+        cloud_init: Some("#cloud-config\ntimezone: UTC\n".to_owned()),
+        ..LaunchSpec::new("agent-1")
+    };
+
+    multipass
+        .launch(&spec, &CancellationToken::new())
+        .await
+        .expect("launch should work");
+
+    assert_eq!(
+        fake.calls()[0][1..],
+        ["launch", "--name", "agent-1", "--cloud-init", "-"]
+    );
+    assert_eq!(
+        fake.stdins(),
+        vec![Some(b"#cloud-config\ntimezone: UTC\n".to_vec())]
+    );
+}
+
+#[tokio::test]
+async fn info_and_list_parse_ipv4_and_ipv6_addresses() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(
+            r#"{"errors":[],"info":{"agent-1":{"state":"Running","ipv4":["10.0.0.5"],"ipv6":["fd42::5"]}}}"#,
+        ),
+        CommandOutput::success(
+            r#"{"errors":[],"list":[{"name":"agent-1","state":"Running","ipv4":["10.0.0.5"],"ipv6":["fd42::5"]},{"name":"agent-2","state":"Running","ipv4":["10.0.0.6"]}]}"#,
+        ),
+    ]);
+
+    let info = multipass.info("agent-1").await.expect("info should work");
+    let listed = multipass.list().await.expect("list should work");
+
+    assert_eq!(info.ipv4, Some(vec!["10.0.0.5".to_owned()]));
+    assert_eq!(info.ipv6, Some(vec!["fd42::5".to_owned()]));
+    assert_eq!(listed[0].ipv6, Some(vec!["fd42::5".to_owned()]));
+    assert_eq!(listed[1].ipv4, Some(vec!["10.0.0.6".to_owned()]));
+    assert_eq!(listed[1].ipv6, None);
+}
+
+#[tokio::test]
+async fn info_without_state_reports_unknown() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        r#"{"errors":[],"info":{"agent-1":{}}}"#,
+    )]);
+
+    let info = multipass
+        .info("agent-1")
+        .await
+        .expect("a name-only entry should still parse");
+
+    assert_eq!(info.name, "agent-1");
+    assert_eq!(info.state, VmState::Unknown("Unknown".to_owned()));
+    assert_eq!(info.ipv4, None);
+    assert_eq!(info.memory_total, None);
+}
+
+#[tokio::test]
+async fn managed_prefix_filters_list_to_safepaw_vms() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        r#"{"errors":[],"list":[{"name":"sp-agent-1","state":"Running"},{"name":"dev-box","state":"Running"},{"name":"sp-agent-2","state":"Stopped"}]}"#,
+    )]);
+    let multipass = multipass.with_managed_prefix("sp-");
+
+    let listed = multipass.list().await.expect("list should work");
+
+    let names: Vec<_> = listed.iter().map(|vm| vm.name.as_str()).collect();
+    assert_eq!(names, vec!["sp-agent-1", "sp-agent-2"]);
+}
+
+#[tokio::test]
+async fn list_without_managed_prefix_returns_all_vms() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        r#"{"errors":[],"list":[{"name":"sp-agent-1","state":"Running"},{"name":"dev-box","state":"Running"}]}"#,
+    )]);
+
+    let listed = multipass.list().await.expect("list should work");
+
+    assert_eq!(listed.len(), 2);
+}
+
+#[tokio::test]
+async fn managed_prefix_refuses_to_launch_other_names() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let multipass = multipass.with_managed_prefix("sp-");
+
+    let err = multipass
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect_err("agent-1 would be hidden from list");
+    multipass
+        .launch(&LaunchSpec::new("sp-agent-2"), &CancellationToken::new())
+        .await
+        .expect("launch should work");
+
+    assert!(matches!(err, VmError::InvalidRequest { .. }));
+    assert!(err.to_string().contains("managed prefix 'sp-'"));
+    let calls = fake.calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0][3], "sp-agent-2");
+}
+
+#[tokio::test]
+async fn launch_passes_one_network_flag_per_network() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let spec = LaunchSpec {
+        networks: vec!["en0".to_owned(), "bridge0".to_owned()],
+        image: Some("24.04".to_owned()),
+        ..LaunchSpec::new("agent-1")
+    };
+
+    multipass
+        .launch(&spec, &CancellationToken::new())
+        .await
+        .expect("launch should work");
+
+    assert_eq!(
+        fake.calls(),
+        vec![
+            [
+                "multipass",
+                "launch",
+                "--name",
+                "agent-1",
+                "--network",
+                "en0",
+                "--network",
+                "bridge0",
+                "24.04"
+            ]
+            .map(String::from)
+            .to_vec()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn networks_parses_multipass_json() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        r#"{"list":[{"description":"Wi-Fi","name":"en0","type":"wifi"},{"description":"Network bridge","name":"bridge0","type":"bridge"}]}"#,
+    )]);
+
+    let networks = multipass.networks().await.expect("networks should work");
+
+    assert_eq!(
+        networks,
+        vec![
+            NetworkInfo {
+                name: "en0".to_owned(),
+                kind: "wifi".to_owned(),
+                description: "Wi-Fi".to_owned(),
+            },
+            NetworkInfo {
+                name: "bridge0".to_owned(),
+                kind: "bridge".to_owned(),
+                description: "Network bridge".to_owned(),
+            },
+        ]
+    );
+    assert_eq!(
+        fake.calls(),
+        vec![
+            ["multipass", "networks", "--format", "json"]
+                .map(String::from)
+                .to_vec()
+        ]
+    );
+}
+
+#[test]
+fn networks_parser_rejects_missing_list() {
+    let err = parse_networks_output(r#"{"errors":[]}"#).expect_err("list is required");
+    assert!(matches!(
+        err,
+        VmError::InvalidOutput {
+            action: "networks",
+            ..
+        }
+    ));
+}
+
+const FIND_FIXTURE: &str = include_str!("fixtures/multipass_find.json");
+
+#[test]
+fn find_parser_lists_images_then_blueprints() {
+    let images = parse_find_output(FIND_FIXTURE).expect("find output should parse");
+
+    assert_eq!(
+        images,
+        vec![
+            ImageInfo {
+                alias: "22.04".to_owned(),
+                kind: "image".to_owned(),
+                version: "20240821".to_owned(),
+                description: "Ubuntu 22.04 LTS".to_owned(),
+            },
+            ImageInfo {
+                alias: "24.04".to_owned(),
+                kind: "image".to_owned(),
+                version: "20240821".to_owned(),
+                description: "Ubuntu 24.04 LTS".to_owned(),
+            },
+            ImageInfo {
+                alias: "docker".to_owned(),
+                kind: "blueprint".to_owned(),
+                version: "0.4".to_owned(),
+                description: String::new(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn find_parser_rejects_missing_images() {
+    let err = parse_find_output(r#"{"errors":[]}"#).expect_err("images are required");
+
+    assert!(matches!(err, VmError::InvalidOutput { action: "find", .. }));
+}
+
+#[tokio::test]
+async fn find_runs_multipass_find_as_json() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success(FIND_FIXTURE)]);
+
+    let images = multipass.find().await.expect("find should work");
+
+    assert_eq!(images.len(), 3);
+    assert_eq!(
+        fake.calls(),
+        vec![
+            ["multipass", "find", "--format", "json"]
+                .map(String::from)
+                .to_vec()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stop_appends_force_when_requested() {
+    let (multipass, fake) =
+        multipass_cli_with_outputs(vec![CommandOutput::success(""), CommandOutput::success("")]);
+
+    multipass
+        .stop("agent-1", &StopOptions::default())
+        .await
+        .expect("stop should work");
+    multipass
+        .stop("agent-1", &StopOptions::forced())
+        .await
+        .expect("forced stop should work");
+
+    let calls = fake.calls();
+    assert_eq!(calls[0], ["multipass", "stop", "agent-1"].map(String::from));
+    assert_eq!(
+        calls[1],
+        ["multipass", "stop", "agent-1", "--force"].map(String::from)
+    );
+}
+
+#[tokio::test]
+async fn clone_names_the_copy() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+
+    multipass
+        .clone_vm("scratch", "agent-7")
+        .await
+        .expect("clone should work");
+
+    assert_eq!(
+        fake.calls(),
+        vec![["multipass", "clone", "scratch", "--name", "agent-7"].map(String::from)]
+    );
+}
+
+#[tokio::test]
+async fn set_property_targets_the_instance_settings_key() {
+    let (multipass, fake) =
+        multipass_cli_with_outputs(vec![CommandOutput::success(""), CommandOutput::success("")]);
+
+    multipass
+        .set_property("agent-1", VmProperty::Cpus, "4")
+        .await
+        .expect("set should work");
+    multipass
+        .set_property("agent-1", VmProperty::Memory, "8G")
+        .await
+        .expect("set should work");
+
+    assert_eq!(
+        fake.calls(),
+        vec![
+            ["multipass", "set", "local.agent-1.cpus=4"].map(String::from),
+            ["multipass", "set", "local.agent-1.memory=8G"].map(String::from),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_property_returns_the_trimmed_value() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("20GiB\n")]);
+
+    let value = multipass
+        .get_property("agent-1", VmProperty::Disk)
+        .await
+        .expect("get should work");
+
+    assert_eq!(value, "20GiB");
+    assert_eq!(
+        fake.calls(),
+        vec![["multipass", "get", "local.agent-1.disk"].map(String::from)]
+    );
+}
+
+#[tokio::test]
+async fn delete_many_removes_every_vm_in_one_command() {
+    let (multipass, fake) =
+        multipass_cli_with_outputs(vec![CommandOutput::success(""), CommandOutput::success("")]);
+    let names = ["agent-1", "agent-2", "agent-3"].map(String::from);
+
+    multipass
+        .delete_many(&names, true)
+        .await
+        .expect("delete should work");
+    multipass
+        .delete_many(&names[..1], false)
+        .await
+        .expect("delete should work");
+
+    assert_eq!(
+        fake.calls(),
+        vec![
+            [
+                "multipass",
+                "delete",
+                "agent-1",
+                "agent-2",
+                "agent-3",
+                "--purge"
+            ]
+            .map(String::from)
+            .to_vec(),
+            ["multipass", "delete", "agent-1"]
+                .map(String::from)
+                .to_vec(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn delete_many_rejects_an_empty_name_list() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+
+    let err = multipass
+        .delete_many(&[], true)
+        .await
+        .expect_err("an empty delete must not run");
+
+    assert!(matches!(
+        err,
+        VmError::InvalidRequest {
+            action: "delete",
+            ..
+        }
+    ));
+    assert!(fake.calls().is_empty());
+}
+
+#[tokio::test]
+async fn list_keeps_the_vms_that_came_back_alongside_errors() {
+    let partial = r#"{"errors":["instance \"agent-2\" is in an unknown state"],"list":[{"name":"agent-1","state":"Running"}]}"#;
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(partial),
+        CommandOutput::success(partial),
+    ]);
+
+    let vms = multipass.list().await.expect("partial list should work");
+    let listing = multipass
+        .list_with_warnings()
+        .await
+        .expect("partial list should work");
+
+    assert_eq!(vms.len(), 1);
+    assert_eq!(vms[0].name, "agent-1");
+    assert_eq!(listing.vms, vms);
+    assert_eq!(
+        listing.warnings,
+        vec![r#"instance "agent-2" is in an unknown state"#]
+    );
+}
+
+#[tokio::test]
+async fn info_reports_errors_next_to_the_vm_as_warnings() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        r#"{"errors":["mount /data could not be inspected"],"info":{"agent-1":{"state":"Running"}}}"#,
+    )]);
+
+    let info = multipass
+        .info("agent-1")
+        .await
+        .expect("partial info should work");
+
+    assert_eq!(info.state, VmState::Running);
+    assert_eq!(info.warnings, vec!["mount /data could not be inspected"]);
+}
+
+#[tokio::test]
+async fn errors_in_place_of_the_data_are_a_partial_failure() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(r#"{"errors":["failed to reach the daemon", {"code": 14}]}"#),
+        CommandOutput::success(r#"{"errors":["instance \"ghost\" does not exist"],"info":{}}"#),
+    ]);
+
+    let list_err = multipass.list().await.expect_err("no list came back");
+    let info_err = multipass
+        .info("ghost")
+        .await
+        .expect_err("no entry came back");
+
+    match &list_err {
+        VmError::PartialFailure { action, messages } => {
+            assert_eq!(*action, "list");
+            assert_eq!(messages, &["failed to reach the daemon", r#"{"code":14}"#]);
+        }
+        other => panic!("expected partial failure, got {other:?}"),
+    }
+    assert!(matches!(
+        &info_err,
+        VmError::PartialFailure { action: "info", .. }
+    ));
+    assert!(info_err.is_not_found());
+    assert_eq!(
+        info_err.to_string(),
+        r#"multipass info reported errors: instance "ghost" does not exist"#
+    );
+}
+
+#[tokio::test]
+async fn an_empty_errors_array_changes_nothing() {
+    let (multipass, _fake) =
+        multipass_cli_with_outputs(vec![CommandOutput::success(r#"{"errors":[],"info":{}}"#)]);
+
+    let err = multipass.info("agent-1").await.expect_err("no entry");
+
+    assert!(matches!(err, VmError::InvalidOutput { .. }));
+    assert!(err.to_string().contains("missing VM entry for agent-1"));
+}
+
+#[tokio::test]
+async fn version_runs_multipass_version() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success(
+        "multipass   1.14.0\nmultipassd  1.14.1\n",
+    )]);
+
+    let versions = multipass.version().await.expect("version should work");
+
+    assert_eq!(versions.client.as_deref(), Some("1.14.0"));
+    assert_eq!(versions.daemon.as_deref(), Some("1.14.1"));
+    assert_eq!(
+        fake.calls(),
+        vec![vec!["multipass".to_owned(), "version".to_owned()]]
+    );
+}
+
+#[tokio::test]
+async fn commands_keep_their_args_inside_tracing_spans() {
+    let subscriber = tracing_subscriber::fmt()
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_test_writer()
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let (multipass, fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(""),
+        CommandOutput::success(""),
+        CommandOutput::success(""),
+        CommandOutput {
+            status_code: 3,
+            stderr: "boom".to_owned(),
+            ..CommandOutput::success("")
+        },
+        CommandOutput::success(""),
+    ]);
+
+    multipass.start("agent-1").await.expect("start should work");
+    multipass
+        .restart("agent-1")
+        .await
+        .expect("restart should work");
+    multipass
+        .delete("agent-1")
+        .await
+        .expect("delete should work");
+    let output = multipass
+        .exec("agent-1", &["false".to_owned()])
+        .await
+        .expect("a failing command is still output");
+    multipass
+        .transfer(
+            "agent-1",
+            "notes.txt",
+            "/tmp/notes.txt",
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("transfer should work");
+
+    assert_eq!(output.status_code, 3);
+    let calls = fake.calls();
+    let calls: Vec<Vec<&str>> = calls
+        .iter()
+        .map(|call| call.iter().map(String::as_str).collect())
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            vec!["multipass", "start", "agent-1"],
+            vec!["multipass", "restart", "agent-1"],
+            vec!["multipass", "delete", "agent-1", "--purge"],
+            vec!["multipass", "exec", "agent-1", "--", "false"],
+            vec![
+                "multipass",
+                "transfer",
+                "notes.txt",
+                "agent-1:/tmp/notes.txt"
+            ],
+        ]
+    );
+}
@@ -0,0 +1,233 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use safepaw::vm::{
+    CommandExecutor, CommandOutput, LaunchSpec, Multipass, MultipassCli, RetryConfig, SpawnError,
+    TokioCommandExecutor, VmError,
+};
+use tokio_util::sync::CancellationToken;
+
+// ============================================================================
+// FlakyExecutor - fails a fixed number of times, then succeeds
+// ============================================================================
+
+#[derive(Clone, Copy)]
+enum FailureMode {
+    /// The command could not be started at all.
+    Spawn,
+    /// The command started, then reading its output failed.
+    PostSpawnIo,
+    /// multipass ran but reported the daemon as unavailable.
+    DaemonUnavailable,
+}
+
+#[derive(Clone)]
+struct FlakyExecutor {
+    failures: u32,
+    mode: FailureMode,
+    success_stdout: &'static str,
+    attempts: Arc<AtomicU32>,
+}
+
+impl FlakyExecutor {
+    fn new(failures: u32, mode: FailureMode, success_stdout: &'static str) -> Self {
+        Self {
+            failures,
+            mode,
+            success_stdout,
+            attempts: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for FlakyExecutor {
+    async fn run_with_stdin(
+        &self,
+        _program: &str,
+        _args: &[String],
+        _stdin: Option<&[u8]>,
+        _cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+        if attempt >= self.failures {
+            return Ok(CommandOutput::success(self.success_stdout));
+        }
+
+        match self.mode {
+            FailureMode::Spawn => Err(SpawnError {
+                program: "multipass".to_owned(),
+                source: std::io::Error::from(std::io::ErrorKind::NotFound),
+            }
+            .into()),
+            FailureMode::PostSpawnIo => anyhow::bail!("failed to read multipass stdout"),
+            FailureMode::DaemonUnavailable => Ok(CommandOutput {
+                status_code: 2,
+                stdout: String::new(),
+                stderr: "cannot connect to the multipass socket\n".to_owned(),
+                truncated: false,
+            }),
+        }
+    }
+}
+
+fn fast_retry(max_retries: u32) -> RetryConfig {
+    RetryConfig {
+        max_retries,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+        ..RetryConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn list_retries_daemon_unavailable_until_success() {
+    let executor = FlakyExecutor::new(2, FailureMode::DaemonUnavailable, r#"{"list":[]}"#);
+    let multipass = MultipassCli::new_with_retry(executor.clone(), fast_retry(3));
+
+    let vms = multipass
+        .list()
+        .await
+        .expect("list should eventually succeed");
+
+    assert!(vms.is_empty());
+    assert_eq!(executor.attempts(), 3);
+}
+
+#[tokio::test]
+async fn list_gives_up_after_max_retries() {
+    let executor = FlakyExecutor::new(5, FailureMode::DaemonUnavailable, r#"{"list":[]}"#);
+    let multipass = MultipassCli::new_with_retry(executor.clone(), fast_retry(2));
+
+    let err = multipass.list().await.expect_err("list should fail");
+
+    assert!(matches!(err, VmError::CommandFailed { action: "list", .. }));
+    assert_eq!(executor.attempts(), 3);
+}
+
+#[tokio::test]
+async fn launch_does_not_retry_failures_reported_by_multipass() {
+    let executor = FlakyExecutor::new(1, FailureMode::DaemonUnavailable, "");
+    let multipass = MultipassCli::new_with_retry(executor.clone(), fast_retry(3));
+
+    let err = multipass
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect_err("launch should not be retried");
+
+    assert!(matches!(
+        err,
+        VmError::CommandFailed {
+            action: "launch",
+            ..
+        }
+    ));
+    assert_eq!(executor.attempts(), 1);
+}
+
+#[tokio::test]
+async fn launch_retries_spawn_failures() {
+    let executor = FlakyExecutor::new(1, FailureMode::Spawn, "");
+    let multipass = MultipassCli::new_with_retry(executor.clone(), fast_retry(3));
+
+    multipass
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect("launch should succeed after a spawn failure");
+
+    assert_eq!(executor.attempts(), 2);
+}
+
+#[tokio::test]
+async fn disabled_retry_runs_command_once() {
+    let executor = FlakyExecutor::new(1, FailureMode::Spawn, r#"{"list":[]}"#);
+    let multipass = MultipassCli::new_with_retry(executor.clone(), RetryConfig::disabled());
+
+    let err = multipass.list().await.expect_err("list should fail");
+
+    assert!(matches!(err, VmError::SpawnFailed(_)));
+    assert_eq!(executor.attempts(), 1);
+}
+
+#[tokio::test]
+async fn launch_does_not_retry_io_errors_after_spawning() {
+    let executor = FlakyExecutor::new(1, FailureMode::PostSpawnIo, "");
+    let multipass = MultipassCli::new_with_retry(executor.clone(), fast_retry(3));
+
+    let err = multipass
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect_err("a launch that may have started should not run again");
+
+    assert!(matches!(err, VmError::CommandIo(_)));
+    assert_eq!(executor.attempts(), 1);
+}
+
+#[tokio::test]
+async fn list_retries_io_errors_after_spawning() {
+    let executor = FlakyExecutor::new(1, FailureMode::PostSpawnIo, r#"{"list":[]}"#);
+    let multipass = MultipassCli::new_with_retry(executor.clone(), fast_retry(3));
+
+    multipass
+        .list()
+        .await
+        .expect("list is idempotent and should be retried");
+
+    assert_eq!(executor.attempts(), 2);
+}
+
+#[tokio::test]
+async fn daemon_errors_mentioning_multipassd_are_not_retried() {
+    #[derive(Clone)]
+    struct DaemonError(Arc<AtomicU32>);
+
+    #[async_trait]
+    impl CommandExecutor for DaemonError {
+        async fn run_with_stdin(
+            &self,
+            _program: &str,
+            _args: &[String],
+            _stdin: Option<&[u8]>,
+            _cancel: &CancellationToken,
+        ) -> anyhow::Result<CommandOutput> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(CommandOutput {
+                status_code: 2,
+                stdout: String::new(),
+                stderr: "multipassd: instance image is corrupt\n".to_owned(),
+                truncated: false,
+            })
+        }
+    }
+
+    let attempts = Arc::new(AtomicU32::new(0));
+    let multipass = MultipassCli::new_with_retry(DaemonError(attempts.clone()), fast_retry(3));
+
+    multipass.list().await.expect_err("list should fail");
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn a_missing_binary_is_a_spawn_failure() {
+    let multipass = MultipassCli::new_with_retry(TokioCommandExecutor::new(), fast_retry(1))
+        .with_binary("/nonexistent/safepaw-test/multipass");
+
+    let err = multipass
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .expect_err("the binary does not exist");
+
+    assert!(matches!(err, VmError::SpawnFailed(_)), "{err:?}");
+    assert!(err.is_unavailable());
+}
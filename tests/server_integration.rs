@@ -1,105 +1,82 @@
-use std::sync::{Arc, Mutex};
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 
-use async_trait::async_trait;
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    extract::ConnectInfo,
+    http::{HeaderMap, Request, StatusCode, header},
+};
+use common::{
+    FakeExecutor, FakeMultipass, FakeVmApi, build_app, build_app_with, build_state,
+    multipass_cli_with_outputs,
 };
 use safepaw::{
-    agent::LocalAgentManager,
-    db::SafePawDb,
-    server::create_api_router,
-    vm::{VmApi, VmStatusResponse, VmSummary},
+    cli::build_cli,
+    config::Config,
+    doctor::MultipassVersions,
+    jobs::JobStore,
+    rate_limit::{RateLimit, RateLimits},
+    server::{
+        BannerFormat, BindAddrs, CorsConfig, HEALTH_PROBE_TTL, StartupBanner, create_api_router,
+        create_single_port_router, create_ui_router, resolve_bind_addrs,
+    },
+    util::env_enables_verbose_errors,
+    vm::{CommandOutput, ImageInfo, LocalVmApi, VmError, VmState, VmStatusResponse, VmSummary},
 };
+use serde_json::{Value, json};
 use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tower::ServiceExt;
 
-#[derive(Default)]
-struct FakeState {
-    vms: Vec<VmSummary>,
+/// The API router around a `LocalVmApi` driving `multipass`.
+fn build_multipass_app(multipass: FakeMultipass) -> (TempDir, axum::Router) {
+    build_app(LocalVmApi::new(Arc::new(multipass)))
 }
 
-#[derive(Clone, Default)]
-struct FakeVmApi {
-    state: Arc<Mutex<FakeState>>,
+/// Sends `request`, returning the status, headers and JSON body (`null` when
+/// the body isn't JSON).
+async fn send_request(
+    app: &axum::Router,
+    request: Request<Body>,
+) -> (StatusCode, HeaderMap, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("body should be readable");
+    (
+        status,
+        headers,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
 }
 
-impl FakeVmApi {
-    fn with_vms(self, vms: Vec<VmSummary>) -> Self {
-        self.state.lock().expect("poisoned fake state").vms = vms;
-        self
+/// Sends `body` as JSON, or no body at all when it is empty.
+async fn send(app: &axum::Router, method: &str, uri: &str, body: &str) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if !body.is_empty() {
+        request = request.header("content-type", "application/json");
     }
+    let (status, _, body) =
+        send_request(app, request.body(Body::from(body.to_owned())).unwrap()).await;
+    (status, body)
 }
 
-fn build_app(fake_api: Arc<FakeVmApi>) -> (TempDir, axum::Router) {
-    let temp_dir = tempfile::tempdir().expect("temp dir should be created");
-    let db = Arc::new(
-        SafePawDb::open(temp_dir.path().join("safepaw.data")).expect("DB should initialize"),
-    );
-    let agent_manager = Arc::new(LocalAgentManager::new_with_db(fake_api.clone(), db));
-    let app_state = safepaw::server::AppState::new(fake_api as Arc<_>, agent_manager as Arc<_>);
-
-    (temp_dir, create_api_router(app_state))
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    send(app, "GET", uri, "").await
 }
 
-#[async_trait]
-impl VmApi for FakeVmApi {
-    async fn launch(&self, _name: &str) -> anyhow::Result<()> {
-        Ok(())
-    }
-
-    async fn start(&self, _name: &str) -> anyhow::Result<()> {
-        Ok(())
-    }
-
-    async fn stop(&self, _name: &str) -> anyhow::Result<()> {
-        Ok(())
-    }
-
-    async fn restart(&self, _name: &str) -> anyhow::Result<()> {
-        Ok(())
-    }
-
-    async fn delete(&self, _name: &str) -> anyhow::Result<()> {
-        Ok(())
-    }
-
-    async fn info(&self, name: &str) -> anyhow::Result<VmStatusResponse> {
-        Ok(VmStatusResponse {
-            name: name.to_owned(),
-            state: "Running".to_owned(),
-            ipv4: Some(vec!["192.168.1.100".to_owned()]),
-            release: Some("Ubuntu 22.04".to_owned()),
-            image_release: Some("Ubuntu 22.04 LTS".to_owned()),
-            cpu_count: Some("2".to_owned()),
-            memory_total: Some(2 * 1024 * 1024 * 1024), // 2 GiB
-            memory_used: Some(1024 * 1024 * 1024),      // 1 GiB
-            disk_total: Some(10 * 1024 * 1024 * 1024),  // 10 GiB
-            disk_used: Some(5 * 1024 * 1024 * 1024),    // 5 GiB
-        })
-    }
-
-    async fn list(&self) -> anyhow::Result<Vec<VmSummary>> {
-        Ok(self.state.lock().expect("poisoned fake state").vms.clone())
-    }
-
-    async fn exec(
-        &self,
-        _name: &str,
-        _command: &[String],
-    ) -> anyhow::Result<safepaw::vm::CommandOutput> {
-        Ok(safepaw::vm::CommandOutput::success(""))
-    }
-
-    async fn transfer(&self, _name: &str, _source: &str, _destination: &str) -> anyhow::Result<()> {
-        Ok(())
-    }
+async fn post_vms(app: &axum::Router, body: &str) -> (StatusCode, Value) {
+    send(app, "POST", "/vms", body).await
 }
 
 #[tokio::test]
 async fn health_check_returns_ok() {
-    let fake_api = Arc::new(FakeVmApi::default());
-    let (_temp_dir, app) = build_app(fake_api);
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
 
     let response = app
         .oneshot(
@@ -123,8 +100,7 @@ async fn health_check_returns_ok() {
 
 #[tokio::test]
 async fn list_vms_returns_empty_array_when_no_vms() {
-    let fake_api = Arc::new(FakeVmApi::default());
-    let (_temp_dir, app) = build_app(fake_api);
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
 
     let response = app
         .oneshot(Request::builder().uri("/vms").body(Body::empty()).unwrap())
@@ -143,21 +119,22 @@ async fn list_vms_returns_empty_array_when_no_vms() {
 
 #[tokio::test]
 async fn list_vms_returns_vms() {
-    let fake_api = FakeVmApi::default().with_vms(vec![
+    let fake_api = FakeVmApi::new().with_list_response(vec![
         VmSummary {
             name: "agent-1".to_owned(),
-            state: "Running".to_owned(),
+            state: VmState::Running,
             ipv4: Some(vec!["192.168.1.100".to_owned()]),
+            ipv6: None,
             release: Some("Ubuntu 22.04".to_owned()),
         },
         VmSummary {
             name: "agent-2".to_owned(),
-            state: "Stopped".to_owned(),
+            state: VmState::Stopped,
             ipv4: None,
+            ipv6: None,
             release: Some("Ubuntu 22.04".to_owned()),
         },
     ]);
-    let fake_api = Arc::new(fake_api);
     let (_temp_dir, app) = build_app(fake_api);
 
     let response = app
@@ -181,8 +158,17 @@ async fn list_vms_returns_vms() {
 
 #[tokio::test]
 async fn get_vm_info_returns_vm_details() {
-    let fake_api = Arc::new(FakeVmApi::default());
-    let (_temp_dir, app) = build_app(fake_api);
+    let (_temp_dir, app) = build_app(FakeVmApi::new().with_info_response(VmStatusResponse {
+        ipv4: Some(vec!["192.168.1.100".to_owned()]),
+        release: Some("Ubuntu 22.04".to_owned()),
+        image_release: Some("Ubuntu 22.04 LTS".to_owned()),
+        cpu_count: Some("2".to_owned()),
+        memory_total: Some(2 * 1024 * 1024 * 1024), // 2 GiB
+        memory_used: Some(1024 * 1024 * 1024),      // 1 GiB
+        disk_total: Some(10 * 1024 * 1024 * 1024),  // 10 GiB
+        disk_used: Some(5 * 1024 * 1024 * 1024),    // 5 GiB
+        ..VmStatusResponse::minimal("agent-1", VmState::Running)
+    }));
 
     let response = app
         .oneshot(
@@ -203,8 +189,1898 @@ async fn get_vm_info_returns_vm_details() {
 
     assert_eq!(vm.name, "agent-1");
     assert_eq!(vm.state, "Running");
+    assert_eq!(vm.image_release.as_deref(), Some("Ubuntu 22.04 LTS"));
+    assert_eq!(vm.cpu_count.as_deref(), Some("2"));
     assert_eq!(vm.memory_total, Some(2 * 1024 * 1024 * 1024));
     assert_eq!(vm.memory_used, Some(1024 * 1024 * 1024));
     assert_eq!(vm.disk_total, Some(10 * 1024 * 1024 * 1024));
     assert_eq!(vm.disk_used, Some(5 * 1024 * 1024 * 1024));
 }
+
+#[tokio::test]
+async fn cors_reflects_configured_origin() {
+    let cors = CorsConfig::parse("http://localhost:8888, https://paw.example")
+        .expect("origins should parse");
+    let (_temp_dir, app) = build_app_with(FakeVmApi::new(), |state| state.with_cors(cors));
+
+    let allowed = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .header("origin", "https://paw.example")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let denied = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .header("origin", "https://evil.example")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        allowed
+            .headers()
+            .get("access-control-allow-origin")
+            .unwrap(),
+        "https://paw.example"
+    );
+    assert_eq!(
+        allowed
+            .headers()
+            .get("access-control-allow-credentials")
+            .unwrap(),
+        "true"
+    );
+    assert!(
+        denied
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn cors_wildcard_does_not_allow_credentials() {
+    let cors = CorsConfig::parse("*").expect("wildcard should parse");
+    let (_temp_dir, app) = build_app_with(FakeVmApi::new(), |state| state.with_cors(cors));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .header("origin", "https://paw.example")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .unwrap(),
+        "*"
+    );
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-credentials")
+            .is_none()
+    );
+}
+
+#[test]
+fn cors_config_rejects_wildcard_mixed_with_origins() {
+    assert_eq!(CorsConfig::parse("").unwrap(), CorsConfig::Permissive);
+    assert!(CorsConfig::parse("https://paw.example,*").is_err());
+}
+
+#[tokio::test]
+async fn unknown_api_route_returns_json_404() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/vms/foo/bar/baz")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["code"], "route_not_found");
+    assert!(
+        json["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("API route not found")
+    );
+}
+
+#[tokio::test]
+async fn truncated_launch_body_returns_json_400() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let (status, json) = post_vms(&app, "{").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "validation_failed");
+    assert!(json["message"].as_str().unwrap().contains("EOF"));
+}
+
+#[tokio::test]
+async fn launch_body_missing_fields_returns_json_400() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let (status, json) = post_vms(&app, r#"{"cpus":2}"#).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "validation_failed");
+    assert!(json["message"].as_str().unwrap().contains("name"));
+}
+
+#[tokio::test]
+async fn oversized_launch_body_returns_json_413() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+    let huge = format!(
+        r#"{{"name":"{}"}}"#,
+        "a".repeat(safepaw::server::MAX_REQUEST_BODY_BYTES)
+    );
+
+    let (status, json) = post_vms(&app, &huge).await;
+
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(json["code"], "payload_too_large");
+}
+
+#[tokio::test]
+async fn small_json_responses_are_not_compressed() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .header("accept-encoding", "gzip, br")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("content-encoding").is_none());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["status"], "ok");
+}
+
+#[tokio::test]
+async fn openapi_spec_covers_vm_routes() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let spec: utoipa::openapi::OpenApi =
+        serde_json::from_slice(&body).expect("spec should deserialize");
+
+    let vms = spec
+        .paths
+        .paths
+        .get("/vms")
+        .expect("/vms should be documented");
+    assert!(vms.get.is_some());
+    assert!(vms.post.is_some());
+    let vm = &spec.paths.paths["/vms/{name}"];
+    assert!(vm.get.is_some());
+    assert!(vm.delete.is_some());
+    let start = &spec.paths.paths["/vms/{name}/start"];
+    assert!(start.post.is_some());
+    assert!(start.get.is_none());
+    for path in [
+        "/health",
+        "/vms/{name}/stop",
+        "/vms/{name}/restart",
+        "/agents/{vm_name}/install",
+        "/agents/{vm_name}/check",
+        "/agents/{vm_name}/onboard",
+        "/agents/{vm_name}",
+        "/agents/{vm_name}/{agent_id}/stop",
+    ] {
+        assert!(spec.paths.paths.contains_key(path), "{path} missing");
+    }
+    let agent = &spec.paths.paths["/agents/{vm_name}/{agent_id}"];
+    assert!(agent.get.is_some());
+    assert!(agent.delete.is_some());
+    let schemas = &spec.components.expect("components").schemas;
+    for schema in [
+        "VmStatusDto",
+        "LaunchVmRequest",
+        "ApiError",
+        "ErrorCode",
+        "MutationResponse",
+        "OnboardAgentRequest",
+        "AgentInstance",
+    ] {
+        assert!(schemas.contains_key(schema), "{schema} missing");
+    }
+}
+
+#[tokio::test]
+async fn docs_page_points_swagger_ui_at_the_spec() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let response = app
+        .oneshot(Request::builder().uri("/docs").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+    assert!(html.contains("SwaggerUIBundle"));
+    assert!(html.contains(r#"url: "openapi.json""#));
+}
+
+#[test]
+fn startup_banner_json_is_one_ascii_line() {
+    let line = StartupBanner::new("127.0.0.1", 8888, 8889).to_json_line();
+
+    assert!(!line.contains('\n'));
+    assert!(line.is_ascii());
+    let banner: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(
+        banner,
+        serde_json::json!({
+            "ui_url": "http://127.0.0.1:8888",
+            "api_url": "http://127.0.0.1:8889",
+            "health_url": "http://127.0.0.1:8889/health",
+        })
+    );
+}
+
+#[test]
+fn bind_addresses_fall_back_to_the_shared_host() {
+    let localhost: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+    let any: std::net::IpAddr = "0.0.0.0".parse().unwrap();
+
+    assert_eq!(
+        resolve_bind_addrs("0.0.0.0", None, None).unwrap(),
+        BindAddrs { ui: any, api: any }
+    );
+    assert_eq!(
+        resolve_bind_addrs("0.0.0.0", Some("127.0.0.1"), None).unwrap(),
+        BindAddrs {
+            ui: localhost,
+            api: any
+        }
+    );
+    assert_eq!(
+        resolve_bind_addrs("127.0.0.1", None, Some("::"))
+            .unwrap()
+            .api,
+        "::".parse::<std::net::IpAddr>().unwrap()
+    );
+}
+
+#[test]
+fn bind_addresses_must_be_ip_addresses() {
+    let err = resolve_bind_addrs("0.0.0.0", Some("my-laptop"), None).unwrap_err();
+    assert_eq!(err.to_string(), "invalid --bind-ui address: my-laptop");
+
+    let err = resolve_bind_addrs("0.0.0.0", None, Some("10.0.0")).unwrap_err();
+    assert_eq!(err.to_string(), "invalid --bind-api address: 10.0.0");
+
+    // The shared host is only parsed when a server falls back to it.
+    assert!(resolve_bind_addrs("nowhere", Some("127.0.0.1"), Some("127.0.0.1")).is_ok());
+    let err = resolve_bind_addrs("nowhere", Some("127.0.0.1"), None).unwrap_err();
+    assert_eq!(err.to_string(), "invalid host address: nowhere");
+}
+
+#[test]
+fn banner_for_split_hosts_points_at_each_server() {
+    let banner = StartupBanner::for_hosts("127.0.0.1", 8888, "0.0.0.0", 8889);
+
+    assert_eq!(banner.ui_url, "http://127.0.0.1:8888");
+    assert_eq!(banner.api_url, "http://0.0.0.0:8889");
+    assert_eq!(banner.health_url, "http://0.0.0.0:8889/health");
+}
+
+#[test]
+fn banner_format_parses_cli_values() {
+    assert_eq!("text".parse::<BannerFormat>().unwrap(), BannerFormat::Text);
+    assert_eq!("json".parse::<BannerFormat>().unwrap(), BannerFormat::Json);
+    assert_eq!("none".parse::<BannerFormat>().unwrap(), BannerFormat::None);
+    assert!("yaml".parse::<BannerFormat>().is_err());
+    assert_eq!(BannerFormat::default(), BannerFormat::Text);
+}
+
+#[tokio::test]
+async fn oversized_error_bodies_pass_through_intact() {
+    let details = "x".repeat(70 * 1024);
+    let body = serde_json::json!({
+        "code": "internal",
+        "message": "boom",
+        "details": { "log": details },
+    })
+    .to_string();
+    let expected = body.clone();
+    let app = axum::Router::new()
+        .route(
+            "/big",
+            axum::routing::get(
+                move || async move { (StatusCode::INTERNAL_SERVER_ERROR, body.clone()) },
+            ),
+        )
+        .layer(axum::middleware::from_fn(
+            safepaw::api_error::with_request_id,
+        ));
+
+    let response = app
+        .oneshot(Request::builder().uri("/big").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.headers().contains_key("x-request-id"));
+    let received = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(received, expected.as_bytes());
+}
+
+// ============================================================================
+// Error status codes and request ids
+// ============================================================================
+
+fn command_failed(action: &'static str, stderr: &str) -> VmError {
+    VmError::CommandFailed {
+        action,
+        status_code: 2,
+        stderr: stderr.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn missing_vm_is_404_with_code() {
+    let multipass = FakeMultipass::new().with_info_response(Err(command_failed(
+        "info",
+        "instance \"ghost\" does not exist",
+    )));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "GET", "/vms/ghost", "").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "vm_not_found");
+    assert!(body["message"].as_str().unwrap().contains("ghost"));
+    assert!(body["request_id"].is_string());
+}
+
+#[tokio::test]
+async fn deleting_a_missing_vm_is_404() {
+    let multipass = FakeMultipass::new().with_delete_response(Err(command_failed(
+        "delete",
+        "instance \"ghost\" does not exist",
+    )));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "DELETE", "/vms/ghost", "").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "vm_not_found");
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Failed to delete VM")
+    );
+}
+
+#[tokio::test]
+async fn launching_a_taken_name_is_409() {
+    let multipass = FakeMultipass::new().with_launch_response(Err(command_failed(
+        "launch",
+        "instance \"dev\" already exists",
+    )));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "POST", "/vms", r#"{"name":"dev"}"#).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "vm_already_exists");
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Failed to launch VM 'dev'")
+    );
+}
+
+#[tokio::test]
+async fn invalid_launch_name_is_422_without_calling_multipass() {
+    let multipass = FakeMultipass::new();
+    let (_temp_dir, app) = build_multipass_app(multipass.clone());
+
+    let (status, body) = send(&app, "POST", "/vms", r#"{"name":"-bad-"}"#).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"]["field"], "name");
+    assert_eq!(body["details"]["violation"], "leading_hyphen");
+    assert!(multipass.calls().is_empty());
+}
+
+#[tokio::test]
+async fn launch_reports_which_name_rule_was_broken() {
+    let long = "a".repeat(200);
+    for (name, violation) in [
+        ("has space", "invalid_character"),
+        ("a/b", "invalid_character"),
+        ("Agent", "uppercase"),
+        (long.as_str(), "too_long"),
+        ("1agent", "starts_with_digit"),
+        ("agent-", "trailing_hyphen"),
+        ("", "empty"),
+    ] {
+        let multipass = FakeMultipass::new();
+        let (_temp_dir, app) = build_multipass_app(multipass.clone());
+        let body = serde_json::json!({ "name": name }).to_string();
+
+        let (status, body) = send(&app, "POST", "/vms", &body).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{name:?}");
+        assert_eq!(body["details"]["violation"], violation, "{name:?}");
+        assert!(multipass.calls().is_empty(), "{name:?}");
+    }
+}
+
+#[tokio::test]
+async fn path_routes_reject_invalid_names_with_422() {
+    for (method, uri) in [
+        ("GET", "/vms/has%20space"),
+        ("DELETE", "/vms/Agent"),
+        ("POST", "/vms/-agent/start"),
+        ("POST", "/vms/agent_1/stop"),
+        ("GET", "/agents/1agent"),
+        ("POST", "/agents/agent-/agent-7/stop"),
+    ] {
+        let multipass = FakeMultipass::new();
+        let (_temp_dir, app) = build_multipass_app(multipass.clone());
+
+        let (status, body) = send(&app, method, uri, "").await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{method} {uri}");
+        assert_eq!(body["code"], "validation_failed", "{method} {uri}");
+        assert!(
+            body["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("invalid VM name"),
+            "{method} {uri}"
+        );
+        assert!(multipass.calls().is_empty(), "{method} {uri}");
+    }
+}
+
+#[tokio::test]
+async fn unreachable_multipass_is_503() {
+    let multipass = FakeMultipass::new()
+        .with_list_response(Err(VmError::CommandIo("multipass: not found".to_owned())));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "GET", "/vms", "").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "multipass_unavailable");
+}
+
+#[tokio::test]
+async fn daemon_socket_errors_are_503() {
+    let multipass = FakeMultipass::new().with_stop_response(Err(command_failed(
+        "stop",
+        "cannot connect to the multipass socket",
+    )));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "POST", "/vms/dev/stop", "").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "multipass_unavailable");
+}
+
+#[tokio::test]
+async fn other_daemon_errors_are_500() {
+    let multipass = FakeMultipass::new().with_stop_response(Err(command_failed(
+        "stop",
+        "multipassd: instance image is corrupt",
+    )));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "POST", "/vms/dev/stop", "").await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], "internal");
+}
+
+#[tokio::test]
+async fn timed_out_multipass_is_504() {
+    let multipass = FakeMultipass::new().with_info_response(Err(VmError::TimedOut {
+        action: "info",
+        timeout: Duration::from_secs(30),
+    }));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "GET", "/vms/dev", "").await;
+
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["code"], "multipass_timeout");
+}
+
+#[tokio::test]
+async fn other_info_failures_are_500_not_404() {
+    let multipass = FakeMultipass::new().with_info_response(Err(VmError::InvalidOutput {
+        action: "info",
+        reason: "expected JSON".to_owned(),
+    }));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "GET", "/vms/dev", "").await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], "internal");
+}
+
+#[tokio::test]
+async fn images_lists_what_multipass_find_returns() {
+    let multipass = FakeMultipass::new().with_find_response(Ok(vec![ImageInfo {
+        alias: "24.04".to_owned(),
+        kind: "image".to_owned(),
+        version: "20240821".to_owned(),
+        description: "Ubuntu 24.04 LTS".to_owned(),
+    }]));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "GET", "/images", "").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        serde_json::json!([{
+            "alias": "24.04",
+            "type": "image",
+            "version": "20240821",
+            "description": "Ubuntu 24.04 LTS"
+        }])
+    );
+}
+
+#[tokio::test]
+async fn images_is_503_when_multipass_is_unreachable() {
+    let multipass =
+        FakeMultipass::new().with_find_response(Err(VmError::CommandIo("No such file".to_owned())));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = send(&app, "GET", "/images", "").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "multipass_unavailable");
+}
+
+#[tokio::test]
+async fn errors_echo_the_client_request_id() {
+    let (_temp_dir, app) = build_multipass_app(FakeMultipass::new());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/nope")
+                .header("x-request-id", "req-42")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["x-request-id"], "req-42");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "route_not_found");
+    assert_eq!(body["request_id"], "req-42");
+}
+
+#[tokio::test]
+async fn unusable_request_ids_are_replaced() {
+    let (_temp_dir, app) = build_multipass_app(FakeMultipass::new());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/livez")
+                .header("x-request-id", "has space")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let id = response.headers()["x-request-id"].to_str().unwrap();
+    assert_ne!(id, "has space");
+    assert_eq!(id.len(), 32);
+}
+
+#[tokio::test]
+async fn wrong_methods_get_the_error_envelope() {
+    let (_temp_dir, app) = build_multipass_app(FakeMultipass::new());
+
+    let (status, body) = send(&app, "PUT", "/vms/agent-1/start", "").await;
+
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(body["code"], "method_not_allowed");
+    assert!(body["message"].is_string());
+    assert!(body["request_id"].is_string());
+}
+
+#[tokio::test]
+async fn mutations_name_the_vm_and_action() {
+    let (_temp_dir, app) = build_multipass_app(FakeMultipass::new());
+
+    let (status, body) = send(&app, "POST", "/vms/agent-1/restart", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "agent-1");
+    assert_eq!(body["action"], "restart");
+    assert!(body["message"].is_string());
+    assert!(body.get("success").is_none(), "{body}");
+
+    let (status, body) = send(&app, "DELETE", "/vms/agent-1", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["action"], "delete");
+}
+
+// ============================================================================
+// Error causes
+// ============================================================================
+
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("body should be readable");
+    serde_json::from_slice(&body).expect("body should be JSON")
+}
+
+// Tests are compiled with debug assertions, so verbose errors are always on.
+
+#[tokio::test]
+async fn test_vm_info_error_includes_multipass_cause() {
+    let multipass = FakeMultipass::new().with_info_response(Err(VmError::CommandFailed {
+        action: "info",
+        status_code: 2,
+        stderr: "instance \"ghost\" does not exist".to_owned(),
+    }));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/vms/ghost")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "vm_not_found");
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("failed to get info for VM ghost")
+    );
+    let causes = body["details"]["causes"]
+        .as_array()
+        .expect("causes should be reported in debug builds");
+    assert_eq!(causes.len(), 1);
+    assert!(
+        causes[0]
+            .as_str()
+            .unwrap()
+            .contains("instance \"ghost\" does not exist")
+    );
+}
+
+#[tokio::test]
+async fn test_handler_error_includes_cause_chain() {
+    let multipass = FakeMultipass::new().with_launch_response(Err(VmError::CommandIo(
+        "multipass: command not found".to_owned(),
+    )));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/vms")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"name":"dev"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "multipass_unavailable");
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Failed to launch VM 'dev'")
+    );
+    let causes = body["details"]["causes"].as_array().unwrap();
+    assert!(
+        causes
+            .iter()
+            .any(|cause| cause.as_str().unwrap().contains("command not found"))
+    );
+}
+
+#[test]
+fn test_verbose_errors_env_values() {
+    assert!(!env_enables_verbose_errors(None));
+    assert!(!env_enables_verbose_errors(Some("")));
+    assert!(!env_enables_verbose_errors(Some("0")));
+    assert!(!env_enables_verbose_errors(Some("FALSE")));
+    assert!(env_enables_verbose_errors(Some("1")));
+    assert!(env_enables_verbose_errors(Some("true")));
+}
+
+// ============================================================================
+// Launch options
+// ============================================================================
+
+/// The API router around a real `MultipassCli`, so tests can see the
+/// multipass command line a request turned into.
+fn build_cli_backed_app() -> (TempDir, axum::Router, FakeExecutor) {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let (temp_dir, app) = build_app(LocalVmApi::new(Arc::new(multipass)));
+    (temp_dir, app, fake)
+}
+
+#[tokio::test]
+async fn fully_specified_launch_reaches_multipass() {
+    let (_temp_dir, app, fake) = build_cli_backed_app();
+
+    let (status, json) = post_vms(
+        &app,
+        r##"{"name":"agent-1","cpus":2,"memory":"4G","disk":"20G","image":"24.04",
+            "cloud_init":"#cloud-config\npackages: [git]\n"}"##,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CREATED, "{json}");
+    assert_eq!(
+        fake.calls()[0][1..],
+        [
+            "launch",
+            "--name",
+            "agent-1",
+            "--cpus",
+            "2",
+            "--memory",
+            "4G",
+            "--disk",
+            "20G",
+            "--cloud-init",
+            "-",
+            "24.04"
+        ]
+    );
+    assert_eq!(
+        fake.stdins(),
+        vec![Some(b"#cloud-config\npackages: [git]\n".to_vec())]
+    );
+}
+
+#[tokio::test]
+async fn name_only_launch_still_works() {
+    let (_temp_dir, app, fake) = build_cli_backed_app();
+
+    let (status, _) = post_vms(&app, r#"{"name":"agent-1"}"#).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(fake.calls()[0][1..], ["launch", "--name", "agent-1"]);
+}
+
+#[tokio::test]
+async fn invalid_launch_options_are_422() {
+    let cases = [
+        (r#"{"name":"agent-1","cpus":0}"#, "cpus must be between 1"),
+        (
+            r#"{"name":"agent-1","memory":"lots"}"#,
+            "memory 'lots' is not a size",
+        ),
+        (r#"{"name":"agent-1","disk":"0"}"#, "disk '0' is not a size"),
+        (
+            r#"{"name":"agent-1","image":"24.04; rm -rf /"}"#,
+            "image '24.04; rm -rf /'",
+        ),
+        (
+            r#"{"name":"agent-1","cloud_init":"  "}"#,
+            "cloud_init must not be empty",
+        ),
+        (r#"{"name":"-agent"}"#, "invalid VM name"),
+    ];
+
+    for (body, expected) in cases {
+        let (_temp_dir, app, fake) = build_cli_backed_app();
+
+        let (status, json) = post_vms(&app, body).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+        assert_eq!(json["code"], "validation_failed", "{body}");
+        let message = json["message"].as_str().unwrap();
+        assert!(message.contains(expected), "{body}: {message}");
+        assert!(fake.calls().is_empty(), "{body} reached multipass");
+    }
+}
+
+#[tokio::test]
+async fn unknown_fields_are_422() {
+    let (_temp_dir, app, fake) = build_cli_backed_app();
+
+    let (status, json) = post_vms(&app, r#"{"name":"agent-1","memmory":"4G"}"#).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "validation_failed");
+    assert!(json["message"].as_str().unwrap().contains("memmory"));
+    assert!(fake.calls().is_empty());
+}
+
+// ============================================================================
+// Health probes
+// ============================================================================
+
+#[tokio::test]
+async fn livez_and_health_answer_even_when_multipass_is_down() {
+    for uri in ["/livez", "/health"] {
+        let multipass = FakeMultipass::new()
+            .with_version_response(Err(VmError::CommandIo("No such file".to_owned())));
+        let (_temp_dir, app) = build_multipass_app(multipass);
+
+        let (status, body) = get(&app, uri).await;
+
+        assert_eq!(status, StatusCode::OK, "{uri}");
+        assert_eq!(body["status"], "ok", "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn readyz_is_ok_when_multipassd_reports_a_version() {
+    let multipass = FakeMultipass::new();
+    let (_temp_dir, app) = build_multipass_app(multipass.clone());
+
+    let (status, body) = get(&app, "/readyz").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["multipass"], "1.14.0");
+    assert_eq!(body["multipassd"], "1.14.0");
+    assert_eq!(multipass.calls(), vec!["version"]);
+}
+
+#[tokio::test]
+async fn readyz_is_unavailable_when_multipass_fails() {
+    let multipass = FakeMultipass::new()
+        .with_version_response(Err(VmError::CommandIo("No such file".to_owned())));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = get(&app, "/readyz").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "multipass_unavailable");
+}
+
+#[tokio::test]
+async fn readyz_is_unavailable_without_a_daemon_version() {
+    let multipass = FakeMultipass::new().with_version_response(Ok(MultipassVersions {
+        client: Some("1.14.0".to_owned()),
+        daemon: None,
+    }));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = get(&app, "/readyz").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "multipass_unavailable");
+}
+
+fn version_calls(multipass: &FakeMultipass) -> usize {
+    multipass
+        .calls()
+        .iter()
+        .filter(|call| *call == "version")
+        .count()
+}
+
+#[tokio::test]
+async fn plain_health_does_not_probe_multipass() {
+    let multipass = FakeMultipass::new();
+    let (_temp_dir, app) = build_multipass_app(multipass.clone());
+
+    let (status, body) = get(&app, "/health").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::json!({ "status": "ok" }));
+    assert!(multipass.calls().is_empty());
+}
+
+#[tokio::test]
+async fn deep_health_is_ok_when_multipass_answers() {
+    let (_temp_dir, app) = build_multipass_app(FakeMultipass::new());
+
+    let (status, body) = get(&app, "/health?deep=true").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+    let checks = &body["checks"];
+    assert_eq!(checks["multipass_binary"]["ok"], true);
+    assert_eq!(checks["multipass_binary"]["detail"], "1.14.0");
+    assert_eq!(checks["daemon"]["ok"], true);
+    assert_eq!(checks["list"]["ok"], true);
+    assert!(checks["list_latency_ms"].is_u64());
+}
+
+#[tokio::test]
+async fn deep_health_is_degraded_without_multipass() {
+    let multipass = FakeMultipass::new()
+        .with_version_response(Err(VmError::CommandIo("No such file".to_owned())))
+        .with_list_response(Err(VmError::CommandIo("No such file".to_owned())));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = get(&app, "/health?deep=true").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "multipass_unavailable");
+    assert_eq!(body["details"]["status"], "degraded");
+    let checks = &body["details"]["checks"];
+    assert_eq!(checks["multipass_binary"]["ok"], false);
+    assert_eq!(checks["daemon"]["ok"], false);
+    assert_eq!(checks["list"]["ok"], false);
+    assert_eq!(checks["list_latency_ms"], Value::Null);
+}
+
+#[tokio::test]
+async fn deep_health_is_degraded_when_list_fails() {
+    let multipass = FakeMultipass::new().with_list_response(Err(VmError::CommandFailed {
+        action: "list",
+        status_code: 1,
+        stderr: "list failed: cannot connect to the multipass socket".to_owned(),
+    }));
+    let (_temp_dir, app) = build_multipass_app(multipass);
+
+    let (status, body) = get(&app, "/health?deep=true").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["details"]["checks"]["multipass_binary"]["ok"], true);
+    assert_eq!(body["details"]["checks"]["list"]["ok"], false);
+}
+
+#[tokio::test(start_paused = true)]
+async fn deep_health_reuses_a_recent_probe() {
+    let multipass = FakeMultipass::new();
+    let (_temp_dir, app) = build_multipass_app(multipass.clone());
+
+    get(&app, "/health?deep=true").await;
+    get(&app, "/health?deep=true").await;
+    assert_eq!(version_calls(&multipass), 1);
+
+    tokio::time::advance(HEALTH_PROBE_TTL).await;
+    get(&app, "/health?deep=true").await;
+    assert_eq!(version_calls(&multipass), 2);
+}
+
+#[tokio::test]
+async fn deep_must_be_a_bool() {
+    let (_temp_dir, app) = build_multipass_app(FakeMultipass::new());
+
+    let (status, body) = get(&app, "/health?deep=yes").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "validation_failed");
+}
+
+// ============================================================================
+// State filter
+// ============================================================================
+
+fn mixed_states() -> FakeVmApi {
+    FakeVmApi::new().with_list_response(vec![
+        VmSummary::minimal("agent-1", "Running"),
+        VmSummary::minimal("agent-2", "Stopped"),
+        VmSummary::minimal("agent-3", "Suspended"),
+        VmSummary::minimal("agent-4", "Running"),
+    ])
+}
+
+fn names(vms: &Value) -> Vec<&str> {
+    vms.as_array()
+        .unwrap()
+        .iter()
+        .map(|vm| vm["name"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn without_a_filter_every_vm_is_listed() {
+    let (_temp_dir, app) = build_app(mixed_states());
+
+    let (status, vms) = get(&app, "/vms").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&vms), ["agent-1", "agent-2", "agent-3", "agent-4"]);
+}
+
+#[tokio::test]
+async fn a_single_state_filter_is_case_insensitive() {
+    let (_temp_dir, app) = build_app(mixed_states());
+
+    for uri in [
+        "/vms?state=Running",
+        "/vms?state=running",
+        "/vms?state=RUNNING",
+    ] {
+        let (status, vms) = get(&app, uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(names(&vms), ["agent-1", "agent-4"], "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn repeated_state_filters_match_any_of_them() {
+    let (_temp_dir, app) = build_app(mixed_states());
+
+    let (status, vms) = get(&app, "/vms?state=stopped&state=Suspended").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&vms), ["agent-2", "agent-3"]);
+
+    let (_, vms) = get(&app, "/vms?state=Deleted").await;
+    assert!(names(&vms).is_empty());
+}
+
+#[tokio::test]
+async fn unknown_states_are_a_bad_request() {
+    let (_temp_dir, app) = build_app(mixed_states());
+
+    let (status, body) = get(&app, "/vms?state=Running&state=asleep").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(
+        body["message"],
+        "unknown VM state 'asleep' (expected one of: Running, Stopped, Suspended, Starting, Deleted)"
+    );
+}
+
+// ============================================================================
+// Pagination
+// ============================================================================
+
+/// Five VMs, deliberately not in name order.
+fn fleet() -> FakeVmApi {
+    FakeVmApi::new().with_list_response(vec![
+        VmSummary::minimal("vm-c", "Running"),
+        VmSummary::minimal("vm-a", "Running"),
+        VmSummary::minimal("vm-e", "Stopped"),
+        VmSummary::minimal("vm-b", "Running"),
+        VmSummary::minimal("vm-d", "Stopped"),
+    ])
+}
+
+/// `items` names and the rest of a page envelope.
+fn page(body: &Value) -> (Vec<&str>, &Value, &Value) {
+    let names = body["items"]
+        .as_array()
+        .expect("a page envelope")
+        .iter()
+        .map(|vm| vm["name"].as_str().unwrap())
+        .collect();
+    (names, &body["total"], &body["next_offset"])
+}
+
+#[tokio::test]
+async fn without_pagination_params_the_bare_array_is_kept() {
+    let (_temp_dir, app) = build_app(fleet());
+
+    let (status, body) = get(&app, "/vms").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<&str> = body
+        .as_array()
+        .expect("a bare array")
+        .iter()
+        .map(|vm| vm["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["vm-c", "vm-a", "vm-e", "vm-b", "vm-d"]);
+}
+
+#[tokio::test]
+async fn pages_are_sorted_by_name_and_point_at_the_next_one() {
+    let (_temp_dir, app) = build_app(fleet());
+
+    let (status, first) = get(&app, "/vms?limit=2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page(&first), (vec!["vm-a", "vm-b"], &json!(5), &json!(2)));
+
+    let (_, second) = get(&app, "/vms?limit=2&offset=2").await;
+    assert_eq!(page(&second), (vec!["vm-c", "vm-d"], &json!(5), &json!(4)));
+
+    let (_, last) = get(&app, "/vms?limit=2&offset=4").await;
+    assert_eq!(page(&last), (vec!["vm-e"], &json!(5), &Value::Null));
+}
+
+#[tokio::test]
+async fn boundary_offsets() {
+    let (_temp_dir, app) = build_app(fleet());
+
+    // An offset alone switches to the envelope and returns the rest.
+    let (_, body) = get(&app, "/vms?offset=0").await;
+    assert_eq!(page(&body).0.len(), 5);
+    assert_eq!(body["next_offset"], Value::Null);
+
+    // A page ending exactly at the last VM has no next page.
+    let (_, body) = get(&app, "/vms?limit=5").await;
+    assert_eq!(body["next_offset"], Value::Null);
+
+    for uri in ["/vms?offset=5", "/vms?offset=500&limit=10"] {
+        let (status, body) = get(&app, uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page(&body), (vec![], &json!(5), &Value::Null), "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn pagination_applies_after_the_state_filter() {
+    let (_temp_dir, app) = build_app(fleet());
+
+    let (_, body) = get(&app, "/vms?state=stopped&limit=1").await;
+
+    assert_eq!(page(&body), (vec!["vm-d"], &json!(2), &json!(1)));
+}
+
+#[tokio::test]
+async fn bad_limits_and_offsets_are_a_bad_request() {
+    let (_temp_dir, app) = build_app(fleet());
+
+    for (uri, message) in [
+        (
+            "/vms?limit=0",
+            "limit must be a whole number of at least 1, got '0'",
+        ),
+        (
+            "/vms?limit=ten",
+            "limit must be a whole number of at least 1, got 'ten'",
+        ),
+        ("/vms?offset=-1", "offset must be a whole number, got '-1'"),
+    ] {
+        let (status, body) = get(&app, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["message"], message);
+    }
+}
+
+#[tokio::test]
+async fn vms_multipass_failed_to_list_are_reported_as_warnings() {
+    let (_temp_dir, app) = build_app(
+        fleet().with_list_warnings(vec![r#"instance "vm-f" is in an unknown state"#.to_owned()]),
+    );
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/vms").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let warnings: Vec<&str> = response
+        .headers()
+        .get_all("warning")
+        .iter()
+        .map(|value| value.to_str().unwrap())
+        .collect();
+    assert_eq!(
+        warnings,
+        [r#"199 safepaw "instance 'vm-f' is in an unknown state""#]
+    );
+
+    let (_, body) = get(&app, "/vms?limit=2").await;
+    assert_eq!(page(&body).0, ["vm-a", "vm-b"]);
+    assert_eq!(
+        body["warnings"],
+        json!([r#"instance "vm-f" is in an unknown state"#])
+    );
+    // Without any, the page envelope is unchanged.
+    let (_temp_dir, app) = build_app(fleet());
+    let (_, body) = get(&app, "/vms?limit=2").await;
+    assert!(body.get("warnings").is_none());
+}
+
+// ============================================================================
+// Background jobs
+// ============================================================================
+
+async fn launch(app: &axum::Router, uri: &str, name: &str) -> (StatusCode, Option<String>, Value) {
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "name": name }).to_string()))
+        .unwrap();
+    let (status, headers, body) = send_request(app, request).await;
+    let location = headers
+        .get(header::LOCATION)
+        .map(|value| value.to_str().unwrap().to_owned());
+    (status, location, body)
+}
+
+async fn delete(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    send(app, "DELETE", uri, "").await
+}
+
+/// Polls `status_url` until the job has finished.
+async fn wait_for_job(app: &axum::Router, status_url: &str) -> Value {
+    for _ in 0..200 {
+        let (status, job) = get(app, status_url).await;
+        assert_eq!(status, StatusCode::OK, "{job}");
+        if ["succeeded", "failed", "cancelled"].contains(&job["state"].as_str().unwrap()) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("job at {status_url} never finished");
+}
+
+#[tokio::test]
+async fn async_launch_returns_a_job_that_records_progress_and_outcome() {
+    let vm_api = FakeVmApi::new()
+        .with_launch_delay(Duration::from_millis(100))
+        .with_launch_progress(&["Retrieving image: 50%", "Starting agent-1"]);
+    let (_temp_dir, app) = build_app(vm_api.clone());
+
+    let (status, location, accepted) = launch(&app, "/vms?async=true", "agent-1").await;
+
+    assert_eq!(status, StatusCode::ACCEPTED, "{accepted}");
+    let job_id = accepted["job_id"].as_str().expect("a job id");
+    let status_url = format!("/jobs/{job_id}");
+    assert_eq!(accepted["status_url"], status_url);
+    assert_eq!(location.as_deref(), Some(status_url.as_str()));
+
+    let job = wait_for_job(&app, &status_url).await;
+    assert_eq!(job["id"], job_id);
+    assert_eq!(job["kind"], "launch");
+    assert_eq!(job["vm_name"], "agent-1");
+    assert_eq!(job["state"], "succeeded");
+    assert_eq!(job["progress"], "Starting agent-1");
+    assert_eq!(job["message"], "VM 'agent-1' launched successfully");
+    assert!(job.get("error").is_none(), "{job}");
+    for field in ["created_at", "started_at", "finished_at"] {
+        assert!(job[field].is_string(), "{field} missing from {job}");
+    }
+    assert!(job["created_at"].as_str() <= job["finished_at"].as_str());
+    assert_eq!(vm_api.calls(), vec!["launch:agent-1"]);
+}
+
+#[tokio::test]
+async fn failed_async_launch_records_the_error() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new().with_launch_failure("agent-1"));
+
+    let (status, _, accepted) = launch(&app, "/vms?async=true", "agent-1").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let job = wait_for_job(&app, accepted["status_url"].as_str().unwrap()).await;
+    assert_eq!(job["state"], "failed");
+    assert!(
+        job["error"]
+            .as_str()
+            .unwrap()
+            .contains("launch of 'agent-1' failed"),
+        "{job}"
+    );
+    assert!(job["finished_at"].is_string());
+    assert!(job.get("message").is_none(), "{job}");
+}
+
+#[tokio::test]
+async fn server_flag_makes_launches_async_unless_the_request_opts_out() {
+    let (_temp_dir, app) = build_app_with(FakeVmApi::new(), |state| state.with_async_launch(true));
+
+    let (status, _, body) = launch(&app, "/vms", "agent-1").await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+    wait_for_job(&app, body["status_url"].as_str().unwrap()).await;
+
+    let (status, _, body) = launch(&app, "/vms?async=false", "agent-2").await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    assert_eq!(body["message"], "VM 'agent-2' launched successfully");
+}
+
+#[tokio::test]
+async fn unknown_jobs_and_bad_flags_are_rejected() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let (status, body) = get(&app, "/jobs/no-such-job").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "job_not_found");
+
+    let (status, _, body) = launch(&app, "/vms?async=soon", "agent-1").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "validation_failed");
+}
+
+#[tokio::test]
+async fn jobs_are_listed_oldest_first_with_their_kind() {
+    let vm_api = FakeVmApi::new();
+    let (_temp_dir, app) = build_app(vm_api.clone());
+
+    let (_, _, launched) = launch(&app, "/vms?async=true", "agent-1").await;
+    wait_for_job(&app, launched["status_url"].as_str().unwrap()).await;
+    let (status, deleted) = delete(&app, "/vms/agent-1?async=true").await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{deleted}");
+    wait_for_job(&app, deleted["status_url"].as_str().unwrap()).await;
+
+    let (status, jobs) = get(&app, "/jobs").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let summary: Vec<(&str, &str, &str)> = jobs
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|job| {
+            (
+                job["kind"].as_str().unwrap(),
+                job["vm_name"].as_str().unwrap(),
+                job["state"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("launch", "agent-1", "succeeded"),
+            ("delete", "agent-1", "succeeded")
+        ]
+    );
+    assert_eq!(jobs[0]["id"], launched["job_id"]);
+    assert_eq!(vm_api.calls(), vec!["launch:agent-1", "delete:agent-1"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn finished_jobs_are_collected_after_the_retention_period() {
+    let (_temp_dir, state) = build_state(FakeVmApi::new());
+    let state = state.with_jobs(JobStore::new(Duration::from_secs(60), 1));
+    tokio::spawn(state.jobs().collect_garbage());
+    let app = create_api_router(state);
+
+    let (_, _, accepted) = launch(&app, "/vms?async=true", "agent-1").await;
+    let status_url = accepted["status_url"].as_str().unwrap();
+    wait_for_job(&app, status_url).await;
+
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(get(&app, status_url).await.0, StatusCode::OK);
+
+    tokio::time::sleep(Duration::from_secs(61)).await;
+    assert_eq!(get(&app, status_url).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(get(&app, "/jobs").await.1, json!([]));
+}
+
+#[tokio::test]
+async fn only_queued_jobs_can_be_cancelled() {
+    let vm_api = FakeVmApi::new().with_launch_delay(Duration::from_millis(200));
+    let (_temp_dir, state) = build_state(vm_api.clone());
+    let state = state.with_jobs(JobStore::new(Duration::from_secs(60), 1));
+    let app = create_api_router(state);
+
+    let (_, _, first) = launch(&app, "/vms?async=true", "agent-1").await;
+    let (_, _, second) = launch(&app, "/vms?async=true", "agent-2").await;
+    let first_url = first["status_url"].as_str().unwrap();
+    let second_url = second["status_url"].as_str().unwrap();
+    // The single slot goes to the first launch; the second waits for it.
+    while get(&app, first_url).await.1["state"] != "running" {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(get(&app, second_url).await.1["state"], "queued");
+
+    let (status, body) = delete(&app, first_url).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+    assert_eq!(body["code"], "job_not_queued");
+
+    let (status, cancelled) = delete(&app, second_url).await;
+    assert_eq!(status, StatusCode::OK, "{cancelled}");
+    assert_eq!(cancelled["state"], "cancelled");
+    assert!(cancelled["started_at"].is_null());
+    assert!(cancelled["finished_at"].is_string());
+
+    assert_eq!(wait_for_job(&app, first_url).await["state"], "succeeded");
+    assert_eq!(get(&app, second_url).await.1["state"], "cancelled");
+    assert_eq!(vm_api.calls(), vec!["launch:agent-1"]);
+
+    let (status, _) = delete(&app, "/jobs/no-such-job").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// ============================================================================
+// Info cache
+// ============================================================================
+
+fn info_calls(api: &FakeVmApi) -> usize {
+    api.calls()
+        .iter()
+        .filter(|call| call.starts_with("info:"))
+        .count()
+}
+
+#[tokio::test(start_paused = true)]
+async fn rapid_info_requests_hit_the_backend_once() {
+    let api = FakeVmApi::new();
+    let (_temp_dir, app) = build_app_with(api.clone(), |state| {
+        state.with_info_cache(Duration::from_secs(5))
+    });
+
+    assert_eq!(get(&app, "/vms/agent-1").await.0, StatusCode::OK);
+    assert_eq!(get(&app, "/vms/agent-1").await.0, StatusCode::OK);
+    assert_eq!(info_calls(&api), 1);
+
+    // Other VMs have their own entries.
+    get(&app, "/vms/agent-2").await;
+    assert_eq!(info_calls(&api), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn entries_expire_after_the_ttl() {
+    let api = FakeVmApi::new();
+    let (_temp_dir, app) = build_app_with(api.clone(), |state| {
+        state.with_info_cache(Duration::from_secs(5))
+    });
+
+    get(&app, "/vms/agent-1").await;
+    tokio::time::advance(Duration::from_secs(5)).await;
+    get(&app, "/vms/agent-1").await;
+
+    assert_eq!(info_calls(&api), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn operations_on_a_vm_invalidate_its_entry() {
+    let api = FakeVmApi::new();
+    let (_temp_dir, app) = build_app_with(api.clone(), |state| {
+        state.with_info_cache(Duration::from_secs(60))
+    });
+
+    for (method, uri) in [
+        ("POST", "/vms/agent-1/stop"),
+        ("POST", "/vms/agent-1/start"),
+        ("DELETE", "/vms/agent-1"),
+    ] {
+        get(&app, "/vms/agent-1").await;
+        let cached = info_calls(&api);
+        assert_eq!(send(&app, method, uri, "").await.0, StatusCode::OK);
+        get(&app, "/vms/agent-1").await;
+        assert_eq!(info_calls(&api), cached + 1, "{method} {uri}");
+    }
+}
+
+#[tokio::test]
+async fn a_zero_ttl_leaves_caching_off() {
+    let api = FakeVmApi::new();
+    let (_temp_dir, app) =
+        build_app_with(api.clone(), |state| state.with_info_cache(Duration::ZERO));
+
+    get(&app, "/vms/agent-1").await;
+    get(&app, "/vms/agent-1").await;
+
+    assert_eq!(info_calls(&api), 2);
+}
+
+#[test]
+fn info_cache_ttl_defaults_to_off() {
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "start"])
+        .unwrap();
+    let start = matches.subcommand_matches("start").unwrap();
+    assert_eq!(start.get_one::<u64>("info-cache-ttl"), Some(&0));
+
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "start", "--info-cache-ttl", "3"])
+        .unwrap();
+    let start = matches.subcommand_matches("start").unwrap();
+    assert_eq!(start.get_one::<u64>("info-cache-ttl"), Some(&3));
+}
+
+// ============================================================================
+// Rate limits
+// ============================================================================
+
+fn build_limited_app(limits: &str) -> (TempDir, axum::Router) {
+    let limits = RateLimits::parse(limits).expect("limits should parse");
+    build_app_with(FakeVmApi::new(), |state| state.with_rate_limits(limits))
+}
+
+/// A request as if it came from `client`; launches get a valid body.
+fn client_request(method: &str, uri: &str, client: &str) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    if method == "POST" {
+        request = request.header(header::CONTENT_TYPE, "application/json");
+    }
+    let body = if uri == "/vms" && method == "POST" {
+        Body::from(json!({ "name": "agent" }).to_string())
+    } else {
+        Body::empty()
+    };
+    let mut request = request.body(body).unwrap();
+    let addr: SocketAddr = client.parse().unwrap();
+    request.extensions_mut().insert(ConnectInfo(addr));
+    request
+}
+
+const CLIENT: &str = "10.0.0.1:40000";
+
+#[test]
+fn limits_parse_each_class() {
+    let limits = RateLimits::parse("launches=2/min, mutations=30/min,reads=10/sec").unwrap();
+    assert_eq!(
+        limits.launches,
+        Some(RateLimit {
+            requests: 2,
+            per: Duration::from_secs(60)
+        })
+    );
+    assert_eq!(limits.mutations.unwrap().requests, 30);
+    assert_eq!(limits.reads.unwrap().per, Duration::from_secs(1));
+    assert!(RateLimits::parse("").unwrap().is_disabled());
+}
+
+#[test]
+fn invalid_limits_are_rejected() {
+    for spec in [
+        "launches",
+        "launches=0/min",
+        "launches=2/week",
+        "writes=2/min",
+    ] {
+        assert!(RateLimits::parse(spec).is_err(), "{spec} should not parse");
+    }
+}
+
+#[test]
+fn config_validate_reports_a_bad_rate_limit() {
+    let config: Config = toml::from_str("[server]\nrate_limit = \"launches=fast\"\n").unwrap();
+
+    let issues = config.validate();
+
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].is_error());
+    assert_eq!(issues[0].key, "server.rate_limit");
+}
+
+#[tokio::test(start_paused = true)]
+async fn launches_over_the_limit_get_429_with_retry_after() {
+    let (_temp_dir, app) = build_limited_app("launches=2/min");
+
+    for _ in 0..2 {
+        let (status, headers, _) = send_request(&app, client_request("POST", "/vms", CLIENT)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(!headers.contains_key(header::RETRY_AFTER));
+    }
+    let (status, headers, body) = send_request(&app, client_request("POST", "/vms", CLIENT)).await;
+
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(headers[header::RETRY_AFTER], "30");
+    assert_eq!(body["code"], "rate_limited");
+    assert_eq!(body["details"]["retry_after"], 30);
+}
+
+#[tokio::test(start_paused = true)]
+async fn tokens_refill_over_time() {
+    let (_temp_dir, app) = build_limited_app("launches=2/min");
+    for _ in 0..2 {
+        send_request(&app, client_request("POST", "/vms", CLIENT)).await;
+    }
+    assert_eq!(
+        send_request(&app, client_request("POST", "/vms", CLIENT))
+            .await
+            .0,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    tokio::time::advance(Duration::from_secs(30)).await;
+
+    assert_eq!(
+        send_request(&app, client_request("POST", "/vms", CLIENT))
+            .await
+            .0,
+        StatusCode::CREATED
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn each_client_ip_has_its_own_budget() {
+    let (_temp_dir, app) = build_limited_app("launches=1/min");
+
+    assert_eq!(
+        send_request(&app, client_request("POST", "/vms", CLIENT))
+            .await
+            .0,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        send_request(&app, client_request("POST", "/vms", CLIENT))
+            .await
+            .0,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(
+        send_request(&app, client_request("POST", "/vms", "10.0.0.2:40000"))
+            .await
+            .0,
+        StatusCode::CREATED
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn classes_are_limited_separately() {
+    let (_temp_dir, app) = build_limited_app("launches=1/min,mutations=30/min");
+
+    send_request(&app, client_request("POST", "/vms", CLIENT)).await;
+    assert_eq!(
+        send_request(&app, client_request("DELETE", "/vms/agent", CLIENT))
+            .await
+            .0,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_ne!(
+        send_request(&app, client_request("POST", "/vms/agent/start", CLIENT))
+            .await
+            .0,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    // Reads have no limit configured.
+    for _ in 0..50 {
+        assert_eq!(
+            send_request(&app, client_request("GET", "/vms", CLIENT))
+                .await
+                .0,
+            StatusCode::OK
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn health_is_never_limited() {
+    let (_temp_dir, app) = build_limited_app("reads=1/min");
+
+    for _ in 0..5 {
+        assert_eq!(
+            send_request(&app, client_request("GET", "/health", CLIENT))
+                .await
+                .0,
+            StatusCode::OK
+        );
+    }
+    send_request(&app, client_request("GET", "/vms", CLIENT)).await;
+    assert_eq!(
+        send_request(&app, client_request("GET", "/vms", CLIENT))
+            .await
+            .0,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[tokio::test]
+async fn rate_limiting_is_off_by_default() {
+    let (_temp_dir, app) = build_limited_app("");
+
+    for _ in 0..20 {
+        assert_eq!(
+            send_request(&app, client_request("POST", "/vms", CLIENT))
+                .await
+                .0,
+            StatusCode::CREATED
+        );
+    }
+}
+
+// ============================================================================
+// Single-port mode
+// ============================================================================
+
+fn build_single_port_app() -> (TempDir, axum::Router) {
+    let (temp_dir, state) = build_state(
+        FakeVmApi::new().with_list_response(vec![VmSummary::minimal("agent-1", "Running")]),
+    );
+    (
+        temp_dir,
+        create_single_port_router(state, create_ui_router()),
+    )
+}
+
+async fn get_text(app: &axum::Router, uri: &str) -> (StatusCode, String) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+#[tokio::test]
+async fn api_is_nested_under_api_and_the_ui_serves_the_rest() {
+    let (_temp_dir, app) = build_single_port_app();
+
+    let (status, vms) = get_text(&app, "/api/vms").await;
+    assert_eq!(status, StatusCode::OK);
+    let vms: Value = serde_json::from_str(&vms).unwrap();
+    assert_eq!(vms[0]["name"], "agent-1");
+
+    let (status, health) = get_text(&app, "/api/health").await;
+    assert_eq!(status, StatusCode::OK, "{health}");
+
+    let (status, html) = get_text(&app, "/index.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("SafePaw Village"));
+}
+
+#[tokio::test]
+async fn async_launch_status_url_keeps_the_api_prefix() {
+    let (_temp_dir, app) = build_single_port_app();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/vms?async=true")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"name": "agent-2"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let accepted: Value = serde_json::from_slice(&body).unwrap();
+    let status_url = accepted["status_url"].as_str().unwrap();
+
+    assert!(status_url.starts_with("/api/jobs/"), "{status_url}");
+    let (status, job) = get_text(&app, status_url).await;
+    assert_eq!(status, StatusCode::OK, "{job}");
+}
+
+#[tokio::test]
+async fn config_json_points_the_ui_at_the_api() {
+    let (_temp_dir, app) = build_single_port_app();
+
+    let (status, config) = get_text(&app, "/config.json").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_str::<Value>(&config).unwrap(),
+        serde_json::json!({"api_base": "/api"})
+    );
+}
+
+#[tokio::test]
+async fn unknown_api_routes_keep_the_json_not_found() {
+    let (_temp_dir, app) = build_single_port_app();
+
+    let (status, body) = get_text(&app, "/api/nope").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["code"], "route_not_found");
+}
+
+/// Sends a bare HTTP/1.1 GET over a fresh connection and returns the raw
+/// response.
+async fn raw_get(addr: std::net::SocketAddr, path: &str) -> String {
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+    String::from_utf8_lossy(&response).into_owned()
+}
+
+#[tokio::test]
+async fn one_listener_serves_both_the_api_and_the_ui() {
+    let (_temp_dir, app) = build_single_port_app();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let vms = raw_get(addr, "/api/vms").await;
+    let index = raw_get(addr, "/index.html").await;
+
+    assert!(vms.starts_with("HTTP/1.1 200"), "{vms}");
+    assert!(vms.contains("agent-1"));
+    assert!(index.starts_with("HTTP/1.1 200"), "{index}");
+    assert!(index.contains("SafePaw Village"));
+}
+
+#[test]
+fn banner_puts_the_api_under_the_ui_port() {
+    let banner = StartupBanner::single_port("127.0.0.1", 8888);
+
+    assert_eq!(banner.ui_url, "http://127.0.0.1:8888");
+    assert_eq!(banner.api_url, "http://127.0.0.1:8888/api");
+    assert_eq!(banner.health_url, "http://127.0.0.1:8888/api/health");
+}
+
+#[test]
+fn single_port_conflicts_with_api_only_flags() {
+    for flag in [["--api-port", "9000"], ["--bind-api", "127.0.0.1"]] {
+        let err = build_cli()
+            .try_get_matches_from(["safepaw", "start", "--single-port", flag[0], flag[1]])
+            .expect_err("conflicting flags");
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "start", "--single-port"])
+        .unwrap();
+    assert!(
+        matches
+            .subcommand_matches("start")
+            .unwrap()
+            .get_flag("single-port")
+    );
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use common::{FakeVmApi, build_state};
+use safepaw::{
+    jobs::{JobKind, JobState, JobStore},
+    server::{AppState, PendingWork, SHUTDOWN_CANCEL_GRACE, create_api_router},
+};
+use serde_json::{Value, json};
+use tokio::time::Instant;
+use tower::ServiceExt;
+
+fn launch_request(uri: &str, name: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "name": name }).to_string()))
+        .unwrap()
+}
+
+async fn send(app: axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn wait_for_request(state: &AppState) {
+    while state.pending().requests == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn drain_returns_at_once_when_idle() {
+    let (_temp_dir, state) = build_state(FakeVmApi::new());
+    let started = Instant::now();
+
+    assert!(state.drain(Duration::from_secs(30)).await);
+    assert_eq!(started.elapsed(), Duration::ZERO);
+}
+
+#[tokio::test(start_paused = true)]
+async fn drain_waits_for_a_slow_background_launch() {
+    let api = FakeVmApi::new().with_launch_delay(Duration::from_secs(10));
+    let (_temp_dir, state) = build_state(api);
+    let app = create_api_router(state.clone());
+
+    let (status, body) = send(app, launch_request("/vms?async=true", "agent-1")).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let job_id = body["job_id"].as_str().unwrap().to_owned();
+    let started = Instant::now();
+
+    assert!(state.drain(Duration::from_secs(30)).await);
+
+    let waited = started.elapsed();
+    assert!(
+        waited >= Duration::from_secs(10) && waited < Duration::from_secs(11),
+        "waited {waited:?}"
+    );
+    assert_eq!(
+        state.jobs().get(&job_id).unwrap().state,
+        JobState::Succeeded
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn drain_cancels_a_background_launch_after_the_timeout() {
+    let api = FakeVmApi::new().with_launch_delay(Duration::from_secs(120));
+    let (_temp_dir, state) = build_state(api);
+    let app = create_api_router(state.clone());
+
+    let (_, body) = send(app, launch_request("/vms?async=true", "agent-1")).await;
+    let job_id = body["job_id"].as_str().unwrap().to_owned();
+    let started = Instant::now();
+
+    assert!(!state.drain(Duration::from_secs(30)).await);
+
+    let waited = started.elapsed();
+    assert!(
+        waited >= Duration::from_secs(30)
+            && waited < Duration::from_secs(30) + SHUTDOWN_CANCEL_GRACE,
+        "waited {waited:?}"
+    );
+    let job = state.jobs().get(&job_id).unwrap();
+    assert_eq!(job.state, JobState::Failed);
+    assert!(job.error.unwrap().contains("cancelled"));
+    assert!(state.pending().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn drain_waits_for_an_in_flight_request() {
+    let api = FakeVmApi::new().with_launch_delay(Duration::from_secs(10));
+    let (_temp_dir, state) = build_state(api);
+    let app = create_api_router(state.clone());
+
+    let request = tokio::spawn(send(app, launch_request("/vms", "agent-1")));
+    wait_for_request(&state).await;
+
+    assert!(state.drain(Duration::from_secs(30)).await);
+    let (status, _) = request.await.unwrap();
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test(start_paused = true)]
+async fn drain_cancels_an_in_flight_request_after_the_timeout() {
+    let api = FakeVmApi::new().with_launch_delay(Duration::from_secs(120));
+    let (_temp_dir, state) = build_state(api);
+    let app = create_api_router(state.clone());
+
+    let request = tokio::spawn(send(app, launch_request("/vms", "agent-1")));
+    wait_for_request(&state).await;
+    let started = Instant::now();
+
+    assert!(!state.drain(Duration::from_secs(5)).await);
+
+    assert!(started.elapsed() < Duration::from_secs(5) + SHUTDOWN_CANCEL_GRACE);
+    let (status, body) = request.await.unwrap();
+    assert_ne!(status, StatusCode::CREATED);
+    assert!(
+        body["message"].as_str().unwrap().contains("cancelled"),
+        "{body}"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn cancel_all_cancels_queued_jobs() {
+    let jobs = Arc::new(JobStore::new(Duration::from_secs(60), 1));
+    let running = jobs.spawn(JobKind::Launch, "agent-1", |progress| async move {
+        progress.cancel_token().cancelled().await;
+        Err("cancelled".to_owned())
+    });
+    let queued = jobs.spawn(JobKind::Launch, "agent-2", |_| async {
+        Ok("done".to_owned())
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    jobs.cancel_all();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(jobs.get(&running).unwrap().state, JobState::Failed);
+    assert_eq!(jobs.get(&queued).unwrap().state, JobState::Cancelled);
+    assert!(jobs.unfinished().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn pending_work_names_the_jobs_it_waits_for() {
+    let jobs = Arc::new(JobStore::default());
+    jobs.spawn(JobKind::Launch, "agent-1", |_| std::future::pending());
+    let pending = PendingWork {
+        requests: 2,
+        jobs: jobs.unfinished(),
+    };
+
+    assert_eq!(
+        pending.to_string(),
+        "2 request(s) and 1 job(s): launch agent-1"
+    );
+    assert_eq!(PendingWork::default().to_string(), "0 request(s)");
+}
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use safepaw::cli::build_cli;
+use safepaw::server::{StartupBanner, TlsConfig};
+
+// Self-signed certificate for `localhost`, only ever used by these tests.
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/tls")
+        .join(name)
+}
+
+#[tokio::test]
+async fn tls_config_loads_pem_certificate_and_key() {
+    TlsConfig::new(fixture("cert.pem"), fixture("key.pem"))
+        .load()
+        .await
+        .expect("fixture certificate should load");
+}
+
+#[tokio::test]
+async fn tls_config_reports_missing_files_by_path() {
+    let err = TlsConfig::new(fixture("missing.pem"), fixture("key.pem"))
+        .load()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("TLS certificate not found: "));
+    assert!(err.to_string().contains("missing.pem"));
+
+    let err = TlsConfig::new(fixture("cert.pem"), fixture("missing.pem"))
+        .load()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("TLS key not found: "));
+}
+
+#[tokio::test]
+async fn tls_config_rejects_files_that_are_not_pem() {
+    let dir = tempfile::tempdir().unwrap();
+    let junk = dir.path().join("junk.pem");
+    std::fs::write(&junk, "not a certificate").unwrap();
+
+    let err = TlsConfig::new(&junk, fixture("key.pem"))
+        .load()
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().starts_with("invalid TLS certificate"));
+}
+
+#[test]
+fn banner_uses_https_for_tls_servers() {
+    let banner = StartupBanner::new("127.0.0.1", 8888, 8889).with_tls(false);
+    assert_eq!(banner.api_url, "https://127.0.0.1:8889");
+    assert_eq!(banner.health_url, "https://127.0.0.1:8889/health");
+    assert_eq!(banner.ui_url, "http://127.0.0.1:8888");
+
+    let banner = StartupBanner::new("127.0.0.1", 8888, 8889).with_tls(true);
+    assert_eq!(banner.ui_url, "https://127.0.0.1:8888");
+}
+
+#[test]
+fn tls_flags_must_come_together() {
+    for args in [
+        &["safepaw", "start", "--tls-cert", "cert.pem"][..],
+        &["safepaw", "start", "--tls-key", "key.pem"][..],
+        &["safepaw", "start", "--tls-ui"][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err());
+    }
+    assert!(
+        build_cli()
+            .try_get_matches_from([
+                "safepaw",
+                "start",
+                "--tls-cert",
+                "cert.pem",
+                "--tls-key",
+                "key.pem",
+                "--tls-ui",
+            ])
+            .is_ok()
+    );
+}
@@ -0,0 +1,27 @@
+use safepaw::util::{format_bytes, format_percent};
+
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+
+#[test]
+fn picks_the_unit_at_each_boundary() {
+    assert_eq!(format_bytes(0), "0 B");
+    assert_eq!(format_bytes(1023), "1023 B");
+    assert_eq!(format_bytes(KIB), "1.0 KiB");
+    assert_eq!(format_bytes(MIB - 1), "1.0 MiB");
+    assert_eq!(format_bytes(MIB), "1.0 MiB");
+    assert_eq!(format_bytes(512 * MIB), "512.0 MiB");
+    assert_eq!(format_bytes(GIB - MIB), "1023.0 MiB");
+    assert_eq!(format_bytes(GIB), "1.0 GiB");
+    assert_eq!(format_bytes(GIB + 400 * MIB), "1.4 GiB");
+    assert_eq!(format_bytes(1024 * GIB), "1.0 TiB");
+}
+
+#[test]
+fn percent_has_one_decimal_and_handles_zero_total() {
+    assert_eq!(format_percent(1, 3).as_deref(), Some("33.3%"));
+    assert_eq!(format_percent(0, 10).as_deref(), Some("0.0%"));
+    assert_eq!(format_percent(10, 10).as_deref(), Some("100.0%"));
+    assert_eq!(format_percent(5, 0), None);
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::FakeExecutor;
+use safepaw::vm::{
+    CommandExecutor, CommandOutput, Multipass, MultipassCli, RetryConfig, SshCommandExecutor,
+    SshConfig, SshTransportError, VmError,
+};
+use tokio_util::sync::CancellationToken;
+
+fn lab_config() -> SshConfig {
+    let mut config = SshConfig::new("lab.local");
+    config.user = Some("ops".to_owned());
+    config.identity_file = Some(PathBuf::from("/home/me/.ssh/lab"));
+    config
+}
+
+#[tokio::test]
+async fn ssh_executor_quotes_remote_arguments() {
+    let fake = FakeExecutor::new(vec![CommandOutput::success("hi there\n")]);
+    let executor = SshCommandExecutor::with_executor(lab_config(), fake.clone());
+
+    let output = executor
+        .run(
+            "multipass",
+            &[
+                "exec".to_owned(),
+                "agent-1".to_owned(),
+                "--".to_owned(),
+                "echo".to_owned(),
+                "hi there".to_owned(),
+                "it's".to_owned(),
+            ],
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("ssh run should succeed");
+
+    assert_eq!(output.stdout, "hi there\n");
+    assert_eq!(
+        fake.calls(),
+        vec![vec![
+            "ssh".to_owned(),
+            "-o".to_owned(),
+            "BatchMode=yes".to_owned(),
+            "-o".to_owned(),
+            "ConnectTimeout=10".to_owned(),
+            "-i".to_owned(),
+            "/home/me/.ssh/lab".to_owned(),
+            "ops@lab.local".to_owned(),
+            "--".to_owned(),
+            r#"multipass exec agent-1 -- echo 'hi there' 'it'\''s'"#.to_owned(),
+        ]]
+    );
+}
+
+#[tokio::test]
+async fn ssh_failures_are_reported_separately_from_multipass_failures() {
+    let fake = FakeExecutor::new(vec![
+        CommandOutput {
+            status_code: 255,
+            stdout: String::new(),
+            stderr: "ops@lab.local: Permission denied (publickey).\n".to_owned(),
+            truncated: false,
+        },
+        CommandOutput {
+            status_code: 2,
+            stdout: String::new(),
+            stderr: "instance \"agent-1\" does not exist\n".to_owned(),
+            truncated: false,
+        },
+    ]);
+    let executor = SshCommandExecutor::with_executor(lab_config(), fake.clone());
+    let multipass = MultipassCli::new_with_retry(executor, RetryConfig::disabled());
+
+    let transport_err = multipass
+        .start("agent-1")
+        .await
+        .expect_err("ssh should fail");
+    let multipass_err = multipass
+        .start("agent-1")
+        .await
+        .expect_err("start should fail");
+
+    match transport_err {
+        VmError::Transport(message) => {
+            assert!(message.contains("ops@lab.local"));
+            assert!(message.contains("Permission denied"));
+        }
+        other => panic!("expected transport error, got {other:?}"),
+    }
+    assert!(matches!(
+        multipass_err,
+        VmError::CommandFailed {
+            action: "start",
+            status_code: 2,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn ssh_executor_surfaces_typed_transport_error() {
+    let fake = FakeExecutor::new(vec![CommandOutput {
+        status_code: 255,
+        stdout: String::new(),
+        stderr: "Host key verification failed.\n".to_owned(),
+        truncated: false,
+    }]);
+    let executor = SshCommandExecutor::with_executor(SshConfig::new("lab.local"), fake);
+
+    let err = executor
+        .run("multipass", &["list".to_owned()], &CancellationToken::new())
+        .await
+        .expect_err("ssh should fail");
+
+    let transport = err
+        .downcast_ref::<SshTransportError>()
+        .expect("error should be an ssh transport error");
+    assert_eq!(transport.destination, "lab.local");
+    assert_eq!(transport.stderr, "Host key verification failed.");
+}
+
+#[tokio::test]
+async fn exec_commands_exiting_255_keep_their_output() {
+    let fake = FakeExecutor::new(vec![
+        CommandOutput {
+            status_code: 255,
+            stdout: "partial\n".to_owned(),
+            stderr: "fatal: giving up\n".to_owned(),
+            truncated: false,
+        },
+        CommandOutput {
+            status_code: 255,
+            stdout: String::new(),
+            stderr: "ssh: connect to host lab.local port 22: Connection refused\n".to_owned(),
+            truncated: false,
+        },
+    ]);
+    let executor = SshCommandExecutor::with_executor(lab_config(), fake);
+    let multipass = MultipassCli::new_with_retry(executor, RetryConfig::disabled());
+
+    let output = multipass
+        .exec("agent-1", &["deploy".to_owned()])
+        .await
+        .expect("the command ran and exited 255");
+    let err = multipass
+        .exec("agent-1", &["deploy".to_owned()])
+        .await
+        .expect_err("ssh never reached the host");
+
+    assert_eq!(output.status_code, 255);
+    assert_eq!(output.stdout, "partial\n");
+    assert!(matches!(err, VmError::Transport(message) if message.contains("Connection refused")));
+}
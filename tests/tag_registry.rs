@@ -0,0 +1,169 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::FakeMultipass;
+use safepaw::cli::{RenderOptions, build_cli, run_vm_subcommand};
+use safepaw::tags::TagRegistry;
+use safepaw::vm::{LocalVmApi, VmApi, VmSummary};
+
+fn tags(values: &[&str]) -> Vec<String> {
+    values.iter().map(|tag| tag.to_string()).collect()
+}
+
+#[test]
+fn missing_registry_file_is_empty() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let registry = TagRegistry::new(temp_dir.path().join("tags.json"));
+
+    assert!(registry.tags("agent-1").unwrap().is_empty());
+    assert!(!registry.path().exists());
+}
+
+#[test]
+fn tags_are_persisted_and_deduplicated() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join(".safepaw").join("tags.json");
+
+    let registry = TagRegistry::new(&path);
+    assert_eq!(
+        registry
+            .tag("agent-1", &tags(&["project-a", "gpu"]))
+            .unwrap(),
+        tags(&["gpu", "project-a"])
+    );
+    registry.tag("agent-1", &tags(&["gpu"])).unwrap();
+    registry.tag("agent-2", &tags(&["project-b"])).unwrap();
+
+    let reopened = TagRegistry::new(&path);
+    assert_eq!(
+        reopened.tags("agent-1").unwrap(),
+        tags(&["gpu", "project-a"])
+    );
+    assert_eq!(
+        reopened
+            .untag("agent-1", &tags(&["gpu", "unknown"]))
+            .unwrap(),
+        tags(&["project-a"])
+    );
+    assert_eq!(
+        reopened.untag("agent-2", &tags(&["project-b"])).unwrap(),
+        tags(&[])
+    );
+
+    let stored: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(stored, serde_json::json!({"agent-1": ["project-a"]}));
+}
+
+#[test]
+fn blank_tags_are_rejected() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let registry = TagRegistry::new(temp_dir.path().join("tags.json"));
+
+    assert!(registry.tag("agent-1", &tags(&["two words"])).is_err());
+    assert!(registry.tag("agent-1", &tags(&[" "])).is_err());
+    assert!(registry.tags("agent-1").unwrap().is_empty());
+}
+
+#[test]
+fn concurrent_writers_do_not_lose_tags() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let registry = Arc::new(TagRegistry::new(temp_dir.path().join("tags.json")));
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let registry = registry.clone();
+            std::thread::spawn(move || {
+                registry.tag("agent-1", &[format!("tag-{i}")]).unwrap();
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(registry.tags("agent-1").unwrap().len(), 8);
+}
+
+#[tokio::test]
+async fn vm_list_filters_by_tag_and_delete_clears_tags() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let registry = Arc::new(TagRegistry::new(temp_dir.path().join("tags.json")));
+    let multipass = FakeMultipass::new().with_list(vec![
+        VmSummary::minimal("agent-1", "Running"),
+        VmSummary::minimal("agent-2", "Running"),
+        VmSummary::minimal("agent-3", "Stopped"),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass)).with_tag_registry(registry.clone());
+
+    for args in [
+        ["safeclaw", "vm", "tag", "agent-1", "project-a"],
+        ["safeclaw", "vm", "tag", "agent-3", "project-a"],
+        ["safeclaw", "vm", "tag", "agent-2", "project-b"],
+    ] {
+        let matches = build_cli().try_get_matches_from(args).unwrap();
+        run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+            .await
+            .expect("tag command failed");
+    }
+
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "list",
+            "--tag",
+            "project-a",
+            "-o",
+            "plain",
+        ])
+        .unwrap();
+    let vm_matches = matches.subcommand_matches("vm").unwrap();
+    let lines = run_vm_subcommand(vm_matches, &api)
+        .await
+        .expect("list command failed")
+        .render(&RenderOptions::from_matches(vm_matches));
+    assert_eq!(lines, vec!["agent-1 | Running", "agent-3 | Stopped"]);
+
+    api.delete("agent-1").await.unwrap();
+    assert!(registry.tags("agent-1").unwrap().is_empty());
+    assert_eq!(registry.tags("agent-3").unwrap(), tags(&["project-a"]));
+}
+
+#[tokio::test]
+async fn vm_untag_reports_remaining_tags() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let registry = Arc::new(TagRegistry::new(temp_dir.path().join("tags.json")));
+    registry.tag("agent-1", &tags(&["a", "b"])).unwrap();
+    let api = LocalVmApi::new(Arc::new(FakeMultipass::new())).with_tag_registry(registry);
+
+    let matches = build_cli()
+        .try_get_matches_from(["safeclaw", "vm", "untag", "agent-1", "a"])
+        .unwrap();
+    let lines = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .expect("untag command failed")
+        .into_lines();
+
+    assert_eq!(lines, vec!["VM 'agent-1' tags: b"]);
+}
+
+#[tokio::test]
+async fn delete_many_clears_tags_of_every_deleted_vm() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let registry = Arc::new(TagRegistry::new(temp_dir.path().join("tags.json")));
+    registry.tag("agent-1", &tags(&["a"])).unwrap();
+    registry.tag("agent-2", &tags(&["b"])).unwrap();
+    let multipass = FakeMultipass::new();
+    let api = LocalVmApi::new(Arc::new(multipass.clone())).with_tag_registry(registry.clone());
+
+    assert!(api.delete_many(&[]).await.is_err());
+    api.delete_many(&tags(&["agent-1", "agent-2"]))
+        .await
+        .unwrap();
+
+    assert_eq!(multipass.calls(), vec!["delete:agent-1,agent-2"]);
+    assert!(registry.tags("agent-1").unwrap().is_empty());
+    assert!(registry.tags("agent-2").unwrap().is_empty());
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::FakeMultipass;
+use safepaw::audit::{AuditAction, AuditOutcome, AuditRecord, AuditSink, FileAuditSink};
+use safepaw::vm::{LaunchSpec, LocalVmApi, StopOptions, VmApi, VmError};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Default)]
+struct MemorySink {
+    records: Mutex<Vec<AuditRecord>>,
+}
+
+impl MemorySink {
+    fn records(&self) -> Vec<AuditRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl AuditSink for MemorySink {
+    fn record(&self, record: &AuditRecord) -> anyhow::Result<()> {
+        self.records.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+struct BrokenSink;
+
+impl AuditSink for BrokenSink {
+    fn record(&self, _record: &AuditRecord) -> anyhow::Result<()> {
+        anyhow::bail!("disk full")
+    }
+}
+
+fn audited_api(multipass: FakeMultipass) -> (LocalVmApi, Arc<MemorySink>) {
+    let sink = Arc::new(MemorySink::default());
+    let api = LocalVmApi::new(Arc::new(multipass)).with_audit_sink(sink.clone());
+    (api, sink)
+}
+
+#[tokio::test]
+async fn launch_produces_one_audit_record() {
+    let (api, sink) = audited_api(FakeMultipass::new().with_launch_response(Ok(())));
+
+    api.launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .unwrap();
+
+    let records = sink.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].action, AuditAction::Launch);
+    assert_eq!(records[0].vm_name, "agent-1");
+    assert_eq!(records[0].outcome, AuditOutcome::Success);
+    assert_eq!(records[0].error, None);
+}
+
+#[tokio::test]
+async fn failures_are_recorded_with_the_error() {
+    let (api, sink) = audited_api(FakeMultipass::new().with_stop_response(Err(
+        VmError::CommandFailed {
+            action: "stop",
+            status_code: 2,
+            stderr: "instance \"ghost\" does not exist".to_owned(),
+        },
+    )));
+
+    assert!(api.stop("ghost", &StopOptions::default()).await.is_err());
+
+    let records = sink.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].action, AuditAction::Stop);
+    assert_eq!(records[0].outcome, AuditOutcome::Failure);
+    assert!(
+        records[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("does not exist")
+    );
+}
+
+#[tokio::test]
+async fn every_lifecycle_operation_is_recorded() {
+    let (api, sink) = audited_api(FakeMultipass::new());
+
+    api.start("agent-1").await.unwrap();
+    api.stop("agent-1", &StopOptions::default()).await.unwrap();
+    api.restart("agent-1").await.unwrap();
+    api.delete_many(&["agent-1".to_owned(), "agent-2".to_owned()])
+        .await
+        .unwrap();
+    api.info("agent-1").await.ok();
+
+    let actions: Vec<(AuditAction, String)> = sink
+        .records()
+        .into_iter()
+        .map(|record| (record.action, record.vm_name))
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            (AuditAction::Start, "agent-1".to_owned()),
+            (AuditAction::Stop, "agent-1".to_owned()),
+            (AuditAction::Restart, "agent-1".to_owned()),
+            (AuditAction::Delete, "agent-1".to_owned()),
+            (AuditAction::Delete, "agent-2".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_failing_sink_never_fails_the_operation() {
+    let api = LocalVmApi::new(Arc::new(FakeMultipass::new())).with_audit_sink(Arc::new(BrokenSink));
+
+    api.start("agent-1")
+        .await
+        .expect("start should still succeed");
+}
+
+#[test]
+fn file_sink_appends_json_lines() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("logs").join("audit.log");
+    let sink = FileAuditSink::new(&path);
+    let failed: anyhow::Result<()> = Err(anyhow::anyhow!("boom"));
+
+    sink.record(&AuditRecord::new(
+        AuditAction::Launch,
+        "agent-1",
+        &Ok::<(), anyhow::Error>(()),
+    ))
+    .unwrap();
+    sink.record(&AuditRecord::new(AuditAction::Delete, "agent-1", &failed))
+        .unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<serde_json::Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["action"], "launch");
+    assert_eq!(lines[0]["vm_name"], "agent-1");
+    assert_eq!(lines[0]["outcome"], "success");
+    assert!(lines[0]["timestamp"].is_string());
+    assert!(lines[0].get("error").is_none());
+    assert_eq!(lines[1]["action"], "delete");
+    assert_eq!(lines[1]["outcome"], "failure");
+    assert_eq!(lines[1]["error"], "boom");
+}
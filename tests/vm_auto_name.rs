@@ -0,0 +1,111 @@
+mod common;
+
+use common::FakeVmApi;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use safepaw::cli::{CommandResult, EXIT_USAGE, build_cli, exit_code, run_vm_subcommand};
+use safepaw::vm::{VmSummary, generate_vm_name, validate_vm_name};
+use serde_json::Value;
+
+#[test]
+fn generated_names_are_deterministic_for_a_seed() {
+    let first = generate_vm_name("agent", &[], &mut StdRng::seed_from_u64(7));
+    let again = generate_vm_name("agent", &[], &mut StdRng::seed_from_u64(7));
+
+    assert_eq!(first, again);
+    assert!(first.starts_with("agent-"));
+    assert_eq!(first.len(), "agent-".len() + 6);
+    validate_vm_name(&first).expect("generated names should be valid VM names");
+}
+
+#[test]
+fn generated_names_skip_taken_names_and_count_bases() {
+    let taken_name = generate_vm_name("agent", &[], &mut StdRng::seed_from_u64(7));
+
+    for taken in [taken_name.clone(), format!("{taken_name}-1")] {
+        let name = generate_vm_name("agent", &[taken], &mut StdRng::seed_from_u64(7));
+        assert_ne!(name, taken_name);
+    }
+
+    // A longer name that merely starts with the candidate is no clash.
+    let unrelated = format!("{taken_name}x");
+    let name = generate_vm_name("agent", &[unrelated], &mut StdRng::seed_from_u64(7));
+    assert_eq!(name, taken_name);
+}
+
+#[test]
+fn auto_and_a_positional_name_are_exclusive() {
+    for args in [
+        &["safepaw", "vm", "launch", "web", "--auto"][..],
+        &["safepaw", "vm", "launch", "web", "--name-prefix", "ci"][..],
+        &["safepaw", "vm", "launch"][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err(), "{args:?}");
+    }
+}
+
+async fn launch(args: &[&str], api: &FakeVmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli().try_get_matches_from(args).unwrap();
+    run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api).await
+}
+
+#[tokio::test]
+async fn auto_launches_under_a_generated_name() {
+    let api = FakeVmApi::new().with_list_response(vec![VmSummary::minimal("agent-1", "Running")]);
+
+    let output = launch(&["safepaw", "vm", "-o", "json", "launch", "--auto"], &api)
+        .await
+        .expect("auto launch should succeed");
+
+    let CommandResult::Json(value) = output else {
+        panic!("expected JSON output");
+    };
+    let name = value["name"].as_str().unwrap();
+    assert!(name.starts_with("agent-"));
+    assert_eq!(
+        api.calls(),
+        vec!["list".to_owned(), format!("launch:{name}")]
+    );
+}
+
+#[tokio::test]
+async fn name_prefix_sets_the_generated_prefix() {
+    let api = FakeVmApi::new();
+
+    let output = launch(
+        &[
+            "safepaw",
+            "vm",
+            "-o",
+            "json",
+            "launch",
+            "--name-prefix",
+            "ci",
+        ],
+        &api,
+    )
+    .await
+    .unwrap();
+
+    let CommandResult::Json(value) = output else {
+        panic!("expected JSON output");
+    };
+    assert!(
+        value["name"]
+            .as_str()
+            .is_some_and(|name| name.starts_with("ci-"))
+    );
+    assert_eq!(value["ok"], Value::Bool(true));
+}
+
+#[tokio::test]
+async fn invalid_prefixes_are_usage_errors() {
+    let err = launch(
+        &["safepaw", "vm", "launch", "--name-prefix", "9lives"],
+        &FakeVmApi::new(),
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(exit_code(&err), EXIT_USAGE);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::multipass_cli_with_outputs;
+use safepaw::cli::{EXIT_USAGE, build_cli, exit_code, run_vm_subcommand};
+use safepaw::vm::{CloudConfig, CommandOutput, LocalVmApi};
+
+#[test]
+fn cloud_config_lists_only_the_given_keys() {
+    let config = CloudConfig {
+        timezone: Some("Europe/Berlin".to_owned()),
+        packages: vec!["git".to_owned(), "curl".to_owned()],
+        run_cmds: vec!["echo ready > /tmp/ready".to_owned(), "true".to_owned()],
+    };
+
+    assert_eq!(
+        config.to_yaml().unwrap(),
+        "#cloud-config\n\
+         timezone: Europe/Berlin\n\
+         packages:\n\
+         - git\n\
+         - curl\n\
+         runcmd:\n\
+         - echo ready > /tmp/ready\n\
+         - 'true'\n"
+    );
+    let packages_only = CloudConfig {
+        packages: vec!["git".to_owned()],
+        ..CloudConfig::default()
+    };
+    assert_eq!(
+        packages_only.to_yaml().unwrap(),
+        "#cloud-config\npackages:\n- git\n"
+    );
+    assert!(CloudConfig::default().is_empty());
+}
+
+#[tokio::test]
+async fn launch_flags_feed_the_cloud_config_on_stdin() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safepaw",
+            "vm",
+            "launch",
+            "agent-1",
+            "--timezone",
+            "UTC",
+            "--package",
+            "git",
+            "--run-cmd",
+            "git --version",
+        ])
+        .unwrap();
+
+    run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .expect("launch should work");
+
+    assert_eq!(
+        fake.calls()[0],
+        [
+            "multipass",
+            "launch",
+            "--name",
+            "agent-1",
+            "--cloud-init",
+            "-"
+        ]
+        .map(String::from)
+    );
+    let stdin = String::from_utf8(fake.stdins()[0].clone().expect("cloud-init on stdin")).unwrap();
+    assert_eq!(
+        stdin,
+        "#cloud-config\ntimezone: UTC\npackages:\n- git\nruncmd:\n- git --version\n"
+    );
+}
+
+#[tokio::test]
+async fn blank_cloud_config_values_are_usage_errors() {
+    let (multipass, fake) = multipass_cli_with_outputs(Vec::new());
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "launch", "agent-1", "--package", " "])
+        .unwrap();
+
+    let err = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .expect_err("blank package");
+
+    assert_eq!(exit_code(&err), EXIT_USAGE);
+    assert!(format!("{err:#}").contains("package names must not be empty"));
+    assert!(fake.calls().is_empty());
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use common::{FakeVmApi, build_app};
+use futures::StreamExt;
+use safepaw::{
+    events::{EventBus, VmEvent, VmEventKind, watch_vm_states},
+    vm::{VmState, VmSummary},
+};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+fn request(method: &str, uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("last-event-id", "41")
+        .body(Body::from(body.to_owned()))
+        .unwrap()
+}
+
+/// Reads SSE chunks until one carries an event, returning its `event:` name
+/// and parsed `data:`.
+async fn next_event(
+    body: &mut (impl futures::Stream<Item = Result<axum::body::Bytes, axum::Error>> + Unpin),
+) -> (String, VmEvent) {
+    let mut buffer = String::new();
+    loop {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+            .await
+            .expect("an event should arrive")
+            .expect("the stream should stay open")
+            .unwrap();
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        if let Some(end) = buffer.find("\n\n") {
+            let frame = &buffer[..end];
+            let field = |name: &str| {
+                frame
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .map(str::to_owned)
+            };
+            if let (Some(kind), Some(data)) = (field("event: "), field("data: ")) {
+                return (kind, serde_json::from_str(&data).unwrap());
+            }
+            buffer.drain(..end + 2);
+        }
+    }
+}
+
+fn vm(name: &str, state: &str) -> VmSummary {
+    VmSummary::minimal(name, state)
+}
+
+#[tokio::test]
+async fn launch_publishes_an_event_to_subscribers() {
+    let (_temp_dir, app) = build_app(FakeVmApi::new());
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/events", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut events = response.into_body().into_data_stream();
+
+    let launched = app
+        .clone()
+        .oneshot(request("POST", "/vms", r#"{"name":"agent-1"}"#))
+        .await
+        .unwrap();
+    assert_eq!(launched.status(), StatusCode::CREATED);
+
+    let (kind, event) = next_event(&mut events).await;
+    assert_eq!(kind, "vm.launched");
+    assert_eq!(event.kind, VmEventKind::Launched);
+    assert_eq!(event.vm_name, "agent-1");
+
+    app.oneshot(request("DELETE", "/vms/agent-1", ""))
+        .await
+        .unwrap();
+    let (kind, deleted) = next_event(&mut events).await;
+    assert_eq!(kind, "vm.deleted");
+    assert!(deleted.id > event.id);
+}
+
+#[tokio::test(start_paused = true)]
+async fn poller_reports_state_differences_between_snapshots() {
+    let api = FakeVmApi::new().with_list_sequence(vec![
+        Ok(vec![vm("web", "Running"), vm("db", "Running")]),
+        Ok(vec![vm("web", "Stopped"), vm("cache", "Starting")]),
+    ]);
+    let bus = Arc::new(EventBus::default());
+    let mut receiver = bus.subscribe();
+    let cancel = CancellationToken::new();
+    let poller = tokio::spawn(watch_vm_states(
+        Arc::new(api),
+        bus,
+        Duration::from_secs(1),
+        cancel.clone(),
+    ));
+
+    let mut changes = Vec::new();
+    for _ in 0..3 {
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.kind, VmEventKind::StateChanged);
+        changes.push((event.vm_name, event.previous_state, event.state));
+    }
+    cancel.cancel();
+    poller.await.unwrap();
+
+    let running = Some(VmState::Running.to_string());
+    assert_eq!(
+        changes,
+        vec![
+            (
+                "cache".to_owned(),
+                None,
+                Some(VmState::Starting.to_string())
+            ),
+            ("db".to_owned(), running.clone(), None),
+            (
+                "web".to_owned(),
+                running,
+                Some(VmState::Stopped.to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn event_ids_increase_and_serialize_with_a_type() {
+    let bus = EventBus::default();
+
+    let first = bus.publish(VmEventKind::Started, "agent-1");
+    let second = bus.publish(VmEventKind::Stopped, "agent-1");
+
+    assert_eq!(second.id, first.id + 1);
+    let json = serde_json::to_value(&second).unwrap();
+    assert_eq!(json["type"], "vm.stopped");
+    assert_eq!(json["vm_name"], "agent-1");
+    assert!(json["timestamp"].is_string());
+    assert!(json.get("state").is_none());
+}
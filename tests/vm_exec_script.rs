@@ -0,0 +1,227 @@
+mod common;
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use common::{FakeVmApi, multipass_cli_with_outputs};
+use safepaw::cli::{
+    CommandResult, EXIT_USAGE, RemoteExit, build_cli, exit_code, run_vm_subcommand,
+};
+use safepaw::vm::{CommandOutput, LocalVmApi, ScriptOptions, remote_script_path, run_script};
+use tokio_util::sync::CancellationToken;
+
+fn failed(status_code: i32) -> CommandOutput {
+    CommandOutput {
+        status_code,
+        ..CommandOutput::success("")
+    }
+}
+
+fn collect() -> (Arc<Mutex<Vec<String>>>, impl Fn(&str) + Send + Sync) {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let lines = lines.clone();
+        move |line: &str| lines.lock().unwrap().push(line.to_owned())
+    };
+    (lines, sink)
+}
+
+#[test]
+fn remote_paths_are_unique_and_keep_the_file_name() {
+    let first = remote_script_path(Path::new("scripts/setup.sh"));
+    let second = remote_script_path(Path::new("scripts/setup.sh"));
+
+    assert!(first.starts_with("/tmp/safepaw-"));
+    assert!(first.ends_with("-setup.sh"));
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn script_is_uploaded_run_and_removed() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(""),
+        CommandOutput::success(""),
+        CommandOutput::success("installing\ndone\n"),
+        CommandOutput::success(""),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let (lines, sink) = collect();
+    let opts = ScriptOptions {
+        args: vec!["--verbose".to_owned(), "prod".to_owned()],
+        keep: false,
+    };
+
+    let status = run_script(
+        &api,
+        "agent-1",
+        Path::new("setup.sh"),
+        &opts,
+        &sink,
+        &CancellationToken::new(),
+    )
+    .await
+    .expect("script should run");
+
+    assert_eq!(status, 0);
+    assert_eq!(*lines.lock().unwrap(), vec!["installing", "done"]);
+    let calls = fake.calls();
+    assert_eq!(calls.len(), 4);
+    let remote = calls[0][3].strip_prefix("agent-1:").unwrap().to_owned();
+    assert_eq!(calls[0][..3], ["multipass", "transfer", "setup.sh"]);
+    assert_eq!(
+        calls[1][1..],
+        ["exec", "agent-1", "--", "chmod", "+x", remote.as_str()]
+    );
+    assert_eq!(
+        calls[2][1..],
+        [
+            "exec",
+            "agent-1",
+            "--",
+            "sh",
+            "-c",
+            "\"$0\" \"$@\" 2>&1",
+            remote.as_str(),
+            "--verbose",
+            "prod"
+        ]
+    );
+    assert_eq!(
+        calls[3][1..],
+        ["exec", "agent-1", "--", "rm", "-f", remote.as_str()]
+    );
+}
+
+#[tokio::test]
+async fn failing_scripts_report_their_status_and_are_still_removed() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(""),
+        CommandOutput::success(""),
+        failed(3),
+        CommandOutput::success(""),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let (_lines, sink) = collect();
+
+    let status = run_script(
+        &api,
+        "agent-1",
+        Path::new("setup.sh"),
+        &ScriptOptions::default(),
+        &sink,
+        &CancellationToken::new(),
+    )
+    .await
+    .expect("a failing script is not an error in itself");
+
+    assert_eq!(status, 3);
+    assert_eq!(fake.calls()[3][4..6], ["rm", "-f"]);
+}
+
+#[tokio::test]
+async fn keep_skips_cleanup() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success(""),
+        CommandOutput::success(""),
+        CommandOutput::success(""),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+    let (_lines, sink) = collect();
+    let opts = ScriptOptions {
+        keep: true,
+        ..ScriptOptions::default()
+    };
+
+    run_script(
+        &api,
+        "agent-1",
+        Path::new("setup.sh"),
+        &opts,
+        &sink,
+        &CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(fake.calls().len(), 3);
+}
+
+#[tokio::test]
+async fn the_script_status_becomes_the_exit_code() {
+    let api = FakeVmApi::new()
+        .with_exec_response(Ok(CommandOutput::success("")))
+        .with_exec_response(Ok(failed(7)));
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safepaw", "vm", "exec", "agent-1", "--script", "setup.sh", "--args", "-x",
+        ])
+        .unwrap();
+
+    let err = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .unwrap_err();
+
+    assert!(err.is::<RemoteExit>());
+    assert_eq!(exit_code(&err), 7);
+    let commands: Vec<Vec<String>> = api.exec_calls().into_iter().map(|c| c.command).collect();
+    assert_eq!(commands[1].last().map(String::as_str), Some("-x"));
+    assert_eq!(commands[2][..2], ["rm", "-f"]);
+}
+
+#[tokio::test]
+async fn a_failing_command_status_becomes_the_exit_code() {
+    let api = FakeVmApi::new()
+        .with_exec_response(Ok(failed(3)))
+        .with_exec_response(Ok(failed(3)));
+    let run = |args: &'static [&'static str]| {
+        let matches = build_cli().try_get_matches_from(args).unwrap();
+        let api = &api;
+        async move { run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api).await }
+    };
+
+    let err = run(&["safepaw", "vm", "exec", "agent-1", "--", "false"])
+        .await
+        .unwrap_err();
+    let json = run(&[
+        "safepaw", "vm", "-o", "json", "exec", "agent-1", "--", "false",
+    ])
+    .await
+    .unwrap();
+
+    assert_eq!(exit_code(&err), 3);
+    let CommandResult::Json(json) = json else {
+        panic!("expected JSON output");
+    };
+    assert_eq!(json["status_code"], 3);
+}
+
+#[tokio::test]
+async fn script_rejects_json_output() {
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safepaw", "vm", "-o", "json", "exec", "agent-1", "--script", "setup.sh",
+        ])
+        .unwrap();
+
+    let err = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &FakeVmApi::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(exit_code(&err), EXIT_USAGE);
+}
+
+#[test]
+fn script_conflicts_with_a_command_and_stdin() {
+    for args in [
+        &[
+            "safepaw", "vm", "exec", "agent-1", "--script", "a.sh", "--", "ls",
+        ][..],
+        &[
+            "safepaw", "vm", "exec", "agent-1", "--script", "a.sh", "--stdin",
+        ][..],
+        &["safepaw", "vm", "exec", "agent-1", "--keep", "--", "ls"][..],
+        &["safepaw", "vm", "exec", "agent-1", "--stdin", "--keep"][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err(), "{args:?}");
+    }
+}
@@ -1,8 +1,9 @@
 mod common;
 
 use common::multipass_cli_with_outputs;
-use safepaw::vm::{CommandOutput, LocalVmApi, Multipass, VmApi};
+use safepaw::vm::{CommandOutput, LocalVmApi, Multipass, VmApi, VmError};
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 // ============================================================================
 // Multipass trait tests for exec and transfer
@@ -14,6 +15,7 @@ async fn exec_sends_correct_multipass_command() {
         status_code: 0,
         stdout: "/usr/bin/zeroclaw\n".to_owned(),
         stderr: String::new(),
+        truncated: false,
     }]);
 
     let output = multipass
@@ -40,19 +42,89 @@ async fn exec_sends_correct_multipass_command() {
 #[tokio::test]
 async fn exec_returns_non_zero_exit_code_when_command_fails() {
     let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
-        status_code: 1,
+        status_code: 127,
         stdout: String::new(),
-        stderr: "command not found\n".to_owned(),
+        stderr: "bash: nonexistent-command: command not found\n".to_owned(),
+        truncated: false,
     }]);
 
-    let result = multipass
+    let output = multipass
         .exec("test-vm", &["nonexistent-command".to_string()])
-        .await;
+        .await
+        .expect("a failing command is still a successful exec");
 
-    // exec should return error for non-zero exit codes
-    assert!(result.is_err());
-    let err = result.unwrap_err();
-    assert!(err.to_string().contains("exec"));
+    assert_eq!(output.status_code, 127);
+    assert!(output.stderr.contains("command not found"));
+}
+
+#[tokio::test]
+async fn exec_in_a_missing_vm_is_not_found() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
+        status_code: 2,
+        stdout: String::new(),
+        stderr: "exec failed: instance \"ghost\" does not exist\n".to_owned(),
+        truncated: false,
+    }]);
+
+    let err = multipass
+        .exec("ghost", &["true".to_string()])
+        .await
+        .expect_err("missing VM");
+
+    assert!(matches!(&err, VmError::NotFound { name } if name == "ghost"));
+    assert!(err.is_not_found());
+}
+
+#[tokio::test]
+async fn exec_output_mentioning_a_missing_file_is_not_a_missing_vm() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
+        status_code: 1,
+        stdout: String::new(),
+        stderr: "error: path /srv/app does not exist\n".to_owned(),
+        truncated: false,
+    }]);
+
+    let output = multipass
+        .exec("test-vm", &["deploy".to_string()])
+        .await
+        .expect("the command ran");
+
+    assert_eq!(output.status_code, 1);
+}
+
+#[tokio::test]
+async fn exec_output_mentioning_a_failed_connection_is_the_commands_own() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
+        status_code: 7,
+        stdout: String::new(),
+        stderr: "curl: (7) Failed to connect to host port 443: Connection refused\n".to_owned(),
+        truncated: false,
+    }]);
+
+    let output = multipass
+        .exec("test-vm", &["curl".to_string(), "https://host".to_string()])
+        .await
+        .expect("the command ran");
+
+    assert_eq!(output.status_code, 7);
+    assert!(output.stderr.contains("Failed to connect"));
+}
+
+#[tokio::test]
+async fn exec_without_the_daemon_socket_is_unavailable() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
+        status_code: 2,
+        stdout: String::new(),
+        stderr: "exec failed: cannot connect to the multipass socket\n".to_owned(),
+        truncated: false,
+    }]);
+
+    let err = multipass
+        .exec("test-vm", &["true".to_string()])
+        .await
+        .expect_err("multipass never ran the command");
+
+    assert!(err.is_unavailable());
 }
 
 #[tokio::test]
@@ -83,12 +155,40 @@ async fn exec_with_multiple_args_works() {
     assert_eq!(calls[0][6], "echo hello > /tmp/test.txt");
 }
 
+#[tokio::test]
+async fn exec_with_stdin_passes_bytes_to_executor() {
+    let (multipass_cli, fake) =
+        multipass_cli_with_outputs(vec![CommandOutput::success(""), CommandOutput::success("")]);
+    let vm_api = LocalVmApi::new(Arc::new(multipass_cli));
+
+    vm_api
+        .exec_with_stdin(
+            "test-vm",
+            &["tee".to_string(), "/etc/config".to_string()],
+            b"key = value\n",
+        )
+        .await
+        .expect("exec with stdin should work");
+    vm_api
+        .exec("test-vm", &["true".to_string()])
+        .await
+        .expect("exec should work");
+
+    assert_eq!(fake.stdins(), vec![Some(b"key = value\n".to_vec()), None]);
+    assert_eq!(fake.calls()[0][4..], ["tee", "/etc/config"]);
+}
+
 #[tokio::test]
 async fn transfer_sends_correct_multipass_command() {
     let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
 
     multipass
-        .transfer("test-vm", "/local/path/script.sh", "/tmp/script.sh")
+        .transfer(
+            "test-vm",
+            "/local/path/script.sh",
+            "/tmp/script.sh",
+            &CancellationToken::new(),
+        )
         .await
         .expect("transfer should work");
 
@@ -109,10 +209,16 @@ async fn transfer_returns_error_when_file_not_found() {
         status_code: 1,
         stdout: String::new(),
         stderr: "file not found: /nonexistent/file.txt".to_owned(),
+        truncated: false,
     }]);
 
     let result = multipass
-        .transfer("test-vm", "/nonexistent/file.txt", "/tmp/file.txt")
+        .transfer(
+            "test-vm",
+            "/nonexistent/file.txt",
+            "/tmp/file.txt",
+            &CancellationToken::new(),
+        )
         .await;
 
     assert!(result.is_err());
@@ -131,6 +237,7 @@ async fn vm_api_exec_delegates_to_multipass() {
         status_code: 0,
         stdout: "Hello from VM\n".to_owned(),
         stderr: String::new(),
+        truncated: false,
     }]);
     let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
     let vm_api = LocalVmApi::new(multipass);
@@ -166,7 +273,12 @@ async fn vm_api_transfer_delegates_to_multipass() {
     let vm_api = LocalVmApi::new(multipass);
 
     vm_api
-        .transfer("test-vm", "/local/file.txt", "/remote/file.txt")
+        .transfer(
+            "test-vm",
+            "/local/file.txt",
+            "/remote/file.txt",
+            &CancellationToken::new(),
+        )
         .await
         .expect("transfer should work");
 
@@ -182,20 +294,46 @@ async fn vm_api_transfer_delegates_to_multipass() {
 }
 
 #[tokio::test]
-async fn vm_api_exec_returns_error_on_failure() {
+async fn vm_api_exec_returns_the_exit_code_of_a_failing_command() {
     let (multipass_cli, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
         status_code: 127,
         stdout: String::new(),
         stderr: "command not found".to_owned(),
+        truncated: false,
     }]);
     let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
     let vm_api = LocalVmApi::new(multipass);
 
-    let result = vm_api.exec("test-vm", &["invalid-cmd".to_string()]).await;
+    let output = vm_api
+        .exec("test-vm", &["invalid-cmd".to_string()])
+        .await
+        .expect("exec should work");
+
+    assert_eq!(output.status_code, 127);
+}
+
+#[tokio::test]
+async fn vm_api_exec_returns_error_when_the_vm_is_missing() {
+    let (multipass_cli, _fake) = multipass_cli_with_outputs(vec![CommandOutput {
+        status_code: 2,
+        stdout: String::new(),
+        stderr: "exec failed: instance \"test-vm\" does not exist".to_owned(),
+        truncated: false,
+    }]);
+    let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
+    let vm_api = LocalVmApi::new(multipass);
+
+    let err = vm_api
+        .exec("test-vm", &["true".to_string()])
+        .await
+        .expect_err("missing VM");
 
-    assert!(result.is_err());
-    let err = result.unwrap_err();
     assert!(err.to_string().contains("failed to exec command"));
+    assert!(
+        err.chain()
+            .filter_map(|cause| cause.downcast_ref::<VmError>())
+            .any(VmError::is_not_found)
+    );
 }
 
 #[tokio::test]
@@ -204,12 +342,18 @@ async fn vm_api_transfer_returns_error_on_failure() {
         status_code: 1,
         stdout: String::new(),
         stderr: "permission denied".to_owned(),
+        truncated: false,
     }]);
     let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
     let vm_api = LocalVmApi::new(multipass);
 
     let result = vm_api
-        .transfer("test-vm", "/local/file.txt", "/root/file.txt")
+        .transfer(
+            "test-vm",
+            "/local/file.txt",
+            "/root/file.txt",
+            &CancellationToken::new(),
+        )
         .await;
 
     assert!(result.is_err());
@@ -232,6 +376,7 @@ async fn full_script_installation_sequence() {
             status_code: 0,
             stdout: "Installation successful\n".to_owned(),
             stderr: String::new(),
+            truncated: false,
         },
     ]);
     let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
@@ -239,7 +384,12 @@ async fn full_script_installation_sequence() {
 
     // Transfer script
     vm_api
-        .transfer("test-vm", "/local/install.sh", "/tmp/install.sh")
+        .transfer(
+            "test-vm",
+            "/local/install.sh",
+            "/tmp/install.sh",
+            &CancellationToken::new(),
+        )
         .await
         .expect("transfer should work");
 
@@ -283,16 +433,18 @@ async fn check_if_command_exists_sequence() {
         status_code: 1,
         stdout: String::new(),
         stderr: String::new(),
+        truncated: false,
     }]);
     let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
     let vm_api = LocalVmApi::new(multipass);
 
-    let result = vm_api
+    let output = vm_api
         .exec("test-vm", &["which".to_string(), "zeroclaw".to_string()])
-        .await;
+        .await
+        .expect("exec should work");
 
     // which returns non-zero when command not found
-    assert!(result.is_err());
+    assert_eq!(output.status_code, 1);
 
     let calls = fake.calls();
     assert_eq!(calls.len(), 1);
@@ -307,6 +459,7 @@ async fn check_if_command_exists_found() {
         status_code: 0,
         stdout: "/usr/local/bin/zeroclaw\n".to_owned(),
         stderr: String::new(),
+        truncated: false,
     }]);
     let multipass = Arc::new(multipass_cli) as Arc<dyn Multipass>;
     let vm_api = LocalVmApi::new(multipass);
@@ -0,0 +1,85 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::FakeMultipass;
+use safepaw::cli::{build_cli, run_vm_subcommand};
+use safepaw::vm::{LocalVmApi, StopOptions, VmApi, VmError};
+
+async fn run_stop(args: &[&str], api: &LocalVmApi) -> anyhow::Result<Vec<String>> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    Ok(
+        run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api)
+            .await?
+            .into_lines(),
+    )
+}
+
+#[tokio::test]
+async fn stop_timeout_falls_back_to_force() {
+    let fake = FakeMultipass::new().with_stop_response(Err(VmError::TimedOut {
+        action: "stop",
+        timeout: Duration::from_secs(5),
+    }));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let lines = run_stop(
+        &["safeclaw", "vm", "stop", "agent-1", "--force-after", "5"],
+        &api,
+    )
+    .await
+    .expect("forced stop should succeed");
+
+    assert_eq!(lines, vec!["VM 'agent-1' stopped successfully"]);
+    assert_eq!(fake.calls(), vec!["stop:agent-1", "stop:agent-1:force"]);
+}
+
+#[tokio::test]
+async fn stop_force_skips_the_graceful_attempt() {
+    let fake = FakeMultipass::new();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    run_stop(&["safeclaw", "vm", "stop", "agent-1", "--force"], &api)
+        .await
+        .expect("forced stop should succeed");
+
+    assert_eq!(fake.calls(), vec!["stop:agent-1:force"]);
+}
+
+#[tokio::test]
+async fn stop_failures_other_than_timeout_are_not_forced() {
+    let fake = FakeMultipass::new().with_stop_response(Err(VmError::CommandFailed {
+        action: "stop",
+        status_code: 2,
+        stderr: "instance \"agent-1\" does not exist".to_owned(),
+    }));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let err = api
+        .stop(
+            "agent-1",
+            &StopOptions::default().with_timeout(Duration::from_secs(5)),
+        )
+        .await
+        .expect_err("stop should fail");
+
+    assert!(err.to_string().contains("does not exist"));
+    assert_eq!(fake.calls(), vec!["stop:agent-1"]);
+}
+
+#[test]
+fn force_and_force_after_conflict() {
+    let result = build_cli().try_get_matches_from([
+        "safeclaw",
+        "vm",
+        "stop",
+        "agent-1",
+        "--force",
+        "--force-after",
+        "5",
+    ]);
+    assert!(result.is_err());
+}
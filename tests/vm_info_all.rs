@@ -0,0 +1,101 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::FakeMultipass;
+use safepaw::cli::{RenderOptions, build_cli, run_vm_subcommand};
+use safepaw::vm::{LocalVmApi, VmError, VmStatusResponse, VmSummary, info_all};
+use serde_json::json;
+
+fn fleet() -> FakeMultipass {
+    FakeMultipass::new()
+        .with_list(vec![
+            VmSummary::minimal("agent-1", "Running"),
+            VmSummary::minimal("agent-2", "Stopped"),
+        ])
+        .with_status("agent-1", "Running")
+        .with_status("agent-2", "Stopped")
+}
+
+async fn rendered_lines(args: &[&str], api: &LocalVmApi) -> Vec<String> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    let vm_matches = matches.subcommand_matches("vm").unwrap();
+    run_vm_subcommand(vm_matches, api)
+        .await
+        .expect("info --all failed")
+        .render(&RenderOptions::from_matches(vm_matches))
+}
+
+#[tokio::test]
+async fn info_all_prints_each_vm_separated_by_a_blank_line() {
+    let api = LocalVmApi::new(Arc::new(fleet()));
+
+    let lines = rendered_lines(&["safepaw", "vm", "info", "--all"], &api).await;
+
+    assert_eq!(
+        lines,
+        vec![
+            "Name:  agent-1",
+            "State: Running",
+            "",
+            "Name:  agent-2",
+            "State: Stopped",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn info_all_emits_a_json_array() {
+    let api = LocalVmApi::new(Arc::new(fleet()));
+
+    let lines = rendered_lines(&["safepaw", "vm", "info", "--all", "-o", "json"], &api).await;
+
+    let value: serde_json::Value = serde_json::from_str(&lines.join("\n")).unwrap();
+    assert_eq!(
+        value,
+        json!([
+            {"name": "agent-1", "state": "Running"},
+            {"name": "agent-2", "state": "Stopped"}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn info_all_skips_a_vm_deleted_after_listing() {
+    let fake = fleet()
+        .with_info_response(Err(VmError::CommandFailed {
+            action: "info",
+            status_code: 2,
+            stderr: "info failed: instance \"agent-1\" does not exist".to_owned(),
+        }))
+        .with_info_response(Ok(VmStatusResponse::minimal("agent-2", "Stopped")));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let infos = info_all(&api).await.expect("a vanished VM is not an error");
+
+    assert_eq!(infos, vec![VmStatusResponse::minimal("agent-2", "Stopped")]);
+    assert_eq!(fake.calls(), vec!["list", "info:agent-1", "info:agent-2"]);
+}
+
+#[tokio::test]
+async fn info_all_fails_on_other_errors() {
+    let fake = fleet().with_info_response(Err(VmError::CommandIo("socket closed".to_owned())));
+    let api = LocalVmApi::new(Arc::new(fake));
+
+    let err = info_all(&api).await.expect_err("other errors still fail");
+
+    assert!(format!("{err:#}").contains("socket closed"));
+}
+
+#[test]
+fn info_needs_a_name_or_all_but_not_both() {
+    for args in [
+        &["safepaw", "vm", "info"][..],
+        &["safepaw", "vm", "info", "agent-1", "--all"][..],
+        &["safepaw", "vm", "info", "--all", "--watch"][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err());
+    }
+}
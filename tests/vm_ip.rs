@@ -0,0 +1,116 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FakeMultipass, FakeVmApi};
+use safepaw::cli::{
+    CommandResult, EXIT_NO_ADDRESS, EXIT_TIMEOUT, EXIT_VM_NOT_FOUND, build_cli, exit_code,
+    run_vm_subcommand,
+};
+use safepaw::vm::{LocalVmApi, VmApi, VmError, VmStatusResponse};
+use serde_json::json;
+
+fn with_ips(state: &str, ips: &[&str]) -> VmStatusResponse {
+    VmStatusResponse {
+        ipv4: Some(ips.iter().map(|ip| ip.to_string()).collect()),
+        ..VmStatusResponse::minimal("agent-1", state)
+    }
+}
+
+async fn run_ip(args: &[&str], api: &dyn VmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api).await
+}
+
+#[tokio::test]
+async fn ip_prints_the_first_address_or_all_of_them() {
+    let api = FakeVmApi::new().with_info_response(with_ips("Running", &["10.0.0.5", "172.17.0.1"]));
+
+    let first = run_ip(&["safepaw", "vm", "ip", "agent-1"], &api)
+        .await
+        .unwrap()
+        .into_lines();
+    let all = run_ip(&["safepaw", "vm", "ip", "agent-1", "--all"], &api)
+        .await
+        .unwrap()
+        .into_lines();
+    let json = run_ip(&["safepaw", "vm", "-o", "json", "ip", "agent-1"], &api)
+        .await
+        .unwrap();
+
+    assert_eq!(first, vec!["10.0.0.5"]);
+    assert_eq!(all, vec!["10.0.0.5", "172.17.0.1"]);
+    assert_eq!(
+        json,
+        CommandResult::Json(json!({"name": "agent-1", "ipv4": ["10.0.0.5"]}))
+    );
+}
+
+#[tokio::test]
+async fn ip_without_an_address_has_its_own_exit_code() {
+    let api = FakeVmApi::new().with_info_response(VmStatusResponse::minimal("agent-1", "Starting"));
+
+    let err = run_ip(&["safepaw", "vm", "ip", "agent-1"], &api)
+        .await
+        .expect_err("no address yet");
+
+    assert_eq!(exit_code(&err), EXIT_NO_ADDRESS);
+    assert_eq!(err.to_string(), "VM 'agent-1' has no IPv4 address yet");
+}
+
+#[tokio::test]
+async fn ip_of_a_missing_vm_is_not_found() {
+    let fake = FakeMultipass::new().with_info_response(Err(VmError::CommandFailed {
+        action: "info",
+        status_code: 2,
+        stderr: "info failed: instance \"ghost\" does not exist".to_owned(),
+    }));
+    let api = LocalVmApi::new(Arc::new(fake));
+
+    let err = run_ip(&["safepaw", "vm", "ip", "ghost"], &api)
+        .await
+        .expect_err("missing VM");
+
+    assert_eq!(exit_code(&err), EXIT_VM_NOT_FOUND);
+}
+
+#[tokio::test(start_paused = true)]
+async fn ip_wait_polls_until_an_address_appears() {
+    let api = FakeVmApi::new().with_info_sequence(vec![
+        VmStatusResponse::minimal("agent-1", "Starting"),
+        with_ips("Running", &[]),
+        with_ips("Running", &["10.0.0.9"]),
+    ]);
+
+    let lines = run_ip(&["safepaw", "vm", "ip", "agent-1", "--wait"], &api)
+        .await
+        .expect("an address should appear")
+        .into_lines();
+
+    assert_eq!(lines, vec!["10.0.0.9"]);
+    assert_eq!(api.calls().len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn ip_wait_times_out_when_no_address_appears() {
+    let api = FakeVmApi::new().with_info_response(VmStatusResponse::minimal("agent-1", "Running"));
+
+    let err = run_ip(
+        &[
+            "safepaw",
+            "vm",
+            "ip",
+            "agent-1",
+            "--wait",
+            "--wait-timeout",
+            "5",
+        ],
+        &api,
+    )
+    .await
+    .expect_err("no address ever appears");
+
+    assert_eq!(exit_code(&err), EXIT_TIMEOUT);
+}
@@ -0,0 +1,223 @@
+mod common;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use common::{FakeVmApi, ScriptedConfirm};
+use safepaw::cli::{CommandResult, UsageError, build_cli, run_vm_subcommand_with};
+use safepaw::vm::{LaunchSpec, MAX_LAUNCH_COUNT, VmBatchResult, launch_vms, numbered_vm_names};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+async fn run_launch(args: &[&str], api: &FakeVmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand_with(
+        matches.subcommand_matches("vm").unwrap(),
+        api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+}
+
+fn launched(api: &FakeVmApi) -> Vec<String> {
+    let mut calls: Vec<String> = api
+        .calls()
+        .into_iter()
+        .filter(|call| call.starts_with("launch:"))
+        .collect();
+    calls.sort();
+    calls
+}
+
+#[test]
+fn numbered_names_count_up_from_one() {
+    assert_eq!(
+        numbered_vm_names("agent", 3),
+        vec!["agent-1", "agent-2", "agent-3"]
+    );
+    assert_eq!(numbered_vm_names("agent", 1), vec!["agent-1"]);
+}
+
+#[test]
+fn count_must_be_positive() {
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "launch", "agent", "--count", "0"])
+            .is_err()
+    );
+}
+
+#[test]
+fn count_is_capped() {
+    let cap = MAX_LAUNCH_COUNT.to_string();
+    let over = (MAX_LAUNCH_COUNT + 1).to_string();
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "launch", "agent", "--count", &cap])
+            .is_ok()
+    );
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "launch", "agent", "--count", &over])
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn launch_with_count_creates_numbered_vms() {
+    let api = FakeVmApi::new();
+
+    let lines = run_launch(&["safepaw", "vm", "launch", "agent", "--count", "3"], &api)
+        .await
+        .expect("launch should succeed")
+        .into_lines();
+
+    assert_eq!(lines, vec!["Launched 3 VM(s): agent-1, agent-2, agent-3"]);
+    assert_eq!(
+        launched(&api),
+        vec!["launch:agent-1", "launch:agent-2", "launch:agent-3"]
+    );
+}
+
+#[tokio::test]
+async fn launch_with_count_five_creates_five_distinct_vms() {
+    let api = FakeVmApi::new();
+
+    run_launch(&["safepaw", "vm", "launch", "agent", "--count", "5"], &api)
+        .await
+        .expect("launch should succeed");
+
+    assert_eq!(
+        launched(&api),
+        vec![
+            "launch:agent-1",
+            "launch:agent-2",
+            "launch:agent-3",
+            "launch:agent-4",
+            "launch:agent-5",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn count_of_one_launches_the_plain_name() {
+    let api = FakeVmApi::new();
+
+    run_launch(&["safepaw", "vm", "launch", "agent", "--count", "1"], &api)
+        .await
+        .expect("launch should succeed");
+
+    assert_eq!(launched(&api), vec!["launch:agent"]);
+}
+
+#[tokio::test]
+async fn count_rejects_a_name_with_a_numeric_suffix() {
+    let api = FakeVmApi::new();
+
+    let err = run_launch(
+        &["safepaw", "vm", "launch", "agent-1", "--count", "3"],
+        &api,
+    )
+    .await
+    .expect_err("an already numbered name should be rejected");
+
+    assert!(err.is::<UsageError>());
+    assert_eq!(
+        err.to_string(),
+        "'agent-1' already ends in a number; pass the base name with --count, \
+         e.g. 'vm launch agent --count 3'"
+    );
+    assert!(launched(&api).is_empty());
+}
+
+#[tokio::test]
+async fn launch_with_count_reports_json() {
+    let api = FakeVmApi::new();
+
+    let output = run_launch(
+        &[
+            "safepaw", "vm", "-o", "json", "launch", "web", "--count", "2",
+        ],
+        &api,
+    )
+    .await
+    .expect("launch should succeed");
+
+    match output {
+        CommandResult::Json(value) => assert_eq!(
+            value,
+            json!({"ok": true, "action": "launch", "launched": ["web-1", "web-2"]})
+        ),
+        other => panic!("expected JSON output, got {other:?}"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn launches_run_concurrently_up_to_the_limit() {
+    let api = FakeVmApi::new().with_launch_delay(Duration::from_secs(10));
+    let specs: Vec<LaunchSpec> = numbered_vm_names("agent", 10)
+        .into_iter()
+        .map(LaunchSpec::new)
+        .collect();
+    let seen = Mutex::new(Vec::new());
+
+    let results = launch_vms(
+        &api,
+        &specs,
+        4,
+        None,
+        &CancellationToken::new(),
+        &|result| seen.lock().unwrap().push(result.name.clone()),
+    )
+    .await;
+
+    assert_eq!(api.max_concurrent_launches(), 4);
+    assert_eq!(seen.lock().unwrap().len(), 10);
+    let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, numbered_vm_names("agent", 10));
+    assert!(results.iter().all(|r| r.error.is_none()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn partial_failure_finishes_other_launches_and_errors() {
+    let api = FakeVmApi::new()
+        .with_launch_delay(Duration::from_secs(1))
+        .with_launch_failure("agent-2");
+
+    let err = run_launch(&["safepaw", "vm", "launch", "agent", "--count", "3"], &api)
+        .await
+        .expect_err("a failed launch should fail the command");
+
+    assert_eq!(
+        err.to_string(),
+        "launched 2 of 3 VMs; failed to launch agent-2: launch of 'agent-2' failed"
+    );
+    assert_eq!(
+        launched(&api),
+        vec!["launch:agent-1", "launch:agent-2", "launch:agent-3"]
+    );
+}
+
+#[tokio::test]
+async fn progress_reports_each_failure() {
+    let api = FakeVmApi::new().with_launch_failure("agent-1");
+    let specs = vec![LaunchSpec::new("agent-1"), LaunchSpec::new("agent-2")];
+    let seen: Mutex<Vec<VmBatchResult>> = Mutex::new(Vec::new());
+
+    launch_vms(
+        &api,
+        &specs,
+        2,
+        None,
+        &CancellationToken::new(),
+        &|result| seen.lock().unwrap().push(result.clone()),
+    )
+    .await;
+
+    let mut seen = seen.into_inner().unwrap();
+    seen.sort_by(|a, b| a.name.cmp(&b.name));
+    assert!(seen[0].error.is_some());
+    assert!(seen[1].error.is_none());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use common::FakeVmApi;
+use safepaw::cli::{LaunchMessage, ProgressSink, with_launch_messages, with_launch_progress};
+use safepaw::vm::{LaunchSpec, VmApi, VmStatusResponse};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Default)]
+struct CapturedProgress {
+    lines: Mutex<Vec<String>>,
+    finished: AtomicBool,
+}
+
+impl ProgressSink for CapturedProgress {
+    fn update(&self, line: &str) {
+        self.lines.lock().unwrap().push(line.to_owned());
+    }
+
+    fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn launch_progress_reports_elapsed_time_and_state() {
+    let api = FakeVmApi::new()
+        .with_launch_delay(Duration::from_millis(2500))
+        .with_info_response(VmStatusResponse::minimal("agent-1", "Starting"));
+    let sink = CapturedProgress::default();
+    let spec = LaunchSpec::new("agent-1");
+    let cancel = CancellationToken::new();
+
+    with_launch_progress(&api, "agent-1", &sink, api.launch(&spec, &cancel))
+        .await
+        .expect("launch should succeed");
+
+    assert_eq!(
+        sink.lines.into_inner().unwrap(),
+        vec![
+            "| Launching 'agent-1' (0s) - Starting",
+            "/ Launching 'agent-1' (1s) - Starting",
+            "- Launching 'agent-1' (2s) - Starting",
+        ]
+    );
+    assert!(sink.finished.load(Ordering::SeqCst));
+}
+
+#[tokio::test(start_paused = true)]
+async fn launch_progress_passes_the_launch_result_through() {
+    let api = FakeVmApi::new().with_launch_failure("agent-1");
+    let sink = CapturedProgress::default();
+    let spec = LaunchSpec::new("agent-1");
+    let cancel = CancellationToken::new();
+
+    let err = with_launch_progress(&api, "agent-1", &sink, api.launch(&spec, &cancel))
+        .await
+        .expect_err("the launch failure should surface");
+
+    assert_eq!(err.to_string(), "launch of 'agent-1' failed");
+    assert!(sink.finished.load(Ordering::SeqCst));
+}
+
+#[tokio::test(start_paused = true)]
+async fn launch_progress_prefers_the_latest_multipass_message() {
+    let api = FakeVmApi::new().with_info_response(VmStatusResponse::minimal("agent-1", "Starting"));
+    let sink = CapturedProgress::default();
+    let message = LaunchMessage::default();
+
+    let launch = async {
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        message.record("Retrieving image: 35%\r");
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    };
+    with_launch_messages(&api, "agent-1", &sink, &message, launch).await;
+
+    assert_eq!(
+        sink.lines.into_inner().unwrap(),
+        vec![
+            "| Launching 'agent-1' (0s) - Starting",
+            "/ Launching 'agent-1' (1s) - Starting",
+            "- Launching 'agent-1' (2s) - Retrieving image: 35%",
+        ]
+    );
+    assert_eq!(
+        api.calls().iter().filter(|c| c.starts_with("info")).count(),
+        2
+    );
+}
@@ -0,0 +1,127 @@
+#![cfg(unix)]
+
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::process::{Command, Stdio};
+
+use safepaw::cli::{EXIT_USAGE, parse_launch_spec};
+use safepaw::vm::LaunchSpec;
+
+fn binary_path() -> String {
+    std::env::var("NEXTEST_BIN_EXE_safeclaw")
+        .or_else(|_| std::env::var("CARGO_BIN_EXE_safeclaw"))
+        .or_else(|_| std::env::var("NEXTEST_BIN_EXE_safepaw"))
+        .or_else(|_| std::env::var("CARGO_BIN_EXE_safepaw"))
+        .unwrap_or_else(|_| "target/debug/safeclaw".to_owned())
+}
+
+#[test]
+fn spec_json_round_trips_into_a_launch_spec() {
+    let spec = parse_launch_spec(
+        r#"{"name": "agent-1", "cpus": 2, "memory": "4G", "disk": "20G", "networks": ["en0"]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        spec,
+        LaunchSpec {
+            cpus: Some(2),
+            memory: Some("4G".to_owned()),
+            disk: Some("20G".to_owned()),
+            networks: vec!["en0".to_owned()],
+            ..LaunchSpec::new("agent-1")
+        }
+    );
+}
+
+#[test]
+fn invalid_specs_are_usage_errors() {
+    let cases = [
+        ("not json", "invalid launch spec: expected ident"),
+        (
+            r#"{"cpus": 2}"#,
+            "invalid launch spec: missing field `name`",
+        ),
+        (
+            r#"{"name": ""}"#,
+            "invalid launch spec: a VM name is required",
+        ),
+        (
+            r#"{"name": "agent-1", "cpus": 0}"#,
+            "invalid launch spec: cpus must be between 1 and 256, got 0",
+        ),
+        (
+            r#"{"name": "agent-1", "memory": "lots"}"#,
+            "invalid launch spec: memory 'lots' is not a size like 512M, 4G or 20GiB",
+        ),
+        (
+            r#"{"name": "agent-1", "disk": "0G"}"#,
+            "invalid launch spec: disk '0G' is not a size like 512M, 4G or 20GiB",
+        ),
+    ];
+
+    for (json, expected) in cases {
+        let err = parse_launch_spec(json).unwrap_err();
+        assert!(err.to_string().starts_with(expected), "{json}: got {err}");
+        assert_eq!(safepaw::cli::exit_code(&err), EXIT_USAGE, "{json}");
+    }
+}
+
+#[test]
+fn launch_spec_from_stdin_drives_the_multipass_args() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let log = temp_dir.path().join("calls.log");
+    let fake = temp_dir.path().join("multipass");
+    std::fs::write(
+        &fake,
+        format!("#!/bin/sh\necho \"$@\" >> {}\n", log.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut child = Command::new(binary_path())
+        .args(["vm", "launch", "--spec", "-", "--no-preflight"])
+        .env("PATH", temp_dir.path())
+        .env("HOME", temp_dir.path())
+        .env_remove("RUST_LOG")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to execute binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(br#"{"name": "agent-1", "cpus": 2, "memory": "4G", "disk": "20G"}"#)
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let calls = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(
+        calls.lines().next(),
+        Some("launch --name agent-1 --cpus 2 --memory 4G --disk 20G")
+    );
+}
+
+#[test]
+fn spec_conflicts_with_the_individual_launch_flags() {
+    for args in [
+        vec!["safepaw", "vm", "launch", "agent-1", "--spec", "-"],
+        vec!["safepaw", "vm", "launch", "--auto", "--spec", "-"],
+        vec!["safepaw", "vm", "launch", "--spec", "-", "--network", "en0"],
+        vec!["safepaw", "vm", "launch", "--spec", "-", "--package", "git"],
+    ] {
+        assert!(
+            safepaw::cli::build_cli()
+                .try_get_matches_from(&args)
+                .is_err(),
+            "{args:?} should be rejected"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use common::multipass_cli_with_outputs;
+use safepaw::vm::{
+    CommandExecutor, CommandOutput, LaunchSpec, LineSink, LocalVmApi, Multipass, MultipassCli,
+    TokioCommandExecutor, VmApi,
+};
+use tokio::sync::{Notify, mpsc};
+use tokio_util::sync::CancellationToken;
+
+// ============================================================================
+// StreamingExecutor - reports one progress line, waits to be released, then
+// reports a second and exits
+// ============================================================================
+
+#[derive(Clone, Default)]
+struct StreamingExecutor {
+    release: Arc<Notify>,
+    calls: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+#[async_trait]
+impl CommandExecutor for StreamingExecutor {
+    async fn run_with_stdin(
+        &self,
+        program: &str,
+        _args: &[String],
+        _stdin: Option<&[u8]>,
+        _cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        anyhow::bail!("{program} should have been run with progress")
+    }
+
+    async fn run_with_progress(
+        &self,
+        program: &str,
+        args: &[String],
+        _stdin: Option<&[u8]>,
+        on_progress: &LineSink<'_>,
+        _cancel: &CancellationToken,
+    ) -> anyhow::Result<CommandOutput> {
+        let mut call = vec![program.to_owned()];
+        call.extend(args.iter().cloned());
+        self.calls.lock().unwrap().push(call);
+
+        on_progress("Retrieving image: 42%");
+        self.release.notified().await;
+        on_progress("Starting agent-1");
+        Ok(CommandOutput {
+            stderr: "Retrieving image: 42%\rStarting agent-1\n".to_owned(),
+            ..CommandOutput::success("Launched: agent-1\n")
+        })
+    }
+}
+
+#[tokio::test]
+async fn launch_with_progress_forwards_lines_while_multipass_runs() {
+    let executor = StreamingExecutor::default();
+    let api = LocalVmApi::new(Arc::new(MultipassCli::new(executor.clone())));
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let on_progress = move |line: &str| tx.send(line.to_owned()).unwrap();
+    let spec = LaunchSpec::new("agent-1");
+    let cancel = CancellationToken::new();
+
+    let watch = async {
+        // The first line arrives before multipass has finished.
+        let first = rx.recv().await.expect("first progress line");
+        executor.release.notify_one();
+        first
+    };
+    let (launched, first) = tokio::join!(
+        api.launch_with_progress(&spec, &on_progress, &cancel),
+        watch
+    );
+
+    launched.expect("launch should succeed");
+    assert_eq!(first, "Retrieving image: 42%");
+    assert_eq!(rx.recv().await.as_deref(), Some("Starting agent-1"));
+    assert_eq!(
+        executor.calls.lock().unwrap()[0][..3],
+        ["multipass", "launch", "--name"].map(String::from)
+    );
+}
+
+#[tokio::test]
+async fn buffered_executors_replay_stderr_after_the_launch() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput {
+        stderr: "Retrieving image: 100%\r\nStarting agent-1\n".to_owned(),
+        ..CommandOutput::success("")
+    }]);
+    let lines = Mutex::new(Vec::new());
+
+    multipass
+        .launch_with_progress(
+            &LaunchSpec::new("agent-1"),
+            &|line| lines.lock().unwrap().push(line.to_owned()),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("launch should succeed");
+
+    assert_eq!(
+        lines.into_inner().unwrap(),
+        vec!["Retrieving image: 100%", "Starting agent-1"]
+    );
+    assert_eq!(fake.calls().len(), 1);
+}
+
+#[tokio::test]
+async fn tokio_executor_splits_stderr_on_carriage_returns() {
+    let lines = Mutex::new(Vec::new());
+
+    let output = TokioCommandExecutor::new()
+        .run_with_progress(
+            "sh",
+            &[
+                "-c".to_owned(),
+                r"printf 'Retrieving image: 10%%\rRetrieving image: 90%%\n\nStarting' >&2"
+                    .to_owned(),
+            ],
+            None,
+            &|line| lines.lock().unwrap().push(line.to_owned()),
+            &CancellationToken::new(),
+        )
+        .await
+        .expect("command should run");
+
+    assert_eq!(
+        lines.into_inner().unwrap(),
+        vec!["Retrieving image: 10%", "Retrieving image: 90%", "Starting"]
+    );
+    assert_eq!(
+        output.stderr,
+        "Retrieving image: 10%\rRetrieving image: 90%\n\nStarting"
+    );
+}
@@ -8,8 +8,9 @@ use axum::{
     body::{Body, to_bytes},
     http::{Method, Request, StatusCode},
 };
-use safepaw::vm::{self, Multipass, VmError, VmStatusResponse, VmSummary};
+use safepaw::vm::{self, LaunchSpec, Multipass, StopOptions, VmError, VmStatusResponse, VmSummary};
 use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
 use tower::util::ServiceExt;
 
 #[derive(Default)]
@@ -50,12 +51,12 @@ impl FakeMultipass {
 
 #[async_trait]
 impl Multipass for FakeMultipass {
-    async fn launch(&self, name: &str) -> Result<(), VmError> {
+    async fn launch(&self, spec: &LaunchSpec, _cancel: &CancellationToken) -> Result<(), VmError> {
         self.state
             .lock()
             .expect("poisoned fake state")
             .calls
-            .push(format!("launch:{name}"));
+            .push(format!("launch:{}", spec.name));
         Ok(())
     }
 
@@ -68,7 +69,7 @@ impl Multipass for FakeMultipass {
         Ok(())
     }
 
-    async fn stop(&self, name: &str) -> Result<(), VmError> {
+    async fn stop(&self, name: &str, _opts: &StopOptions) -> Result<(), VmError> {
         self.state
             .lock()
             .expect("poisoned fake state")
@@ -124,6 +125,7 @@ impl Multipass for FakeMultipass {
         _name: &str,
         _source: &str,
         _destination: &str,
+        _cancel: &CancellationToken,
     ) -> Result<(), VmError> {
         Ok(())
     }
@@ -224,3 +226,33 @@ async fn terminate_vm_returns_no_content_and_stops_vm() {
     assert_eq!(response.status(), StatusCode::NO_CONTENT);
     assert_eq!(fake.calls(), vec!["stop:agent-1"]);
 }
+
+#[tokio::test]
+async fn errors_use_the_api_error_envelope() {
+    let fake = FakeMultipass::default();
+    let app = vm::app(Arc::new(fake.clone()));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/vm")
+        .header("content-type", "application/json")
+        .body(Body::from("{"))
+        .expect("failed to build request");
+
+    let response = app.oneshot(request).await.expect("failed to call vm app");
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let request_id = response.headers()["x-request-id"]
+        .to_str()
+        .expect("request id should be ASCII")
+        .to_owned();
+    let body = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("failed to read body");
+    let json: Value = serde_json::from_slice(&body).expect("invalid JSON body");
+
+    assert_eq!(json["code"], "validation_failed");
+    assert!(json["message"].as_str().unwrap().contains("EOF"), "{json}");
+    assert_eq!(json["request_id"], request_id);
+    assert!(fake.calls().is_empty());
+}
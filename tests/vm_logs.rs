@@ -0,0 +1,118 @@
+mod common;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use common::FakeVmApi;
+use safepaw::cli::{
+    EXIT_USAGE, LogFollowEnd, build_cli, exit_code, follow_vm_logs, run_vm_subcommand,
+};
+use safepaw::vm::{CommandOutput, LogSource, VmState, VmStatusResponse};
+use tokio_util::sync::CancellationToken;
+
+fn tail_follow() -> Vec<String> {
+    LogSource::CloudInit.command(100, true)
+}
+
+#[test]
+fn sources_map_to_their_files() {
+    assert_eq!(
+        LogSource::CloudInit.command(50, true),
+        vec!["tail", "-n", "50", "-f", "/var/log/cloud-init-output.log"]
+    );
+    assert_eq!(
+        LogSource::Syslog.command(10, false),
+        vec!["tail", "-n", "10", "/var/log/syslog"]
+    );
+    assert_eq!(
+        LogSource::Journal.command(10, true),
+        vec!["journalctl", "--no-pager", "-n", "10", "-f"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn follow_streams_lines_until_cancelled() {
+    let api = FakeVmApi::new().with_exec_response(Ok(CommandOutput::success("line 1\nline 2\n")));
+    let lines = Mutex::new(Vec::new());
+    let cancel = CancellationToken::new();
+    let canceller = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        canceller.cancel();
+    });
+
+    let end = follow_vm_logs(
+        &api,
+        "agent-1",
+        &tail_follow(),
+        &|line: &str| lines.lock().unwrap().push(line.to_owned()),
+        &cancel,
+    )
+    .await
+    .expect("Ctrl+C should end cleanly");
+
+    assert_eq!(end, LogFollowEnd::Interrupted);
+    assert_eq!(lines.into_inner().unwrap(), vec!["line 1", "line 2"]);
+    assert_eq!(api.exec_calls()[0].command, tail_follow());
+}
+
+#[tokio::test(start_paused = true)]
+async fn follow_ends_when_the_vm_stops() {
+    let api = FakeVmApi::new()
+        .with_exec_response(Ok(CommandOutput::success("booting\n")))
+        .with_info_sequence(vec![VmStatusResponse::minimal("agent-1", "Running")])
+        .with_info_response(VmStatusResponse::minimal("agent-1", "Stopped"));
+
+    let end = follow_vm_logs(
+        &api,
+        "agent-1",
+        &tail_follow(),
+        &|_: &str| {},
+        &CancellationToken::new(),
+    )
+    .await
+    .expect("a stopped VM should end the stream cleanly");
+
+    assert_eq!(end, LogFollowEnd::VmStopped(VmState::Stopped));
+}
+
+#[tokio::test]
+async fn logs_refuse_a_vm_that_is_not_running() {
+    let api = FakeVmApi::new().with_info_response(VmStatusResponse::minimal("agent-1", "Stopped"));
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "logs", "agent-1", "--follow"])
+        .unwrap();
+
+    let err = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "VM 'agent-1' is not running (state: Stopped); start it with `safepaw vm start agent-1`"
+    );
+    assert!(api.exec_calls().is_empty());
+}
+
+#[tokio::test]
+async fn follow_rejects_json_output() {
+    let api = FakeVmApi::new();
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "-o", "json", "logs", "agent-1", "-f"])
+        .unwrap();
+
+    let err = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .unwrap_err();
+
+    assert_eq!(exit_code(&err), EXIT_USAGE);
+}
+
+#[test]
+fn unknown_sources_are_rejected() {
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "logs", "agent-1", "--source", "dmesg"])
+            .is_err()
+    );
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use common::FakeVmApi;
+use safepaw::cli::{build_cli, run_vm_subcommand};
+use safepaw::manifest::{
+    ApplyOutcome, MAX_DEFAULT_APPLY_CONCURRENCY, Manifest, apply_manifest,
+    default_apply_concurrency,
+};
+use safepaw::vm::{LaunchSpec, VmSummary};
+use tokio_util::sync::CancellationToken;
+
+const TWO_VM_MANIFEST: &str = r#"
+vms:
+  - name: agent-1
+    cpus: 2
+    memory: 4G
+  - name: agent-2
+    cpus: 4
+    memory: 8G
+    disk: 40G
+    image: "24.04"
+"#;
+
+#[test]
+fn manifest_parses_launch_specs() {
+    let manifest = Manifest::from_yaml(TWO_VM_MANIFEST).expect("manifest should parse");
+
+    assert_eq!(
+        manifest.vms,
+        vec![
+            LaunchSpec {
+                name: "agent-1".to_owned(),
+                cpus: Some(2),
+                memory: Some("4G".to_owned()),
+                disk: None,
+                image: None,
+                networks: vec![],
+                cloud_init: None,
+            },
+            LaunchSpec {
+                name: "agent-2".to_owned(),
+                cpus: Some(4),
+                memory: Some("8G".to_owned()),
+                disk: Some("40G".to_owned()),
+                image: Some("24.04".to_owned()),
+                networks: vec![],
+                cloud_init: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn manifest_rejects_duplicate_names() {
+    let err = Manifest::from_yaml(
+        r#"
+vms:
+  - name: agent-1
+  - name: agent-2
+  - name: agent-1
+    cpus: 2
+"#,
+    )
+    .expect_err("a name listed twice should be rejected");
+
+    assert!(err.to_string().contains("'agent-1'"), "{err}");
+}
+
+#[tokio::test]
+async fn apply_rejects_duplicate_names_without_launching() {
+    let api = FakeVmApi::new();
+    let manifest = Manifest {
+        vms: vec![LaunchSpec::new("agent-1"), LaunchSpec::new("agent-1")],
+    };
+
+    let result = apply_manifest(&api, &manifest, 2, &CancellationToken::new(), &|_| {}).await;
+
+    assert!(result.is_err());
+    assert!(api.calls().is_empty());
+}
+
+#[tokio::test]
+async fn vm_apply_launches_only_missing_vms() {
+    let temp_dir = tempfile::tempdir().expect("temp dir should be created");
+    let manifest_path = temp_dir.path().join("fleet.yaml");
+    std::fs::write(&manifest_path, TWO_VM_MANIFEST).expect("manifest should be written");
+
+    let api =
+        FakeVmApi::default().with_list_response(vec![VmSummary::minimal("agent-1", "Running")]);
+    let matches = build_cli()
+        .try_get_matches_from([
+            "safeclaw",
+            "vm",
+            "apply",
+            manifest_path.to_str().expect("utf-8 path"),
+        ])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(
+        matches
+            .subcommand_matches("vm")
+            .expect("missing vm subcommand"),
+        &api,
+    )
+    .await
+    .expect("apply command failed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["agent-1 | exists", "agent-2 | created"]);
+    assert_eq!(api.calls(), vec!["list", "launch:agent-2"]);
+}
+
+fn fleet(size: usize) -> Manifest {
+    Manifest {
+        vms: (1..=size)
+            .map(|n| LaunchSpec::new(format!("agent-{n}")))
+            .collect(),
+    }
+}
+
+async fn run_apply(api: &FakeVmApi, args: &[&str]) -> anyhow::Result<Vec<String>> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    Ok(
+        run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api)
+            .await?
+            .into_lines(),
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn apply_never_exceeds_the_concurrency_limit() {
+    let api = FakeVmApi::new().with_launch_delay(Duration::from_secs(10));
+    let seen = Mutex::new(Vec::new());
+
+    let results = apply_manifest(&api, &fleet(7), 3, &CancellationToken::new(), &|result| {
+        seen.lock().unwrap().push(result.name.clone())
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(api.max_concurrent_launches(), 3);
+    assert_eq!(seen.into_inner().unwrap().len(), 7);
+    let names: Vec<String> = results.into_iter().map(|result| result.name).collect();
+    assert_eq!(
+        names,
+        fleet(7)
+            .vms
+            .into_iter()
+            .map(|spec| spec.name)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn apply_attempts_every_vm_and_fails_when_any_did() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let manifest_path = temp_dir.path().join("fleet.yaml");
+    std::fs::write(&manifest_path, TWO_VM_MANIFEST).unwrap();
+    let api = FakeVmApi::new().with_launch_failure("agent-1");
+
+    let err = run_apply(
+        &api,
+        &[
+            "safepaw",
+            "vm",
+            "apply",
+            manifest_path.to_str().unwrap(),
+            "--concurrency",
+            "1",
+        ],
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "applied 1 of 2 VMs; failed to launch agent-1: launch of 'agent-1' failed"
+    );
+    assert_eq!(
+        api.calls(),
+        vec!["list", "launch:agent-1", "launch:agent-2"]
+    );
+}
+
+#[tokio::test]
+async fn apply_reports_failures_in_results() {
+    let api = FakeVmApi::new().with_launch_failure("agent-2");
+
+    let results = apply_manifest(&api, &fleet(2), 2, &CancellationToken::new(), &|_| {})
+        .await
+        .unwrap();
+
+    assert_eq!(results[0].outcome, ApplyOutcome::Created);
+    assert!(matches!(results[1].outcome, ApplyOutcome::Failed(_)));
+}
+
+#[test]
+fn apply_concurrency_must_be_positive() {
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "apply", "f.yaml", "--concurrency", "0"])
+            .is_err()
+    );
+    assert!((1..=MAX_DEFAULT_APPLY_CONCURRENCY).contains(&default_apply_concurrency()));
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::{FakeVmApi, ScriptedConfirm};
+use safepaw::cli::{CommandResult, EXIT_USAGE, build_cli, exit_code, run_vm_subcommand_with};
+use safepaw::vm::{NamePattern, VmSummary};
+use serde_json::json;
+
+fn fleet() -> FakeVmApi {
+    FakeVmApi::new().with_list_response(vec![
+        VmSummary::minimal("agent-eu-1", "Running"),
+        VmSummary::minimal("agent-us-1", "Running"),
+        VmSummary::minimal("agent-eu-2", "Stopped"),
+        VmSummary::minimal("my-agent-eu-3", "Running"),
+    ])
+}
+
+async fn run(args: &[&str], api: &FakeVmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand_with(
+        matches.subcommand_matches("vm").unwrap(),
+        api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+}
+
+fn mutations(api: &FakeVmApi) -> Vec<String> {
+    api.calls()
+        .into_iter()
+        .filter(|call| call != "list")
+        .collect()
+}
+
+#[test]
+fn patterns_are_anchored_to_the_whole_name() {
+    let pattern = NamePattern::new("agent-eu-*").unwrap();
+
+    assert!(pattern.matches("agent-eu-1"));
+    assert!(!pattern.matches("my-agent-eu-1"));
+    assert!(!NamePattern::new("agent").unwrap().matches("agent-1"));
+}
+
+#[test]
+fn patterns_are_case_sensitive() {
+    let pattern = NamePattern::new("agent-*").unwrap();
+
+    assert!(pattern.matches("agent-1"));
+    assert!(!pattern.matches("Agent-1"));
+    assert!(!pattern.matches("AGENT-1"));
+}
+
+#[test]
+fn malformed_patterns_are_rejected() {
+    let err = NamePattern::new("agent-[").unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("invalid name pattern 'agent-['")
+    );
+}
+
+#[tokio::test]
+async fn list_filters_by_name_pattern() {
+    let api = fleet();
+
+    let output = run(&["safepaw", "vm", "list", "--name", "agent-eu-*"], &api)
+        .await
+        .unwrap();
+
+    match output {
+        CommandResult::Summaries(vms) => {
+            let names: Vec<&str> = vms.iter().map(|vm| vm.name.as_str()).collect();
+            assert_eq!(names, vec!["agent-eu-1", "agent-eu-2"]);
+        }
+        other => panic!("expected summaries, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn list_without_matches_says_so_and_succeeds() {
+    let api = fleet();
+
+    let lines = run(&["safepaw", "vm", "list", "--name", "web-*"], &api)
+        .await
+        .expect("an empty list is not an error")
+        .into_lines();
+    let json = run(
+        &["safepaw", "vm", "-o", "json", "list", "--name", "web-*"],
+        &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(lines, vec!["No VMs matched 'web-*'"]);
+    assert_eq!(json, CommandResult::Summaries(Vec::new()));
+}
+
+#[tokio::test]
+async fn list_rejects_a_malformed_pattern_as_usage() {
+    let err = run(&["safepaw", "vm", "list", "--name", "agent-["], &fleet())
+        .await
+        .unwrap_err();
+
+    assert_eq!(exit_code(&err), EXIT_USAGE);
+}
+
+#[tokio::test]
+async fn stop_acts_on_every_matching_vm() {
+    let api = fleet();
+
+    let lines = run(&["safepaw", "vm", "stop", "--name", "agent-eu-*"], &api)
+        .await
+        .expect("bulk stop should succeed")
+        .into_lines();
+
+    assert_eq!(lines, vec!["Stopped 2 VM(s): agent-eu-1, agent-eu-2"]);
+    assert_eq!(mutations(&api), vec!["stop:agent-eu-1", "stop:agent-eu-2"]);
+}
+
+#[tokio::test]
+async fn start_reports_json_for_matching_vms() {
+    let api = fleet();
+
+    let output = run(
+        &[
+            "safepaw",
+            "vm",
+            "-o",
+            "json",
+            "start",
+            "--name",
+            "agent-us-*",
+        ],
+        &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        output,
+        CommandResult::Json(json!({"ok": true, "action": "start", "started": ["agent-us-1"]}))
+    );
+    assert_eq!(mutations(&api), vec!["start:agent-us-1"]);
+}
+
+#[tokio::test]
+async fn delete_removes_matching_vms_in_one_call() {
+    let api = fleet();
+
+    run(
+        &["safepaw", "vm", "delete", "--name", "agent-eu-*", "--yes"],
+        &api,
+    )
+    .await
+    .expect("bulk delete should succeed");
+
+    assert_eq!(mutations(&api), vec!["delete:agent-eu-1,agent-eu-2"]);
+}
+
+#[tokio::test]
+async fn mutations_fail_when_nothing_matches() {
+    for command in ["start", "stop", "delete"] {
+        let api = fleet();
+
+        let err = run(&["safepaw", "vm", command, "--name", "web-*"], &api)
+            .await
+            .expect_err("an empty match should fail");
+
+        assert_eq!(err.to_string(), "no VMs matched 'web-*'");
+        assert_ne!(exit_code(&err), 0);
+        assert!(mutations(&api).is_empty());
+    }
+}
+
+#[tokio::test]
+async fn allow_empty_lets_an_empty_match_succeed() {
+    let api = fleet();
+
+    let lines = run(
+        &["safepaw", "vm", "stop", "--name", "web-*", "--allow-empty"],
+        &api,
+    )
+    .await
+    .expect("--allow-empty should succeed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["No VMs matched 'web-*'"]);
+    assert!(mutations(&api).is_empty());
+}
+
+#[test]
+fn name_pattern_excludes_names_and_wait() {
+    for args in [
+        &["safepaw", "vm", "stop", "agent-1", "--name", "agent-*"][..],
+        &["safepaw", "vm", "start", "--name", "agent-*", "--wait"][..],
+        &["safepaw", "vm", "stop", "--allow-empty"][..],
+        &["safepaw", "vm", "stop", "agent-1", "--allow-empty"][..],
+        &["safepaw", "vm", "start"][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err());
+    }
+}
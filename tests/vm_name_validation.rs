@@ -0,0 +1,72 @@
+use clap::error::ErrorKind;
+use safepaw::cli::build_cli;
+use safepaw::vm::{MAX_VM_NAME_LEN, NameViolation, validate_vm_name};
+
+fn violation(name: &str) -> NameViolation {
+    validate_vm_name(name)
+        .expect_err("name should be rejected")
+        .violation
+}
+
+#[test]
+fn a_maximal_name_is_valid() {
+    let name = format!("a{}", "-b".repeat(30));
+    assert_eq!(name.len(), MAX_VM_NAME_LEN);
+
+    validate_vm_name(&name).expect("a 61-character name should be valid");
+}
+
+#[test]
+fn each_rule_is_reported_by_name() {
+    let cases = [
+        ("", NameViolation::Empty),
+        (
+            &"a".repeat(MAX_VM_NAME_LEN + 1),
+            NameViolation::TooLong { length: 62 },
+        ),
+        ("1agent", NameViolation::StartsWithDigit),
+        ("-agent", NameViolation::LeadingHyphen),
+        ("agent-", NameViolation::TrailingHyphen),
+        ("Agent", NameViolation::Uppercase('A')),
+        ("has space", NameViolation::InvalidCharacter(' ')),
+        ("a/b", NameViolation::InvalidCharacter('/')),
+        ("bad_name", NameViolation::InvalidCharacter('_')),
+    ];
+
+    for (name, expected) in cases {
+        assert_eq!(violation(name), expected, "{name:?}");
+    }
+}
+
+#[test]
+fn errors_name_the_vm_and_the_rule() {
+    let err = validate_vm_name("agent-").unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "invalid VM name 'agent-': it must not end with a hyphen"
+    );
+    assert_eq!(err.violation.code(), "trailing_hyphen");
+    assert_eq!(
+        validate_vm_name("").unwrap_err().to_string(),
+        "a VM name is required"
+    );
+}
+
+#[test]
+fn cli_rejects_invalid_names_before_running_anything() {
+    for args in [
+        vec!["safepaw", "vm", "launch", "Bad"],
+        vec!["safepaw", "vm", "start", "has space"],
+        vec!["safepaw", "vm", "delete", "ok", "bad-"],
+        vec!["safepaw", "vm", "rename", "old", "new_name"],
+        vec!["safepaw", "agent", "list", "--vm", "a/b"],
+    ] {
+        let err = build_cli()
+            .try_get_matches_from(&args)
+            .expect_err("invalid name should not parse");
+
+        assert_eq!(err.kind(), ErrorKind::ValueValidation, "{args:?}");
+        assert!(err.to_string().contains("invalid VM name"), "{args:?}");
+    }
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::FakeMultipass;
+use safepaw::cli::build_cli;
+use safepaw::config::VmDefaults;
+use safepaw::vm::{
+    HostResourceProbe, HostResources, LaunchSpec, LocalVmApi, VmApi, check_host_resources,
+};
+use tokio_util::sync::CancellationToken;
+
+const GIB: u64 = 1 << 30;
+
+struct FixedProbe(HostResources);
+
+impl HostResourceProbe for FixedProbe {
+    fn resources(&self) -> HostResources {
+        self.0
+    }
+}
+
+fn host(memory_gib: u64, disk_gib: u64) -> HostResources {
+    HostResources {
+        available_memory: memory_gib * GIB,
+        available_disk: disk_gib * GIB,
+    }
+}
+
+fn spec(memory: &str, disk: &str) -> LaunchSpec {
+    LaunchSpec {
+        memory: Some(memory.to_owned()),
+        disk: Some(disk.to_owned()),
+        ..LaunchSpec::new("agent-1")
+    }
+}
+
+#[test]
+fn launches_that_fit_pass() {
+    assert!(check_host_resources(&spec("4G", "20G"), host(8, 100)).is_ok());
+    assert!(check_host_resources(&spec("8G", "100G"), host(8, 100)).is_ok());
+    assert!(check_host_resources(&LaunchSpec::new("agent-1"), host(2, 10)).is_ok());
+}
+
+#[test]
+fn oversized_requests_fail_with_the_shortfall() {
+    let err = check_host_resources(&spec("16G", "20G"), host(8, 100)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "not enough host memory to launch VM agent-1: it needs 16.0 GiB but only 8.0 GiB is free"
+    );
+
+    let err = check_host_resources(&spec("4G", "50G"), host(8, 10)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "not enough host disk space to launch VM agent-1: it needs 50.0 GiB but only 10.0 GiB is free"
+    );
+}
+
+#[test]
+fn multipass_defaults_are_checked_when_sizing_is_unset() {
+    let nearly_full = HostResources {
+        available_memory: 512 << 20,
+        available_disk: 100 * GIB,
+    };
+
+    assert!(check_host_resources(&LaunchSpec::new("agent-1"), nearly_full).is_err());
+}
+
+#[test]
+fn unknown_values_never_block_a_launch() {
+    assert!(check_host_resources(&spec("64G", "1T"), HostResources::default()).is_ok());
+    assert!(check_host_resources(&spec("lots", "more"), host(1, 1)).is_ok());
+}
+
+#[tokio::test]
+async fn launch_stops_before_multipass_when_preflight_fails() {
+    let multipass = Arc::new(FakeMultipass::new());
+    let api = LocalVmApi::new(multipass.clone())
+        .with_launch_defaults(VmDefaults {
+            memory: Some("32G".to_owned()),
+            ..VmDefaults::default()
+        })
+        .with_preflight(Arc::new(FixedProbe(host(8, 100))));
+
+    let err = api
+        .launch(&LaunchSpec::new("agent-1"), &CancellationToken::new())
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().starts_with("not enough host memory"));
+    assert!(multipass.calls().is_empty());
+}
+
+#[tokio::test]
+async fn launch_proceeds_when_the_host_has_room() {
+    let multipass = Arc::new(FakeMultipass::new().with_launch_response(Ok(())));
+    let api = LocalVmApi::new(multipass.clone()).with_preflight(Arc::new(FixedProbe(host(8, 100))));
+
+    api.launch(&spec("4G", "20G"), &CancellationToken::new())
+        .await
+        .expect("launch should succeed");
+
+    assert_eq!(multipass.calls(), vec!["launch:agent-1"]);
+}
+
+#[test]
+fn launch_accepts_no_preflight() {
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "launch", "agent-1", "--no-preflight"])
+        .unwrap();
+    let launch = matches
+        .subcommand_matches("vm")
+        .and_then(|vm| vm.subcommand_matches("launch"))
+        .unwrap();
+
+    assert!(launch.get_flag("no-preflight"));
+}
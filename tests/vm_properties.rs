@@ -0,0 +1,94 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::multipass_cli_with_outputs;
+use safepaw::cli::{CommandResult, build_cli, run_vm_subcommand};
+use safepaw::vm::{CommandOutput, LocalVmApi, VmApi, VmProperty};
+use serde_json::json;
+
+async fn run_vm(args: &[&str], api: &dyn VmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api).await
+}
+
+#[tokio::test]
+async fn set_passes_the_property_through_to_multipass() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![CommandOutput::success("")]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+
+    let lines = run_vm(&["safepaw", "vm", "set", "agent-1", "cpus", "4"], &api)
+        .await
+        .expect("set should work")
+        .into_lines();
+
+    assert_eq!(lines, vec!["Set cpus of VM 'agent-1' to 4"]);
+    assert_eq!(
+        fake.calls(),
+        vec![["multipass", "set", "local.agent-1.cpus=4"].map(String::from)]
+    );
+}
+
+#[tokio::test]
+async fn get_prints_the_value_or_json() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![
+        CommandOutput::success("8.0GiB\n"),
+        CommandOutput::success("8.0GiB\n"),
+    ]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+
+    let text = run_vm(&["safepaw", "vm", "get", "agent-1", "memory"], &api)
+        .await
+        .unwrap()
+        .into_lines();
+    let json = run_vm(
+        &["safepaw", "vm", "-o", "json", "get", "agent-1", "memory"],
+        &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(text, vec!["8.0GiB"]);
+    assert_eq!(
+        json,
+        CommandResult::Json(json!({"name": "agent-1", "key": "memory", "value": "8.0GiB"}))
+    );
+}
+
+#[test]
+fn keys_outside_the_allowlist_are_rejected() {
+    for args in [
+        ["safepaw", "vm", "set", "agent-1", "bridged", "true"].as_slice(),
+        ["safepaw", "vm", "get", "agent-1", "mounts"].as_slice(),
+    ] {
+        let err = build_cli()
+            .try_get_matches_from(args)
+            .expect_err("unknown key");
+        assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+    }
+    assert!("driver".parse::<VmProperty>().is_err());
+}
+
+#[tokio::test]
+async fn invalid_values_never_reach_multipass() {
+    let (multipass, fake) = multipass_cli_with_outputs(Vec::new());
+    let api = LocalVmApi::new(Arc::new(multipass));
+
+    for (key, value) in [
+        ("cpus", "0"),
+        ("cpus", "two"),
+        ("memory", "lots"),
+        ("disk", "0G"),
+    ] {
+        let err = run_vm(&["safepaw", "vm", "set", "agent-1", key, value], &api)
+            .await
+            .expect_err("invalid value");
+        assert!(
+            format!("{err:#}").contains(&format!("{key} '{value}' is not")),
+            "{err:#}"
+        );
+    }
+    assert!(fake.calls().is_empty());
+}
@@ -0,0 +1,318 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FakeMultipass, ScriptedConfirm};
+use safepaw::cli::{CommandResult, build_cli, run_vm_subcommand_with};
+use safepaw::vm::{LocalVmApi, PruneSelection, VmError, VmSummary, prune_vms};
+use serde_json::json;
+
+fn fleet() -> FakeMultipass {
+    FakeMultipass::new().with_list(vec![
+        VmSummary::minimal("sp-agent-1", "Running"),
+        VmSummary::minimal("other-vm", "Stopped"),
+        VmSummary::minimal("sp-agent-2", "Stopped"),
+    ])
+}
+
+fn mixed_states() -> FakeMultipass {
+    FakeMultipass::new().with_list(vec![
+        VmSummary::minimal("running", "Running"),
+        VmSummary::minimal("stopped", "Stopped"),
+        VmSummary::minimal("suspended", "Suspended"),
+        VmSummary::minimal("starting", "Starting"),
+    ])
+}
+
+async fn run_prune(
+    args: &[&str],
+    api: &LocalVmApi,
+    confirm: &ScriptedConfirm,
+) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand_with(matches.subcommand_matches("vm").unwrap(), api, confirm).await
+}
+
+#[tokio::test]
+async fn prune_all_deletes_every_vm_with_the_prefix() {
+    let fake = fleet();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let lines = run_prune(
+        &[
+            "safepaw", "vm", "prune", "--all", "--prefix", "sp-", "--yes",
+        ],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect("prune should succeed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["Removed 2 VM(s): sp-agent-1, sp-agent-2"]);
+    assert_eq!(
+        fake.calls(),
+        vec!["list", "delete:sp-agent-1", "delete:sp-agent-2"]
+    );
+}
+
+#[tokio::test]
+async fn prune_all_without_prefix_deletes_everything_listed() {
+    let fake = fleet();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let output = run_prune(
+        &["safepaw", "vm", "prune", "--all", "--yes", "-o", "json"],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect("prune should succeed");
+
+    assert_eq!(
+        output,
+        CommandResult::Json(json!({
+            "ok": true,
+            "action": "prune",
+            "removed": ["sp-agent-1", "other-vm", "sp-agent-2"],
+        }))
+    );
+}
+
+#[tokio::test]
+async fn prune_prompts_and_respects_a_no() {
+    let fake = fleet();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+    let confirm = ScriptedConfirm::answering(false);
+
+    let lines = run_prune(
+        &["safepaw", "vm", "prune", "--all", "--prefix", "sp-"],
+        &api,
+        &confirm,
+    )
+    .await
+    .unwrap()
+    .into_lines();
+
+    assert_eq!(lines, vec!["Aborted; no VMs were deleted"]);
+    assert_eq!(
+        confirm.prompts(),
+        vec!["Delete 2 VM(s) (sp-agent-1, sp-agent-2)? This cannot be undone."]
+    );
+    assert_eq!(fake.calls(), vec!["list"]);
+}
+
+#[tokio::test]
+async fn prune_requires_yes_without_a_terminal() {
+    let fake = fleet();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let err = run_prune(
+        &["safepaw", "vm", "prune"],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect_err("prune should refuse");
+
+    assert!(err.to_string().contains("pass --yes"));
+    assert_eq!(fake.calls(), vec!["list"]);
+}
+
+#[tokio::test]
+async fn prune_keeps_going_after_a_failed_delete() {
+    let fake = fleet().with_delete_response(Err(VmError::CommandFailed {
+        action: "delete",
+        status_code: 2,
+        stderr: "instance is busy".to_owned(),
+    }));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let err = run_prune(
+        &["safepaw", "vm", "prune", "--all", "--yes"],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect_err("a failed delete should fail the command");
+
+    let message = err.to_string();
+    assert!(message.starts_with("removed 2 of 3 VMs; failed to delete sp-agent-1: "));
+    assert!(message.contains("instance is busy"));
+    assert_eq!(
+        fake.calls(),
+        vec![
+            "list",
+            "delete:sp-agent-1",
+            "delete:other-vm",
+            "delete:sp-agent-2"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn prune_with_nothing_to_delete_does_not_prompt() {
+    let fake = fleet();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+    let confirm = ScriptedConfirm::answering(true);
+
+    let lines = run_prune(
+        &["safepaw", "vm", "prune", "--prefix", "zz-"],
+        &api,
+        &confirm,
+    )
+    .await
+    .unwrap()
+    .into_lines();
+
+    assert_eq!(lines, vec!["No VMs to prune"]);
+    assert!(confirm.prompts().is_empty());
+}
+
+#[tokio::test]
+async fn prune_vms_reports_results_in_order() {
+    let fake = FakeMultipass::new()
+        .with_delete_response(Ok(()))
+        .with_delete_response(Err(VmError::CommandIo("gone".to_owned())));
+    let api = LocalVmApi::new(Arc::new(fake));
+
+    let results = prune_vms(&api, &["a".to_owned(), "b".to_owned()], 2, &|_| {}).await;
+
+    assert_eq!(results[0].name, "a");
+    assert!(results[0].error.is_none());
+    assert_eq!(results[1].name, "b");
+    assert!(results[1].error.as_deref().unwrap().contains("gone"));
+}
+
+#[test]
+fn selection_defaults_to_stopped_vms() {
+    let vms = [
+        VmSummary::minimal("sp-a", "Running"),
+        VmSummary::minimal("sp-b", "Stopped"),
+        VmSummary::minimal("sp-c", "Suspended"),
+        VmSummary::minimal("other", "Stopped"),
+    ];
+    let selected = |selection: PruneSelection| -> Vec<&str> {
+        vms.iter()
+            .filter(|vm| selection.matches(vm))
+            .map(|vm| vm.name.as_str())
+            .collect()
+    };
+
+    assert_eq!(selected(PruneSelection::default()), vec!["sp-b", "other"]);
+    assert_eq!(
+        selected(PruneSelection {
+            include_suspended: true,
+            ..PruneSelection::default()
+        }),
+        vec!["sp-b", "sp-c", "other"]
+    );
+    assert_eq!(
+        selected(PruneSelection {
+            prefix: "sp-".to_owned(),
+            any_state: true,
+            ..PruneSelection::default()
+        }),
+        vec!["sp-a", "sp-b", "sp-c"]
+    );
+}
+
+#[tokio::test]
+async fn prune_deletes_only_stopped_vms_by_default() {
+    let fake = mixed_states();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let lines = run_prune(
+        &["safepaw", "vm", "prune", "--yes"],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect("prune should succeed")
+    .into_lines();
+
+    assert_eq!(lines, vec!["Removed 1 VM(s): stopped"]);
+    assert_eq!(fake.calls(), vec!["list", "delete:stopped"]);
+}
+
+#[tokio::test]
+async fn prune_can_include_suspended_vms() {
+    let fake = mixed_states();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    run_prune(
+        &["safepaw", "vm", "prune", "--include-suspended", "--yes"],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect("prune should succeed");
+
+    assert_eq!(
+        fake.calls(),
+        vec!["list", "delete:stopped", "delete:suspended"]
+    );
+}
+
+#[tokio::test]
+async fn dry_run_lists_candidates_without_prompting_or_deleting() {
+    let fake = mixed_states();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+    let confirm = ScriptedConfirm::answering(true);
+
+    let lines = run_prune(
+        &["safepaw", "vm", "prune", "--include-suspended", "--dry-run"],
+        &api,
+        &confirm,
+    )
+    .await
+    .expect("dry run should succeed")
+    .into_lines();
+
+    assert_eq!(
+        lines,
+        vec![
+            "Would delete 2 VM(s):",
+            "  stopped (Stopped)",
+            "  suspended (Suspended)",
+        ]
+    );
+    assert!(confirm.prompts().is_empty());
+    assert_eq!(fake.calls(), vec!["list"]);
+}
+
+#[tokio::test]
+async fn dry_run_reports_candidates_as_json() {
+    let fake = mixed_states();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let output = run_prune(
+        &["safepaw", "vm", "-o", "json", "prune", "--dry-run"],
+        &api,
+        &ScriptedConfirm::non_interactive(),
+    )
+    .await
+    .expect("dry run should succeed");
+
+    assert_eq!(
+        output,
+        CommandResult::Json(json!({
+            "ok": true,
+            "action": "prune",
+            "dry_run": true,
+            "candidates": ["stopped"],
+        }))
+    );
+    assert_eq!(fake.calls(), vec!["list"]);
+}
+
+#[test]
+fn all_conflicts_with_include_suspended() {
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "prune", "--all", "--include-suspended"])
+            .is_err()
+    );
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::FakeMultipass;
+use safepaw::cli::{build_cli, rename_progress_line, run_vm_subcommand};
+use safepaw::tags::TagRegistry;
+use safepaw::vm::{
+    LaunchSpec, LocalVmApi, RenameOptions, RenameStep, VmApi, VmError, validate_vm_name,
+};
+use tokio_util::sync::CancellationToken;
+
+async fn rename(api: &LocalVmApi, force_stop: bool) -> (anyhow::Result<()>, Vec<RenameStep>) {
+    let steps = Mutex::new(Vec::new());
+    let result = api
+        .rename(
+            "scratch",
+            "agent-7",
+            &RenameOptions { force_stop },
+            &|step| steps.lock().unwrap().push(step),
+        )
+        .await;
+    (result, steps.into_inner().unwrap())
+}
+
+fn not_found(name: &str) -> VmError {
+    VmError::CommandFailed {
+        action: "info",
+        status_code: 2,
+        stderr: format!("instance \"{name}\" does not exist"),
+    }
+}
+
+#[tokio::test]
+async fn rename_clones_verifies_then_deletes_the_original() {
+    let fake = FakeMultipass::new().with_status("scratch", "Stopped");
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let (result, steps) = rename(&api, false).await;
+
+    result.expect("rename should succeed");
+    assert_eq!(
+        fake.calls(),
+        vec![
+            "info:scratch",
+            "clone:scratch:agent-7",
+            "info:agent-7",
+            "delete:scratch",
+        ]
+    );
+    assert_eq!(
+        steps,
+        vec![
+            RenameStep::Cloning,
+            RenameStep::Verifying,
+            RenameStep::DeletingOriginal,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn rename_refuses_a_running_vm_without_force_stop() {
+    let fake = FakeMultipass::new().with_status("scratch", "Running");
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let (result, steps) = rename(&api, false).await;
+
+    let err = result.expect_err("running VM should not be renamed");
+    assert!(err.to_string().contains("--force-stop"));
+    assert_eq!(fake.calls(), vec!["info:scratch"]);
+    assert!(steps.is_empty());
+}
+
+#[tokio::test]
+async fn rename_with_force_stop_stops_a_running_vm_first() {
+    let fake = FakeMultipass::new().with_status("scratch", "Running");
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let (result, steps) = rename(&api, true).await;
+
+    result.expect("rename should succeed");
+    assert_eq!(
+        fake.calls(),
+        vec![
+            "info:scratch",
+            "stop:scratch",
+            "clone:scratch:agent-7",
+            "info:agent-7",
+            "delete:scratch",
+        ]
+    );
+    assert_eq!(steps[0], RenameStep::Stopping);
+}
+
+#[tokio::test]
+async fn failed_clone_keeps_the_original() {
+    let fake = FakeMultipass::new()
+        .with_status("scratch", "Stopped")
+        .with_clone_response(Err(VmError::CommandFailed {
+            action: "clone",
+            status_code: 2,
+            stderr: "not enough disk space".to_owned(),
+        }));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let (result, _) = rename(&api, false).await;
+
+    let err = result.expect_err("clone failure should fail the rename");
+    assert!(err.to_string().contains("original was left untouched"));
+    assert_eq!(fake.calls(), vec!["info:scratch", "clone:scratch:agent-7"]);
+}
+
+#[tokio::test]
+async fn unverified_clone_keeps_the_original() {
+    let fake = FakeMultipass::new()
+        .with_info_response(Ok(safepaw::vm::VmStatusResponse::minimal(
+            "scratch", "Stopped",
+        )))
+        .with_info_response(Err(not_found("agent-7")));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let (result, _) = rename(&api, false).await;
+
+    assert!(result.is_err());
+    assert!(!fake.calls().iter().any(|call| call.starts_with("delete:")));
+}
+
+#[tokio::test]
+async fn rename_validates_the_new_name_before_touching_multipass() {
+    let fake = FakeMultipass::new();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let err = api
+        .rename("scratch", "bad_name", &RenameOptions::default(), &|_| {})
+        .await
+        .expect_err("invalid name should be rejected");
+
+    assert!(err.to_string().contains("invalid VM name 'bad_name'"));
+    assert!(fake.calls().is_empty());
+}
+
+#[tokio::test]
+async fn rename_moves_tags_to_the_new_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = Arc::new(TagRegistry::new(dir.path().join("tags.json")));
+    registry.tag("scratch", &["gpu".to_owned()]).unwrap();
+    let fake = FakeMultipass::new().with_status("scratch", "Stopped");
+    let api = LocalVmApi::new(Arc::new(fake)).with_tag_registry(registry.clone());
+
+    rename(&api, false).await.0.expect("rename should succeed");
+
+    assert!(registry.tags("scratch").unwrap().is_empty());
+    assert_eq!(registry.tags("agent-7").unwrap(), vec!["gpu"]);
+}
+
+#[tokio::test]
+async fn cli_rename_reports_the_new_name() {
+    let fake = FakeMultipass::new().with_status("scratch", "Stopped");
+    let api = LocalVmApi::new(Arc::new(fake));
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "rename", "scratch", "agent-7"])
+        .unwrap();
+
+    let lines = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .expect("rename should succeed")
+        .into_lines();
+
+    assert_eq!(lines, vec!["VM 'scratch' renamed to 'agent-7'"]);
+    assert_eq!(
+        rename_progress_line(RenameStep::Cloning, "scratch", "agent-7"),
+        "Cloning VM 'scratch' to 'agent-7'..."
+    );
+}
+
+#[test]
+fn vm_names_follow_multipass_rules() {
+    for name in ["dev", "agent-1", "a", "a1-b2"] {
+        assert!(validate_vm_name(name).is_ok(), "{name} should be valid");
+    }
+    for name in [
+        "",
+        "1agent",
+        "-agent",
+        "agent-",
+        "bad_name",
+        "has space",
+        "A1-b2",
+    ] {
+        assert!(
+            validate_vm_name(name).is_err(),
+            "{name:?} should be invalid"
+        );
+    }
+    assert!(validate_vm_name(&"a".repeat(62)).is_err());
+}
+
+#[tokio::test]
+async fn launch_uses_the_same_name_validation() {
+    let fake = FakeMultipass::new();
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let spec = LaunchSpec {
+        name: "bad_name".to_owned(),
+        ..LaunchSpec::default()
+    };
+    assert!(api.launch(&spec, &CancellationToken::new()).await.is_err());
+    assert!(fake.calls().is_empty());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{FakeExecutor, multipass_cli_with_outputs};
+use safepaw::cli::{CommandResult, EXIT_USAGE, build_cli, exit_code, run_vm_subcommand};
+use safepaw::vm::{
+    CommandExecutor, CommandOutput, LocalVmApi, SshCommandExecutor, SshConfig,
+    TokioCommandExecutor, VmApi,
+};
+
+async fn run_vm(args: &[&str], api: &dyn VmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api).await
+}
+
+fn exited_with(status: i32) -> CommandOutput {
+    CommandOutput {
+        status_code: status,
+        ..CommandOutput::success("")
+    }
+}
+
+#[tokio::test]
+async fn shell_runs_multipass_shell_interactively() {
+    let (multipass, fake) = multipass_cli_with_outputs(vec![exited_with(0)]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+
+    let lines = run_vm(&["safepaw", "vm", "shell", "agent-1"], &api)
+        .await
+        .expect("shell should work")
+        .into_lines();
+
+    assert!(lines.is_empty());
+    assert_eq!(
+        fake.calls(),
+        vec![["multipass", "shell", "agent-1"].map(String::from)]
+    );
+}
+
+#[tokio::test]
+async fn shell_exits_with_the_shell_status() {
+    let (multipass, _fake) = multipass_cli_with_outputs(vec![exited_with(7)]);
+    let api = LocalVmApi::new(Arc::new(multipass));
+
+    let err = run_vm(&["safepaw", "vm", "shell", "agent-1"], &api)
+        .await
+        .expect_err("a failing shell is an error");
+
+    assert_eq!(exit_code(&err), 7);
+}
+
+#[tokio::test]
+async fn shell_refuses_json_output() {
+    let (multipass, fake) = multipass_cli_with_outputs(Vec::new());
+    let api = LocalVmApi::new(Arc::new(multipass));
+
+    let err = run_vm(&["safepaw", "vm", "-o", "json", "shell", "agent-1"], &api)
+        .await
+        .expect_err("shell is interactive only");
+
+    assert_eq!(exit_code(&err), EXIT_USAGE);
+    assert!(fake.calls().is_empty());
+}
+
+#[tokio::test]
+async fn ssh_shell_asks_for_a_remote_terminal() {
+    let fake = FakeExecutor::new(vec![exited_with(0)]);
+    let executor = SshCommandExecutor::with_executor(SshConfig::new("lab.local"), fake.clone());
+
+    let status = executor
+        .run_interactive("multipass", &["shell".to_owned(), "agent-1".to_owned()])
+        .await
+        .expect("ssh shell should run");
+
+    assert_eq!(status, 0);
+    let call = &fake.calls()[0];
+    assert_eq!(call[..2], ["ssh", "-t"].map(String::from));
+    assert_eq!(call.last().unwrap(), "multipass shell agent-1");
+}
+
+#[tokio::test]
+async fn tokio_executor_returns_the_interactive_exit_status() {
+    let status = TokioCommandExecutor::new()
+        .run_interactive("sh", &["-c".to_owned(), "exit 4".to_owned()])
+        .await
+        .expect("command should run");
+
+    assert_eq!(status, 4);
+}
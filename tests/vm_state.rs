@@ -0,0 +1,39 @@
+use safepaw::vm::{VmState, VmSummary};
+
+#[test]
+fn parses_each_known_multipass_state() {
+    let cases = [
+        ("Running", VmState::Running),
+        ("Stopped", VmState::Stopped),
+        ("Suspended", VmState::Suspended),
+        ("Starting", VmState::Starting),
+        ("Deleted", VmState::Deleted),
+    ];
+    for (text, state) in cases {
+        assert_eq!(text.parse::<VmState>().unwrap(), state);
+        assert_eq!(state.to_string(), text);
+    }
+    assert_eq!("running".parse::<VmState>().unwrap(), VmState::Running);
+}
+
+#[test]
+fn keeps_unknown_states_verbatim() {
+    let state: VmState = "Delayed Shutdown".parse().unwrap();
+    assert_eq!(state, VmState::Unknown("Delayed Shutdown".to_owned()));
+    assert_eq!(state.to_string(), "Delayed Shutdown");
+    assert_eq!(
+        VmState::from("Unknown"),
+        VmState::Unknown("Unknown".to_owned())
+    );
+}
+
+#[test]
+fn serializes_as_a_plain_string() {
+    let vm = VmSummary::minimal("agent-1", VmState::Suspended);
+    let json = serde_json::to_value(&vm).unwrap();
+    assert_eq!(json["state"], "Suspended");
+
+    let parsed: VmSummary =
+        serde_json::from_str(r#"{"name":"agent-1","state":"Restarting"}"#).unwrap();
+    assert_eq!(parsed.state, VmState::Unknown("Restarting".to_owned()));
+}
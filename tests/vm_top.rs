@@ -0,0 +1,85 @@
+use safepaw::cli::{TopSort, build_cli, render_top_table};
+use safepaw::vm::VmStatusResponse;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+fn running(name: &str, memory_used: u64, disk_used: u64) -> VmStatusResponse {
+    VmStatusResponse {
+        cpu_count: Some("2".to_owned()),
+        memory_used: Some(memory_used),
+        memory_total: Some(4 * GIB),
+        disk_used: Some(disk_used),
+        disk_total: Some(10 * GIB),
+        ..VmStatusResponse::minimal(name, "Running")
+    }
+}
+
+fn fleet() -> Vec<VmStatusResponse> {
+    vec![
+        VmStatusResponse {
+            // Stale figures from before the VM stopped must not show.
+            memory_used: Some(3 * GIB),
+            memory_total: Some(4 * GIB),
+            ..VmStatusResponse::minimal("idle", "Stopped")
+        },
+        running("web", GIB, 5 * GIB),
+        running("db", 3 * GIB, GIB),
+    ]
+}
+
+#[test]
+fn top_sorts_by_memory_usage_with_dashes_for_stopped_vms() {
+    assert_eq!(
+        render_top_table(&fleet(), TopSort::default()),
+        vec![
+            "NAME  STATE    CPUS  MEM    DISK",
+            "db    Running  2     75.0%  10.0%",
+            "web   Running  2     25.0%  50.0%",
+            "idle  Stopped  -     -      -",
+        ]
+    );
+}
+
+#[test]
+fn top_can_sort_by_disk_or_name() {
+    let by_disk = render_top_table(&fleet(), TopSort::Disk);
+    let by_name = render_top_table(&fleet(), TopSort::Name);
+
+    let names = |lines: &[String]| -> Vec<String> {
+        lines[1..]
+            .iter()
+            .map(|line| line.split_whitespace().next().unwrap().to_owned())
+            .collect()
+    };
+    assert_eq!(names(&by_disk), vec!["web", "db", "idle"]);
+    assert_eq!(names(&by_name), vec!["db", "idle", "web"]);
+}
+
+#[test]
+fn top_breaks_usage_ties_by_name() {
+    let infos = vec![running("b", GIB, GIB), running("a", GIB, GIB)];
+
+    let lines = render_top_table(&infos, TopSort::Memory);
+
+    assert!(lines[1].starts_with("a "));
+    assert!(lines[2].starts_with("b "));
+}
+
+#[test]
+fn top_without_vms_says_so() {
+    assert_eq!(render_top_table(&[], TopSort::Memory), vec!["No VMs found"]);
+}
+
+#[test]
+fn top_interval_must_be_positive() {
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "top", "--interval", "0"])
+            .is_err()
+    );
+    assert!(
+        build_cli()
+            .try_get_matches_from(["safepaw", "vm", "top", "--sort", "cpu"])
+            .is_err()
+    );
+}
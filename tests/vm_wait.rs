@@ -0,0 +1,246 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{FakeMultipass, FakeVmApi};
+use safepaw::cli::{
+    CommandResult, EXIT_TIMEOUT, EXIT_VM_NOT_FOUND, build_cli, exit_code, run_vm_subcommand,
+};
+use safepaw::vm::{
+    LocalVmApi, VmApi, VmError, VmState, VmStatusResponse, VmSummary, WaitOptions,
+    wait_for_deletion, wait_for_state,
+};
+use serde_json::json;
+
+fn states(states: &[&str]) -> Vec<VmStatusResponse> {
+    states
+        .iter()
+        .map(|state| VmStatusResponse::minimal("agent-1", *state))
+        .collect()
+}
+
+async fn run_wait(args: &[&str], api: &dyn VmApi) -> anyhow::Result<CommandResult> {
+    let matches = build_cli()
+        .try_get_matches_from(args)
+        .expect("failed to parse CLI args");
+    run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), api).await
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_reports_each_state_change_until_the_target() {
+    let api = FakeVmApi::new().with_info_sequence(states(&[
+        "Running", "Running", "Stopping", "Stopping", "Stopped",
+    ]));
+    let seen = Mutex::new(Vec::new());
+    let opts = WaitOptions::default().with_interval(Duration::from_secs(1));
+
+    let info = wait_for_state(&api, "agent-1", &VmState::Stopped, &opts, &|state| {
+        seen.lock().unwrap().push(state.to_string())
+    })
+    .await
+    .expect("the VM should stop");
+
+    assert_eq!(info.state, VmState::Stopped);
+    assert_eq!(
+        seen.into_inner().unwrap(),
+        vec!["Running", "Stopping", "Stopped"]
+    );
+    assert_eq!(api.calls().len(), 5);
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_times_out_with_the_timeout_exit_code() {
+    let api = FakeVmApi::new().with_info_response(VmStatusResponse::minimal("agent-1", "Running"));
+
+    let err = run_wait(
+        &[
+            "safepaw",
+            "vm",
+            "wait",
+            "agent-1",
+            "--state",
+            "stopped",
+            "--timeout",
+            "10",
+        ],
+        &api,
+    )
+    .await
+    .expect_err("the VM never stops");
+
+    assert_eq!(exit_code(&err), EXIT_TIMEOUT);
+    assert!(err.to_string().contains("last state: Running"));
+    // Polled at 0, 2, 4, 6, 8 and 10 seconds with the default interval.
+    assert_eq!(api.calls().len(), 6);
+}
+
+#[tokio::test]
+async fn wait_for_missing_vm_fails_without_polling() {
+    let fake = FakeMultipass::new().with_info_response(Err(VmError::CommandFailed {
+        action: "info",
+        status_code: 2,
+        stderr: "info failed: instance \"ghost\" does not exist".to_owned(),
+    }));
+    let api = LocalVmApi::new(Arc::new(fake.clone()));
+
+    let err = run_wait(
+        &["safepaw", "vm", "wait", "ghost", "--state", "Running"],
+        &api,
+    )
+    .await
+    .expect_err("a missing VM should fail");
+
+    assert_eq!(exit_code(&err), EXIT_VM_NOT_FOUND);
+    assert_eq!(fake.calls(), vec!["info:ghost"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_command_reports_the_reached_state() {
+    let api = FakeVmApi::new().with_info_sequence(states(&["Starting", "Running"]));
+
+    let output = run_wait(
+        &[
+            "safepaw",
+            "vm",
+            "-o",
+            "json",
+            "wait",
+            "agent-1",
+            "--state",
+            "Running",
+            "--interval",
+            "1",
+        ],
+        &api,
+    )
+    .await
+    .expect("wait should succeed");
+
+    assert_eq!(
+        output,
+        CommandResult::Json(json!({
+            "ok": true,
+            "action": "wait",
+            "name": "agent-1",
+            "state": "Running",
+        }))
+    );
+}
+
+fn listed(state: &str) -> Result<Vec<VmSummary>, String> {
+    Ok(vec![
+        VmSummary::minimal("agent-2", "Running"),
+        VmSummary::minimal("agent-1", state),
+    ])
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_for_deletion_returns_once_the_vm_leaves_the_list() {
+    let api = FakeVmApi::new()
+        .with_list_sequence(vec![
+            listed("Running"),
+            listed("Stopped"),
+            listed("Stopped"),
+        ])
+        .with_list_response(vec![VmSummary::minimal("agent-2", "Running")]);
+    let seen = Mutex::new(Vec::new());
+    let opts = WaitOptions::default().with_interval(Duration::from_secs(1));
+    let started = tokio::time::Instant::now();
+
+    wait_for_deletion(&api, "agent-1", &opts, &|state| {
+        seen.lock().unwrap().push(state.to_string())
+    })
+    .await
+    .expect("the VM should go away");
+
+    assert_eq!(
+        seen.into_inner().unwrap(),
+        vec!["Running", "Stopped", "Deleted"]
+    );
+    assert_eq!(api.calls(), vec!["list"; 4]);
+    assert_eq!(started.elapsed(), Duration::from_secs(3));
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_for_deletion_accepts_a_vm_listed_as_deleted() {
+    let api = FakeVmApi::new().with_list_sequence(vec![listed("Running"), listed("Deleted")]);
+
+    wait_for_deletion(&api, "agent-1", &WaitOptions::default(), &|_| {})
+        .await
+        .expect("a deleted but unpurged VM counts as deleted");
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_deleted_command_exits_zero_when_the_vm_is_gone() {
+    let api = FakeVmApi::new()
+        .with_list_sequence(vec![listed("Running")])
+        .with_list_response(vec![]);
+
+    let output = run_wait(
+        &[
+            "safepaw", "vm", "-o", "json", "wait", "agent-1", "--state", "deleted",
+        ],
+        &api,
+    )
+    .await
+    .expect("wait should succeed");
+
+    assert_eq!(
+        output,
+        CommandResult::Json(json!({
+            "ok": true,
+            "action": "wait",
+            "name": "agent-1",
+            "state": "Deleted",
+        }))
+    );
+    // Only `list` is consulted; `info` would fail for a VM that is gone.
+    assert_eq!(api.calls(), vec!["list", "list"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_deleted_times_out_while_the_vm_is_still_listed() {
+    let api = FakeVmApi::new().with_list_response(vec![VmSummary::minimal("agent-1", "Running")]);
+
+    let err = run_wait(
+        &[
+            "safepaw",
+            "vm",
+            "wait",
+            "agent-1",
+            "--state",
+            "deleted",
+            "--timeout",
+            "10",
+        ],
+        &api,
+    )
+    .await
+    .expect_err("the VM is never deleted");
+
+    assert_eq!(exit_code(&err), EXIT_TIMEOUT);
+    assert!(err.to_string().contains("to reach Deleted"));
+    assert!(err.to_string().contains("last state: Running"));
+    assert_eq!(api.calls().len(), 6);
+}
+
+#[test]
+fn wait_requires_a_known_state() {
+    for args in [
+        &["safepaw", "vm", "wait", "agent-1"][..],
+        &["safepaw", "vm", "wait", "agent-1", "--state", "Gone"][..],
+        &[
+            "safepaw",
+            "vm",
+            "wait",
+            "agent-1",
+            "--state",
+            "Running",
+            "--interval",
+            "0",
+        ][..],
+    ] {
+        assert!(build_cli().try_get_matches_from(args).is_err());
+    }
+}
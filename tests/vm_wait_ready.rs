@@ -0,0 +1,81 @@
+mod common;
+
+use std::time::Duration;
+
+use common::FakeVmApi;
+use safepaw::cli::{build_cli, run_vm_subcommand};
+use safepaw::vm::{VmStatusResponse, WaitOptions, wait_for_ready};
+
+fn with_ipv4(mut status: VmStatusResponse, ip: &str) -> VmStatusResponse {
+    status.ipv4 = Some(vec![ip.to_owned()]);
+    status
+}
+
+#[test]
+fn wait_options_default_to_five_minutes_every_two_seconds() {
+    let opts = WaitOptions::default();
+    assert_eq!(opts.timeout, Duration::from_secs(300));
+    assert_eq!(opts.interval, Duration::from_secs(2));
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_for_ready_requires_running_state_and_ipv4() {
+    let api = FakeVmApi::default()
+        .with_info_sequence(vec![
+            VmStatusResponse::minimal("agent-1", "Starting"),
+            // Running but cloud-init has not brought the network up yet
+            VmStatusResponse::minimal("agent-1", "Running"),
+            with_ipv4(VmStatusResponse::minimal("agent-1", "Starting"), "10.0.0.3"),
+        ])
+        .with_info_response(with_ipv4(
+            VmStatusResponse::minimal("agent-1", "Running"),
+            "10.0.0.3",
+        ));
+
+    let info = wait_for_ready(&api, "agent-1", &WaitOptions::default())
+        .await
+        .expect("VM should become ready");
+
+    assert_eq!(info.ipv4, Some(vec!["10.0.0.3".to_owned()]));
+    assert_eq!(api.calls().len(), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn wait_for_ready_reports_last_state_on_timeout() {
+    let api =
+        FakeVmApi::default().with_info_response(VmStatusResponse::minimal("agent-1", "Starting"));
+    let opts = WaitOptions::default().with_timeout(Duration::from_secs(5));
+
+    let err = wait_for_ready(&api, "agent-1", &opts)
+        .await
+        .expect_err("wait should time out");
+
+    assert_eq!(
+        err.to_string(),
+        "timed out after 5s waiting for VM 'agent-1' to be ready (last state: Starting, no IPv4 address)"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn restart_wait_reports_the_address_once_the_vm_is_back() {
+    let api = FakeVmApi::default()
+        .with_info_sequence(vec![VmStatusResponse::minimal("agent-1", "Running")])
+        .with_info_response(with_ipv4(
+            VmStatusResponse::minimal("agent-1", "Running"),
+            "10.0.0.7",
+        ));
+    let matches = build_cli()
+        .try_get_matches_from(["safepaw", "vm", "restart", "agent-1", "--wait"])
+        .expect("failed to parse CLI args");
+
+    let lines = run_vm_subcommand(matches.subcommand_matches("vm").unwrap(), &api)
+        .await
+        .expect("restart should succeed")
+        .into_lines();
+
+    assert_eq!(
+        api.calls(),
+        vec!["restart:agent-1", "info:agent-1", "info:agent-1"]
+    );
+    assert_eq!(lines.last().unwrap(), "VM 'agent-1' is ready at 10.0.0.7");
+}
@@ -53,8 +53,25 @@ window.SafePawConfig = {
 // APPLICATION INITIALIZATION
 // ============================================================================
 
+// In single-port mode the server describes where the API lives; with
+// separate UI and API ports there is no /config.json and the default stays.
+async function loadServerConfig() {
+    try {
+        const response = await fetch('/config.json');
+        if (!response.ok) return;
+        const config = await response.json();
+        if (typeof config.api_base === 'string') {
+            window.SafePawConfig.API_BASE = window.location.origin + config.api_base;
+        }
+    } catch (error) {
+        console.warn('[CONFIG] Could not load /config.json, using default API base', error);
+    }
+}
+
 // Initialize the village when the page loads
 window.addEventListener('load', async () => {
+    await loadServerConfig();
+
     // Create state manager (imported from state.js)
     const stateManager = new VMStateManager();
 
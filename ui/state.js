@@ -7,6 +7,7 @@ class VMStateManager extends EventTarget {
         this.agents = new Map(); // Map of VM name -> array of agents
         this.isPolling = false;
         this.pollInterval = null;
+        this.events = null;
     }
 
     startPolling(intervalMs = 8000) {
@@ -15,16 +16,52 @@ class VMStateManager extends EventTarget {
         this.isPolling = true;
         this.fetchVMs(); // Initial fetch
 
+        this.schedulePoll(intervalMs);
+        this.subscribe(intervalMs);
+    }
+
+    schedulePoll(intervalMs) {
+        if (this.pollInterval) {
+            clearInterval(this.pollInterval);
+        }
         this.pollInterval = setInterval(() => {
             this.fetchVMs();
         }, intervalMs);
     }
 
+    // Refetch on server-sent VM events; while the stream is up, polling only
+    // backs it up at a slow rate.
+    subscribe(intervalMs) {
+        if (typeof EventSource === 'undefined') return;
+
+        const API_BASE =
+            window.SafePawConfig?.API_BASE ??
+            `${window.location.protocol}//${window.location.hostname}:8889`;
+        const EVENT_TYPES = [
+            'vm.launched',
+            'vm.started',
+            'vm.stopped',
+            'vm.deleted',
+            'vm.state_changed',
+        ];
+
+        this.events = new EventSource(`${API_BASE}/events`);
+        this.events.onopen = () => this.schedulePoll(60000);
+        this.events.onerror = () => this.schedulePoll(intervalMs);
+        for (const type of EVENT_TYPES) {
+            this.events.addEventListener(type, () => this.fetchVMs());
+        }
+    }
+
     stopPolling() {
         if (this.pollInterval) {
             clearInterval(this.pollInterval);
             this.pollInterval = null;
         }
+        if (this.events) {
+            this.events.close();
+            this.events = null;
+        }
         this.isPolling = false;
     }
 
@@ -165,7 +165,7 @@ class SafePawVillage {
 
             if (!response.ok) {
                 const error = await response.json();
-                throw new Error(error.error || 'Failed to launch VM');
+                throw new Error(error.message || 'Failed to launch VM');
             }
 
             const result = await response.json();
@@ -290,7 +290,7 @@ class SafePawVillage {
 
             if (!response.ok) {
                 const error = await response.json();
-                throw new Error(error.error || 'Failed to delete VM');
+                throw new Error(error.message || 'Failed to delete VM');
             }
 
             const result = await response.json();
@@ -327,7 +327,6 @@ class SafePawVillage {
     buildApiError(action, response, parsedBody, fallbackMessage = null) {
         const responseJson = parsedBody.json;
         const message =
-            responseJson?.error ||
             responseJson?.message ||
             fallbackMessage ||
             parsedBody.text ||
@@ -339,6 +338,8 @@ class SafePawVillage {
         error.status = response.status;
         error.statusText = response.statusText;
         error.url = response.url;
+        error.code = responseJson?.code || null;
+        error.requestId = responseJson?.request_id || null;
         error.details = responseJson?.details || null;
         error.responseJson = responseJson;
         error.responseText = parsedBody.text;